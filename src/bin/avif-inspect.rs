@@ -0,0 +1,310 @@
+//! `avif-inspect`: print the structure and metadata of AVIF files (`cli` feature).
+
+use std::process::ExitCode;
+use zenavif_parse::{AV1Config, AvifParser, AvifSummary, ColorInformation, DecodeConfig, Strictness, Unstoppable};
+
+const USAGE: &str = "\
+usage: avif-inspect [--json] [--strict] FILE...
+
+Prints the box tree, item table, frame timing, HDR metadata and validation
+warnings of each FILE.
+
+  --json    print AvifParser::summary() as JSON instead
+  --strict  reject spec violations instead of listing them as warnings";
+
+/// Deepest box nesting printed; real files stay well below this.
+const MAX_DEPTH: usize = 16;
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut strictness = Strictness::Permissive;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--strict" => strictness = Strictness::Standard,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("avif-inspect: unknown option {arg}\n\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let config = DecodeConfig::default().with_strictness(strictness);
+    let mut status = ExitCode::SUCCESS;
+    for path in &paths {
+        if let Err(err) = inspect(path, &config, json, paths.len() > 1) {
+            eprintln!("avif-inspect: {path}: {err}");
+            status = ExitCode::FAILURE;
+        }
+    }
+    status
+}
+
+fn inspect(path: &str, config: &DecodeConfig, json: bool, show_path: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    let parser = AvifParser::from_bytes_with_config(&data, config, &Unstoppable).map_err(|e| e.decompose().0)?;
+    let summary = parser.summary();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+    if show_path {
+        println!("==> {path} <==");
+    }
+    println!("Boxes");
+    print_boxes(&data, 0, 1);
+    print_items(&summary);
+    print_image(&summary);
+    print_frames(&summary);
+    print_hdr(&summary);
+    print_warnings(&parser, &summary);
+    print_stats(&parser);
+    if show_path {
+        println!();
+    }
+    Ok(())
+}
+
+/// Print the boxes in `data`, which starts at file offset `offset`.
+fn print_boxes(data: &[u8], offset: u64, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut pos = 0;
+    while data.len() - pos >= 8 {
+        let header = &data[pos..];
+        let fourcc: [u8; 4] = header[4..8].try_into().unwrap();
+        let name = String::from_utf8_lossy(&fourcc);
+        let at = offset + pos as u64;
+        let (header_len, size) = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+            0 => (8, (data.len() - pos) as u64),
+            1 if header.len() >= 16 => (16, u64::from_be_bytes(header[8..16].try_into().unwrap())),
+            1 => {
+                println!("{indent}{name} @{at}: truncated header");
+                return;
+            }
+            size => (8, u64::from(size)),
+        };
+        let end = usize::try_from(size).ok()
+            .filter(|&size| size >= header_len)
+            .and_then(|size| pos.checked_add(size))
+            .filter(|&end| end <= data.len());
+        let Some(end) = end else {
+            println!("{indent}{name} @{at} size {size}: overruns its parent");
+            return;
+        };
+        println!("{indent}{name} @{at} size {size}");
+
+        let payload = &data[pos + header_len..end];
+        if let Some(skip) = children_offset(&fourcc, payload)
+            && skip <= payload.len()
+            && depth < MAX_DEPTH
+        {
+            print_boxes(&payload[skip..], at + (header_len + skip) as u64, depth + 1);
+        }
+        pos = end;
+    }
+}
+
+/// Where the child boxes of a container start within its payload.
+fn children_offset(fourcc: &[u8; 4], payload: &[u8]) -> Option<usize> {
+    match fourcc {
+        b"moov" | b"trak" | b"mdia" | b"minf" | b"stbl" | b"dinf" | b"edts" | b"udta" | b"tref" | b"iprp" | b"ipco" | b"grpl" => Some(0),
+        // Full boxes
+        b"meta" | b"iref" => Some(4),
+        // Full boxes with an entry count
+        b"stsd" | b"dref" => Some(8),
+        b"iinf" => Some(if payload.first() == Some(&0) { 6 } else { 8 }),
+        // VisualSampleEntry fields precede the codec configuration boxes
+        b"av01" if payload.len() > 78 => Some(78),
+        _ => None,
+    }
+}
+
+fn print_items(summary: &AvifSummary) {
+    if summary.items.is_empty() {
+        return;
+    }
+    println!("\nItems");
+    for item in &summary.items {
+        let location = if item.in_idat {
+            "in idat".to_string()
+        } else {
+            item.byte_ranges.iter().map(|r| format!("{}..{}", r.start, r.end)).collect::<Vec<_>>().join(", ")
+        };
+        let primary = if item.primary { " (primary)" } else { "" };
+        let content_type = item.content_type.as_deref().map(|t| format!(" {t}")).unwrap_or_default();
+        println!("  #{} {}{content_type}{primary}: {} bytes [{location}]", item.id, item.item_type, item.size);
+        if !item.properties.is_empty() {
+            // Essential properties are marked with `!`
+            let properties: Vec<_> = item.properties.iter()
+                .map(|p| if p.essential { format!("{}!", p.fourcc) } else { p.fourcc.clone() })
+                .collect();
+            println!("      properties: {}", properties.join(" "));
+        }
+        for reference in &item.references {
+            println!("      {} -> #{}", String::from_utf8_lossy(&reference.reference_type), reference.to_item_id);
+        }
+    }
+}
+
+fn print_image(summary: &AvifSummary) {
+    println!("\nImage");
+    println!("  brands: {} [{}]", summary.major_brand, summary.compatible_brands.join(" "));
+    if let Some(e) = summary.spatial_extents {
+        println!("  ispe: {}x{}", e.width, e.height);
+    }
+    if let Some(c) = &summary.av1_config {
+        println!(
+            "  av1C: profile {} level {} tier {}, {}-bit {}",
+            c.profile, c.level, c.tier, c.bit_depth, subsampling(c),
+        );
+    }
+    if let Some(seq) = &summary.sequence_header {
+        println!(
+            "  sequence header: {}x{} max, {}-bit, profile {} level {} tier {}, cicp {}/{}/{} {} range{}",
+            seq.max_frame_width, seq.max_frame_height, seq.bit_depth, seq.seq_profile, seq.seq_level_idx,
+            seq.seq_tier, seq.color_primaries, seq.transfer_characteristics, seq.matrix_coefficients,
+            if seq.full_range { "full" } else { "limited" },
+            if seq.film_grain_params_present { ", film grain" } else { "" },
+        );
+    }
+    if let Some(ColorInformation::Nclx { color_primaries, transfer_characteristics, matrix_coefficients, full_range }) = &summary.nclx {
+        println!(
+            "  colr nclx: {color_primaries}/{transfer_characteristics}/{matrix_coefficients} {} range",
+            if *full_range { "full" } else { "limited" },
+        );
+    }
+    if let Some(size) = summary.icc_profile_size {
+        println!("  colr ICC: {size} bytes");
+    }
+    if summary.has_alpha {
+        println!("  alpha{}", if summary.premultiplied_alpha { " (premultiplied)" } else { "" });
+    }
+    if let Some(r) = summary.rotation {
+        println!("  irot: {} degrees", r.angle);
+    }
+    if let Some(m) = summary.mirror {
+        println!("  imir: axis {}", m.axis);
+    }
+    if let Some(clap) = &summary.clean_aperture {
+        println!("  clap: {clap:?}");
+    }
+    if let Some(pasp) = &summary.pixel_aspect_ratio {
+        println!("  pasp: {pasp:?}");
+    }
+    if let Some(grid) = &summary.grid {
+        println!("  grid: {}x{} tiles, {}x{} output", grid.columns, grid.rows, grid.output_width, grid.output_height);
+    }
+    if let Some(overlay) = &summary.overlay {
+        println!("  overlay: {overlay:?}");
+    }
+    let mut extras = Vec::new();
+    for (present, name) in [
+        (summary.has_exif, "Exif"),
+        (summary.has_xmp, "XMP"),
+        (summary.has_thumbnail, "thumbnail"),
+        (summary.gain_map.is_some(), "gain map"),
+        (summary.has_depth_map, "depth map"),
+    ] {
+        if present {
+            extras.push(name);
+        }
+    }
+    if !extras.is_empty() {
+        println!("  also: {}", extras.join(", "));
+    }
+}
+
+fn subsampling(config: &AV1Config) -> &'static str {
+    match (config.monochrome, config.chroma_subsampling_x, config.chroma_subsampling_y) {
+        (true, ..) => "4:0:0",
+        (false, 1, 1) => "4:2:0",
+        (false, 1, 0) => "4:2:2",
+        _ => "4:4:4",
+    }
+}
+
+fn print_frames(summary: &AvifSummary) {
+    let Some(animation) = summary.animation else {
+        return;
+    };
+    let loops = if animation.loop_count == 0 { "forever".to_string() } else { animation.loop_count.to_string() };
+    println!(
+        "\nFrames: {}, timescale {}, loops {loops}{}",
+        animation.frame_count, animation.timescale, if animation.has_alpha { ", alpha track" } else { "" },
+    );
+    for track in &summary.tracks {
+        println!(
+            "  track {} {} {}x{} {}: {} samples, timescale {}{}",
+            track.track_id, track.handler_type, track.width, track.height, track.codec.as_deref().unwrap_or("-"),
+            track.frame_count, track.timescale, if track.selected { " (selected)" } else { "" },
+        );
+    }
+    println!("  {:>6} {:>10} {:>10} {:>10} {:>10}", "frame", "pts_ms", "duration", "size", "alpha");
+    for (index, frame) in summary.frames.iter().enumerate() {
+        let alpha = frame.alpha_size.map(|size| size.to_string()).unwrap_or_else(|| "-".to_string());
+        println!("  {index:>6} {:>10} {:>10} {:>10} {alpha:>10}", frame.timestamp_ms, frame.duration_ms, frame.size);
+    }
+}
+
+fn print_hdr(summary: &AvifSummary) {
+    let bitstream = summary.sequence_header.as_ref().map(|seq| &seq.hdr);
+    let cll = [("clli", summary.content_light_level), ("bitstream CLL", bitstream.and_then(|hdr| hdr.content_light_level))];
+    let mdcv = [("mdcv", summary.mastering_display), ("bitstream MDCV", bitstream.and_then(|hdr| hdr.mastering_display))];
+    let t35 = bitstream.map_or(0, |hdr| hdr.itut_t35.len());
+    if cll.iter().all(|(_, v)| v.is_none()) && mdcv.iter().all(|(_, v)| v.is_none()) && t35 == 0 {
+        return;
+    }
+    println!("\nHDR");
+    for (name, cll) in cll {
+        let Some(cll) = cll else { continue };
+        println!("  {name}: MaxCLL {} MaxFALL {} cd/m²", cll.max_content_light_level, cll.max_pic_average_light_level);
+    }
+    for (name, mdcv) in mdcv {
+        let Some(mdcv) = mdcv else { continue };
+        println!(
+            "  {name}: luminance {:.4}..{:.4} cd/m², primaries {:?}, white point {:?}",
+            f64::from(mdcv.min_luminance) / 10000.0, f64::from(mdcv.max_luminance) / 10000.0,
+            mdcv.primaries, mdcv.white_point,
+        );
+    }
+    if t35 > 0 {
+        println!("  ITU-T T.35 metadata OBUs: {t35}");
+    }
+}
+
+fn print_stats(parser: &AvifParser<'_>) {
+    let stats = parser.stats();
+    println!("\nParse");
+    println!(
+        "  {} boxes, {} items, {} properties, {} extents, {} mdat ({} bytes), peak memory {} bytes",
+        stats.boxes, stats.items, stats.properties, stats.extents, stats.mdat_count, stats.mdat_bytes, stats.peak_memory,
+    );
+    if let Some(duration) = stats.parse_duration {
+        println!("  parsed in {duration:?}");
+    }
+}
+
+fn print_warnings(parser: &AvifParser<'_>, summary: &AvifSummary) {
+    let mismatches = parser.verify_codec_config().unwrap_or_default();
+    if summary.warnings.is_empty() && mismatches.is_empty() {
+        return;
+    }
+    println!("\nWarnings");
+    for warning in &summary.warnings {
+        println!("  {:?} in {} @{}", warning.kind, String::from_utf8_lossy(&warning.fourcc), warning.offset);
+    }
+    for m in mismatches {
+        println!("  av1C {} is {}, the sequence header says {}", m.field, m.av1c, m.sequence_header);
+    }
+}
@@ -1,7 +1,7 @@
 // This Source Code Form is subject to the terms of the Mozilla Public
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
-use std::fmt;
+use core::fmt;
 
 // To ensure we don't use stdlib allocating types by accident
 #[allow(dead_code)]
@@ -78,7 +78,7 @@ impl From<[u8; 4]> for FourCC {
 
 impl fmt::Debug for FourCC {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match std::str::from_utf8(&self.value) {
+        match core::str::from_utf8(&self.value) {
             Ok(s) => f.write_str(s),
             Err(_) => self.value.fmt(f),
         }
@@ -87,7 +87,7 @@ impl fmt::Debug for FourCC {
 
 impl fmt::Display for FourCC {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(std::str::from_utf8(&self.value).unwrap_or("null"))
+        f.write_str(core::str::from_utf8(&self.value).unwrap_or("null"))
     }
 }
 
@@ -100,11 +100,16 @@ impl PartialEq<&[u8; 4]> for FourCC {
 box_database!(
     FileTypeBox                       0x6674_7970, // "ftyp"
     MediaDataBox                      0x6d64_6174, // "mdat"
+    FreeSpaceBox                      0x6672_6565, // "free"
+    SkipBox                           0x736b_6970, // "skip"
+    WideBox                           0x7769_6465, // "wide" - reserves room for a 64-bit size
     PrimaryItemBox                    0x7069_746d, // "pitm"
     ItemInfoBox                       0x6969_6e66, // "iinf"
     ItemInfoEntry                     0x696e_6665, // "infe"
     ItemLocationBox                   0x696c_6f63, // "iloc"
     ItemDataBox                       0x6964_6174, // "idat" - item data for construction_method=1
+    ItemProtectionBox                 0x6970_726f, // "ipro"
+    MinimizedImageBox                 0x6d69_6e69, // "mini" - replaces meta in minimized (mif3) files
     MovieBox                          0x6d6f_6f76, // "moov"
     MovieHeaderBox                    0x6d76_6864, // "mvhd"
     TrackBox                          0x7472_616b, // "trak"
@@ -128,17 +133,21 @@ box_database!(
     VideoMediaHeaderBox               0x766d_6864, // "vmhd" - video media header (for animation)
     DataInformationBox                0x6469_6e66, // "dinf" - data information (for animation)
     DataReferenceBox                  0x6472_6566, // "dref" - data reference (for animation)
+    DataEntryUrlBox                   0x7572_6c20, // "url "
+    DataEntryUrnBox                   0x7572_6e20, // "urn "
     SampleTableBox                    0x7374_626c, // "stbl"
     SampleDescriptionBox              0x7374_7364, // "stsd"
     TimeToSampleBox                   0x7374_7473, // "stts"
     SampleToChunkBox                  0x7374_7363, // "stsc"
     SampleSizeBox                     0x7374_737a, // "stsz"
+    CompactSampleSizeBox              0x7374_7a32, // "stz2"
     ChunkOffsetBox                    0x7374_636f, // "stco"
     ChunkLargeOffsetBox               0x636f_3634, // "co64"
     SyncSampleBox                     0x7374_7373, // "stss"
     AVCSampleEntry                    0x6176_6331, // "avc1"
     AVC3SampleEntry                   0x6176_6333, // "avc3" - Need to check official name in spec.
     AVCConfigurationBox               0x6176_6343, // "avcC"
+    HEVCConfigurationBox              0x6876_6343, // "hvcC"
     MP4AudioSampleEntry               0x6d70_3461, // "mp4a"
     MP4VideoSampleEntry               0x6d70_3476, // "mp4v"
     ESDBox                            0x6573_6473, // "esds"
@@ -158,6 +167,7 @@ box_database!(
     OperatingPointSelectorBox         0x6131_6f70, // "a1op"
     LayerSelectorBox                  0x6c73_656c, // "lsel"
     AV1LayeredImageIndexingBox        0x6131_6c78, // "a1lx"
+    UserDescriptionBox                0x7564_6573, // "udes"
     FLACSampleEntry                   0x664c_6143, // "fLaC"
     FLACSpecificBox                   0x6466_4c61, // "dfLa"
     OpusSampleEntry                   0x4f70_7573, // "Opus"
@@ -0,0 +1,138 @@
+//! Feeding parsed AVIF payloads to the [`dav1d`](::dav1d) decoder (`dav1d` feature).
+//!
+//! ```no_run
+//! use zenavif_parse::{AvifParser, dav1d};
+//!
+//! # fn run(file: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let parser = AvifParser::from_bytes(file)?;
+//! let mut decoder = dav1d::Decoder::with_settings(&dav1d::settings(&parser))?;
+//! dav1d::send_primary(&mut decoder, &parser)?;
+//! let picture = decoder.get_picture()?;
+//! let hints = dav1d::OutputHints::new(&parser);
+//! assert_eq!(Some(picture.bit_depth() as u8), hints.bit_depth);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{AvifParser, Error, cow_to_bytes};
+use bytes::Bytes;
+use whereat::At;
+
+pub use ::dav1d::{Decoder, Settings};
+
+/// Decoder settings for the images of `parser`.
+///
+/// Selects the operating point from `a1op` and outputs all layers only when
+/// `lsel` asks for progressive rendering. Frames are limited to the `ispe`
+/// area, and the frame delay is 1 so each `send_*` call yields its picture
+/// without waiting for more input.
+pub fn settings(parser: &AvifParser<'_>) -> Settings {
+    let mut settings = Settings::new();
+    settings.set_max_frame_delay(1);
+    settings.set_operating_point(parser.operating_point().map_or(0, |op| u32::from(op.op_index)));
+    settings.set_all_layers(parser.layer_selector().is_some_and(|lsel| lsel.layer_id == 0xFFFF));
+    if let Some(extents) = parser.spatial_extents() {
+        let area = u64::from(extents.width) * u64::from(extents.height);
+        settings.set_frame_size_limit(u32::try_from(area).unwrap_or(u32::MAX));
+    }
+    settings
+}
+
+/// Send the primary image, prefixed with its `av1C` configOBUs.
+///
+/// Grids have no single payload; send each [`tile`](send_tile) instead.
+pub fn send_primary(decoder: &mut Decoder, parser: &AvifParser<'_>) -> Result<(), SendError> {
+    let has_config_obus = parser.av1_config().is_some_and(|c| !c.config_obus.is_empty());
+    let data = if has_config_obus {
+        cow_to_bytes(parser.primary_decoder_payload()?)
+    } else {
+        parser.primary_bytes()?
+    };
+    send(decoder, data, None, None)
+}
+
+/// Send the alpha image. Does nothing if there is none.
+pub fn send_alpha(decoder: &mut Decoder, parser: &AvifParser<'_>) -> Result<(), SendError> {
+    match parser.alpha_bytes() {
+        Some(data) => send(decoder, data?, None, None),
+        None => Ok(()),
+    }
+}
+
+/// Send grid tile `index`. The picture's `offset()` is the tile index.
+pub fn send_tile(decoder: &mut Decoder, parser: &AvifParser<'_>, index: usize) -> Result<(), SendError> {
+    send(decoder, parser.tile_bytes(index)?, Some(index), None)
+}
+
+/// Send animation frame `index`, tagged with its duration. The picture's
+/// `offset()` is the frame index.
+pub fn send_frame(decoder: &mut Decoder, parser: &AvifParser<'_>, index: usize) -> Result<(), SendError> {
+    let data = parser.frame_bytes(index)?;
+    let duration_ms = match &parser.inner.animation_data {
+        Some(anim) => parser.calculate_frame_duration(&anim.sample_table, anim.media_timescale, index)?,
+        None => 0,
+    };
+    send(decoder, data, Some(index), Some(duration_ms))
+}
+
+/// dav1d holds on to its input, so payloads are handed over as `Bytes`:
+/// slices of the input for parsers built with
+/// [`AvifParser::from_bytes_shared`], moved-in buffers for owned payloads
+/// (multi-extent items, configOBU prefixes), and copies only of data
+/// borrowed from elsewhere.
+fn send(decoder: &mut Decoder, data: Bytes, index: Option<usize>, duration_ms: Option<u32>) -> Result<(), SendError> {
+    let offset = index.and_then(|i| i64::try_from(i).ok());
+    decoder.send_data(data, offset, None, duration_ms.map(i64::from)).map_err(SendError::Decoder)
+}
+
+/// Container metadata a dav1d consumer needs to interpret the decoded planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OutputHints {
+    /// Bit depth the `av1C` declares; the picture should match.
+    pub bit_depth: Option<u8>,
+    /// The color planes are premultiplied by the alpha image (`prem`).
+    pub premultiplied_alpha: bool,
+    /// There is an alpha image to decode with [`send_alpha`].
+    pub has_alpha: bool,
+}
+
+impl OutputHints {
+    /// Collect the hints for `parser`'s primary image.
+    pub fn new(parser: &AvifParser<'_>) -> Self {
+        Self {
+            bit_depth: parser.av1_config().map(|c| c.bit_depth),
+            premultiplied_alpha: parser.premultiplied_alpha(),
+            has_alpha: parser.has_alpha_item()
+                || parser.is_sequence_only()
+                    && parser.inner.animation_data.as_ref().is_some_and(|anim| anim.alpha_sample_table.is_some()),
+        }
+    }
+}
+
+/// Failure to hand a payload to dav1d.
+#[derive(Debug)]
+pub enum SendError {
+    /// The payload could not be read from the file.
+    Parse(At<Error>),
+    /// dav1d rejected it. [`::dav1d::Error::Again`] means the data is held
+    /// as pending: drain pictures, then call `send_pending_data()`.
+    Decoder(::dav1d::Error),
+}
+
+impl From<At<Error>> for SendError {
+    fn from(err: At<Error>) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "{}", err.error()),
+            Self::Decoder(err) => write!(f, "dav1d: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
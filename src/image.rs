@@ -0,0 +1,123 @@
+//! [`image`](::image) crate integration (`image` feature).
+//!
+//! [`AvifImageDecoder`] answers the header queries of
+//! [`ImageDecoder`](::image::ImageDecoder) from container metadata alone and
+//! hands pixel decoding to a caller-supplied AV1 decoder.
+//!
+//! ```no_run
+//! use image::{DynamicImage, ImageResult};
+//! use zenavif_parse::AvifParser;
+//! use zenavif_parse::image::AvifImageDecoder;
+//!
+//! # fn decode_with_my_av1_decoder(parser: &AvifParser<'_>, out: &mut [u8]) -> ImageResult<()> { Ok(()) }
+//! # fn run(file: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let parser = AvifParser::from_bytes(file)?;
+//! let decoder = AvifImageDecoder::new(parser, |parser, _color_type, out| {
+//!     decode_with_my_av1_decoder(parser, out)
+//! })?;
+//! let image = DynamicImage::from_decoder(decoder)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{AvifParser, Error, Result};
+use ::image::error::{DecodingError, ImageFormatHint};
+use ::image::metadata::Orientation;
+use ::image::{ColorType, ImageDecoder, ImageError, ImageFormat, ImageResult};
+use whereat::{At, at};
+
+/// An [`ImageDecoder`] for the primary image of an [`AvifParser`].
+///
+/// `decode` receives the parser, the [`ColorType`] reported to `image` and
+/// the output buffer, which it must fill with the decoded image: RGB(A) for
+/// color images, luma (plus alpha) for monochrome ones, with the alpha image
+/// merged in. Images deeper than 8 bits are reported as 16-bit, so 10- and
+/// 12-bit samples must be scaled to the full `u16` range. Samples are stored
+/// before the `irot`/`imir` transforms, which `image` applies from
+/// [`orientation()`](ImageDecoder::orientation); `clap` cropping is left to
+/// the caller.
+pub struct AvifImageDecoder<'data, F> {
+    parser: AvifParser<'data>,
+    dimensions: (u32, u32),
+    color_type: ColorType,
+    decode: F,
+}
+
+impl<'data, F> AvifImageDecoder<'data, F>
+where
+    F: FnOnce(&AvifParser<'data>, ColorType, &mut [u8]) -> ImageResult<()>,
+{
+    /// Wrap `parser`. Fails if the primary item has no `ispe` or `av1C`.
+    pub fn new(parser: AvifParser<'data>, decode: F) -> Result<Self> {
+        let extents = parser.spatial_extents()
+            .ok_or_else(|| at!(Error::InvalidData("primary item has no ispe property")))?;
+        let config = parser.av1_config()
+            .ok_or_else(|| at!(Error::InvalidData("primary item has no av1C property")))?;
+        let has_alpha = parser.has_alpha_item();
+        let color_type = match (config.monochrome, has_alpha, config.bit_depth > 8) {
+            (true, false, false) => ColorType::L8,
+            (true, true, false) => ColorType::La8,
+            (false, false, false) => ColorType::Rgb8,
+            (false, true, false) => ColorType::Rgba8,
+            (true, false, true) => ColorType::L16,
+            (true, true, true) => ColorType::La16,
+            (false, false, true) => ColorType::Rgb16,
+            (false, true, true) => ColorType::Rgba16,
+        };
+        Ok(Self { dimensions: (extents.width, extents.height), color_type, parser, decode })
+    }
+
+    /// The wrapped parser, for metadata `ImageDecoder` has no accessor for.
+    pub fn parser(&self) -> &AvifParser<'data> {
+        &self.parser
+    }
+}
+
+impl<'data, F> ImageDecoder for AvifImageDecoder<'data, F>
+where
+    F: FnOnce(&AvifParser<'data>, ColorType, &mut [u8]) -> ImageResult<()>,
+{
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    fn icc_profile(&mut self) -> ImageResult<Option<std::vec::Vec<u8>>> {
+        Ok(self.parser.icc_profile().map(|icc| icc.into_owned()))
+    }
+
+    fn exif_metadata(&mut self) -> ImageResult<Option<std::vec::Vec<u8>>> {
+        self.parser.exif_data().transpose().map(|exif| exif.map(|e| e.into_owned())).map_err(image_error)
+    }
+
+    fn orientation(&mut self) -> ImageResult<Orientation> {
+        // irot is counter-clockwise and applied before imir; Orientation rotates clockwise
+        let angle = self.parser.rotation().map_or(0, |r| r.angle);
+        let mirror = self.parser.mirror().map(|m| m.axis);
+        Ok(match (angle, mirror) {
+            (90, None) => Orientation::Rotate270,
+            (180, None) => Orientation::Rotate180,
+            (270, None) => Orientation::Rotate90,
+            (0, Some(0)) | (180, Some(1)) => Orientation::FlipHorizontal,
+            (0, Some(_)) | (180, Some(_)) => Orientation::FlipVertical,
+            (90, Some(0)) | (270, Some(1)) => Orientation::Rotate270FlipH,
+            (90, Some(_)) | (270, Some(_)) => Orientation::Rotate90FlipH,
+            _ => Orientation::NoTransforms,
+        })
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        (self.decode)(&self.parser, self.color_type, buf)
+    }
+
+    fn read_image_boxed(self: std::boxed::Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+fn image_error(err: At<Error>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(ImageFormat::Avif), err.decompose().0))
+}
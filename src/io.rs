@@ -0,0 +1,366 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The I/O traits the parser reads through.
+//!
+//! With the `std` feature (the default) these are re-exports of
+//! `std::io`. Without it they are minimal stand-ins with the same
+//! signatures, so `no_std` callers can implement [`Read`] for their own
+//! sources and pass [`BoxHandler`](crate::BoxHandler)s a reader.
+
+use byteorder::ByteOrder;
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+#[cfg(feature = "std")]
+pub(crate) use std::io::{Cursor, Take};
+
+#[cfg(not(feature = "std"))]
+pub use self::core_io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+#[cfg(not(feature = "std"))]
+pub(crate) use self::core_io::{Cursor, Take};
+
+/// Big- and little-endian integer reads, as `byteorder::ReadBytesExt`
+/// provides them for `std::io::Read` only.
+pub(crate) trait ReadBytesExt: Read {
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        self.read_u8().map(|b| b as i8)
+    }
+
+    fn read_u16<T: ByteOrder>(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_u16(&buf))
+    }
+
+    fn read_i16<T: ByteOrder>(&mut self) -> Result<i16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_i16(&buf))
+    }
+
+    fn read_u32<T: ByteOrder>(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_u32(&buf))
+    }
+
+    fn read_i32<T: ByteOrder>(&mut self) -> Result<i32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_i32(&buf))
+    }
+
+    fn read_u64<T: ByteOrder>(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_u64(&buf))
+    }
+
+    fn read_i64<T: ByteOrder>(&mut self) -> Result<i64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(T::read_i64(&buf))
+    }
+}
+
+impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+/// `std::io` stand-ins for `no_std` builds. Only what the parser uses is
+/// here; the behavior matches `std` for it.
+#[cfg(not(feature = "std"))]
+mod core_io {
+    use core::fmt;
+
+    /// Result of an I/O operation.
+    pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+    /// The kinds of [`Error`] the parser raises or reacts to, a subset of
+    /// `std::io::ErrorKind`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        /// The data is malformed.
+        InvalidData,
+        /// An argument was invalid, such as a seek before the start.
+        InvalidInput,
+        /// The input ended early.
+        UnexpectedEof,
+        /// An allocation failed.
+        OutOfMemory,
+        /// The read was interrupted and may be retried.
+        Interrupted,
+        /// Any other failure.
+        Other,
+    }
+
+    /// An I/O error: a kind and a static description.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        message: &'static str,
+    }
+
+    impl Error {
+        /// An error of `kind`, described by `message`.
+        pub const fn new(kind: ErrorKind, message: &'static str) -> Self {
+            Self { kind, message }
+        }
+
+        /// The kind of failure.
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            let message = match kind {
+                ErrorKind::InvalidData => "invalid data",
+                ErrorKind::InvalidInput => "invalid input",
+                ErrorKind::UnexpectedEof => "unexpected end of file",
+                ErrorKind::OutOfMemory => "out of memory",
+                ErrorKind::Interrupted => "operation interrupted",
+                ErrorKind::Other => "other error",
+            };
+            Self { kind, message }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl core::error::Error for Error {}
+
+    /// A byte source, like `std::io::Read`.
+    pub trait Read {
+        /// Read into `buf`, returning how many bytes were read; 0 at the end
+        /// of the input.
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        /// Fill `buf` completely, or fail with
+        /// [`ErrorKind::UnexpectedEof`].
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => buf = &mut buf[n..],
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        }
+
+        /// Append everything up to the end of the input to `buf`.
+        fn read_to_end(&mut self, buf: &mut alloc::vec::Vec<u8>) -> Result<usize> {
+            let mut chunk = [0; 4096];
+            let start = buf.len();
+            loop {
+                match self.read(&mut chunk) {
+                    Ok(0) => return Ok(buf.len() - start),
+                    Ok(n) => {
+                        buf.try_reserve(n).map_err(|_| Error::from(ErrorKind::OutOfMemory))?;
+                        buf.extend_from_slice(&chunk[..n]);
+                    },
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        /// Borrow this reader, e.g. to [`take`](Self::take) from it.
+        fn by_ref(&mut self) -> &mut Self
+        where
+            Self: Sized,
+        {
+            self
+        }
+
+        /// A reader of at most `limit` bytes of this one.
+        fn take(self, limit: u64) -> Take<Self>
+        where
+            Self: Sized,
+        {
+            Take { inner: self, limit }
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for alloc::boxed::Box<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(self.len());
+            let (head, tail) = self.split_at(n);
+            buf[..n].copy_from_slice(head);
+            *self = tail;
+            Ok(n)
+        }
+    }
+
+    /// Where to [`seek`](Seek::seek) to, like `std::io::SeekFrom`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SeekFrom {
+        /// Bytes from the start.
+        Start(u64),
+        /// Bytes from the end; negative to seek back.
+        End(i64),
+        /// Bytes from the current position.
+        Current(i64),
+    }
+
+    /// A seekable source, like `std::io::Seek`.
+    pub trait Seek {
+        /// Move to `pos`, returning the new position from the start.
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+    }
+
+    impl<S: Seek + ?Sized> Seek for &mut S {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            (**self).seek(pos)
+        }
+    }
+
+    /// A byte sink, like `std::io::Write`.
+    pub trait Write {
+        /// Write some of `buf`, returning how many bytes were taken.
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        /// Write all of `buf`.
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf) {
+                    Ok(0) => return Err(Error::new(ErrorKind::Other, "failed to write whole buffer")),
+                    Ok(n) => buf = &buf[n..],
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+    }
+
+    impl Write for alloc::vec::Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.try_reserve(buf.len()).map_err(|_| Error::from(ErrorKind::OutOfMemory))?;
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    /// A reader limited to a number of bytes, from [`Read::take`].
+    #[derive(Debug)]
+    pub struct Take<T> {
+        inner: T,
+        limit: u64,
+    }
+
+    impl<T> Take<T> {
+        /// Bytes left before this reader reports the end of the input.
+        pub fn limit(&self) -> u64 {
+            self.limit
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.inner
+        }
+
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.inner
+        }
+
+        pub fn into_inner(self) -> T {
+            self.inner
+        }
+    }
+
+    impl<T: Read> Read for Take<T> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.limit == 0 {
+                return Ok(0);
+            }
+            let max = usize::try_from(self.limit).map_or(buf.len(), |limit| limit.min(buf.len()));
+            let n = self.inner.read(&mut buf[..max])?;
+            self.limit -= n as u64;
+            Ok(n)
+        }
+    }
+
+    /// A reader over an in-memory buffer that tracks its position.
+    #[derive(Debug)]
+    pub struct Cursor<T> {
+        inner: T,
+        position: u64,
+    }
+
+    impl<T> Cursor<T> {
+        pub fn new(inner: T) -> Self {
+            Self { inner, position: 0 }
+        }
+
+        pub fn position(&self) -> u64 {
+            self.position
+        }
+
+        pub fn set_position(&mut self, position: u64) {
+            self.position = position;
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.inner
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            let (base, offset) = match pos {
+                SeekFrom::Start(position) => {
+                    self.position = position;
+                    return Ok(position);
+                },
+                SeekFrom::End(offset) => (self.inner.as_ref().len() as u64, offset),
+                SeekFrom::Current(offset) => (self.position, offset),
+            };
+            self.position = base
+                .checked_add_signed(offset)
+                .ok_or(Error::new(ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))?;
+            Ok(self.position)
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Read for Cursor<T> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let data = self.inner.as_ref();
+            let start = usize::try_from(self.position).map_or(data.len(), |position| position.min(data.len()));
+            let n = (&data[start..]).read(buf)?;
+            self.position += n as u64;
+            Ok(n)
+        }
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(unsafe_code)]
 #![allow(clippy::missing_safety_doc)]
 //! AVIF container parser (ISOBMFF/MIAF demuxer).
@@ -10,29 +11,60 @@
 //! recording byte offsets and resolving data on demand.
 //!
 //! A legacy eager API (`read_avif`) is available behind the `eager` feature flag.
+//!
+//! Without the default `std` feature the crate is `no_std` + `alloc`: it
+//! parses byte slices and any [`io::Read`] source, but has no seekable, file
+//! or async input, and no clock for [`DecodeConfig::time_budget`].
 
 // This Source Code Form is subject to the terms of the Mozilla Public
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+#[cfg(feature = "rich-errors")]
 use arrayvec::ArrayVec;
 use log::{debug, warn};
 
+#[macro_use]
+extern crate alloc;
+
 use bitreader::BitReader;
-use byteorder::ReadBytesExt;
-use fallible_collections::{TryClone, TryReserveError};
-use std::borrow::Cow;
-use std::convert::{TryFrom, TryInto as _};
+use fallible_collections::TryReserveError;
+use alloc::borrow::Cow;
+use core::convert::{TryFrom, TryInto as _};
 
-use std::io::{Read, Take};
-use std::num::NonZeroU32;
-use std::ops::{Range, RangeFrom};
+use crate::io::{Read, ReadBytesExt, Seek, SeekFrom, Take};
+use core::num::NonZeroU32;
+use core::ops::{Range, RangeFrom};
+
+pub mod io;
 
 mod obu;
+pub use obu::{Obu, ObuIter, ObuType};
 
 mod boxes;
 use crate::boxes::{BoxType, FourCC};
 
+mod push;
+pub use push::{AvifPushParser, ParseProgress};
+
+mod writer;
+pub use writer::AvifBuilder;
+
+mod rewrite;
+pub use rewrite::{MetadataEdit, MetadataRewriter, set_loop_count};
+
+/// Feeding payloads to the dav1d decoder.
+#[cfg(feature = "dav1d")]
+pub mod dav1d;
+
+/// `image::ImageDecoder` backed by container metadata.
+#[cfg(feature = "image")]
+pub mod image;
+
+/// JavaScript bindings.
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
 /// This crate can be used from C.
 #[cfg(feature = "c_api")]
 pub mod c_api;
@@ -56,7 +88,7 @@ trait ToU64 {
 /// Infallible: usize always fits in u64.
 impl ToU64 for usize {
     fn to_u64(self) -> u64 {
-        const _: () = assert!(std::mem::size_of::<usize>() <= std::mem::size_of::<u64>());
+        const _: () = assert!(core::mem::size_of::<usize>() <= core::mem::size_of::<u64>());
         self as u64
     }
 }
@@ -72,7 +104,7 @@ macro_rules! impl_to_usize_from {
     ( $from_type:ty ) => {
         impl ToUsize for $from_type {
             fn to_usize(self) -> usize {
-                const _: () = assert!(std::mem::size_of::<$from_type>() <= std::mem::size_of::<usize>());
+                const _: () = assert!(core::mem::size_of::<$from_type>() <= core::mem::size_of::<usize>());
                 self as usize
             }
         }
@@ -107,7 +139,7 @@ impl<T: ?Sized> Offset for OffsetReader<'_, T> {
 }
 
 impl<T: Read + ?Sized> Read for OffsetReader<'_, T> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let bytes_read = self.reader.read(buf)?;
         self.offset = self
             .offset
@@ -137,16 +169,20 @@ struct String;
 ///
 /// This enum wraps the standard `io::Error` type, unified with
 /// our own parser error states and those of crates we use.
+///
+/// Non-exhaustive: the `rich-errors` feature adds a variant, and new failure
+/// modes may get their own.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Parse error caused by corrupt or malformed data.
     InvalidData(&'static str),
     /// Parse error caused by limited parser support rather than invalid data.
     Unsupported(&'static str),
-    /// Reflect `std::io::ErrorKind::UnexpectedEof` for short data.
+    /// Reflect [`io::ErrorKind::UnexpectedEof`] for short data.
     UnexpectedEOF,
-    /// Propagate underlying errors from `std::io`.
-    Io(std::io::Error),
+    /// Propagate underlying errors from [`io`].
+    Io(io::Error),
     /// `read_mp4` terminated without detecting a moov box.
     NoMoov,
     /// Out of memory
@@ -155,23 +191,104 @@ pub enum Error {
     ResourceLimitExceeded(&'static str),
     /// Operation was stopped/cancelled
     Stopped(enough::StopReason),
+    /// The requested data lies beyond the end of truncated input.
+    ///
+    /// Only returned by parsers created with [`DecodeConfig::allow_truncated`].
+    DataNotAvailable,
+    /// Item or track data is stored in other files, which this parser cannot
+    /// read. `urls` are their locations from the `dref` box, for telling the
+    /// user what is missing.
+    ExternalDataReference {
+        urls: alloc::vec::Vec<alloc::string::String>,
+    },
+    /// An item is protected (e.g. encrypted), which this parser cannot read.
+    /// `scheme` is the `schm` scheme type from its `ipro` entry, such as
+    /// `cenc`, if the entry has one.
+    ProtectedItem {
+        item_id: u32,
+        scheme: Option<[u8; 4]>,
+    },
+    /// A data error annotated with the box it was found in.
+    ///
+    /// Errors raised while parsing boxes arrive wrapped in this variant when
+    /// the `rich-errors` feature is enabled; match on [`Error::root`] to see
+    /// the underlying error either way.
+    #[cfg(feature = "rich-errors")]
+    WithContext(alloc::boxed::Box<(Error, ErrorContext)>),
+}
+
+/// Where in the file an error occurred (`rich-errors` feature).
+#[cfg(feature = "rich-errors")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Absolute file offset of the innermost box being parsed.
+    pub offset: u64,
+    /// FourCCs of the enclosing boxes, outermost first. Only the innermost
+    /// eight levels are kept.
+    pub box_path: ArrayVec<[u8; 4], 8>,
+}
+
+impl Error {
+    /// The underlying error, without the [`ErrorContext`] annotation the
+    /// `rich-errors` feature adds.
+    pub fn root(&self) -> &Self {
+        match self {
+            #[cfg(feature = "rich-errors")]
+            Self::WithContext(inner) => &inner.0,
+            _ => self,
+        }
+    }
+
+    /// Owned version of [`Error::root`].
+    pub fn into_root(self) -> Self {
+        match self {
+            #[cfg(feature = "rich-errors")]
+            Self::WithContext(inner) => inner.0,
+            other => other,
+        }
+    }
+
+    /// The box the error was found in, if known.
+    #[cfg(feature = "rich-errors")]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::WithContext(inner) => Some(&inner.1),
+            _ => None,
+        }
+    }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let msg = match self {
             Self::InvalidData(s) | Self::Unsupported(s) | Self::ResourceLimitExceeded(s) => s,
             Self::UnexpectedEOF => "EOF",
             Self::Io(err) => return err.fmt(f),
             Self::NoMoov => "Missing Moov box",
             Self::OutOfMemory => "OOM",
+            Self::DataNotAvailable => "data not available in truncated input",
+            Self::ExternalDataReference { urls } => return write!(f, "data stored in external files: {}", urls.join(", ")),
+            Self::ProtectedItem { item_id, scheme } => {
+                let scheme = scheme.map_or_else(|| "unknown".into(), |s| fourcc_string(&s));
+                return write!(f, "item {item_id} is protected with scheme {scheme}");
+            }
             Self::Stopped(reason) => return write!(f, "Stopped: {}", reason),
+            #[cfg(feature = "rich-errors")]
+            Self::WithContext(inner) => {
+                let (error, context) = &**inner;
+                write!(f, "{error} (in ")?;
+                for (i, fourcc) in context.box_path.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { "/" };
+                    write!(f, "{sep}{}", FourCC::from(*fourcc))?;
+                }
+                return write!(f, " at byte {})", context.offset);
+            }
         };
         f.write_str(msg)
     }
 }
 
-impl std::error::Error for Error {}
+impl core::error::Error for Error {}
 
 impl From<bitreader::BitReaderError> for Error {
     #[cold]
@@ -182,36 +299,45 @@ impl From<bitreader::BitReaderError> for Error {
     }
 }
 
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
         match err.kind() {
-            std::io::ErrorKind::UnexpectedEof => Self::UnexpectedEOF,
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEOF,
             _ => Self::Io(err),
         }
     }
 }
 
-impl From<std::string::FromUtf8Error> for Error {
-    fn from(_: std::string::FromUtf8Error) -> Self {
+impl From<alloc::string::FromUtf8Error> for Error {
+    fn from(_: alloc::string::FromUtf8Error) -> Self {
         Self::InvalidData("invalid utf8")
     }
 }
 
-impl From<std::num::TryFromIntError> for Error {
-    fn from(_: std::num::TryFromIntError) -> Self {
+impl From<core::num::TryFromIntError> for Error {
+    fn from(_: core::num::TryFromIntError) -> Self {
         Self::Unsupported("integer conversion failed")
     }
 }
 
-impl From<Error> for std::io::Error {
+impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        let kind = match err {
-            Error::InvalidData(_) => std::io::ErrorKind::InvalidData,
-            Error::UnexpectedEOF => std::io::ErrorKind::UnexpectedEof,
-            Error::Io(io_err) => return io_err,
-            _ => std::io::ErrorKind::Other,
+        if let Error::Io(io_err) = err {
+            return io_err;
+        }
+        let kind = match err.root() {
+            Error::InvalidData(_) => io::ErrorKind::InvalidData,
+            Error::UnexpectedEOF | Error::DataNotAvailable => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::Other,
+        };
+        #[cfg(feature = "std")]
+        return Self::new(kind, err);
+        // The `no_std` error only keeps a static description
+        #[cfg(not(feature = "std"))]
+        return match *err.root() {
+            Error::InvalidData(msg) | Error::Unsupported(msg) | Error::ResourceLimitExceeded(msg) => Self::new(kind, msg),
+            _ => kind.into(),
         };
-        Self::new(kind, err)
     }
 }
 
@@ -260,8 +386,15 @@ impl zencodec::CategorizedError for Error {
             Self::NoMoov => ImageError::Malformed.into(),
             // The container parsed, but uses a feature this demuxer doesn't handle.
             Self::Unsupported(_) => U::Feature.into(),
+            // Media data in other files is a feature too, not a broken file.
+            Self::ExternalDataReference { .. } | Self::ProtectedItem { .. } => U::Feature.into(),
             // Input ended before a complete structure could be read.
             Self::UnexpectedEOF => ImageError::UnexpectedEof.into(),
+            // A truncated file parsed, but the requested payload is past its end.
+            Self::DataNotAvailable => ImageError::UnexpectedEof.into(),
+            // Box context doesn't change what went wrong.
+            #[cfg(feature = "rich-errors")]
+            Self::WithContext(inner) => inner.0.category(),
             // An underlying `std::io` failure. The opaque `CodecIoKind` is the
             // portable choice: this crate enables `zencodec` with
             // `default-features = false`, so the std `ErrorKind` payload is absent.
@@ -278,8 +411,8 @@ impl zencodec::CategorizedError for Error {
                 // Reader-side cap on raw bytes read from an untrusted input
                 // stream before any container parsing happens — bounds the
                 // size of the encoded input, not decoded pixel memory.
-                "input exceeds peak_memory_limit" => L::InputSize.into(),
-                // Tracked peak allocation during eager box/sample parsing.
+                "input exceeds peak_memory_limit" | "input exceeds max_input_bytes" => L::InputSize.into(),
+                // Tracked peak allocation while parsing and resolving item data.
                 "peak memory limit exceeded" => L::Memory.into(),
                 "total megapixels limit exceeded" => L::TotalPixels.into(),
                 "animation frame count limit exceeded" => L::Frames.into(),
@@ -287,6 +420,18 @@ impl zencodec::CategorizedError for Error {
                 // the number of image tiles composited into the final decode,
                 // so `Pixels` (the decode-size axis) is the true fallback.
                 "grid tile count limit exceeded" => L::Pixels.into(),
+                // Items built from other items are stitched into one buffer on
+                // resolve; the nesting cap bounds that work and allocation.
+                "item construction depth limit exceeded" => L::Memory.into(),
+                // Structural caps on untrusted counts and nesting; each one
+                // bounds the bookkeeping allocated while parsing boxes.
+                "box nesting exceeds max_box_depth"
+                | "box count exceeds max_total_boxes"
+                | "item count limit exceeded"
+                | "extent count limit exceeded" => L::Memory.into(),
+                // The configured deadline passed: the same outcome as a
+                // caller-supplied `Stop` timing out.
+                "time budget exceeded" => ErrorCategory::Stopped(enough::StopReason::TimedOut),
                 _ => L::Pixels.into(),
             },
             // Cooperative cancellation / deadline — delegate to the zencodec
@@ -317,11 +462,11 @@ impl zencodec::CategorizedError for Error {
 /// [`Error`] together with the source location(s) it propagated through.
 /// Use [`At::error`] (borrow) or [`At::decompose`] (consume) to inspect the
 /// inner [`Error`].
-pub type Result<T, E = whereat::At<Error>> = std::result::Result<T, E>;
+pub type Result<T, E = whereat::At<Error>> = core::result::Result<T, E>;
 
 #[cfg(test)]
 mod error_category_tests {
-    use super::Error;
+    use super::{Error, io};
     use zencodec::{
         CategorizedError, ErrorCategory as C, ImageError, LimitKind as L, ResourceError, UnsupportedImageKind as U,
     };
@@ -339,13 +484,22 @@ mod error_category_tests {
             Error::Unsupported("construction method").category(),
             C::Image(ImageError::Unsupported(U::Feature))
         );
+        assert_eq!(
+            Error::ExternalDataReference { urls: vec!["frames.mp4".into()] }.category(),
+            C::Image(ImageError::Unsupported(U::Feature))
+        );
+        assert_eq!(
+            Error::ProtectedItem { item_id: 1, scheme: Some(*b"cenc") }.category(),
+            C::Image(ImageError::Unsupported(U::Feature))
+        );
 
         // Truncated input.
         assert_eq!(Error::UnexpectedEOF.category(), C::Image(ImageError::UnexpectedEof));
+        assert_eq!(Error::DataNotAvailable.category(), C::Image(ImageError::UnexpectedEof));
 
         // Underlying I/O.
         assert_eq!(
-            Error::Io(std::io::Error::other("disk")).category(),
+            Error::Io(io::Error::from(io::ErrorKind::Other)).category(),
             C::Io(zencodec::CodecIoKind::opaque())
         );
 
@@ -358,6 +512,10 @@ mod error_category_tests {
             Error::ResourceLimitExceeded("input exceeds peak_memory_limit").category(),
             C::Resource(ResourceError::Limits(L::InputSize))
         );
+        assert_eq!(
+            Error::ResourceLimitExceeded("input exceeds max_input_bytes").category(),
+            C::Resource(ResourceError::Limits(L::InputSize))
+        );
         assert_eq!(
             Error::ResourceLimitExceeded("peak memory limit exceeded").category(),
             C::Resource(ResourceError::Limits(L::Memory))
@@ -374,6 +532,25 @@ mod error_category_tests {
             Error::ResourceLimitExceeded("grid tile count limit exceeded").category(),
             C::Resource(ResourceError::Limits(L::Pixels))
         );
+        assert_eq!(
+            Error::ResourceLimitExceeded("item construction depth limit exceeded").category(),
+            C::Resource(ResourceError::Limits(L::Memory))
+        );
+        assert_eq!(
+            Error::ResourceLimitExceeded("time budget exceeded").category(),
+            C::Stopped(enough::StopReason::TimedOut)
+        );
+        for label in [
+            "box nesting exceeds max_box_depth",
+            "box count exceeds max_total_boxes",
+            "item count limit exceeded",
+            "extent count limit exceeded",
+        ] {
+            assert_eq!(
+                Error::ResourceLimitExceeded(label).category(),
+                C::Resource(ResourceError::Limits(L::Memory))
+            );
+        }
         // Unrecognized label -> true fallback (Pixels).
         assert_eq!(
             Error::ResourceLimitExceeded("some future limit").category(),
@@ -446,6 +623,7 @@ struct HandlerBox {
 /// Contains the AV1 codec parameters as signaled in the container.
 /// See AV1-ISOBMFF § 2.3.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AV1Config {
     /// AV1 seq_profile (0=Main, 1=High, 2=Professional)
     pub profile: u8,
@@ -463,6 +641,89 @@ pub struct AV1Config {
     pub chroma_subsampling_y: u8,
     /// Chroma sample position (0=unknown, 1=vertical, 2=colocated)
     pub chroma_sample_position: u8,
+    /// The `configOBUs`: sequence header and metadata OBUs stored in the box
+    /// after the fixed fields, in low-overhead format. Often empty.
+    pub config_obus: alloc::vec::Vec<u8>,
+}
+
+/// HEVC codec configuration from the `hvcC` property box of an `hvc1` or
+/// `hev1` item.
+///
+/// See ISO 14496-15 § 8.3.3.1.
+#[cfg(feature = "heif")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HevcConfig {
+    /// general_profile_space
+    pub profile_space: u8,
+    /// general_tier_flag (0=Main, 1=High)
+    pub tier: u8,
+    /// general_profile_idc (1=Main, 2=Main 10, 3=Main Still Picture, 4=Range extensions)
+    pub profile_idc: u8,
+    /// general_profile_compatibility_flags
+    pub profile_compatibility_flags: u32,
+    /// general_constraint_indicator_flags (48 bits)
+    pub constraint_indicator_flags: u64,
+    /// general_level_idc (30 times the level number)
+    pub level_idc: u8,
+    /// chroma_format_idc (0=monochrome, 1=4:2:0, 2=4:2:2, 3=4:4:4)
+    pub chroma_format: u8,
+    /// Luma bit depth
+    pub bit_depth_luma: u8,
+    /// Chroma bit depth
+    pub bit_depth_chroma: u8,
+    /// Size in bytes of the length prefix of each NAL unit in the item (1, 2 or 4)
+    pub length_size: u8,
+    /// The parameter set and SEI NAL units stored in the box.
+    pub nal_arrays: alloc::vec::Vec<HevcNalArray>,
+}
+
+/// NAL units of one type from an `hvcC` box, e.g. the VPS, SPS or PPS.
+#[cfg(feature = "heif")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HevcNalArray {
+    /// True if all NAL units of this type are here rather than in the bitstream
+    pub complete: bool,
+    /// nal_unit_type (32=VPS, 33=SPS, 34=PPS, 39/40=SEI)
+    pub nal_unit_type: u8,
+    /// The NAL units, without length prefixes
+    pub units: alloc::vec::Vec<alloc::vec::Vec<u8>>,
+}
+
+/// A field where the `av1C` property disagrees with the AV1 sequence header
+/// it describes. See [`AvifParser::verify_codec_config()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct CodecConfigMismatch {
+    /// The `av1C` field name, e.g. `"seq_level_idx_0"`.
+    pub field: &'static str,
+    /// The value in the `av1C` box.
+    pub av1c: u8,
+    /// The value the sequence header implies.
+    pub sequence_header: u8,
+}
+
+/// A requirement of an advertised AVIF profile brand that the file breaks,
+/// from [`AvifParser::profile_compliance()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ProfileViolation {
+    /// The brand, `*b"MA1B"`, `*b"MA1A"` or `*b"avio"`.
+    pub brand: [u8; 4],
+    /// The image that breaks it: `"primary"`, `"thumbnail"`, `"gain map"`,
+    /// `"depth"` or `"track"`.
+    pub image: &'static str,
+    /// What is out of bounds: `"seq_profile"`, `"seq_level_idx_0"`,
+    /// `"bit_depth"`, `"chroma_subsampling"` (as 420, 422 or 444) or
+    /// `"non_sync_samples"`.
+    pub field: &'static str,
+    /// The value found.
+    pub value: u32,
+    /// The largest value the brand allows.
+    pub limit: u32,
 }
 
 /// Colour information from the `colr` property box.
@@ -470,6 +731,7 @@ pub struct AV1Config {
 /// Can be either CICP-based (`nclx`) or an ICC profile (`rICC`/`prof`).
 /// See ISOBMFF § 12.1.5.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ColorInformation {
     /// CICP-based color information (colour_type = 'nclx')
     Nclx {
@@ -483,7 +745,70 @@ pub enum ColorInformation {
         full_range: bool,
     },
     /// ICC profile (colour_type = 'rICC' or 'prof')
-    IccProfile(std::vec::Vec<u8>),
+    IccProfile(alloc::vec::Vec<u8>),
+}
+
+/// A common color space identified from CICP values, from
+/// [`AvifParser::known_color_space()`].
+///
+/// Only the colour primaries and transfer characteristics are matched; the
+/// matrix coefficients and range describe how YUV maps to RGB, not the RGB
+/// color space itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum KnownColorSpace {
+    /// BT.709 primaries, sRGB transfer (1/13).
+    Srgb,
+    /// BT.709 primaries, linear transfer (1/8).
+    LinearSrgb,
+    /// BT.709 primaries and transfer, as in HD video (1/1).
+    Bt709,
+    /// Display P3: SMPTE EG 432-1 primaries, sRGB transfer (12/13).
+    DisplayP3,
+    /// Display P3 primaries with the PQ transfer (12/16).
+    DisplayP3Pq,
+    /// BT.2020 primaries with the BT.709-style SDR transfer (9/1, 9/14, 9/15).
+    Bt2020,
+    /// BT.2100 PQ: BT.2020 primaries, SMPTE ST 2084 transfer (9/16).
+    Bt2020Pq,
+    /// BT.2100 HLG: BT.2020 primaries, ARIB STD-B67 transfer (9/18).
+    Bt2020Hlg,
+}
+
+/// How the primary image carries high dynamic range, from
+/// [`AvifParser::hdr_kind()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum HdrKind {
+    /// Standard dynamic range.
+    Sdr,
+    /// SMPTE ST 2084 (PQ) transfer, as in HDR10.
+    Pq,
+    /// ARIB STD-B67 (HLG) transfer.
+    Hlg,
+    /// An SDR base image with a `tmap` gain map for HDR displays.
+    GainMap,
+}
+
+impl KnownColorSpace {
+    /// Identify `color_primaries` and `transfer_characteristics` (ITU-T
+    /// H.273 Tables 2 and 3), or `None` for any other combination.
+    pub fn from_cicp(color_primaries: u16, transfer_characteristics: u16) -> Option<Self> {
+        Some(match (color_primaries, transfer_characteristics) {
+            (1, 13) => Self::Srgb,
+            (1, 8) => Self::LinearSrgb,
+            // BT.601 and BT.2020 10/12-bit use the same curve as BT.709
+            (1, 1 | 6 | 14 | 15) => Self::Bt709,
+            (12, 13) => Self::DisplayP3,
+            (12, 16) => Self::DisplayP3Pq,
+            (9, 1 | 6 | 14 | 15) => Self::Bt2020,
+            (9, 16) => Self::Bt2020Pq,
+            (9, 18) => Self::Bt2020Hlg,
+            _ => return None,
+        })
+    }
 }
 
 /// Image dimensions declared by the container's `ispe` property.
@@ -491,6 +816,7 @@ pub enum ColorInformation {
 /// These values come only from the primary item's Image Spatial Extents box;
 /// they are not inferred from the AV1 bitstream.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ImageSpatialExtents {
     /// Width in pixels.
     pub width: u32,
@@ -503,6 +829,7 @@ pub struct ImageSpatialExtents {
 /// Specifies a counter-clockwise rotation to apply after decoding.
 /// See ISOBMFF § 12.1.4.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ImageRotation {
     /// Rotation angle in degrees counter-clockwise: 0, 90, 180, or 270.
     pub angle: u16,
@@ -513,18 +840,53 @@ pub struct ImageRotation {
 /// Specifies a mirror (flip) axis to apply after rotation.
 /// See ISOBMFF § 12.1.4.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ImageMirror {
     /// Mirror axis: 0 = top-to-bottom (vertical axis, left-right flip),
     /// 1 = left-to-right (horizontal axis, top-bottom flip).
     pub axis: u8,
 }
 
+/// Pixel information from the `pixi` property box.
+///
+/// Version 0 carries only the bit depth of each channel; version 1 adds
+/// [`channels`](Self::channels), what each channel holds and how it is
+/// sampled. See HEIF § 6.5.6.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct PixelInformation {
+    /// Bits per channel, one entry per channel.
+    pub bits_per_channel: alloc::vec::Vec<u8>,
+    /// Per-channel details, in the same order; empty for version 0.
+    pub channels: alloc::vec::Vec<ChannelInformation>,
+}
+
+/// One channel's entry in a version 1 `pixi` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ChannelInformation {
+    /// What the channel holds (`channel_idc`, 3 bits).
+    pub channel_idc: u8,
+    /// How samples are coded (`component_format`, 2 bits): 0 = unsigned
+    /// integer, 1 = floating point, 2 = complex.
+    pub component_format: u8,
+    /// `subsampling_type` (4 bits), if the channel is subsampled.
+    pub subsampling_type: Option<u8>,
+    /// `subsampling_location` (4 bits), if the channel is subsampled.
+    pub subsampling_location: Option<u8>,
+    /// `channel_label`, invalid UTF-8 replaced, if present.
+    pub label: Option<alloc::string::String>,
+}
+
 /// Clean aperture from the `clap` property box.
 ///
 /// Defines a crop rectangle as a centered region. All values are
 /// stored as exact rationals (numerator/denominator).
 /// See ISOBMFF § 12.1.4.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CleanAperture {
     /// Width of the clean aperture (numerator)
     pub width_n: u32,
@@ -544,11 +906,94 @@ pub struct CleanAperture {
     pub vert_off_d: u32,
 }
 
+impl CleanAperture {
+    /// The rectangle this aperture crops from a `width`×`height` image, or
+    /// `None` unless that is a whole-pixel rectangle inside the image
+    /// (MIAF § 7.3.6.7).
+    fn pixel_rect(&self, width: u32, height: u32) -> Option<Rect> {
+        let axis = |size: u32, n: u32, d: u32, off_n: i32, off_d: u32| -> Option<(u32, u32)> {
+            if d == 0 || off_d == 0 || !n.is_multiple_of(d) {
+                return None;
+            }
+            let crop = n / d;
+            if crop == 0 || crop > size {
+                return None;
+            }
+            // Centred at offset + (size - 1) / 2, so the first pixel is at
+            // offset + (size - crop) / 2
+            let num = (i64::from(size) - i64::from(crop))
+                .checked_mul(i64::from(off_d))?
+                .checked_add(2 * i64::from(off_n))?;
+            let den = 2 * i64::from(off_d);
+            if num % den != 0 {
+                return None;
+            }
+            let start = u32::try_from(num / den).ok()?;
+            (start.checked_add(crop)? <= size).then_some((start, crop))
+        };
+        let (x, crop_width) = axis(width, self.width_n, self.width_d, self.horiz_off_n, self.horiz_off_d)?;
+        let (y, crop_height) = axis(height, self.height_n, self.height_d, self.vert_off_n, self.vert_off_d)?;
+        Some(Rect { x, y, width: crop_width, height: crop_height })
+    }
+}
+
+/// A pixel rectangle, from [`Transform::crop`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Rect {
+    /// Left edge, in pixels from the left of the image.
+    pub x: u32,
+    /// Top edge, in pixels from the top of the image.
+    pub y: u32,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+/// The axis an image is mirrored about, from [`Transform::flip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Axis {
+    /// Mirrored about the vertical axis: left and right swap
+    /// (`imir` axis 0).
+    Vertical,
+    /// Mirrored about the horizontal axis: top and bottom swap
+    /// (`imir` axis 1).
+    Horizontal,
+}
+
+/// How to turn the decoded primary image into the displayed one, from
+/// [`AvifParser::display_transform()`].
+///
+/// Apply the steps in field order: crop to `crop`, rotate by `rotation`,
+/// then flip about `flip`. This is the order HEIF prescribes for `clap`,
+/// `irot` and `imir` (ISO/IEC 23008-12 § 6.5.1), whatever order the
+/// properties are listed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Transform {
+    /// The region of the decoded image to keep; the whole image without
+    /// `clap`.
+    pub crop: Rect,
+    /// Counter-clockwise rotation in degrees, applied to the cropped image:
+    /// 0, 90, 180 or 270.
+    pub rotation: u16,
+    /// Mirroring applied after the rotation, if any.
+    pub flip: Option<Axis>,
+    /// Width of the displayed image.
+    pub width: u32,
+    /// Height of the displayed image.
+    pub height: u32,
+}
+
 /// Pixel aspect ratio from the `pasp` property box.
 ///
 /// For AVIF, the spec requires this to be 1:1 if present.
 /// See ISOBMFF § 12.1.4.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PixelAspectRatio {
     /// Horizontal spacing
     pub h_spacing: u32,
@@ -561,6 +1006,7 @@ pub struct PixelAspectRatio {
 /// HDR metadata for display mapping.
 /// See ISOBMFF § 12.1.5 / ITU-T H.274.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ContentLightLevel {
     /// Maximum content light level (cd/m²)
     pub max_content_light_level: u16,
@@ -573,6 +1019,7 @@ pub struct ContentLightLevel {
 /// HDR metadata describing the mastering display's color volume.
 /// See ISOBMFF § 12.1.5 / SMPTE ST 2086.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MasteringDisplayColourVolume {
     /// Display primaries: [(x, y); 3] in 0.00002 units (CIE 1931)
     /// Order: green, blue, red (per SMPTE ST 2086)
@@ -591,6 +1038,7 @@ pub struct MasteringDisplayColourVolume {
 /// ITU-T H.274. All fields are optional, controlled by presence flags.
 /// See ISOBMFF § 12.1.5.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ContentColourVolume {
     /// Content colour primaries (x, y) for 3 primaries, as signed i32.
     /// Present only if `ccv_primaries_present_flag` was set.
@@ -608,6 +1056,7 @@ pub struct ContentColourVolume {
 /// Describes the ambient viewing conditions under which the content
 /// was authored. See ISOBMFF § 12.1.5 / H.265 D.2.39.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AmbientViewingEnvironment {
     /// Ambient illuminance in units of 1/10000 cd/m²
     pub ambient_illuminance: u32,
@@ -622,6 +1071,7 @@ pub struct AmbientViewingEnvironment {
 /// Each field is a rational number (numerator/denominator pair) describing
 /// how to apply the gain map for this channel.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GainMapChannel {
     /// Minimum gain map value (numerator).
     pub gain_map_min_n: i32,
@@ -653,6 +1103,7 @@ pub struct GainMapChannel {
 ///
 /// See ISO 21496-1:2025 for the full specification.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GainMapMetadata {
     /// If true, each RGB channel has independent gain map parameters.
     /// If false, `channels[0]` applies to all three channels.
@@ -704,9 +1155,9 @@ impl GainMapMetadata {
     /// `version` and `minimum_version` are always written as 0.
     /// Writer version is always emitted as `0` (we don't claim any
     /// extensions beyond the base ISO 21496-1 spec).
-    pub fn to_bytes(&self) -> std::vec::Vec<u8> {
+    pub fn to_bytes(&self) -> alloc::vec::Vec<u8> {
         let channel_count = if self.is_multichannel { 3usize } else { 1usize };
-        let mut buf = std::vec::Vec::with_capacity(6 + 16 + channel_count * 40);
+        let mut buf = alloc::vec::Vec::with_capacity(6 + 16 + channel_count * 40);
         buf.push(0u8); // version
         buf.extend_from_slice(&0u16.to_be_bytes()); // minimum_version
         buf.extend_from_slice(&0u16.to_be_bytes()); // writer_version (always 0)
@@ -835,7 +1286,7 @@ pub struct AvifGainMap {
     pub metadata: GainMapMetadata,
     /// Raw AV1 bitstream of the gain map image. Decode with an AV1 decoder
     /// to obtain the gain map pixel values.
-    pub gain_map_data: std::vec::Vec<u8>,
+    pub gain_map_data: alloc::vec::Vec<u8>,
     /// Color information for the alternate (typically HDR) rendition,
     /// from the `tmap` item's `colr` property.
     pub alt_color_info: Option<ColorInformation>,
@@ -866,7 +1317,7 @@ pub struct AvifGainMap {
 pub struct AvifDepthMap {
     /// Raw AV1 bitstream of the depth auxiliary image. Decode with an AV1
     /// decoder to obtain grayscale depth pixel values.
-    pub data: std::vec::Vec<u8>,
+    pub data: alloc::vec::Vec<u8>,
     /// Width of the depth image in pixels (from `ispe` property).
     pub width: u32,
     /// Height of the depth image in pixels (from `ispe` property).
@@ -882,6 +1333,7 @@ pub struct AvifDepthMap {
 /// Selects which AV1 operating point to decode for multi-operating-point images.
 /// See AVIF § 4.3.4.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OperatingPointSelector {
     /// Operating point index (0..31)
     pub op_index: u8,
@@ -892,6 +1344,7 @@ pub struct OperatingPointSelector {
 /// Selects which spatial layer to render for layered/progressive images.
 /// See HEIF (ISO 23008-12).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LayerSelector {
     /// Layer ID to render (0-3), or 0xFFFF for all layers (progressive)
     pub layer_id: u16,
@@ -903,15 +1356,33 @@ pub struct LayerSelector {
 /// to a specific layer without parsing the full bitstream.
 /// See AVIF § 4.3.6.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AV1LayeredImageIndexing {
     /// Byte sizes of layers 0, 1, 2. The last layer's size is implicit
     /// (total item size minus the sum of these three).
     pub layer_sizes: [u32; 3],
 }
 
+/// Human-readable description from a `udes` property box.
+///
+/// An item may carry one per language. Any field may be empty.
+/// See HEIF (ISO/IEC 23008-12) § 6.5.20.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UserDescription {
+    /// Language tag per RFC 5646 (e.g. `"en-US"`).
+    pub lang: alloc::string::String,
+    /// Name of the item, such as a title.
+    pub name: alloc::string::String,
+    /// Longer description of the item.
+    pub description: alloc::string::String,
+    /// Comma-separated user-defined tags.
+    pub tags: alloc::string::String,
+}
+
 /// Options for parsing AVIF files
 ///
-/// Prefer using [`DecodeConfig::lenient()`] with [`AvifParser`] instead.
+/// Prefer using [`DecodeConfig::with_strictness()`] with [`AvifParser`] instead.
 #[derive(Debug, Clone, Copy)]
 #[derive(Default)]
 pub struct ParseOptions {
@@ -925,6 +1396,217 @@ pub struct ParseOptions {
     pub lenient: bool,
 }
 
+/// How closely a file must follow the AVIF, MIAF and HEIF specifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Strictness {
+    /// Everything in [`Standard`](Self::Standard), plus rules real-world
+    /// encoders often get wrong: `av1C` must be marked essential, `ipma` must
+    /// not reference missing properties, and `ftyp` must list the HEIF
+    /// structural brand (`mif1` for `avif`, `msf1` for `avis`).
+    Pedantic,
+    /// Reject spec violations that affect how the file is read, including
+    /// essential-property rules for transformative properties.
+    #[default]
+    Standard,
+    /// Recover from common encoder bugs instead of failing: non-zero flags,
+    /// trailing bytes in boxes, essential-flag mistakes, a missing `pitm`,
+    /// alpha that does not match the color image, padding boxes before `ftyp`
+    /// and garbage after the last box.
+    /// Each one is recorded in [`AvifParser::warnings`].
+    Permissive,
+}
+
+/// A spec violation tolerated by [`Strictness::Permissive`] parsing.
+///
+/// Collected in [`AvifParser::warnings`] so permissive parsing can be audited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ParseWarning {
+    /// What was wrong.
+    pub kind: ParseWarningKind,
+    /// FourCC of the offending box or property.
+    pub fourcc: [u8; 4],
+    /// Absolute file offset of the offending box header.
+    pub offset: u64,
+}
+
+/// The kind of issue recorded in a [`ParseWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ParseWarningKind {
+    /// A full box whose flags must be zero has flags set.
+    NonZeroFlags,
+    /// A box has bytes left over after its last field.
+    TrailingBytes,
+    /// A property that must be marked essential in `ipma` is not.
+    PropertyNotEssential,
+    /// A property that must not be marked essential in `ipma` is.
+    PropertyWronglyEssential,
+    /// An unsupported property is marked essential; the item it belongs to
+    /// cannot be decoded correctly.
+    UnsupportedEssentialProperty,
+    /// The `meta` box has no `pitm`; the first top-level image item was used
+    /// as the primary item.
+    MissingPrimaryItem,
+    /// The alpha image differs from the color image in size (`ispe`,
+    /// `tkhd`), bit depth (`av1C`, `pixi`) or frame count (`stsz`). These
+    /// are compared after parsing, so the offset is 0.
+    AlphaMismatch,
+    /// A `free`, `skip` or `wide` box precedes `ftyp`; it was skipped.
+    BoxBeforeFileType,
+    /// The bytes after the structural boxes do not form a top-level box: the
+    /// header is malformed, or a box other than `mdat` runs past the end of
+    /// the input. Parsing stopped there; `fourcc` holds whatever bytes sit
+    /// where the box type would be.
+    TrailingData,
+    /// File bytes are read by more than one item or track sample (`fourcc`
+    /// is `iloc` or `trak`), at `offset`. Only reported, in any strictness
+    /// mode, with [`DecodeConfig::detect_overlapping_extents`].
+    OverlappingExtents,
+    /// The `sato` sample transform item is malformed or uses an unknown
+    /// token. It was ignored, in any strictness mode, and the primary item
+    /// is shown as its alternative. The item is read after parsing, so the
+    /// offset is 0.
+    MalformedSampleTransform,
+    /// The `thmb` thumbnail item cannot be located (e.g. it has no `iloc`
+    /// entry). It was ignored, in any strictness mode, and the file has no
+    /// thumbnail. The item is resolved after parsing, so the offset is 0.
+    MalformedThumbnail,
+}
+
+/// Strictness state threaded through the box readers.
+struct ParseContext {
+    strictness: Strictness,
+    max_item_count: Option<u32>,
+    max_extents_per_item: Option<u32>,
+    max_animation_frames: Option<u32>,
+    warnings: core::cell::RefCell<TryVec<ParseWarning>>,
+}
+
+impl ParseContext {
+    fn new(config: &DecodeConfig) -> Self {
+        Self {
+            strictness: config.effective_strictness(),
+            max_item_count: config.max_item_count,
+            max_extents_per_item: config.max_extents_per_item,
+            max_animation_frames: config.max_animation_frames,
+            warnings: core::cell::RefCell::new(TryVec::new()),
+        }
+    }
+
+    /// Fail if `count` items, declared or seen in `iinf`/`iloc`, exceed `max_item_count`.
+    fn check_item_count(&self, count: u64) -> Result<()> {
+        match self.max_item_count {
+            Some(limit) if count > u64::from(limit) => Err(at!(Error::ResourceLimitExceeded("item count limit exceeded"))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if a track's sample count exceeds `max_animation_frames`.
+    fn check_sample_count(&self, count: usize) -> Result<()> {
+        match self.max_animation_frames {
+            Some(limit) if count.to_u64() > u64::from(limit) => {
+                Err(at!(Error::ResourceLimitExceeded("animation frame count limit exceeded")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// In permissive mode record a warning, otherwise fail with `err`.
+    fn tolerate(&self, kind: ParseWarningKind, fourcc: &FourCC, offset: u64, err: At<Error>) -> Result<()> {
+        if self.strictness != Strictness::Permissive {
+            return Err(err);
+        }
+        let warning = ParseWarning { kind, fourcc: fourcc.value, offset };
+        self.warnings.borrow_mut().push(warning).map_err(|e| at!(Error::from(e)))
+    }
+
+    /// Fail with `err` in pedantic mode only.
+    fn pedantic(&self, err: impl FnOnce() -> At<Error>) -> Result<()> {
+        if self.strictness == Strictness::Pedantic {
+            return Err(err());
+        }
+        Ok(())
+    }
+
+    fn into_warnings(self) -> TryVec<ParseWarning> {
+        self.warnings.into_inner()
+    }
+}
+
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::time::Instant;
+
+/// There is no clock without `std`, and `std::time::Instant` panics on
+/// `wasm32-unknown-unknown`, so no `Instant` can exist there.
+#[cfg(not(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown")))))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Instant {}
+
+#[cfg(not(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown")))))]
+impl Instant {
+    fn checked_add(&self, _: core::time::Duration) -> Option<Self> {
+        match *self {}
+    }
+
+    fn elapsed(&self) -> core::time::Duration {
+        match *self {}
+    }
+}
+
+/// The current time, where there is a clock to read it from.
+fn now() -> Option<Instant> {
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    return Some(Instant::now());
+    #[cfg(not(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown")))))]
+    return None;
+}
+
+/// The caller's [`Stop`] combined with [`DecodeConfig::time_budget`].
+struct TimeBudget<'a> {
+    inner: &'a dyn Stop,
+    deadline: Option<Instant>,
+}
+
+impl<'a> TimeBudget<'a> {
+    fn new(inner: &'a dyn Stop, budget: Option<core::time::Duration>) -> Result<Self> {
+        let deadline = match budget {
+            Some(budget) => {
+                let now = now().ok_or_else(|| at!(Error::Unsupported("time_budget needs a clock, which this target lacks")))?;
+                now.checked_add(budget)
+            },
+            None => None,
+        };
+        Ok(Self { inner, deadline })
+    }
+
+    fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| now().is_some_and(|now| now >= deadline))
+    }
+
+    /// Report a stop caused by the budget as a resource limit, not a caller request.
+    fn classify(&self, e: At<Error>) -> At<Error> {
+        let timed_out = matches!(e.error(), Error::Stopped(StopReason::TimedOut));
+        if timed_out && self.is_expired() && self.inner.check().is_ok() {
+            return at!(Error::ResourceLimitExceeded("time budget exceeded"));
+        }
+        e
+    }
+}
+
+impl Stop for TimeBudget<'_> {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        self.inner.check()?;
+        if self.is_expired() {
+            return Err(StopReason::TimedOut);
+        }
+        Ok(())
+    }
+}
+
 /// Configuration for parsing AVIF files with resource limits and validation options
 ///
 /// Provides fine-grained control over resource consumption during AVIF parsing,
@@ -956,6 +1638,12 @@ pub struct DecodeConfig {
     /// Default: 1GB (1,000,000,000 bytes)
     pub peak_memory_limit: Option<u64>,
 
+    /// Maximum number of bytes read from a reader
+    /// ([`AvifParser::from_reader`], the async and push parsers) before
+    /// parsing starts. Reading also stops at `peak_memory_limit`.
+    /// Default: None (only `peak_memory_limit` applies)
+    pub max_input_bytes: Option<u64>,
+
     /// Maximum total megapixels for grid images.
     /// Default: 512 megapixels
     pub total_megapixels_limit: Option<u32>,
@@ -968,19 +1656,167 @@ pub struct DecodeConfig {
     /// Default: 1,000 tiles
     pub max_grid_tiles: Option<u32>,
 
-    /// Enable lenient parsing mode.
-    /// Default: false (strict validation)
+    /// Maximum nesting depth for items built from other items
+    /// (`iloc` construction_method 2, "item_offset").
+    /// Default: 8 levels
+    pub max_item_construction_depth: Option<u32>,
+
+    /// Maximum nesting depth of boxes, counting top-level boxes as 1.
+    /// Valid files stay below 10 (e.g. `moov/trak/mdia/minf/stbl/stsd/av01/av1C`).
+    /// Default: 32 levels
+    pub max_box_depth: Option<u32>,
+
+    /// Maximum number of box headers read, at every nesting level, so that
+    /// a file packed with tiny boxes cannot make parsing time grow with its
+    /// size. Boxes whose content is skipped count; their children do not.
+    /// Default: 100,000 boxes
+    pub max_total_boxes: Option<u64>,
+
+    /// Maximum number of items declared in `iinf` or `iloc`.
+    /// Default: 10,000 items
+    pub max_item_count: Option<u32>,
+
+    /// Maximum number of `iloc` extents for a single item, and for all
+    /// `item_offset` constructions together once flattened.
+    /// Default: 1,000 extents
+    pub max_extents_per_item: Option<u32>,
+
+    /// Wall-clock time allowed for parsing, measured from the start of each
+    /// parse. When it runs out, parsing stops at the next cancellation check
+    /// with `ResourceLimitExceeded("time budget exceeded")`. Reading the
+    /// input (e.g. in [`AvifParser::from_reader`]) does not count.
+    /// Default: None (no time limit)
+    ///
+    /// Not supported without the `std` feature or on
+    /// `wasm32-unknown-unknown`, which have no clock: parsing fails with
+    /// [`Error::Unsupported`] if this is set.
+    pub time_budget: Option<core::time::Duration>,
+
+    /// How closely files must follow the specifications.
+    /// Default: [`Strictness::Standard`]
+    pub strictness: Strictness,
+
+    /// Parse as [`Strictness::Permissive`] regardless of `strictness`.
+    /// Default: false
+    #[deprecated(since = "0.7.0", note = "Use `strictness` / `with_strictness(Strictness::Permissive)` instead")]
     pub lenient: bool,
+
+    /// Replace the primary item with the first supported entry of its `altr`
+    /// alternative group, if it belongs to one.
+    /// Default: false (the `pitm` item is used as-is)
+    pub select_alternative: bool,
+
+    /// Accept input that ends before its last top-level box does, e.g. a
+    /// partial download. The parser reports [`AvifParser::is_complete`] as
+    /// false and data accessors whose extents lie past the end return
+    /// [`Error::DataNotAvailable`]. `meta` and `moov` must still be complete,
+    /// as must derived-image payloads (`iovl`, `tmap`) needed while parsing.
+    /// Default: false (truncated top-level boxes are an error)
+    pub allow_truncated: bool,
+
+    /// Lay out a grid whose rows and columns are unknown (no ImageGrid
+    /// descriptor, and `ispe`s that are missing or do not divide evenly) as
+    /// a single column of tiles with output dimensions 0. The guess is
+    /// usually wrong and the composed image scrambled.
+    /// Default: false (such grids are an error naming the missing input)
+    pub allow_grid_fallback: bool,
+
+    /// Check every item's `iloc` extents while parsing instead of when the
+    /// item is read: extents must lie within an `mdat` (or `idat`), and no
+    /// two may partly overlap. Items sharing exactly the same bytes are
+    /// allowed. Failures are [`Error::InvalidData`].
+    /// Default: false
+    pub validate_extents: bool,
+
+    /// Report file bytes read by more than one item or track sample as
+    /// [`ParseWarningKind::OverlappingExtents`] warnings, whatever the
+    /// strictness. Aliased bytes can make a file show different content in
+    /// different readers. An item that is exactly one track sample is not
+    /// reported: libavif stores an animation's still image that way.
+    /// Default: false
+    pub detect_overlapping_extents: bool,
+
+    /// Read animation frames from the track with this `tkhd` track ID
+    /// instead of the first `pict` track; see [`AvifParser::tracks`]. Its
+    /// alpha is the track whose `auxl` reference names it, if any. Parsing
+    /// fails if no track has this ID.
+    /// Default: None (the first `pict` track, else the first non-audio one)
+    pub track_id: Option<u32>,
+
+    /// Receives the unknown and `uuid` boxes the parser skips, at any
+    /// nesting level; see [`BoxHandler`].
+    /// Default: None
+    pub box_handler: Option<alloc::sync::Arc<dyn BoxHandler>>,
+
+    /// Record the byte range of every box read, for writers that copy boxes
+    /// through verbatim; see [`AvifParser::layout`]. Not used by `read_avif`.
+    /// Default: false
+    pub preserve_layout: bool,
+}
+
+/// Callback for boxes the parser does not understand, set with
+/// [`DecodeConfig::with_box_handler`].
+///
+/// ```rust
+/// use std::io::Read;
+/// use zenavif_parse::{BoxHandler, DecodeConfig};
+///
+/// #[derive(Default)]
+/// struct Collect(std::sync::Mutex<Vec<(Vec<[u8; 4]>, Vec<u8>)>>);
+///
+/// impl BoxHandler for Collect {
+///     fn unknown_box(&self, path: &[[u8; 4]], _uuid: Option<&[u8; 16]>, payload: &mut dyn Read) -> std::io::Result<()> {
+///         let mut data = Vec::new();
+///         payload.read_to_end(&mut data)?;
+///         self.0.lock().unwrap().push((path.to_vec(), data));
+///         Ok(())
+///     }
+/// }
+///
+/// let config = DecodeConfig::default().with_box_handler(Collect::default());
+/// ```
+pub trait BoxHandler: Send + Sync {
+    /// Called before an unknown or `uuid` box is skipped.
+    ///
+    /// `path` lists the box types from the top level down to this box
+    /// (e.g. `[b"meta", b"iprp", b"ipco", b"abcd"]`), `uuid` is the
+    /// extended type of `uuid` boxes, and `payload` reads the box content
+    /// after the header. Whatever is left unread is skipped. An error
+    /// stops parsing.
+    fn unknown_box(&self, path: &[[u8; 4]], uuid: Option<&[u8; 16]>, payload: &mut dyn Read) -> io::Result<()>;
+}
+
+impl core::fmt::Debug for dyn BoxHandler {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("BoxHandler")
+    }
 }
 
 impl Default for DecodeConfig {
     fn default() -> Self {
         Self {
             peak_memory_limit: Some(1_000_000_000),
+            max_input_bytes: None,
             total_megapixels_limit: Some(512),
             max_animation_frames: Some(10_000),
             max_grid_tiles: Some(1_000),
+            max_item_construction_depth: Some(8),
+            max_box_depth: Some(32),
+            max_total_boxes: Some(100_000),
+            max_item_count: Some(10_000),
+            max_extents_per_item: Some(1_000),
+            time_budget: None,
+            strictness: Strictness::Standard,
+            #[allow(deprecated)]
             lenient: false,
+            select_alternative: false,
+            allow_truncated: false,
+            allow_grid_fallback: false,
+            validate_extents: false,
+            detect_overlapping_extents: false,
+            track_id: None,
+            box_handler: None,
+            preserve_layout: false,
         }
     }
 }
@@ -992,10 +1828,27 @@ impl DecodeConfig {
     pub fn unlimited() -> Self {
         Self {
             peak_memory_limit: None,
+            max_input_bytes: None,
             total_megapixels_limit: None,
             max_animation_frames: None,
             max_grid_tiles: None,
+            max_item_construction_depth: None,
+            max_box_depth: None,
+            max_total_boxes: None,
+            max_item_count: None,
+            max_extents_per_item: None,
+            time_budget: None,
+            strictness: Strictness::Standard,
+            #[allow(deprecated)]
             lenient: false,
+            select_alternative: false,
+            allow_truncated: false,
+            allow_grid_fallback: false,
+            validate_extents: false,
+            detect_overlapping_extents: false,
+            track_id: None,
+            box_handler: None,
+            preserve_layout: false,
         }
     }
 
@@ -1005,6 +1858,12 @@ impl DecodeConfig {
         self
     }
 
+    /// Set the maximum number of bytes read from a reader
+    pub fn with_max_input_bytes(mut self, bytes: u64) -> Self {
+        self.max_input_bytes = Some(bytes);
+        self
+    }
+
     /// Set the total megapixels limit for grid images
     pub fn with_total_megapixels_limit(mut self, megapixels: u32) -> Self {
         self.total_megapixels_limit = Some(megapixels);
@@ -1023,15 +1882,130 @@ impl DecodeConfig {
         self
     }
 
-    /// Enable lenient parsing mode
-    pub fn lenient(mut self, lenient: bool) -> Self {
-        self.lenient = lenient;
+    /// Set the maximum nesting depth for item-constructed items
+    pub fn with_max_item_construction_depth(mut self, depth: u32) -> Self {
+        self.max_item_construction_depth = Some(depth);
+        self
+    }
+
+    /// Set the maximum box nesting depth
+    pub fn with_max_box_depth(mut self, depth: u32) -> Self {
+        self.max_box_depth = Some(depth);
+        self
+    }
+
+    /// Set the maximum number of boxes read
+    pub fn with_max_total_boxes(mut self, boxes: u64) -> Self {
+        self.max_total_boxes = Some(boxes);
+        self
+    }
+
+    /// Set the maximum item count
+    pub fn with_max_item_count(mut self, items: u32) -> Self {
+        self.max_item_count = Some(items);
+        self
+    }
+
+    /// Set the maximum extent count per item
+    pub fn with_max_extents_per_item(mut self, extents: u32) -> Self {
+        self.max_extents_per_item = Some(extents);
+        self
+    }
+
+    /// Set the wall-clock time budget for parsing
+    pub fn with_time_budget(mut self, budget: core::time::Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Set how closely files must follow the specifications
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Enable lenient parsing mode: [`Strictness::Permissive`] when true,
+    /// [`Strictness::Standard`] when false
+    #[deprecated(since = "0.7.0", note = "Use `with_strictness()` instead")]
+    pub fn lenient(self, lenient: bool) -> Self {
+        self.with_strictness(if lenient { Strictness::Permissive } else { Strictness::Standard })
+    }
+
+    /// Reject input from a reader once `len` bytes exceed
+    /// `max_input_bytes` or `peak_memory_limit`.
+    fn check_input_len(&self, len: u64) -> Result<()> {
+        if self.max_input_bytes.is_some_and(|limit| len > limit) {
+            return Err(at!(Error::ResourceLimitExceeded("input exceeds max_input_bytes")));
+        }
+        if self.peak_memory_limit.is_some_and(|limit| len > limit) {
+            return Err(at!(Error::ResourceLimitExceeded("input exceeds peak_memory_limit")));
+        }
+        Ok(())
+    }
+
+    /// `strictness`, overridden by the deprecated `lenient` flag.
+    fn effective_strictness(&self) -> Strictness {
+        #[allow(deprecated)]
+        let lenient = self.lenient;
+        if lenient { Strictness::Permissive } else { self.strictness }
+    }
+
+    /// Use the first supported `altr` alternative of the primary item as the primary item
+    pub fn with_select_alternative(mut self, select: bool) -> Self {
+        self.select_alternative = select;
+        self
+    }
+
+    /// Accept truncated input (see [`DecodeConfig::allow_truncated`])
+    pub fn allow_truncated(mut self, allow: bool) -> Self {
+        self.allow_truncated = allow;
+        self
+    }
+
+    /// Guess the layout of grids that do not describe it (see
+    /// [`DecodeConfig::allow_grid_fallback`])
+    pub fn allow_grid_fallback(mut self, allow: bool) -> Self {
+        self.allow_grid_fallback = allow;
+        self
+    }
+
+    /// Check item extents while parsing (see
+    /// [`DecodeConfig::validate_extents`])
+    pub fn validate_extents(mut self, validate: bool) -> Self {
+        self.validate_extents = validate;
+        self
+    }
+
+    /// Report bytes shared between items and tracks (see
+    /// [`DecodeConfig::detect_overlapping_extents`])
+    pub fn detect_overlapping_extents(mut self, detect: bool) -> Self {
+        self.detect_overlapping_extents = detect;
+        self
+    }
+
+    /// Read animation frames from the track with this ID (see
+    /// [`DecodeConfig::track_id`])
+    pub fn with_track(mut self, track_id: u32) -> Self {
+        self.track_id = Some(track_id);
+        self
+    }
+
+    /// Pass the unknown and `uuid` boxes the parser skips to `handler`
+    pub fn with_box_handler(mut self, handler: impl BoxHandler + 'static) -> Self {
+        self.box_handler = Some(alloc::sync::Arc::new(handler));
+        self
+    }
+
+    /// Record where every box is (see [`DecodeConfig::preserve_layout`])
+    pub fn preserve_layout(mut self, preserve: bool) -> Self {
+        self.preserve_layout = preserve;
         self
     }
 }
 
 /// Grid configuration for tiled/grid-based AVIF images
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// Grid image configuration
 ///
 /// For tiled/grid AVIF images, this describes the grid layout.
@@ -1039,9 +2013,11 @@ impl DecodeConfig {
 ///
 /// ## Grid Layout Determination
 ///
-/// Grid layout can be specified in two ways:
+/// Grid layout can be specified in three ways:
 /// 1. **Explicit ImageGrid property box** - contains rows, columns, and output dimensions
-/// 2. **Calculated from ispe properties** - when no ImageGrid box exists, dimensions are
+/// 2. **The grid item's ImageGrid payload** - the same fields, as the spec stores them
+///    ([`AvifParser`] only; edge tiles may be cropped, see [`AvifParser::tile_layout()`])
+/// 3. **Calculated from ispe properties** - when neither is usable, dimensions are
 ///    calculated by dividing the grid item's dimensions by a tile's dimensions
 ///
 /// ## Output Dimensions
@@ -1051,58 +2027,265 @@ impl DecodeConfig {
 /// - When non-zero, they specify the exact output dimensions of the composed image
 pub struct GridConfig {
     /// Number of tile rows (1-256)
-    pub rows: u8,
+    pub rows: u16,
     /// Number of tile columns (1-256)
-    pub columns: u8,
+    pub columns: u16,
     /// Output width in pixels (0 = calculate from tiles)
     pub output_width: u32,
     /// Output height in pixels (0 = calculate from tiles)
     pub output_height: u32,
 }
 
-/// Frame information for animated AVIF
-#[cfg(feature = "eager")]
-#[deprecated(since = "1.5.0", note = "Use `AvifParser::frame()` which returns `FrameRef` instead")]
-#[derive(Debug)]
-pub struct AnimationFrame {
-    /// AV1 bitstream data for this frame
-    pub data: TryVec<u8>,
-    /// Duration in milliseconds (0 if unknown)
-    pub duration_ms: u32,
-}
-
-/// Animation configuration for animated AVIF (avis brand)
-#[cfg(feature = "eager")]
-#[deprecated(since = "1.5.0", note = "Use `AvifParser::animation_info()` and `AvifParser::frames()` instead")]
-#[derive(Debug)]
-#[allow(deprecated)]
-pub struct AnimationConfig {
-    /// Number of times to loop (0 = infinite)
-    pub loop_count: u32,
-    /// All frames in the animation
-    pub frames: TryVec<AnimationFrame>,
+/// Where a grid tile goes in the output image, from [`AvifParser::tile_layout()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct TilePlacement {
+    /// Row of the tile, from 0.
+    pub row: u32,
+    /// Column of the tile, from 0.
+    pub column: u32,
+    /// Left edge in output pixels.
+    pub x: u32,
+    /// Top edge in output pixels.
+    pub y: u32,
+    /// Visible width: the tile width, less what the right edge of the output crops off.
+    pub width: u32,
+    /// Visible height: the tile height, less what the bottom edge of the output crops off.
+    pub height: u32,
+}
+
+/// Overlay derived image configuration, from the payload of an `iovl` item.
+///
+/// An overlay composes its input images (the `dimg` references of the overlay
+/// item, in reference order) onto a canvas of `output_width` × `output_height`
+/// pre-filled with `canvas_fill_value`. Inputs are drawn in order, so later
+/// inputs cover earlier ones. See ISO 23008-12:2017 § 6.6.2.3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OverlayConfig {
+    /// Canvas fill colour as 16-bit R, G, B, A values
+    pub canvas_fill_value: [u16; 4],
+    /// Canvas width in pixels
+    pub output_width: u32,
+    /// Canvas height in pixels
+    pub output_height: u32,
+    /// Top-left position `(horizontal, vertical)` of each input on the canvas, in
+    /// input order. Offsets may be negative or place inputs partly off-canvas.
+    pub offsets: alloc::vec::Vec<(i32, i32)>,
+}
+
+/// An alternative group (`altr`) from the `grpl` box.
+///
+/// The entities are alternatives of each other (e.g. an HDR and an SDR rendition), listed
+/// in the file's order of preference; a reader should use the first one it can process.
+/// See ISO 14496-12:2015 § 8.18.3.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AlternativeGroup {
+    /// The group's `group_id`.
+    pub group_id: u32,
+    /// Item (or track) IDs of the alternatives, in order of preference.
+    pub entity_ids: alloc::vec::Vec<u32>,
+}
+
+/// A sample transform derived image item (`sato`), from
+/// [`AvifParser::sample_transform()`].
+///
+/// Each output sample is computed from the samples at the same position
+/// in the input images by evaluating `tokens`, a postfix expression, in
+/// signed `bit_depth`-bit arithmetic; AVIF 1.2 uses it to store images
+/// deeper than 12 bits as several AV1 images. See AVIF § 4.2.3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SampleTransform {
+    /// The `sato` item's `item_ID`.
+    pub item_id: u32,
+    /// Precision of the intermediate values: 8, 16, 32 or 64 bits.
+    pub bit_depth: u8,
+    /// The expression, in postfix order. It is checked to leave exactly
+    /// one value.
+    pub tokens: alloc::vec::Vec<SampleTransformToken>,
+    /// The input image items (`dimg` references), in order.
+    pub input_item_ids: alloc::vec::Vec<u32>,
+}
+
+/// One token of a [`SampleTransform`] expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum SampleTransformToken {
+    /// Push a constant.
+    Constant(i64),
+    /// Push the sample of input image `n`, an index into
+    /// [`SampleTransform::input_item_ids`].
+    Input(u8),
+    /// Replace the top value `a` with `-a`.
+    Negation,
+    /// Replace the top value `a` with `|a|`.
+    Absolute,
+    /// Replace the top value `a` with its bitwise complement.
+    Not,
+    /// Replace the top value `a` with the index of its highest set bit
+    /// (0 if `a` ≤ 0).
+    BitScanReverse,
+    /// Pop `b`, then `a`, and push `a + b`.
+    Sum,
+    /// Pop `b`, then `a`, and push `a - b`.
+    Difference,
+    /// Pop `b`, then `a`, and push `a * b`.
+    Product,
+    /// Pop `b`, then `a`, and push `a / b` (`a` if `b` is 0).
+    Quotient,
+    /// Pop `b`, then `a`, and push `a & b`.
+    And,
+    /// Pop `b`, then `a`, and push `a | b`.
+    Or,
+    /// Pop `b`, then `a`, and push `a ^ b`.
+    Xor,
+    /// Pop `b`, then `a`, and push `a` to the power `b`.
+    Pow,
+    /// Pop `b`, then `a`, and push the smaller.
+    Min,
+    /// Pop `b`, then `a`, and push the larger.
+    Max,
+}
+
+/// A region item (`rgan`), from [`AvifParser::regions()`]: areas of the
+/// images it describes, such as detected faces.
+///
+/// Coordinates are in a `reference_width` × `reference_height` space that
+/// is stretched over each described image. See ISO 23008-12:2022 § 6.10.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RegionItem {
+    /// The region item's `item_ID`.
+    pub item_id: u32,
+    /// Width of the coordinate space.
+    pub reference_width: u32,
+    /// Height of the coordinate space.
+    pub reference_height: u32,
+    /// The regions, in item order.
+    pub regions: alloc::vec::Vec<Region>,
+    /// Items the regions apply to (the item's `cdsc` references).
+    pub described_items: alloc::vec::Vec<u32>,
+}
+
+/// An auxiliary image item, one with an `auxC` property, from
+/// [`AvifParser::auxiliary_items()`].
+///
+/// Besides alpha (`urn:mpeg:mpegB:cicp:systems:auxiliary:alpha`), the type
+/// identifies depth and disparity maps and vendor-specific images such as
+/// Apple's HDR gain map (`urn:com:apple:photo:2020:aux:hdrgainmap`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct AuxiliaryItem {
+    /// The auxiliary item's `item_ID`.
+    pub item_id: u32,
+    /// The `aux_type` URN, invalid UTF-8 replaced.
+    pub aux_type: alloc::string::String,
+    /// The `aux_subtype` bytes after the URN's null terminator; their
+    /// meaning depends on `aux_type`.
+    pub aux_subtype: alloc::vec::Vec<u8>,
+    /// Items this is an auxiliary image of (the item's `auxl` references).
+    pub auxiliary_for: alloc::vec::Vec<u32>,
+}
+
+/// The geometry of one region of a [`RegionItem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum Region {
+    /// A single point.
+    Point { x: i32, y: i32 },
+    /// A rectangle with its top-left corner at `(x, y)`.
+    Rectangle { x: i32, y: i32, width: u32, height: u32 },
+    /// An ellipse centered on `(x, y)`.
+    Ellipse { x: i32, y: i32, radius_x: u32, radius_y: u32 },
+    /// A closed polygon.
+    Polygon { points: alloc::vec::Vec<(i32, i32)> },
+    /// An open line through the points.
+    Polyline { points: alloc::vec::Vec<(i32, i32)> },
+    /// A mask from a `mask` item the region item references, placed in the
+    /// rectangle with its top-left corner at `(x, y)`.
+    ReferencedMask { x: i32, y: i32, width: u32, height: u32 },
+    /// A mask stored in the region item, placed like
+    /// [`Region::ReferencedMask`]. Only its placement is reported.
+    InlineMask { x: i32, y: i32, width: u32, height: u32 },
+}
+
+/// Frame information for animated AVIF
+#[cfg(feature = "eager")]
+#[deprecated(since = "1.5.0", note = "Use `AvifParser::frame()` which returns `FrameRef` instead")]
+#[derive(Debug)]
+pub struct AnimationFrame {
+    /// AV1 bitstream data for this frame
+    pub data: TryVec<u8>,
+    /// Duration in milliseconds (0 if unknown)
+    pub duration_ms: u32,
+}
+
+/// Animation configuration for animated AVIF (avis brand)
+#[cfg(feature = "eager")]
+#[deprecated(since = "1.5.0", note = "Use `AvifParser::animation_info()` and `AvifParser::frames()` instead")]
+#[derive(Debug)]
+#[allow(deprecated)]
+pub struct AnimationConfig {
+    /// Number of times to loop (0 = infinite)
+    pub loop_count: u32,
+    /// All frames in the animation
+    pub frames: TryVec<AnimationFrame>,
 }
 
 // Internal structures for animation parsing
 
 #[derive(Debug)]
 struct MovieHeader {
-    _timescale: u32,
+    timescale: u32,
     _duration: u64,
 }
 
+#[derive(Debug)]
+struct TrackHeader {
+    track_id: u32,
+    /// Integer part of the 16.16 fixed-point presentation size.
+    width: u32,
+    height: u32,
+}
+
 #[derive(Debug)]
 struct MediaHeader {
     timescale: u32,
     _duration: u64,
 }
 
+/// The first non-empty entry of an edit list (`elst`).
+#[derive(Debug, Clone, Copy)]
+struct EditListEntry {
+    /// Length of the edit in movie (`mvhd`) timescale units; 0 or all ones
+    /// when unbounded.
+    segment_duration: u64,
+    /// Start of the edit in media timescale units.
+    media_time: u64,
+}
+
 #[derive(Debug)]
 struct TimeToSampleEntry {
     sample_count: u32,
     sample_delta: u32,
 }
 
+/// A run of samples sharing one composition offset, from `ctts`.
+#[derive(Debug)]
+struct CompositionOffsetEntry {
+    sample_count: u32,
+    /// Composition time minus decode time, in media timescale units.
+    sample_offset: i64,
+}
+
 #[derive(Debug)]
 struct SampleToChunkEntry {
     first_chunk: u32,
@@ -1145,11 +2328,92 @@ impl SampleSizes {
 
 #[derive(Debug)]
 struct SampleTable {
-    time_to_sample: TryVec<TimeToSampleEntry>,
     sample_sizes: SampleSizes,
     /// Precomputed byte offset for each sample, derived from
     /// sample_to_chunk + chunk_offsets + sample_sizes during parsing.
     sample_offsets: TryVec<u64>,
+    /// 1-based numbers of the sync samples from `stss`, ascending.
+    /// `None` when the box is absent: every sample is a sync sample.
+    sync_samples: Option<TryVec<u32>>,
+    /// Sample indices sorted by presentation time, for tracks with `ctts`
+    /// whose presentation order may differ from decode order. Empty otherwise.
+    presentation_order: TryVec<u32>,
+    /// Precomputed timing and sync flag for each sample, derived from
+    /// `stts` + `ctts` + sync_samples during parsing.
+    sample_times: TryVec<SampleTime>,
+    /// Sum of the `stts` deltas of all samples.
+    media_duration: u64,
+}
+
+/// Flattened per-sample timing, see [`precompute_sample_times`].
+#[derive(Debug, Clone, Copy)]
+struct SampleTime {
+    /// Decode time plus composition offset, clamped at zero.
+    presentation_time: u64,
+    /// The `stts` delta; 0 for samples past the end of the table.
+    duration: u32,
+    is_sync: bool,
+}
+
+impl SampleTable {
+    /// Whether decoding can start at sample `index` (0-based).
+    fn is_sync(&self, index: usize) -> bool {
+        self.sample_times.get(index).is_some_and(|t| t.is_sync)
+    }
+
+    /// The `stts` delta of sample `index`, if the table covers it.
+    fn sample_delta(&self, index: usize) -> Option<u32> {
+        self.sample_times.get(index).map(|t| t.duration)
+    }
+
+    /// Presentation time of sample `index` in media timescale units: decode
+    /// time plus composition offset, clamped at zero.
+    fn presentation_time(&self, index: usize) -> u64 {
+        self.sample_times.get(index).map_or(0, |t| t.presentation_time)
+    }
+
+    /// The sample displayed at `ticks`: the one with the latest presentation
+    /// time not after it, else the earliest. The table must not be empty.
+    fn sample_at(&self, ticks: u64) -> usize {
+        if !self.presentation_order.is_empty() {
+            // Sorted by (time, index), so ties resolve to the last sample in decode order
+            let order = &self.presentation_order;
+            let after = order.partition_point(|&i| self.presentation_time(i as usize) <= ticks);
+            return order.get(after.saturating_sub(1)).map_or(0, |&i| i as usize);
+        }
+
+        // Decode times ascend
+        self.sample_times.partition_point(|t| t.presentation_time <= ticks).saturating_sub(1)
+    }
+
+    /// The nearest sync sample at or before `index`; sample 0 when none is.
+    fn sync_at_or_before(&self, index: usize) -> usize {
+        let Some(sync) = &self.sync_samples else {
+            return index;
+        };
+        let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        match sync.partition_point(|&n| n <= number) {
+            0 => 0,
+            after => sync.get(after - 1).map_or(0, |&n| (n as usize).saturating_sub(1)),
+        }
+    }
+
+    /// Presentation time of sample `index` in milliseconds.
+    fn presentation_time_ms(&self, index: usize, timescale: u32) -> u64 {
+        if timescale == 0 {
+            return 0;
+        }
+        u64::try_from(u128::from(self.presentation_time(index)) * 1000 / u128::from(timescale)).unwrap_or(u64::MAX)
+    }
+}
+
+/// `time` shifted by a signed `ctts` offset, clamped to the `u64` range.
+fn apply_composition_offset(time: u64, offset: i64) -> u64 {
+    if offset < 0 {
+        time.saturating_sub(offset.unsigned_abs())
+    } else {
+        time.saturating_add(offset.unsigned_abs())
+    }
 }
 
 /// A track reference entry (e.g., auxl, cdsc) parsed from a `tref` sub-box.
@@ -1162,8 +2426,18 @@ struct TrackReference {
 /// Codec properties extracted from a `stsd` VisualSampleEntry.
 #[derive(Debug, Clone, Default)]
 struct TrackCodecConfig {
+    /// Type of the first sample entry, e.g. `av01`.
+    sample_entry: Option<FourCC>,
     av1_config: Option<AV1Config>,
     color_info: Option<ColorInformation>,
+    icc_profile: Option<alloc::vec::Vec<u8>>,
+    pixel_aspect_ratio: Option<PixelAspectRatio>,
+    content_light_level: Option<ContentLightLevel>,
+    mastering_display: Option<MasteringDisplayColourVolume>,
+    content_colour_volume: Option<ContentColourVolume>,
+    ambient_viewing: Option<AmbientViewingEnvironment>,
+    /// The `av01` entry's index into the track's `dref`.
+    data_reference_index: u16,
 }
 
 /// Parsed data from a single track box (`trak`).
@@ -1171,20 +2445,53 @@ struct TrackCodecConfig {
 struct ParsedTrack {
     track_id: u32,
     handler_type: FourCC,
+    kind: TrackKind,
     media_timescale: u32,
     sample_table: SampleTable,
     references: TryVec<TrackReference>,
     loop_count: u32,
+    edit: Option<EditListEntry>,
+    /// Presented length of one loop in media timescale units; trimmed to
+    /// the edit list by [`read_moov`], which knows the movie timescale.
+    duration: u64,
     codec_config: TrackCodecConfig,
+    width: u32,
+    height: u32,
+}
+
+impl ParsedTrack {
+    fn info(&self) -> TrackInfo {
+        TrackInfo {
+            track_id: self.track_id,
+            handler_type: fourcc_string(&self.handler_type.value),
+            kind: self.kind,
+            width: self.width,
+            height: self.height,
+            codec: self.codec_config.sample_entry.as_ref().map(|fourcc| fourcc_string(&fourcc.value)),
+            av1_config: self.codec_config.av1_config.clone(),
+            color_info: self.codec_config.color_info.clone(),
+            pixel_aspect_ratio: self.codec_config.pixel_aspect_ratio,
+            frame_count: self.sample_table.sample_sizes.len(),
+            timescale: self.media_timescale,
+            selected: false,
+        }
+    }
 }
 
 /// Paired color + optional alpha animation data after track association.
 struct ParsedAnimationData {
+    color_track_id: u32,
+    alpha_track_id: Option<u32>,
+    /// Every track in file order.
+    tracks: alloc::vec::Vec<TrackInfo>,
+    /// The tracks other than color and alpha.
+    other_tracks: alloc::vec::Vec<ParsedTrack>,
     color_timescale: u32,
     color_sample_table: SampleTable,
     alpha_timescale: Option<u32>,
     alpha_sample_table: Option<SampleTable>,
     loop_count: u32,
+    color_duration: u64,
     color_codec_config: TrackCodecConfig,
 }
 
@@ -1240,6 +2547,12 @@ pub struct AvifData {
     /// row from left to right, then the second row, etc.
     pub grid_tiles: TryVec<TryVec<u8>>,
 
+    /// Overlay configuration, when the primary item is an `iovl` derived image.
+    pub overlay_config: Option<OverlayConfig>,
+
+    /// AV1 payloads for overlay inputs, in the order of [`OverlayConfig::offsets`].
+    pub overlay_inputs: TryVec<TryVec<u8>>,
+
     /// Animation configuration (for animated AVIF with avis brand)
     ///
     /// When present, primary_item contains the first frame
@@ -1251,6 +2564,11 @@ pub struct AvifData {
     /// Colour information from the container's `colr` property.
     pub color_info: Option<ColorInformation>,
 
+    /// ICC profile from a `colr` property of type `prof`/`rICC`, if present.
+    ///
+    /// Populated even when an `nclx` `colr` box is also associated with the item.
+    pub icc_profile: Option<alloc::vec::Vec<u8>>,
+
     /// Image rotation from the container's `irot` property.
     pub rotation: Option<ImageRotation>,
 
@@ -1284,6 +2602,10 @@ pub struct AvifData {
     /// AV1 layered image indexing from the container's `a1lx` property.
     pub layered_image_indexing: Option<AV1LayeredImageIndexing>,
 
+    /// User descriptions from the container's `udes` properties, in
+    /// association order.
+    pub user_descriptions: alloc::vec::Vec<UserDescription>,
+
     /// EXIF metadata from a `cdsc`-linked `Exif` item.
     ///
     /// Raw EXIF data (TIFF header onwards), with the 4-byte AVIF offset prefix stripped.
@@ -1322,7 +2644,7 @@ pub struct AvifData {
     pub major_brand: [u8; 4],
 
     /// Compatible brands from the `ftyp` box.
-    pub compatible_brands: std::vec::Vec<[u8; 4]>,
+    pub compatible_brands: alloc::vec::Vec<[u8; 4]>,
 }
 
 #[cfg(feature = "eager")]
@@ -1342,6 +2664,16 @@ impl AvifData {
         })
     }
 
+    /// Raw XMP packet from the `cdsc`-linked `application/rdf+xml` item, if present.
+    pub fn xmp_data(&self) -> Option<&[u8]> {
+        self.xmp.as_deref()
+    }
+
+    /// Ambient viewing environment from the container's `amve` property, if present.
+    pub fn ambient_viewing_environment(&self) -> Option<&AmbientViewingEnvironment> {
+        self.ambient_viewing.as_ref()
+    }
+
     /// Get the depth auxiliary image bundle, if present.
     ///
     /// Returns [`AvifDepthMap`] with the raw AV1 depth data, dimensions,
@@ -1403,6 +2735,7 @@ impl AvifData {
 /// `(true, true)` = 4:2:0 (both axes subsampled).
 /// `(true, false)` = 4:2:2 (horizontal only).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ChromaSubsampling {
     /// Whether the horizontal (X) axis is subsampled.
     pub horizontal: bool,
@@ -1436,6 +2769,7 @@ impl From<ChromaSubsampling> for (bool, bool) {
 /// See [`AvifParser::primary_metadata()`] and [`AV1Metadata::parse_av1_bitstream()`].
 #[non_exhaustive]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AV1Metadata {
     /// Should be true for non-animated AVIF
     pub still_picture: bool,
@@ -1445,10 +2779,23 @@ pub struct AV1Metadata {
     pub bit_depth: u8,
     /// 0, 1 or 2 for the level of complexity
     pub seq_profile: u8,
+    /// `seq_level_idx` of operating point 0 (e.g. 8 for level 4.0, 31 if unconstrained)
+    pub seq_level_idx: u8,
+    /// `seq_tier` of operating point 0: 0 = Main, 1 = High
+    pub seq_tier: u8,
     /// Chroma subsampling. Use named fields (`horizontal`, `vertical`) or
     /// constants like [`ChromaSubsampling::YUV420`].
     pub chroma_subsampling: ChromaSubsampling,
     pub monochrome: bool,
+    /// Colour primaries from the color config (ITU-T H.273 Table 2).
+    /// 2 (unspecified) when the sequence header has no color description.
+    pub color_primaries: u8,
+    /// Transfer characteristics (ITU-T H.273 Table 3), 2 if not signaled.
+    pub transfer_characteristics: u8,
+    /// Matrix coefficients (ITU-T H.273 Table 4), 2 if not signaled.
+    pub matrix_coefficients: u8,
+    /// True if full range, false if limited/studio range (`color_range`)
+    pub full_range: bool,
     /// AV1 base quantizer index (0-255) from the first frame header.
     /// `None` if the frame header could not be parsed.
     /// 0 = lossless candidate, 255 = worst quality.
@@ -1457,6 +2804,73 @@ pub struct AV1Metadata {
     /// and chroma is not subsampled).
     /// `None` if the frame header could not be parsed.
     pub lossless: Option<bool>,
+    /// The frame headers may carry film grain parameters, so a faithful
+    /// decode needs grain synthesis (`film_grain_params_present`).
+    pub film_grain_params_present: bool,
+    /// Frames may use superresolution (`enable_superres`).
+    pub enable_superres: bool,
+    /// Frames may use the constrained directional enhancement filter (`enable_cdef`).
+    pub enable_cdef: bool,
+    /// Frames may use loop restoration (`enable_restoration`).
+    pub enable_restoration: bool,
+    /// HDR metadata OBUs in the bitstream.
+    pub hdr: AV1HdrMetadata,
+    /// The first timecode metadata OBU, if any.
+    pub timecode: Option<AV1Timecode>,
+}
+
+/// HDR metadata carried in AV1 metadata OBUs (AV1 § 5.8.3 - § 5.8.4).
+///
+/// Encoders may store this in the bitstream instead of, or in addition to,
+/// the `clli` and `mdcv` properties. See [`AvifParser::bitstream_hdr_metadata()`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AV1HdrMetadata {
+    /// From the first `METADATA_TYPE_HDR_CLL` OBU.
+    pub content_light_level: Option<ContentLightLevel>,
+    /// From the first `METADATA_TYPE_HDR_MDCV` OBU, converted to the units
+    /// and primary order of the `mdcv` property (rounded to the nearest unit).
+    pub mastering_display: Option<MasteringDisplayColourVolume>,
+    /// Every `METADATA_TYPE_ITUT_T35` OBU in bitstream order, e.g. HDR10+
+    /// dynamic metadata.
+    pub itut_t35: alloc::vec::Vec<ItutT35>,
+}
+
+/// An ITU-T T.35 metadata OBU (AV1 § 5.8.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ItutT35 {
+    /// `itu_t_t35_country_code`; 0xB5 is the United States.
+    pub country_code: u8,
+    /// `itu_t_t35_country_code_extension_byte`, present when the country code is 0xFF.
+    pub country_code_extension: Option<u8>,
+    /// The payload bytes, starting with the terminal provider code.
+    pub payload: alloc::vec::Vec<u8>,
+}
+
+/// A timecode metadata OBU (AV1 § 5.8.7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct AV1Timecode {
+    /// `counting_type`, as in SMPTE ST 12-1.
+    pub counting_type: u8,
+    /// `discontinuity_flag`
+    pub discontinuity: bool,
+    /// `cnt_dropped_flag`
+    pub dropped_frames: bool,
+    /// `n_frames`
+    pub n_frames: u16,
+    /// `seconds_value`, if signaled.
+    pub seconds: Option<u8>,
+    /// `minutes_value`, if signaled.
+    pub minutes: Option<u8>,
+    /// `hours_value`, if signaled.
+    pub hours: Option<u8>,
+    /// `time_offset_value`, if `time_offset_length` is non-zero.
+    pub time_offset: Option<u32>,
 }
 
 impl AV1Metadata {
@@ -1467,9 +2881,24 @@ impl AV1Metadata {
     ///
     /// This is for the bare image payload from an encoder, not an AVIF/HEIF file.
     /// To parse AVIF files, see [`AvifParser::from_reader()`].
+    ///
+    /// Both the low-overhead format used in AVIF and the length-delimited
+    /// Annex B format are accepted. Annex B is only tried when the data does
+    /// not parse as low-overhead OBUs.
     #[inline(never)]
     pub fn parse_av1_bitstream(obu_bitstream: &[u8]) -> Result<Self> {
+        match Self::parse_low_overhead(obu_bitstream) {
+            Err(e) => match obu::annex_b_to_low_overhead(obu_bitstream)? {
+                Some(low_overhead) => Self::parse_low_overhead(&low_overhead),
+                None => Err(e),
+            },
+            parsed => parsed,
+        }
+    }
+
+    fn parse_low_overhead(obu_bitstream: &[u8]) -> Result<Self> {
         let (h, frame_quant) = obu::parse_obu_with_frame_info(obu_bitstream)?;
+        let metadata = obu::parse_metadata_obus(obu_bitstream);
         let no_chroma_subsampling = !h.color.chroma_subsampling.horizontal
             && !h.color.chroma_subsampling.vertical;
         Ok(Self {
@@ -1478,12 +2907,37 @@ impl AV1Metadata {
             max_frame_height: h.max_frame_height,
             bit_depth: h.color.bit_depth,
             seq_profile: h.seq_profile,
+            seq_level_idx: h.seq_level_idx,
+            seq_tier: h.seq_tier,
             chroma_subsampling: h.color.chroma_subsampling,
             monochrome: h.color.monochrome,
+            color_primaries: h.color.color_primaries,
+            transfer_characteristics: h.color.transfer_characteristics,
+            matrix_coefficients: h.color.matrix_coefficients,
+            full_range: h.color.color_range != 0,
             base_q_idx: frame_quant.map(|fq| fq.base_q_idx),
             lossless: frame_quant.map(|fq| fq.coded_lossless && no_chroma_subsampling),
+            film_grain_params_present: h.film_grain_params_present,
+            enable_superres: h.enable_superres,
+            enable_cdef: h.enable_cdef,
+            enable_restoration: h.enable_restoration,
+            hdr: metadata.hdr,
+            timecode: metadata.timecode,
         })
     }
+
+    /// The bitstream's CICP as `nclx` color information.
+    ///
+    /// MIAF § 7.3.6.4 makes this authoritative when the item has no `colr`
+    /// property, so use it as the fallback for [`AvifParser::color_info()`].
+    pub fn color_info(&self) -> ColorInformation {
+        ColorInformation::Nclx {
+            color_primaries: self.color_primaries.into(),
+            transfer_characteristics: self.transfer_characteristics.into(),
+            matrix_coefficients: self.matrix_coefficients.into(),
+            full_range: self.full_range,
+        }
+    }
 }
 
 /// A single frame from an animated AVIF, with zero-copy when possible.
@@ -1495,6 +2949,42 @@ pub struct FrameRef<'a> {
     /// Alpha channel data for this frame, if the animation has a separate alpha track.
     pub alpha_data: Option<Cow<'a, [u8]>>,
     pub duration_ms: u32,
+    /// Presentation time in milliseconds: the decode time from `stts` plus
+    /// the composition offset from `ctts`, if the track has one.
+    pub timestamp_ms: u64,
+    /// Presentation time in [`timescale`](Self::timescale) units, exact.
+    pub timestamp: u64,
+    /// Display duration in [`timescale`](Self::timescale) units, exact.
+    pub duration: u32,
+    /// Ticks per second of the color track.
+    pub timescale: u32,
+    /// Decoding can start at this frame (a sync sample in `stss`; every
+    /// frame is one when the track has no `stss`).
+    pub is_keyframe: bool,
+}
+
+impl FrameRef<'_> {
+    /// [`timestamp`](Self::timestamp) as a [`Duration`](core::time::Duration),
+    /// rounded down to the nanosecond. Schedule frames from this rather than
+    /// summing `duration_ms`, which drifts by up to a millisecond per frame.
+    pub fn presentation_time(&self) -> core::time::Duration {
+        ticks_to_duration(self.timestamp, self.timescale)
+    }
+
+    /// [`duration`](Self::duration) as a [`Duration`](core::time::Duration).
+    pub fn display_duration(&self) -> core::time::Duration {
+        ticks_to_duration(u64::from(self.duration), self.timescale)
+    }
+}
+
+/// Convert media timescale ticks to a `Duration`; zero for a zero timescale.
+fn ticks_to_duration(ticks: u64, timescale: u32) -> core::time::Duration {
+    if timescale == 0 {
+        return core::time::Duration::ZERO;
+    }
+    let timescale = u64::from(timescale);
+    let nanos = (ticks % timescale) * 1_000_000_000 / timescale;
+    core::time::Duration::new(ticks / timescale, nanos as u32)
 }
 
 /// Byte range of a media data box within the file.
@@ -1503,13 +2993,145 @@ struct MdatBounds {
     length: u64,
 }
 
+/// A reader that can also seek, for [`AvifParser::from_seekable`].
+#[cfg(feature = "std")]
+trait ReadSeek: Read + Seek {}
+
+#[cfg(feature = "std")]
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// The file bytes behind an [`AvifParser`].
+enum RawData<'data> {
+    /// The whole file, borrowed or owned.
+    Bytes(Cow<'data, [u8]>),
+    /// The whole file, memory-mapped.
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    /// The whole file, reference-counted; item and frame data are handed out
+    /// as `Bytes` slices of it.
+    #[cfg(feature = "bytes")]
+    Shared(bytes::Bytes),
+    /// A seekable reader; item and frame extents are read on demand.
+    #[cfg(feature = "std")]
+    Seekable {
+        reader: std::sync::Mutex<alloc::boxed::Box<dyn ReadSeek + Send>>,
+        len: u64,
+    },
+}
+
+impl RawData<'_> {
+    /// Whether extents are read on demand rather than sliced from memory.
+    fn is_seekable(&self) -> bool {
+        #[cfg(feature = "std")]
+        return matches!(self, Self::Seekable { .. });
+        #[cfg(not(feature = "std"))]
+        return false;
+    }
+
+    /// Total length of the file in bytes.
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(data) => data.len().to_u64(),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map.len().to_u64(),
+            #[cfg(feature = "bytes")]
+            Self::Shared(data) => data.len().to_u64(),
+            #[cfg(feature = "std")]
+            Self::Seekable { len, .. } => *len,
+        }
+    }
+
+    /// Get the bytes in `start..end`, borrowed when the whole file is in memory.
+    fn read_range(&self, start: usize, end: usize) -> Result<Cow<'_, [u8]>> {
+        match self {
+            Self::Bytes(data) => data
+                .get(start..end)
+                .map(Cow::Borrowed)
+                .ok_or_else(|| at!(Error::InvalidData("extent out of bounds in raw buffer"))),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map
+                .get(start..end)
+                .map(Cow::Borrowed)
+                .ok_or_else(|| at!(Error::InvalidData("extent out of bounds in raw buffer"))),
+            #[cfg(feature = "bytes")]
+            Self::Shared(data) => data
+                .get(start..end)
+                .map(Cow::Borrowed)
+                .ok_or_else(|| at!(Error::InvalidData("extent out of bounds in raw buffer"))),
+            #[cfg(feature = "std")]
+            Self::Seekable { reader, len } => {
+                if start > end || end.to_u64() > *len {
+                    return Err(at!(Error::InvalidData("extent out of bounds in raw buffer")));
+                }
+                let mut buf = alloc::vec::Vec::new();
+                buf.try_reserve_exact(end - start).map_err(|_| at!(Error::OutOfMemory))?;
+                buf.resize(end - start, 0);
+                // A panic while holding the lock cannot leave the reader in a state we rely on:
+                // every read seeks first.
+                let mut reader = reader.lock().unwrap_or_else(|e| e.into_inner());
+                reader.seek(SeekFrom::Start(start.to_u64())).map_err(|e| at!(Error::from(e)))?;
+                reader.read_exact(&mut buf).map_err(|e| at!(Error::from(e)))?;
+                Ok(Cow::Owned(buf))
+            }
+        }
+    }
+
+    /// Append `start..end` of the file to `out`, reading a seekable source
+    /// straight into it.
+    fn read_range_into(&self, start: usize, end: usize, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        #[cfg(feature = "std")]
+        if let Self::Seekable { reader, len } = self {
+            if start > end || end.to_u64() > *len {
+                return Err(at!(Error::InvalidData("extent out of bounds in raw buffer")));
+            }
+            let old_len = out.len();
+            out.try_reserve(end - start).map_err(|_| at!(Error::OutOfMemory))?;
+            out.resize(old_len + (end - start), 0);
+            let mut reader = reader.lock().unwrap_or_else(|e| e.into_inner());
+            let read = reader.seek(SeekFrom::Start(start.to_u64()))
+                .and_then(|_| reader.read_exact(&mut out[old_len..]));
+            if let Err(e) = read {
+                out.truncate(old_len);
+                return Err(at!(Error::from(e)));
+            }
+            return Ok(());
+        }
+        let data = self.read_range(start, end)?;
+        out.try_reserve(data.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        out.extend_from_slice(&data);
+        Ok(())
+    }
+
+    /// Get the bytes in `start..end` as `Bytes`: a slice of shared input,
+    /// otherwise a copy.
+    #[cfg(feature = "bytes")]
+    fn read_range_shared(&self, start: usize, end: usize) -> Result<bytes::Bytes> {
+        if let Self::Shared(data) = self {
+            if start > end || end > data.len() {
+                return Err(at!(Error::InvalidData("extent out of bounds in raw buffer")));
+            }
+            return Ok(data.slice(start..end));
+        }
+        Ok(cow_to_bytes(self.read_range(start, end)?))
+    }
+}
+
+/// Hand over an owned buffer, copy a borrowed one.
+#[cfg(feature = "bytes")]
+fn cow_to_bytes(data: Cow<'_, [u8]>) -> bytes::Bytes {
+    match data {
+        Cow::Borrowed(slice) => bytes::Bytes::copy_from_slice(slice),
+        Cow::Owned(vec) => bytes::Bytes::from(vec),
+    }
+}
+
 /// Where an item's data lives: construction method + extent ranges.
 struct ItemExtents {
     construction_method: ConstructionMethod,
     extents: TryVec<ExtentRange>,
 }
 
-/// Zero-copy AVIF parser backed by a borrowed or owned byte buffer.
+/// Zero-copy AVIF parser backed by a borrowed or owned byte buffer, or by a seekable reader.
 ///
 /// `AvifParser` records byte offsets during parsing but does **not** copy
 /// mdat payload data. Data access methods return `Cow<[u8]>` — borrowed
@@ -1523,6 +3145,10 @@ struct ItemExtents {
 /// | [`from_bytes`](Self::from_bytes) | `'data` | Yes — borrows the slice |
 /// | [`from_owned`](Self::from_owned) | `'static` | Within the owned buffer |
 /// | [`from_reader`](Self::from_reader) | `'static` | Reads all, then owned |
+/// | [`from_async_reader`](Self::from_async_reader) (`async` feature) | `'static` | Reads all, then owned |
+/// | [`from_file`](Self::from_file) (`mmap` feature) | `'static` | Yes — borrows the mapping |
+/// | [`from_bytes_shared`](Self::from_bytes_shared) (`bytes` feature) | `'static` | Yes — `Bytes` slices via `*_bytes` |
+/// | [`from_seekable`](Self::from_seekable) | `'static` | No — reads extents on demand |
 ///
 /// # Example
 ///
@@ -1534,19 +3160,43 @@ struct ItemExtents {
 /// let primary = parser.primary_data()?; // Cow::Borrowed for single-extent
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
+///
+/// # Sharing
+///
+/// The parsed file lives behind an [`Arc`](alloc::sync::Arc): `clone()` is a
+/// reference-count bump that neither copies the input nor re-parses it, and
+/// the parser is `Send + Sync`, so each worker of a decode thread pool can
+/// hold its own handle.
+#[derive(Clone)]
 pub struct AvifParser<'data> {
-    raw: Cow<'data, [u8]>,
+    inner: alloc::sync::Arc<ParserState<'data>>,
+}
+
+/// Everything an [`AvifParser`] knows about the file, shared between clones.
+struct ParserState<'data> {
+    raw: RawData<'data>,
     mdat_bounds: TryVec<MdatBounds>,
     idat: Option<TryVec<u8>>,
     primary: ItemExtents,
     alpha: Option<ItemExtents>,
     grid_config: Option<GridConfig>,
     tiles: TryVec<ItemExtents>,
+    tile_spatial_extents: Option<ImageSpatialExtents>,
+    overlay_config: Option<OverlayConfig>,
+    overlay_inputs: TryVec<ItemExtents>,
+    sample_transform: Option<SampleTransform>,
+    sample_transform_inputs: TryVec<ItemExtents>,
+    alternatives: alloc::vec::Vec<AlternativeGroup>,
     animation_data: Option<AnimationParserData>,
     premultiplied_alpha: bool,
     spatial_extents: Option<ImageSpatialExtents>,
     av1_config: Option<AV1Config>,
+    alpha_av1_config: Option<AV1Config>,
+    tile_av1_configs: alloc::vec::Vec<AV1Config>,
+    #[cfg(feature = "heif")]
+    hevc_config: Option<HevcConfig>,
     color_info: Option<ColorInformation>,
+    icc_profile: Option<alloc::vec::Vec<u8>>,
     rotation: Option<ImageRotation>,
     mirror: Option<ImageMirror>,
     clean_aperture: Option<CleanAperture>,
@@ -1558,123 +3208,711 @@ pub struct AvifParser<'data> {
     operating_point: Option<OperatingPointSelector>,
     layer_selector: Option<LayerSelector>,
     layered_image_indexing: Option<AV1LayeredImageIndexing>,
+    user_descriptions: alloc::vec::Vec<UserDescription>,
+    pixel_information: Option<PixelInformation>,
+    alpha_pixel_information: Option<PixelInformation>,
     exif_item: Option<ItemExtents>,
+    /// The Exif item starts at the TIFF header, without an offset field.
+    exif_headerless: bool,
     xmp_item: Option<ItemExtents>,
+    thumbnail: Option<ItemExtents>,
+    thumbnail_spatial_extents: Option<ImageSpatialExtents>,
+    thumbnail_av1_config: Option<AV1Config>,
     gain_map_metadata: Option<GainMapMetadata>,
     gain_map: Option<ItemExtents>,
     gain_map_color_info: Option<ColorInformation>,
+    gain_map_spatial_extents: Option<ImageSpatialExtents>,
+    gain_map_av1_config: Option<AV1Config>,
     depth_item: Option<ItemExtents>,
     depth_width: u32,
     depth_height: u32,
     depth_av1_config: Option<AV1Config>,
     depth_color_info: Option<ColorInformation>,
     major_brand: [u8; 4],
-    compatible_brands: std::vec::Vec<[u8; 4]>,
+    compatible_brands: alloc::vec::Vec<[u8; 4]>,
+    items: alloc::vec::Vec<ItemRecord>,
+    auxiliary_items: alloc::vec::Vec<AuxiliaryItem>,
+    references: alloc::vec::Vec<ItemReference>,
+    uuid_boxes: TryVec<UuidBox>,
+    protected_items: TryVec<ProtectedItem>,
+    layout: Option<FileLayout>,
+    truncated: bool,
+    warnings: TryVec<ParseWarning>,
+    stats: ParseStats,
+}
+
+/// An item as [`AvifParser::summary()`] reports it; the byte ranges are
+/// resolved from `location` on demand.
+struct ItemRecord {
+    summary: ItemSummary,
+    location: Option<ItemExtents>,
 }
 
 struct AnimationParserData {
+    track_id: u32,
+    alpha_track_id: Option<u32>,
+    tracks: alloc::vec::Vec<TrackInfo>,
+    other_tracks: alloc::vec::Vec<ParsedTrack>,
     media_timescale: u32,
     sample_table: SampleTable,
     alpha_media_timescale: Option<u32>,
     alpha_sample_table: Option<SampleTable>,
     loop_count: u32,
+    duration: u64,
     codec_config: TrackCodecConfig,
 }
 
+/// What parsing a file involved, from [`AvifParser::stats()`]. Useful for
+/// tuning [`DecodeConfig`] limits against real uploads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ParseStats {
+    /// Boxes read, at every nesting level; skipped boxes count, their
+    /// children do not.
+    pub boxes: u64,
+    /// Items in `iinf`.
+    pub items: usize,
+    /// Properties in `ipco`, each once however many items share it.
+    pub properties: usize,
+    /// `mdat` boxes with content.
+    pub mdat_count: usize,
+    /// Total size of their content.
+    pub mdat_bytes: u64,
+    /// Extents across all `iloc` items.
+    pub extents: usize,
+    /// The most memory the parser accounted for at once, the figure
+    /// [`DecodeConfig::peak_memory_limit`] is checked against.
+    pub peak_memory: u64,
+    /// Time from the start of parsing until the parser was built; `None`
+    /// without the `std` feature or on `wasm32-unknown-unknown`, which have
+    /// no clock.
+    pub parse_duration: Option<core::time::Duration>,
+}
+
 /// Animation metadata from [`AvifParser`]
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AnimationInfo {
     pub frame_count: usize,
     pub loop_count: u32,
     /// Whether animation has a separate alpha track.
     pub has_alpha: bool,
-    /// Media timescale (ticks per second) for the color track.
+    /// Media timescale (ticks per second) for the color track; the unit of
+    /// [`FrameRef::timestamp`] and [`FrameRef::duration`].
     pub timescale: u32,
+    /// Length of one loop in [`timescale`](Self::timescale) units: the sum
+    /// of the sample durations, trimmed to the track's edit list.
+    pub duration: u64,
 }
 
-/// Parsed structure from the box-level parse pass (no mdat data).
-struct ParsedStructure {
-    /// `None` for pure AVIF sequences (`avis` brand) that have only `moov`+`mdat`.
-    meta: Option<AvifInternalMeta>,
-    mdat_bounds: TryVec<MdatBounds>,
-    animation_data: Option<ParsedAnimationData>,
-    major_brand: [u8; 4],
-    compatible_brands: std::vec::Vec<[u8; 4]>,
-}
-
-impl<'data> AvifParser<'data> {
-    // ========================================
-    // Constructors
-    // ========================================
-
-    /// Parse AVIF from a borrowed byte slice (true zero-copy).
-    ///
-    /// The returned parser borrows `data` — single-extent items will be
-    /// returned as `Cow::Borrowed` slices into this buffer.
-    pub fn from_bytes(data: &'data [u8]) -> Result<Self> {
-        Self::from_bytes_with_config(data, &DecodeConfig::default(), &Unstoppable)
+impl AnimationInfo {
+    /// Length of one loop, from [`duration`](Self::duration).
+    pub fn total_duration(&self) -> core::time::Duration {
+        ticks_to_duration(self.duration, self.timescale)
     }
 
-    /// Parse AVIF from a borrowed byte slice with resource limits.
-    pub fn from_bytes_with_config(
-        data: &'data [u8],
-        config: &DecodeConfig,
-        stop: &dyn Stop,
-    ) -> Result<Self> {
-        let parsed = Self::parse_raw(data, config, stop)?;
-        Self::build(Cow::Borrowed(data), parsed, config)
+    /// Average frames per second over one loop; `None` when the duration
+    /// is unknown (zero).
+    pub fn fps_estimate(&self) -> Option<f64> {
+        if self.duration == 0 || self.timescale == 0 {
+            return None;
+        }
+        Some(self.frame_count as f64 * f64::from(self.timescale) / self.duration as f64)
     }
 
-    /// Parse AVIF from an owned buffer.
-    ///
-    /// The returned parser owns the data — single-extent items will still
-    /// be returned as `Cow::Borrowed` slices (borrowing from the internal buffer).
-    pub fn from_owned(data: std::vec::Vec<u8>) -> Result<AvifParser<'static>> {
-        AvifParser::from_owned_with_config(data, &DecodeConfig::default(), &Unstoppable)
+    /// Convert `ticks` of [`timescale`](Self::timescale) to a
+    /// [`Duration`](core::time::Duration), e.g. a [`FrameSummary::timestamp`].
+    pub fn ticks_to_duration(&self, ticks: u64) -> core::time::Duration {
+        ticks_to_duration(ticks, self.timescale)
     }
+}
 
-    /// Parse AVIF from an owned buffer with resource limits.
-    pub fn from_owned_with_config(
-        data: std::vec::Vec<u8>,
-        config: &DecodeConfig,
-        stop: &dyn Stop,
-    ) -> Result<AvifParser<'static>> {
-        let parsed = AvifParser::parse_raw(&data, config, stop)?;
-        AvifParser::build(Cow::Owned(data), parsed, config)
-    }
+/// The primary image of a parsed file, from [`AvifParser::summary()`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct AvifSummary {
+    /// The `ftyp` major brand, e.g. `"avif"`.
+    pub major_brand: alloc::string::String,
+    /// The `ftyp` compatible brands.
+    pub compatible_brands: alloc::vec::Vec<alloc::string::String>,
+    /// Dimensions from `ispe`.
+    pub spatial_extents: Option<ImageSpatialExtents>,
+    /// The primary item's `av1C`; `None` for grids and overlays.
+    pub av1_config: Option<AV1Config>,
+    /// Sequence header of the primary image's bitstream (the first tile or
+    /// frame for derived images and sequences), if it parses.
+    pub sequence_header: Option<AV1Metadata>,
+    /// `nclx` color information. ICC profiles are only summarized, by
+    /// `icc_profile_size`.
+    pub nclx: Option<ColorInformation>,
+    /// Size of the ICC profile in bytes.
+    pub icc_profile_size: Option<usize>,
+    /// There is an alpha image or alpha track.
+    pub has_alpha: bool,
+    /// The color image is premultiplied by alpha (`prem`).
+    pub premultiplied_alpha: bool,
+    /// `irot`
+    pub rotation: Option<ImageRotation>,
+    /// `imir`
+    pub mirror: Option<ImageMirror>,
+    /// `clap`
+    pub clean_aperture: Option<CleanAperture>,
+    /// `pasp`
+    pub pixel_aspect_ratio: Option<PixelAspectRatio>,
+    /// `clli`
+    pub content_light_level: Option<ContentLightLevel>,
+    /// `mdcv`
+    pub mastering_display: Option<MasteringDisplayColourVolume>,
+    /// `cclv`
+    pub content_colour_volume: Option<ContentColourVolume>,
+    /// `amve`
+    pub ambient_viewing_environment: Option<AmbientViewingEnvironment>,
+    /// Layout of a grid primary image.
+    pub grid: Option<GridConfig>,
+    /// Layout of an overlay primary image.
+    pub overlay: Option<OverlayConfig>,
+    /// Frame count, loop count and timescale of an animation.
+    pub animation: Option<AnimationInfo>,
+    /// There is an EXIF item.
+    pub has_exif: bool,
+    /// There is an XMP item.
+    pub has_xmp: bool,
+    /// There is a thumbnail item.
+    pub has_thumbnail: bool,
+    /// Gain map parameters, if the file has a `tmap` gain map.
+    pub gain_map: Option<GainMapMetadata>,
+    /// There is a depth auxiliary image.
+    pub has_depth_map: bool,
+    /// Every item in `iinf`, in file order; empty for pure sequences.
+    pub items: alloc::vec::Vec<ItemSummary>,
+    /// Size and duration of each animation frame.
+    pub frames: alloc::vec::Vec<FrameSummary>,
+    /// Every track of an image sequence, from [`AvifParser::tracks`].
+    pub tracks: alloc::vec::Vec<TrackInfo>,
+    /// Spec violations tolerated while parsing.
+    pub warnings: alloc::vec::Vec<ParseWarning>,
+}
+
+/// One item of the `meta` box, from [`AvifSummary::items`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ItemSummary {
+    /// `item_ID`
+    pub id: u32,
+    /// This is the primary item (`pitm`), after `iden` redirection.
+    pub primary: bool,
+    /// `item_type`, e.g. `"av01"`, `"grid"` or `"Exif"`.
+    pub item_type: alloc::string::String,
+    /// `content_type` of a `mime` item.
+    pub content_type: Option<alloc::string::String>,
+    /// The data is stored in `idat` rather than in the file's `mdat`.
+    pub in_idat: bool,
+    /// Total size of the item's data in bytes.
+    pub size: u64,
+    /// Absolute file byte ranges of the data; empty for `idat` items and
+    /// items without an `iloc` entry.
+    pub byte_ranges: alloc::vec::Vec<Range<u64>>,
+    /// Associated properties in `ipma` order. Unsupported non-essential
+    /// properties are not recorded.
+    pub properties: alloc::vec::Vec<PropertySummary>,
+    /// References from this item (`iref`).
+    pub references: alloc::vec::Vec<ItemReference>,
+}
+
+/// A property association, from [`ItemSummary::properties`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct PropertySummary {
+    /// Box type of the property, e.g. `"ispe"`.
+    pub fourcc: alloc::string::String,
+    /// The `ipma` essential flag.
+    pub essential: bool,
+    /// Whether this crate understands the property. Unsupported properties
+    /// are only listed when marked essential, which fails parsing unless
+    /// [`Strictness::Permissive`] records an
+    /// [`UnsupportedEssentialProperty`](ParseWarningKind::UnsupportedEssentialProperty)
+    /// warning instead; such an item cannot be displayed correctly.
+    pub supported: bool,
+}
+
+/// An edge of the item reference graph, from [`AvifParser::references()`]
+/// and [`ItemSummary::references`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ItemReference {
+    /// Reference type, e.g. `*b"dimg"`, `*b"auxl"`, `*b"cdsc"` or `*b"thmb"`.
+    pub reference_type: [u8; 4],
+    /// The referencing item.
+    pub from_item_id: u32,
+    /// The referenced item.
+    pub to_item_id: u32,
+    /// Position among the references of this type from `from_item_id`,
+    /// 0-based; for `dimg` this orders grid tiles and overlay inputs.
+    pub index: u16,
+}
+
+/// An item the parser skipped because it is protected (e.g. encrypted),
+/// from [`AvifParser::protected_items`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ProtectedItem {
+    /// The item's ID.
+    pub item_id: u32,
+    /// The `schm` scheme type from the item's `ipro` entry, such as `cenc`,
+    /// if the entry has one.
+    pub scheme: Option<[u8; 4]>,
+}
 
-    /// Parse AVIF from a reader (reads all bytes, then parses).
-    pub fn from_reader<R: Read + ?Sized>(reader: &mut R) -> Result<AvifParser<'static>> {
-        AvifParser::from_reader_with_config(reader, &DecodeConfig::default(), &Unstoppable)
+/// A `uuid` box at the top level or directly in `meta`, from
+/// [`AvifParser::uuid_boxes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct UuidBox {
+    /// The box's `usertype`.
+    pub uuid: [u8; 16],
+    /// Whether the box is in `meta` rather than at the top level.
+    pub in_meta: bool,
+    /// File byte range of the payload, after the `usertype`.
+    pub payload: Range<u64>,
+}
+
+impl UuidBox {
+    /// The `usertype` of Adobe's XMP box, `BE7ACFCB-97A9-42E8-9C71-999491E3AFAC`.
+    pub const XMP: [u8; 16] = [0xbe, 0x7a, 0xcf, 0xcb, 0x97, 0xa9, 0x42, 0xe8, 0x9c, 0x71, 0x99, 0x94, 0x91, 0xe3, 0xaf, 0xac];
+
+    /// The box `b`, if its `usertype` is complete.
+    fn from_box<T: Read + Offset>(b: &BMFFBox<'_, T>, in_meta: bool) -> Option<Self> {
+        let uuid = b.head.uuid?;
+        let start = b.offset();
+        Some(Self { uuid, in_meta, payload: start..start + b.bytes_left() })
     }
+}
 
-    /// Parse AVIF from a reader with resource limits.
-    ///
-    /// If `config.peak_memory_limit` is set, reading is capped at that many
-    /// bytes to prevent unbounded allocation from an untrusted reader.
-    pub fn from_reader_with_config<R: Read + ?Sized>(
-        reader: &mut R,
-        config: &DecodeConfig,
-        stop: &dyn Stop,
+/// Where each box is in the file, from [`AvifParser::layout`] with
+/// [`DecodeConfig::preserve_layout`].
+///
+/// A writer can copy boxes it does not rewrite, unknown ones included,
+/// through verbatim from these ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct FileLayout {
+    /// Every box whose header was read, in file order: each box comes
+    /// before its children. The children of skipped boxes (such as `mdat`
+    /// and unknown boxes) are not read.
+    pub boxes: alloc::vec::Vec<BoxLayout>,
+    /// Length of the input in bytes; data after the last top-level box is
+    /// not in any box.
+    pub file_size: u64,
+}
+
+/// One box in a [`FileLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BoxLayout {
+    /// The box type, e.g. `*b"meta"`.
+    pub box_type: [u8; 4],
+    /// The `usertype` of `uuid` boxes.
+    pub uuid: Option<[u8; 16]>,
+    /// Nesting level: 1 for top-level boxes.
+    pub depth: u32,
+    /// File byte range of the whole box, header included. A box claiming
+    /// more bytes than the input has ends at the end of the input.
+    pub range: Range<u64>,
+    /// Size of the header, up to and including the `usertype`.
+    pub header_size: u64,
+    /// Whether this crate knows the box type; `false` for unknown types
+    /// and `uuid` boxes, which are skipped (see [`BoxHandler`]).
+    pub known: bool,
+}
+
+impl BoxLayout {
+    fn new(head: &BoxHeader, depth: u32, start: u64, size: u64) -> Self {
+        Self {
+            box_type: FourCC::from(head.name).value,
+            uuid: head.uuid,
+            depth,
+            range: start..start.saturating_add(size),
+            header_size: head.offset,
+            known: !matches!(head.name, BoxType::UnknownBox(_) | BoxType::UuidBox),
+        }
+    }
+}
+
+/// One animation frame, from [`AvifSummary::frames`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct FrameSummary {
+    /// Size of the color sample in bytes.
+    pub size: u32,
+    /// Size of the alpha sample, if the alpha track has this frame.
+    pub alpha_size: Option<u32>,
+    /// Display duration in milliseconds.
+    pub duration_ms: u32,
+    /// Presentation time in milliseconds, including any `ctts` offset.
+    pub timestamp_ms: u64,
+    /// Presentation time in [`AnimationInfo::timescale`] units.
+    pub timestamp: u64,
+    /// Display duration in [`AnimationInfo::timescale`] units.
+    pub duration: u32,
+    /// Decoding can start at this frame.
+    pub is_keyframe: bool,
+}
+
+fn fourcc_string(fourcc: &[u8; 4]) -> alloc::string::String {
+    alloc::string::String::from_utf8_lossy(fourcc).into_owned()
+}
+
+/// Header-level summary of an AVIF file, from [`AvifInfo::peek`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvifInfo {
+    /// The `ftyp` major brand (`avif` or `avis`).
+    pub major_brand: [u8; 4],
+    /// Primary image width from its `ispe` property, or 0 if absent.
+    pub width: u32,
+    /// Primary image height from its `ispe` property, or 0 if absent.
+    pub height: u32,
+    /// Bit depth (8, 10 or 12) from the primary item's or color track's `av1C`.
+    pub bit_depth: Option<u8>,
+    /// Whether the image has an alpha auxiliary item or an alpha track.
+    pub has_alpha: bool,
+    /// Whether the file contains an animation track.
+    pub is_animated: bool,
+    /// Number of frames in the color track; 0 for still images.
+    pub frame_count: usize,
+}
+
+impl AvifInfo {
+    /// Summarize an AVIF file from a prefix of its bytes.
+    ///
+    /// Only the `ftyp`, `meta` and `moov` boxes are parsed; `mdat` may be
+    /// missing or cut short. The prefix must contain those boxes in full, so
+    /// a range request covering the start of the file is normally enough.
+    /// When it ends inside an `mdat` that comes before them, this fails with
+    /// [`Error::DataNotAvailable`].
+    pub fn peek(prefix: &[u8]) -> Result<Self> {
+        Self::peek_with_config(prefix, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Summarize an AVIF file from a prefix of its bytes, with resource limits.
+    pub fn peek_with_config(prefix: &[u8], config: &DecodeConfig, stop: &dyn Stop) -> Result<Self> {
+        // The prefix usually ends inside mdat
+        let config = DecodeConfig { allow_truncated: true, ..config.clone() };
+        let (parsed, _) = AvifParser::parse_seekable(&mut io::Cursor::new(prefix), &config, stop)?;
+        let track_config = parsed.animation_data.as_ref().map(|a| &a.color_codec_config);
+
+        let (mut width, mut height, mut bit_depth, mut has_alpha) = (0, 0, None, false);
+        if let Some(meta) = &parsed.meta {
+            if let Some(e) = property_for(meta, meta.primary_item_id, |p| match p {
+                ItemProperty::ImageSpatialExtents(e) => Some(*e),
+                _ => None,
+            }) {
+                (width, height) = (e.width, e.height);
+            }
+            bit_depth = property_for(meta, meta.primary_item_id, |p| match p {
+                ItemProperty::AV1Config(c) => Some(c.bit_depth),
+                _ => None,
+            });
+            has_alpha = alpha_item_id(meta).is_some();
+        }
+        let bit_depth = bit_depth.or_else(|| track_config.and_then(|c| c.av1_config.as_ref()).map(|c| c.bit_depth));
+        let animation = parsed.animation_data.as_ref();
+
+        Ok(Self {
+            major_brand: parsed.major_brand,
+            width,
+            height,
+            bit_depth,
+            has_alpha: has_alpha || animation.is_some_and(|a| a.alpha_sample_table.is_some()),
+            is_animated: animation.is_some(),
+            frame_count: animation.map_or(0, |a| a.color_sample_table.sample_sizes.len()),
+        })
+    }
+}
+
+/// Parsed structure from the box-level parse pass (no mdat data).
+#[derive(Default)]
+struct ParsedStructure {
+    /// `None` for pure AVIF sequences (`avis` brand) that have only `moov`+`mdat`.
+    meta: Option<AvifInternalMeta>,
+    /// A `meta` box that locates no items was found; only a `moov` can
+    /// stand in for it.
+    itemless_meta: bool,
+    mdat_bounds: TryVec<MdatBounds>,
+    animation_data: Option<ParsedAnimationData>,
+    major_brand: [u8; 4],
+    compatible_brands: alloc::vec::Vec<[u8; 4]>,
+    /// The input ended inside a top-level box (only with `allow_truncated`).
+    truncated: bool,
+    warnings: TryVec<ParseWarning>,
+    /// Top-level `uuid` boxes.
+    uuid_boxes: TryVec<UuidBox>,
+    /// With [`DecodeConfig::preserve_layout`].
+    layout: Option<FileLayout>,
+    /// Box headers read, at every level.
+    box_count: u64,
+    started: Option<Instant>,
+}
+
+impl ParsedStructure {
+    /// Whether the boxes an image needs have been found: `ftyp`, which is
+    /// checked first, and `meta` or `moov`.
+    fn has_structure(&self) -> bool {
+        self.meta.is_some() || self.animation_data.is_some()
+    }
+
+    /// [`ParseContext::tolerate`] for problems between top-level boxes.
+    fn tolerate(&mut self, config: &DecodeConfig, kind: ParseWarningKind, fourcc: &FourCC, offset: u64, err: At<Error>) -> Result<()> {
+        let ctx = ParseContext::new(config);
+        ctx.tolerate(kind, fourcc, offset, err)?;
+        self.warnings.append(&mut ctx.into_warnings()).map_err(|e| at!(Error::from(e)))
+    }
+}
+
+/// Padding boxes that some writers put before `ftyp`.
+fn is_padding_box(name: BoxType) -> bool {
+    matches!(name, BoxType::FreeSpaceBox | BoxType::SkipBox | BoxType::WideBox)
+}
+
+/// Whether `err` means the bytes are not valid boxes, which can be trailing
+/// garbage, rather than a limit, I/O failure or cancellation that must stop the parse.
+fn is_malformed_data(err: &Error) -> bool {
+    matches!(err.root(), Error::InvalidData(_) | Error::UnexpectedEOF)
+}
+
+/// Whether a top-level box that runs past the end of the input is garbage
+/// rather than a truncated file.
+fn is_trailing_garbage(name: BoxType, parsed: &ParsedStructure) -> bool {
+    parsed.has_structure()
+        && !matches!(
+            name,
+            BoxType::MediaDataBox | BoxType::MetadataBox | BoxType::MinimizedImageBox | BoxType::MovieBox | BoxType::FileTypeBox
+        )
+}
+
+impl<'data> AvifParser<'data> {
+    // ========================================
+    // Constructors
+    // ========================================
+
+    fn new(state: ParserState<'data>) -> Self {
+        Self { inner: alloc::sync::Arc::new(state) }
+    }
+
+    /// Parse AVIF from a borrowed byte slice (true zero-copy).
+    ///
+    /// The returned parser borrows `data` — single-extent items will be
+    /// returned as `Cow::Borrowed` slices into this buffer.
+    pub fn from_bytes(data: &'data [u8]) -> Result<Self> {
+        Self::from_bytes_with_config(data, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from a borrowed byte slice with resource limits.
+    pub fn from_bytes_with_config(
+        data: &'data [u8],
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<Self> {
+        let parsed = Self::parse_raw(data, config, stop)?;
+        Self::build(RawData::Bytes(Cow::Borrowed(data)), parsed, config)
+    }
+
+    /// Parse AVIF from an owned buffer.
+    ///
+    /// The returned parser owns the data — single-extent items will still
+    /// be returned as `Cow::Borrowed` slices (borrowing from the internal buffer).
+    pub fn from_owned(data: alloc::vec::Vec<u8>) -> Result<AvifParser<'static>> {
+        AvifParser::from_owned_with_config(data, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from an owned buffer with resource limits.
+    pub fn from_owned_with_config(
+        data: alloc::vec::Vec<u8>,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
     ) -> Result<AvifParser<'static>> {
-        let buf = if let Some(limit) = config.peak_memory_limit {
-            let mut limited = reader.take(limit.saturating_add(1));
-            let mut buf = std::vec::Vec::new();
-            limited.read_to_end(&mut buf).map_err(|e| at!(Error::from(e)))?;
-            if buf.len() as u64 > limit {
-                return Err(at!(Error::ResourceLimitExceeded(
-                    "input exceeds peak_memory_limit",
-                )));
-            }
-            buf
-        } else {
-            let mut buf = std::vec::Vec::new();
-            reader.read_to_end(&mut buf).map_err(|e| at!(Error::from(e)))?;
-            buf
-        };
+        let parsed = AvifParser::parse_raw(&data, config, stop)?;
+        AvifParser::build(RawData::Bytes(Cow::Owned(data)), parsed, config)
+    }
+
+    /// Parse AVIF from a reader (reads all bytes, then parses).
+    pub fn from_reader<R: Read + ?Sized>(reader: &mut R) -> Result<AvifParser<'static>> {
+        AvifParser::from_reader_with_config(reader, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from a reader with resource limits.
+    ///
+    /// The input is read in chunks and `stop` is checked before each one, so
+    /// a slow or endless reader can be cancelled. Reading stops with
+    /// [`Error::ResourceLimitExceeded`] once the input exceeds
+    /// `config.max_input_bytes` or `config.peak_memory_limit`.
+    pub fn from_reader_with_config<R: Read + ?Sized>(
+        reader: &mut R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<AvifParser<'static>> {
+        AvifParser::from_reader_with_progress(reader, config, stop, |_| {})
+    }
+
+    /// Parse AVIF from a reader with resource limits, calling `progress`
+    /// with the number of bytes read so far after every chunk.
+    pub fn from_reader_with_progress<R: Read + ?Sized>(
+        reader: &mut R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+        mut progress: impl FnMut(u64),
+    ) -> Result<AvifParser<'static>> {
+        const CHUNK: usize = 64 * 1024;
+        // One scratch chunk is zeroed once and reused for every read; the
+        // input buffer only grows by the bytes actually read.
+        let mut chunk = alloc::vec![0u8; CHUNK];
+        let mut buf = alloc::vec::Vec::new();
+        loop {
+            stop.check().map_err(|e| at!(Error::from(e)))?;
+
+            let n = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(at!(Error::from(e))),
+            };
+            buf.try_reserve(n).map_err(|_| at!(Error::OutOfMemory))?;
+            buf.extend_from_slice(&chunk[..n]);
+            config.check_input_len(buf.len().to_u64())?;
+            progress(buf.len().to_u64());
+        }
         AvifParser::from_owned_with_config(buf, config, stop)
     }
 
+    /// Parse AVIF from an async reader (reads all bytes, then parses).
+    ///
+    /// Works with any [`futures_io::AsyncRead`]; tokio readers can be adapted
+    /// with `tokio_util::compat`.
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader<R: futures_io::AsyncRead + Unpin>(reader: R) -> Result<AvifParser<'static>> {
+        AvifParser::from_async_reader_with_config(reader, &DecodeConfig::default(), &Unstoppable).await
+    }
+
+    /// Parse AVIF from an async reader with resource limits.
+    ///
+    /// `stop` is checked before every read, so a cancelled upload stops being
+    /// consumed at the next chunk. As with
+    /// [`from_reader_with_config`](Self::from_reader_with_config), reading is
+    /// capped at `config.max_input_bytes` and `config.peak_memory_limit`.
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader_with_config<R: futures_io::AsyncRead + Unpin>(
+        mut reader: R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<AvifParser<'static>> {
+        const CHUNK: usize = 64 * 1024;
+        let mut chunk = alloc::vec![0u8; CHUNK];
+        let mut buf = alloc::vec::Vec::new();
+        loop {
+            stop.check().map_err(|e| at!(Error::from(e)))?;
+
+            let read = core::future::poll_fn(|cx| core::pin::Pin::new(&mut reader).poll_read(cx, &mut chunk)).await;
+            let n = match read {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(at!(Error::from(e))),
+            };
+            buf.try_reserve(n).map_err(|_| at!(Error::OutOfMemory))?;
+            buf.extend_from_slice(&chunk[..n]);
+            config.check_input_len(buf.len().to_u64())?;
+        }
+        AvifParser::from_owned_with_config(buf, config, stop)
+    }
+
+    /// Parse AVIF from a file by memory-mapping it.
+    ///
+    /// The mapping is parsed exactly like [`from_bytes`](Self::from_bytes),
+    /// so single-extent items are returned as `Cow::Borrowed` slices of the
+    /// mapping and the file contents never need to be copied to the heap.
+    ///
+    /// The file must not be truncated or modified while the parser is alive;
+    /// on most platforms that is undefined behavior for any memory map.
+    #[cfg(feature = "mmap")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<AvifParser<'static>> {
+        AvifParser::from_file_with_config(path, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from a memory-mapped file with resource limits.
+    #[cfg(feature = "mmap")]
+    #[allow(unsafe_code)]
+    pub fn from_file_with_config<P: AsRef<std::path::Path>>(
+        path: P,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<AvifParser<'static>> {
+        let file = std::fs::File::open(path).map_err(|e| at!(Error::from(e)))?;
+        // SAFETY: the mapping is read-only and owned by the parser. Concurrent
+        // modification of the underlying file is documented as unsupported.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| at!(Error::from(e)))?;
+        let parsed = AvifParser::parse_raw(&map, config, stop)?;
+        AvifParser::build(RawData::Mapped(map), parsed, config)
+    }
+
+    /// Parse AVIF from a reference-counted [`bytes::Bytes`] buffer.
+    ///
+    /// Besides the usual `Cow` accessors, which borrow from the buffer, the
+    /// `*_bytes` accessors ([`primary_bytes`](Self::primary_bytes),
+    /// [`frame_bytes`](Self::frame_bytes), ...) return `Bytes` slices of it:
+    /// no copy, and the payload can be sent to another thread or kept after
+    /// the parser is dropped.
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes_shared(data: bytes::Bytes) -> Result<AvifParser<'static>> {
+        AvifParser::from_bytes_shared_with_config(data, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from a reference-counted buffer with resource limits.
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes_shared_with_config(
+        data: bytes::Bytes,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<AvifParser<'static>> {
+        let parsed = AvifParser::parse_raw(&data, config, stop)?;
+        AvifParser::build(RawData::Shared(data), parsed, config)
+    }
+
+    /// Parse AVIF from a seekable reader without buffering the whole file.
+    ///
+    /// Only the structural boxes (`ftyp`, `meta`, `moov`) are read during
+    /// parsing; `mdat` contents are skipped with a seek. Item and frame data
+    /// are read from `reader` on demand, so data accessors return
+    /// `Cow::Owned` buffers. The parser owns the reader, which must not
+    /// borrow anything; parse a borrowed slice with [`from_bytes`](Self::from_bytes).
+    #[cfg(feature = "std")]
+    pub fn from_seekable<R: Read + Seek + Send + 'static>(reader: R) -> Result<AvifParser<'static>> {
+        AvifParser::from_seekable_with_config(reader, &DecodeConfig::default(), &Unstoppable)
+    }
+
+    /// Parse AVIF from a seekable reader with resource limits.
+    ///
+    /// If `config.peak_memory_limit` is set, it also bounds the size of each
+    /// structural box that is read into memory.
+    #[cfg(feature = "std")]
+    pub fn from_seekable_with_config<R: Read + Seek + Send + 'static>(
+        mut reader: R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<AvifParser<'static>> {
+        let (parsed, len) = AvifParser::parse_seekable(&mut reader, config, stop)?;
+        let raw = RawData::Seekable { reader: std::sync::Mutex::new(alloc::boxed::Box::new(reader)), len };
+        AvifParser::build(raw, parsed, config)
+    }
+
     // ========================================
     // Internal: parse pass (records offsets, no mdat copy)
     // ========================================
@@ -1682,100 +3920,417 @@ impl<'data> AvifParser<'data> {
     /// Parse the AVIF box structure from raw bytes, recording mdat offsets
     /// without copying mdat content.
     fn parse_raw(data: &[u8], config: &DecodeConfig, stop: &dyn Stop) -> Result<ParsedStructure> {
-        let parse_opts = ParseOptions { lenient: config.lenient };
-        let mut cursor = std::io::Cursor::new(data);
+        let started = now();
+        let budget = TimeBudget::new(stop, config.time_budget)?;
+        let mut parsed = Self::parse_raw_within(data, config, &budget).map_err(|e| budget.classify(e))?;
+        parsed.started = started;
+        Ok(parsed)
+    }
+
+    fn parse_raw_within(data: &[u8], config: &DecodeConfig, stop: &dyn Stop) -> Result<ParsedStructure> {
+        let mut cursor = io::Cursor::new(data);
         let mut f = OffsetReader::new(&mut cursor);
-        let mut iter = BoxIter::with_max_remaining(&mut f, data.len() as u64);
+        let mut iter = BoxIter::with_max_remaining(&mut f, data.len() as u64)
+            .with_max_depth(config.max_box_depth)
+            .with_max_boxes(config.max_total_boxes)
+            .with_handler(config.box_handler.clone())
+            .with_layout(config.preserve_layout);
+
+        let mut parsed = ParsedStructure::default();
 
         // 'ftyp' box must occur first; see ISO 14496-12:2015 § 4.3.1
-        let (major_brand, compatible_brands) = if let Some(mut b) = iter.next_box()? {
-            if b.head.name == BoxType::FileTypeBox {
-                let ftyp = read_ftyp(&mut b)?;
-                if ftyp.major_brand != b"avif" && ftyp.major_brand != b"avis" {
-                    return Err(at!(Error::InvalidData("ftyp must be 'avif' or 'avis'")));
+        loop {
+            match iter.next_box()? {
+                Some(mut b) if b.head.name == BoxType::FileTypeBox => {
+                    Self::parse_top_level_box(&mut b, &mut parsed, config, stop)?;
+                    break;
                 }
-                let major = ftyp.major_brand.value;
-                let compat = ftyp.compatible_brands.iter().map(|b| b.value).collect();
-                (major, compat)
-            } else {
-                return Err(at!(Error::InvalidData("'ftyp' box must occur first")));
+                Some(mut b) if is_padding_box(b.head.name) => {
+                    parsed.tolerate(
+                        config, ParseWarningKind::BoxBeforeFileType, &b.head.name.into(), b.start_offset(),
+                        at!(Error::InvalidData("'ftyp' box must occur first")),
+                    )?;
+                    skip_box_content(&mut b)?;
+                }
+                _ => return Err(at!(Error::InvalidData("'ftyp' box must occur first"))),
             }
-        } else {
-            return Err(at!(Error::InvalidData("'ftyp' box must occur first")));
-        };
+        }
 
-        let mut meta = None;
-        let mut mdat_bounds = TryVec::new();
-        let mut animation_data: Option<ParsedAnimationData> = None;
+        let mut trailing_garbage = false;
+        loop {
+            let pos = data.len().to_u64() - iter.remaining();
+            let mut b = match iter.next_box() {
+                Ok(Some(b)) => b,
+                Ok(None) => break,
+                Err(e) if parsed.has_structure() && is_malformed_data(e.error()) => {
+                    let fourcc = usize::try_from(pos).ok()
+                        .and_then(|pos| data.get(pos + 4..pos + 8))
+                        .map_or([0; 4], |t| [t[0], t[1], t[2], t[3]]);
+                    parsed.tolerate(config, ParseWarningKind::TrailingData, &fourcc.into(), pos, e)?;
+                    trailing_garbage = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            stop.check().map_err(|e| at!(Error::from(e)))?;
 
-        while let Some(mut b) = iter.next_box()? {
+            // `BoxIter` clamps a box claiming more bytes than remain
+            let is_truncated = b.head.size - b.head.offset > b.bytes_left();
+            if is_truncated && !config.allow_truncated && is_trailing_garbage(b.head.name, &parsed) {
+                parsed.tolerate(
+                    config, ParseWarningKind::TrailingData, &b.head.name.into(), pos,
+                    at!(Error::InvalidData("box content size mismatch")),
+                )?;
+                skip_box_remain(&mut b)?;
+                trailing_garbage = true;
+                break;
+            }
+            if is_truncated && config.allow_truncated && b.head.name != BoxType::MetadataBox && b.head.name != BoxType::MovieBox {
+                debug!("{:?} truncated at {} bytes", b.head, b.bytes_left());
+                parsed.truncated = true;
+                if b.head.name == BoxType::MediaDataBox && b.bytes_left() > 0 {
+                    let (offset, length) = (b.offset(), b.bytes_left());
+                    parsed.mdat_bounds.push(MdatBounds { offset, length }).map_err(|e| at!(Error::from(e)))?;
+                }
+                skip_box_remain(&mut b)?;
+                continue;
+            }
+
+            match b.head.name {
+                BoxType::MediaDataBox => {
+                    if b.bytes_left() > 0 {
+                        let offset = b.offset();
+                        let length = b.bytes_left();
+                        parsed.mdat_bounds.push(MdatBounds { offset, length }).map_err(|e| at!(Error::from(e)))?;
+                    }
+                    // Skip the content — we'll slice into raw later
+                    skip_box_content(&mut b)?;
+                }
+                BoxType::FileTypeBox => skip_box_content(&mut b)?,
+                _ => Self::parse_top_level_box(&mut b, &mut parsed, config, stop)?,
+            }
+
+            check_parser_state(&b.head, &b.content)?;
+        }
+        parsed.box_count = iter.box_count();
+        parsed.layout = iter.take_layout().map(|boxes| FileLayout { boxes, file_size: data.len().to_u64() });
+
+        // A partial box header at the end is ignored, but still means the input is cut short
+        if config.allow_truncated && !trailing_garbage && f.offset() < data.len().to_u64() {
+            parsed.truncated = true;
+        }
+
+        Self::check_parsed_structure(&parsed)?;
+        Ok(parsed)
+    }
+
+    /// Parse the AVIF box structure from a seekable reader, buffering only
+    /// the structural boxes and seeking past everything else.
+    ///
+    /// Returns the structure and the total length of the stream.
+    fn parse_seekable<R: Read + Seek>(
+        reader: &mut R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<(ParsedStructure, u64)> {
+        let started = now();
+        let budget = TimeBudget::new(stop, config.time_budget)?;
+        let (mut parsed, len) = Self::parse_seekable_within(reader, config, &budget).map_err(|e| budget.classify(e))?;
+        parsed.started = started;
+        Ok((parsed, len))
+    }
+
+    fn parse_seekable_within<R: Read + Seek>(
+        reader: &mut R,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<(ParsedStructure, u64)> {
+        let len = reader.seek(SeekFrom::End(0)).map_err(|e| at!(Error::from(e)))?;
+        let mut parsed = ParsedStructure::default();
+        let mut pos = 0u64;
+        let mut seen_ftyp = false;
+        let mut layout = config.preserve_layout.then(|| FileLayout { boxes: alloc::vec::Vec::new(), file_size: len });
+
+        while pos < len {
             stop.check().map_err(|e| at!(Error::from(e)))?;
 
+            reader.seek(SeekFrom::Start(pos)).map_err(|e| at!(Error::from(e)))?;
+            let mut head = match read_box_header(reader) {
+                Ok(head) => head,
+                Err(e) if matches!(e.error(), Error::UnexpectedEOF) => break,
+                Err(e) if parsed.has_structure() && is_malformed_data(e.error()) => {
+                    let mut fourcc = [0; 4];
+                    let _ = reader.seek(SeekFrom::Start(pos + 4)).map(|_| reader.read_exact(&mut fourcc));
+                    parsed.tolerate(config, ParseWarningKind::TrailingData, &fourcc.into(), pos, e)?;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            parsed.box_count += 1;
+            if config.max_total_boxes.is_some_and(|limit| parsed.box_count > limit) {
+                return Err(at!(Error::ResourceLimitExceeded("box count exceeds max_total_boxes")));
+            }
+            // size=0 (u64::MAX) extends to EOF; oversized claims are clamped like `BoxIter`
+            if head.size == u64::MAX {
+                head.size = len - pos;
+            }
+            let box_len = head.size.min(len - pos);
+            if head.size > box_len {
+                if !config.allow_truncated {
+                    if is_trailing_garbage(head.name, &parsed) {
+                        parsed.tolerate(
+                            config, ParseWarningKind::TrailingData, &head.name.into(), pos,
+                            at!(Error::InvalidData("box content size mismatch")),
+                        )?;
+                        break;
+                    }
+                    return Err(at!(Error::InvalidData("box content size mismatch")));
+                }
+                debug!("{head:?} truncated at {box_len} bytes");
+                parsed.truncated = true;
+            }
+
+            // 'ftyp' box must occur first; see ISO 14496-12:2015 § 4.3.1
+            if !seen_ftyp && head.name != BoxType::FileTypeBox {
+                if !is_padding_box(head.name) {
+                    return Err(at!(Error::InvalidData("'ftyp' box must occur first")));
+                }
+                parsed.tolerate(
+                    config, ParseWarningKind::BoxBeforeFileType, &head.name.into(), pos,
+                    at!(Error::InvalidData("'ftyp' box must occur first")),
+                )?;
+            }
+            let first_ftyp = !seen_ftyp && head.name == BoxType::FileTypeBox;
+
+            // Like `offer_to_handler`, for boxes not read into memory
+            if let Some(handler) = &config.box_handler
+                && matches!(head.name, BoxType::UnknownBox(_) | BoxType::UuidBox) {
+                    let mut payload = (&mut *reader).take(box_len.saturating_sub(head.offset));
+                    handler
+                        .unknown_box(&[FourCC::from(head.name).value], head.uuid.as_ref(), &mut payload)
+                        .map_err(|e| at!(Error::from(e)))?;
+                }
+
+            let structural = first_ftyp || matches!(head.name, BoxType::MetadataBox | BoxType::MinimizedImageBox | BoxType::MovieBox);
+            // Structural boxes are recorded by the iterator that parses them
+            if let Some(layout) = &mut layout
+                && !structural {
+                    layout.boxes.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                    layout.boxes.push(BoxLayout::new(&head, 1, pos, box_len));
+                }
+
+            match head.name {
+                BoxType::MediaDataBox => {
+                    let length = box_len.saturating_sub(head.offset);
+                    if length > 0 {
+                        parsed.mdat_bounds.push(MdatBounds { offset: pos + head.offset, length }).map_err(|e| at!(Error::from(e)))?;
+                    }
+                }
+                BoxType::FileTypeBox if !first_ftyp => debug!("{head:?} (skipped)"),
+                BoxType::FileTypeBox | BoxType::MetadataBox | BoxType::MinimizedImageBox | BoxType::MovieBox => {
+                    if let Some(limit) = config.peak_memory_limit
+                        && box_len > limit {
+                            return Err(at!(Error::ResourceLimitExceeded("peak memory limit exceeded")));
+                        }
+                    let size = usize::try_from(box_len).map_err(|e| at!(Error::from(e)))?;
+                    let mut buf = alloc::vec::Vec::new();
+                    buf.try_reserve_exact(size).map_err(|_| at!(Error::OutOfMemory))?;
+                    buf.resize(size, 0);
+                    reader.seek(SeekFrom::Start(pos)).map_err(|e| at!(Error::from(e)))?;
+                    reader.read_exact(&mut buf).map_err(|e| at!(Error::from(e)))?;
+
+                    // Offsets recorded while parsing stay relative to the start of the file
+                    let mut cursor = io::Cursor::new(buf.as_slice());
+                    let mut f = OffsetReader { reader: &mut cursor, offset: pos };
+                    // This iterator counts the top-level box again
+                    let boxes_left = config.max_total_boxes.map(|limit| limit - (parsed.box_count - 1));
+                    let mut iter = BoxIter::with_max_remaining(&mut f, box_len)
+                        .starting_at(pos)
+                        .with_max_depth(config.max_box_depth)
+                        .with_max_boxes(boxes_left)
+                        .with_handler(config.box_handler.clone())
+                        .with_layout(layout.is_some());
+                    if let Some(mut b) = iter.next_box()? {
+                        Self::parse_top_level_box(&mut b, &mut parsed, config, stop)?;
+                        check_parser_state(&b.head, &b.content)?;
+                    }
+                    // The top-level box itself is already counted
+                    parsed.box_count += iter.box_count().saturating_sub(1);
+                    if let (Some(layout), Some(mut boxes)) = (&mut layout, iter.take_layout()) {
+                        layout.boxes.try_reserve(boxes.len()).map_err(|_| at!(Error::OutOfMemory))?;
+                        layout.boxes.append(&mut boxes);
+                    }
+                }
+                BoxType::UuidBox => {
+                    if let Some(uuid) = head.uuid {
+                        let payload = pos + head.offset.min(box_len)..pos + box_len;
+                        parsed.uuid_boxes.push(UuidBox { uuid, in_meta: false, payload }).map_err(|e| at!(Error::from(e)))?;
+                    }
+                }
+                _ => debug!("{head:?} (skipped)"),
+            }
+
+            seen_ftyp |= first_ftyp;
+            pos += box_len;
+        }
+
+        if !seen_ftyp {
+            return Err(at!(Error::InvalidData("'ftyp' box must occur first")));
+        }
+        parsed.layout = layout;
+        Self::check_parsed_structure(&parsed)?;
+        Ok((parsed, len))
+    }
+
+    /// Parse a structural top-level box (`ftyp`, `meta`, `mini`, `moov`) into `parsed`;
+    /// anything else is skipped.
+    fn parse_top_level_box<T: Read + Offset>(
+        b: &mut BMFFBox<'_, T>,
+        parsed: &mut ParsedStructure,
+        config: &DecodeConfig,
+        stop: &dyn Stop,
+    ) -> Result<()> {
+        in_box(b, |b| {
             match b.head.name {
+                BoxType::FileTypeBox => {
+                    let ftyp = read_ftyp(b)?;
+                    if !is_supported_major_brand(&ftyp) {
+                        return Err(at!(Error::InvalidData(UNSUPPORTED_BRAND)));
+                    }
+                    if config.effective_strictness() == Strictness::Pedantic {
+                        check_structural_brand(&ftyp)?;
+                    }
+                    parsed.major_brand = ftyp.major_brand.value;
+                    parsed.compatible_brands = ftyp.compatible_brands.iter().map(|b| b.value).collect();
+                }
                 BoxType::MetadataBox => {
-                    if meta.is_some() {
+                    if parsed.meta.is_some() || parsed.itemless_meta {
                         return Err(at!(Error::InvalidData(
                             "There should be zero or one meta boxes per ISO 14496-12:2015 § 8.11.1.1",
                         )));
                     }
-                    meta = Some(read_avif_meta(&mut b, &parse_opts)?);
+                    let ctx = ParseContext::new(config);
+                    parsed.meta = read_avif_meta(b, &ctx, config.select_alternative, stop)?;
+                    parsed.itemless_meta = parsed.meta.is_none();
+                    parsed.warnings.append(&mut ctx.into_warnings()).map_err(|e| at!(Error::from(e)))?;
+                }
+                BoxType::MinimizedImageBox => {
+                    if parsed.meta.is_some() || parsed.itemless_meta {
+                        return Err(at!(Error::InvalidData("a file may have a meta box or a mini box, not both")));
+                    }
+                    let offset = b.offset();
+                    let payload = b.read_into_try_vec().map_err(|e| at!(Error::from(e)))?;
+                    let (meta, chunks) = read_mini(&payload, offset)?;
+                    parsed.meta = Some(meta);
+                    parsed.mdat_bounds.push(chunks).map_err(|e| at!(Error::from(e)))?;
                 }
                 BoxType::MovieBox => {
-                    let tracks = read_moov(&mut b, stop)?;
+                    let ctx = ParseContext::new(config);
+                    let tracks = read_moov(b, &ctx, stop)?;
+                    parsed.warnings.append(&mut ctx.into_warnings()).map_err(|e| at!(Error::from(e)))?;
                     if !tracks.is_empty() {
-                        animation_data = Some(associate_tracks(tracks)?);
+                        parsed.animation_data = Some(associate_tracks(tracks, config.track_id)?);
                     }
                 }
-                BoxType::MediaDataBox => {
-                    if b.bytes_left() > 0 {
-                        let offset = b.offset();
-                        let length = b.bytes_left();
-                        mdat_bounds.push(MdatBounds { offset, length }).map_err(|e| at!(Error::from(e)))?;
+                BoxType::UuidBox => {
+                    if let Some(uuid) = UuidBox::from_box(b, false) {
+                        parsed.uuid_boxes.push(uuid).map_err(|e| at!(Error::from(e)))?;
                     }
-                    // Skip the content — we'll slice into raw later
-                    skip_box_content(&mut b)?;
+                    skip_box_content(b)?;
                 }
-                _ => skip_box_content(&mut b)?,
+                _ => skip_box_content(b)?,
             }
+            Ok(())
+        })
+    }
 
-            check_parser_state(&b.head, &b.content)?;
-        }
-
+    fn check_parsed_structure(parsed: &ParsedStructure) -> Result<()> {
         // meta is required for still images, but pure AVIF sequences (avis brand)
         // can have only moov+mdat with no meta box.
-        if meta.is_none() && animation_data.is_none() {
+        if !parsed.has_structure() {
+            // Writers may put mdat first, so a cut-off input can still hold meta
+            if parsed.truncated {
+                return Err(at!(Error::DataNotAvailable));
+            }
+            if parsed.itemless_meta {
+                return Err(at!(Error::InvalidData(ITEMLESS_META)));
+            }
             return Err(at!(Error::InvalidData("missing meta")));
         }
-
-        Ok(ParsedStructure { meta, mdat_bounds, animation_data, major_brand, compatible_brands })
+        Ok(())
     }
 
     /// Build an AvifParser from raw bytes + parsed structure.
-    fn build(raw: Cow<'data, [u8]>, parsed: ParsedStructure, config: &DecodeConfig) -> Result<Self> {
-        let tracker = ResourceTracker::new(config);
+    fn build(raw: RawData<'data>, parsed: ParsedStructure, config: &DecodeConfig) -> Result<Self> {
+        let mut warnings = parsed.warnings;
+        let mut uuid_boxes = parsed.uuid_boxes;
+        let layout = parsed.layout;
+        let mut tracker = ResourceTracker::new(config);
+
+        // An owned input buffer lives as long as the parser; borrowed, mapped and shared input is not ours
+        if let RawData::Bytes(Cow::Owned(data)) = &raw {
+            tracker.reserve(data.len().to_u64())?;
+        }
+
+        let mut stats = ParseStats {
+            boxes: parsed.box_count,
+            mdat_count: parsed.mdat_bounds.len(),
+            mdat_bytes: parsed.mdat_bounds.iter().map(|mdat| mdat.length).sum(),
+            ..ParseStats::default()
+        };
 
         // Store animation metadata if present
         let animation_data = if let Some(anim) = parsed.animation_data {
             tracker.validate_animation_frames(anim.color_sample_table.sample_sizes.len() as u32)?;
             Some(AnimationParserData {
+                track_id: anim.color_track_id,
+                alpha_track_id: anim.alpha_track_id,
+                tracks: anim.tracks,
+                other_tracks: anim.other_tracks,
                 media_timescale: anim.color_timescale,
                 sample_table: anim.color_sample_table,
                 alpha_media_timescale: anim.alpha_timescale,
                 alpha_sample_table: anim.alpha_sample_table,
                 loop_count: anim.loop_count,
+                duration: anim.color_duration,
                 codec_config: anim.color_codec_config,
             })
         } else {
             None
         };
 
-        // Pure sequence (no meta box): only animation methods will work.
+        // The alpha track must be the same size, depth and length as the color track
+        if let Some(anim) = &animation_data
+            && let Some(alpha_track_id) = anim.alpha_track_id
+        {
+            let track = |id| anim.tracks.iter().find(|t| t.track_id == id);
+            if let (Some(color), Some(alpha)) = (track(anim.track_id), track(alpha_track_id)) {
+                let same_size = (color.width, color.height) == (alpha.width, alpha.height);
+                check_alpha_consistent(same_size, b"tkhd", "alpha track size differs from the color track", config, &mut warnings)?;
+                if let (Some(color_av1c), Some(alpha_av1c)) = (&color.av1_config, &alpha.av1_config) {
+                    let same_depth = color_av1c.bit_depth == alpha_av1c.bit_depth;
+                    check_alpha_consistent(same_depth, b"av1C", "alpha track bit depth differs from the color track", config, &mut warnings)?;
+                }
+                let same_length = color.frame_count == alpha.frame_count;
+                check_alpha_consistent(same_length, b"stsz", "alpha track frame count differs from the color track", config, &mut warnings)?;
+            }
+        }
+
+        // Before `item_offset` constructions are flattened into aliases of their sources
+        if config.detect_overlapping_extents {
+            report_overlapping_extents(
+                parsed.meta.as_ref(), animation_data.as_ref(), &parsed.mdat_bounds, raw.len(), &mut warnings,
+            )?;
+        }
+
+        // Pure sequence (no meta box, or one without a still image): the
+        // item accessors fall back to the first frame.
         // Use codec config from the color track's stsd if available.
-        let Some(meta) = parsed.meta else {
+        let Some(mut meta) = parsed.meta else {
             let track_config = animation_data.as_ref()
                 .map(|a| a.codec_config.clone())
                 .unwrap_or_default();
-            return Ok(Self {
+            return Ok(Self::new(ParserState {
                 raw,
                 mdat_bounds: parsed.mdat_bounds,
                 idat: None,
@@ -1783,27 +4338,47 @@ impl<'data> AvifParser<'data> {
                 alpha: None,
                 grid_config: None,
                 tiles: TryVec::new(),
+                tile_spatial_extents: None,
+                overlay_config: None,
+                overlay_inputs: TryVec::new(),
+                sample_transform: None,
+                sample_transform_inputs: TryVec::new(),
+                alternatives: alloc::vec::Vec::new(),
                 animation_data,
                 premultiplied_alpha: false,
                 spatial_extents: None,
                 av1_config: track_config.av1_config,
+                alpha_av1_config: None,
+                tile_av1_configs: alloc::vec::Vec::new(),
+                #[cfg(feature = "heif")]
+                hevc_config: None,
                 color_info: track_config.color_info,
+                icc_profile: track_config.icc_profile,
                 rotation: None,
                 mirror: None,
                 clean_aperture: None,
-                pixel_aspect_ratio: None,
-                content_light_level: None,
-                mastering_display: None,
-                content_colour_volume: None,
-                ambient_viewing: None,
+                pixel_aspect_ratio: track_config.pixel_aspect_ratio,
+                content_light_level: track_config.content_light_level,
+                mastering_display: track_config.mastering_display,
+                content_colour_volume: track_config.content_colour_volume,
+                ambient_viewing: track_config.ambient_viewing,
                 operating_point: None,
                 layer_selector: None,
                 layered_image_indexing: None,
+                user_descriptions: alloc::vec::Vec::new(),
+                pixel_information: None,
+                alpha_pixel_information: None,
                 exif_item: None,
+                exif_headerless: false,
                 xmp_item: None,
+                thumbnail: None,
+                thumbnail_spatial_extents: None,
+                thumbnail_av1_config: None,
                 gain_map_metadata: None,
                 gain_map: None,
                 gain_map_color_info: None,
+                gain_map_spatial_extents: None,
+                gain_map_av1_config: None,
                 depth_item: None,
                 depth_width: 0,
                 depth_height: 0,
@@ -1811,37 +4386,47 @@ impl<'data> AvifParser<'data> {
                 depth_color_info: None,
                 major_brand: parsed.major_brand,
                 compatible_brands: parsed.compatible_brands,
-            });
+                items: alloc::vec::Vec::new(),
+                auxiliary_items: alloc::vec::Vec::new(),
+                references: alloc::vec::Vec::new(),
+                uuid_boxes,
+                protected_items: TryVec::new(),
+                layout,
+                truncated: parsed.truncated,
+                warnings,
+                stats: ParseStats {
+                    peak_memory: tracker.peak_memory,
+                    parse_duration: parsed.started.map(|started| started.elapsed()),
+                    ..stats
+                },
+            }));
         };
 
+        uuid_boxes.append(&mut meta.uuid_boxes).map_err(|e| at!(Error::from(e)))?;
+        uuid_boxes.sort_by_key(|b| b.payload.start);
+
+        stats.items = meta.item_infos.len();
+        stats.properties = meta.property_store.len();
+        stats.extents = meta.iloc_items.iter().map(|item| item.extents.len()).sum();
+
+        // Before flattening, which turns `item_offset` constructions into aliases of their sources
+        if config.validate_extents {
+            validate_item_extents(&meta, &parsed.mdat_bounds, parsed.truncated)?;
+        }
+        flatten_item_constructions(&mut meta, &tracker)?;
+
         // Get primary item extents
         let primary = Self::get_item_extents(&meta, meta.primary_item_id)?;
 
         // Find alpha item and get its extents
-        let alpha_item_id = meta
-            .item_references
-            .iter()
-            .filter(|iref| {
-                iref.to_item_id == meta.primary_item_id
-                    && iref.from_item_id != meta.primary_item_id
-                    && iref.item_type == b"auxl"
-            })
-            .map(|iref| iref.from_item_id)
-            .find(|&item_id| {
-                meta.properties.iter().any(|prop| {
-                    prop.item_id == item_id
-                        && match &prop.property {
-                            ItemProperty::AuxiliaryType(urn) => {
-                                urn.type_subtype().0 == b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha"
-                            }
-                            _ => false,
-                        }
-                })
-            });
+        let alpha_item_id = alpha_item_id(&meta);
 
         let alpha = alpha_item_id
             .map(|id| Self::get_item_extents(&meta, id))
             .transpose()?;
+        if let (Some(id), Some(alpha)) = (alpha_item_id, &alpha) {
+            Self::validate_image_size(&tracker, &meta, &raw, id, alpha)?;
+        }
 
         // Check for premultiplied alpha
         let premultiplied_alpha = alpha_item_id.is_some_and(|alpha_id| {
@@ -1864,12 +4449,12 @@ impl<'data> AvifParser<'data> {
             .map(|iref| iref.from_item_id)
             .find(|&item_id| {
                 // Skip the alpha item if we already found one
-                if alpha_item_id == Some(item_id) {
+                if alpha_item_id == Some(item_id) || meta.is_protected(item_id) {
                     return false;
                 }
                 meta.properties.iter().any(|prop| {
                     prop.item_id == item_id
-                        && match &prop.property {
+                        && match meta.property(prop) {
                             ItemProperty::AuxiliaryType(urn) => {
                                 is_depth_auxiliary_urn(urn.type_subtype().0)
                             }
@@ -1884,7 +4469,7 @@ impl<'data> AvifParser<'data> {
                 // Get dimensions from ispe property
                 let dims = meta.properties.iter().find_map(|p| {
                     if p.item_id == depth_id {
-                        match &p.property {
+                        match meta.property(p) {
                             ItemProperty::ImageSpatialExtents(e) => Some((e.width, e.height)),
                             _ => None,
                         }
@@ -1896,7 +4481,7 @@ impl<'data> AvifParser<'data> {
                 // Get av1C property
                 let av1c = meta.properties.iter().find_map(|p| {
                     if p.item_id == depth_id {
-                        match &p.property {
+                        match meta.property(p) {
                             ItemProperty::AV1Config(c) => Some(c.clone()),
                             _ => None,
                         }
@@ -1907,7 +4492,7 @@ impl<'data> AvifParser<'data> {
                 // Get colr property
                 let colr = meta.properties.iter().find_map(|p| {
                     if p.item_id == depth_id {
-                        match &p.property {
+                        match meta.property(p) {
                             ItemProperty::ColorInformation(c) => Some(c.clone()),
                             _ => None,
                         }
@@ -1931,13 +4516,52 @@ impl<'data> AvifParser<'data> {
             let Some(info) = meta.item_infos.iter().find(|i| i.item_id == desc_item_id) else {
                 continue;
             };
+            if meta.is_protected(desc_item_id) {
+                continue;
+            }
             if info.item_type == b"Exif" && exif_item.is_none() {
                 exif_item = Some(Self::get_item_extents(&meta, desc_item_id)?);
-            } else if info.item_type == b"mime" && xmp_item.is_none() {
+            } else if info.is_xmp() && xmp_item.is_none() {
                 xmp_item = Some(Self::get_item_extents(&meta, desc_item_id)?);
             }
         }
 
+        // Find a thumbnail item: a coded image with a `thmb` reference to the primary item
+        let mut thumbnail = None;
+        let mut thumbnail_spatial_extents = None;
+        let mut thumbnail_av1_config = None;
+        for iref in meta.item_references.iter() {
+            if iref.to_item_id != meta.primary_item_id || iref.item_type != b"thmb" {
+                continue;
+            }
+            let thumb_id = iref.from_item_id;
+            if meta.is_protected(thumb_id)
+                || !meta.item_infos.iter().any(|i| i.item_id == thumb_id && is_coded_image_item(&i.item_type))
+            {
+                continue;
+            }
+            // A broken thumbnail is dropped; the primary image is still usable
+            thumbnail = match Self::get_item_extents(&meta, thumb_id) {
+                Ok(extents) => Some(extents),
+                Err(e) if is_malformed_data(e.error()) => {
+                    warn!("ignoring thumbnail item {thumb_id}: {}", e.error());
+                    let warning = ParseWarning { kind: ParseWarningKind::MalformedThumbnail, fourcc: *b"thmb", offset: 0 };
+                    warnings.push(warning).map_err(|e| at!(Error::from(e)))?;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            thumbnail_spatial_extents = property_for(&meta, thumb_id, |p| match p {
+                ItemProperty::ImageSpatialExtents(e) => Some(*e),
+                _ => None,
+            });
+            thumbnail_av1_config = property_for(&meta, thumb_id, |p| match p {
+                ItemProperty::AV1Config(c) => Some(c.clone()),
+                _ => None,
+            });
+            break;
+        }
+
         // Check if primary item is a grid (tiled image)
         let is_grid = meta
             .item_infos
@@ -1946,7 +4570,7 @@ impl<'data> AvifParser<'data> {
             .is_some_and(|info| info.item_type == b"grid");
 
         // Extract grid configuration and tile extents if this is a grid
-        let (grid_config, tiles) = if is_grid {
+        let (grid_config, tiles, tile_spatial_extents, tile_av1_configs) = if is_grid {
             let mut tiles_with_index: TryVec<(u32, u16)> = TryVec::new();
             for iref in meta.item_references.iter() {
                 if iref.from_item_id == meta.primary_item_id && iref.item_type == b"dimg" {
@@ -1967,7 +4591,12 @@ impl<'data> AvifParser<'data> {
                 tile_ids.push(*tile_id).map_err(|e| at!(Error::from(e)))?;
             }
 
-            let grid_config = Self::calculate_grid_config(&meta, &tile_ids)?;
+            // The grid item's payload is its ImageGrid descriptor, at most 12 bytes
+            let payload_len = Self::item_len(&raw, &parsed.mdat_bounds, meta.idat.as_deref(), &primary);
+            let payload = (payload_len <= 12)
+                .then(|| Self::resolve_extents_from_raw(&raw, &parsed.mdat_bounds, meta.idat.as_deref(), &primary, parsed.truncated))
+                .and_then(Result::ok);
+            let grid_config = Self::calculate_grid_config(&meta, &tile_ids, payload.as_deref(), config.allow_grid_fallback)?;
 
             // Enforce total_megapixels_limit on grid output dimensions on the
             // default zero-copy path (the eager path also calls this at
@@ -1978,7 +4607,7 @@ impl<'data> AvifParser<'data> {
             for (tile_id, _) in tiles_with_index.iter() {
                 for prop in meta.properties.iter() {
                     if prop.item_id == *tile_id {
-                        match &prop.property {
+                        match meta.property(prop) {
                             ItemProperty::Rotation(_)
                             | ItemProperty::Mirror(_)
                             | ItemProperty::CleanAperture(_) => {
@@ -1990,30 +4619,75 @@ impl<'data> AvifParser<'data> {
                 }
             }
 
-            (Some(grid_config), tile_extents)
+            // MIAF § 7.3.11.4.2: every tile has the same dimensions
+            let tile_spatial_extents = tiles_with_index.first().and_then(|&(tile_id, _)| {
+                property_for(&meta, tile_id, |p| match p {
+                    ItemProperty::ImageSpatialExtents(e) => Some(*e),
+                    _ => None,
+                })
+            });
+
+            // The distinct tile av1Cs, for profile_compliance
+            let mut tile_av1_configs: alloc::vec::Vec<AV1Config> = alloc::vec::Vec::new();
+            for (tile_id, _) in tiles_with_index.iter() {
+                let tile_config = property_for(&meta, *tile_id, |p| match p {
+                    ItemProperty::AV1Config(c) => Some(c.clone()),
+                    _ => None,
+                });
+                if let Some(tile_config) = tile_config
+                    && !tile_av1_configs.contains(&tile_config) {
+                        tile_av1_configs.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                        tile_av1_configs.push(tile_config);
+                    }
+            }
+
+            (Some(grid_config), tile_extents, tile_spatial_extents, tile_av1_configs)
         } else {
             // Non-grid primary: enforce total_megapixels_limit on the primary
-            // item's ispe dimensions if present. H1 of 2026-05-06 audit.
-            let primary_dims = meta.properties.iter().find_map(|p| {
-                if p.item_id == meta.primary_item_id {
-                    match &p.property {
-                        ItemProperty::ImageSpatialExtents(e) => Some((e.width, e.height)),
-                        _ => None,
-                    }
-                } else {
-                    None
+            // item's ispe dimensions and sequence header. H1 of 2026-05-06 audit.
+            Self::validate_image_size(&tracker, &meta, &raw, meta.primary_item_id, &primary)?;
+            (None, TryVec::new(), None, alloc::vec::Vec::new())
+        };
+
+        // Overlay derived image: `dimg` inputs composed onto a canvas at per-input offsets
+        let is_overlay = meta
+            .item_infos
+            .iter()
+            .find(|x| x.item_id == meta.primary_item_id)
+            .is_some_and(|info| info.item_type == b"iovl");
+        let (overlay_config, overlay_inputs) = if is_overlay {
+            let mut inputs_with_index: TryVec<(u32, u16)> = TryVec::new();
+            for iref in meta.item_references.iter() {
+                if iref.from_item_id == meta.primary_item_id && iref.item_type == b"dimg" {
+                    inputs_with_index.push((iref.to_item_id, iref.reference_index)).map_err(|e| at!(Error::from(e)))?;
                 }
-            });
-            if let Some((w, h)) = primary_dims {
-                tracker.validate_total_megapixels(w, h)?;
             }
+            // Each input is a separate decode, like a grid tile
+            tracker.validate_grid_tiles(inputs_with_index.len() as u32)?;
+            inputs_with_index.sort_by_key(|&(_, idx)| idx);
+
+            let payload_len = Self::item_len(&raw, &parsed.mdat_bounds, meta.idat.as_deref(), &primary);
+            tracker.reserve(payload_len)?;
+            let payload = Self::resolve_extents_from_raw(
+                &raw, &parsed.mdat_bounds, meta.idat.as_deref(), &primary, parsed.truncated,
+            )?;
+            let overlay_config = parse_overlay(&payload, inputs_with_index.len())?;
+            tracker.release(payload_len);
+            tracker.validate_total_megapixels(overlay_config.output_width, overlay_config.output_height)?;
+
+            let mut input_extents = TryVec::new();
+            for (input_id, _) in inputs_with_index.iter() {
+                input_extents.push(Self::get_item_extents(&meta, *input_id)?).map_err(|e| at!(Error::from(e)))?;
+            }
+            (Some(overlay_config), input_extents)
+        } else {
             (None, TryVec::new())
         };
 
         // Detect gain map (tmap derived image item)
-        let (gain_map_metadata, gain_map, gain_map_color_info) = {
+        let (gain_map_metadata, gain_map, gain_map_color_info, gain_map_spatial_extents, gain_map_av1_config) = {
             let tmap_item = meta.item_infos.iter()
-                .find(|info| info.item_type == b"tmap");
+                .find(|info| info.item_type == b"tmap" && !meta.is_protected(info.item_id));
 
             if let Some(tmap_info) = tmap_item {
                 let tmap_id = tmap_info.item_id;
@@ -2031,13 +4705,16 @@ impl<'data> AvifParser<'data> {
                     let base_item_id = inputs[0].0;
                     let gmap_item_id = inputs[1].0;
 
-                    if base_item_id == meta.primary_item_id {
+                    if base_item_id == meta.primary_item_id && !meta.is_protected(gmap_item_id) {
                         // Read tmap item's data payload (ToneMapImage)
                         let tmap_extents = Self::get_item_extents(&meta, tmap_id)?;
+                        let tmap_len = Self::item_len(&raw, &parsed.mdat_bounds, meta.idat.as_deref(), &tmap_extents);
+                        tracker.reserve(tmap_len)?;
                         let tmap_data = Self::resolve_extents_from_raw(
-                            raw.as_ref(), &parsed.mdat_bounds, &tmap_extents,
+                            &raw, &parsed.mdat_bounds, meta.idat.as_deref(), &tmap_extents, parsed.truncated,
                         )?;
                         let metadata = parse_tone_map_image(&tmap_data)?;
+                        tracker.release(tmap_len);
 
                         // Get gain map image extents
                         let gmap_extents = Self::get_item_extents(&meta, gmap_item_id)?;
@@ -2045,7 +4722,7 @@ impl<'data> AvifParser<'data> {
                         // Get alternate color info from tmap item's properties
                         let alt_color = meta.properties.iter().find_map(|p| {
                             if p.item_id == tmap_id {
-                                match &p.property {
+                                match meta.property(p) {
                                     ItemProperty::ColorInformation(c) => Some(c.clone()),
                                     _ => None,
                                 }
@@ -2054,16 +4731,72 @@ impl<'data> AvifParser<'data> {
                             }
                         });
 
-                        (Some(metadata), Some(gmap_extents), alt_color)
+                        // The gain map image item carries its own ispe/av1C
+                        let gmap_ispe = meta.properties.iter().find_map(|p| match meta.property(p) {
+                            ItemProperty::ImageSpatialExtents(e) if p.item_id == gmap_item_id => Some(*e),
+                            _ => None,
+                        });
+                        let gmap_av1c = meta.properties.iter().find_map(|p| match meta.property(p) {
+                            ItemProperty::AV1Config(c) if p.item_id == gmap_item_id => Some(c.clone()),
+                            _ => None,
+                        });
+
+                        (Some(metadata), Some(gmap_extents), alt_color, gmap_ispe, gmap_av1c)
                     } else {
-                        (None, None, None)
+                        (None, None, None, None, None)
                     }
                 } else {
-                    (None, None, None)
+                    (None, None, None, None, None)
                 }
             } else {
-                (None, None, None)
+                (None, None, None, None, None)
+            }
+        };
+
+        // Sample transform in an altr group with the primary item; the
+        // primary is an alternative this parser can show
+        let (sample_transform, sample_transform_inputs) = match Self::sample_transform_item(&meta) {
+            Some(sato_id) => {
+                let mut inputs: TryVec<(u32, u16)> = TryVec::new();
+                for iref in meta.item_references.iter() {
+                    if iref.from_item_id == sato_id && iref.item_type == b"dimg" {
+                        inputs.push((iref.to_item_id, iref.reference_index)).map_err(|e| at!(Error::from(e)))?;
+                    }
+                }
+                inputs.sort_by_key(|&(_, idx)| idx);
+
+                let sato_extents = Self::get_item_extents(&meta, sato_id)?;
+                let sato_len = Self::item_len(&raw, &parsed.mdat_bounds, meta.idat.as_deref(), &sato_extents);
+                tracker.reserve(sato_len)?;
+                let sato_data = Self::resolve_extents_from_raw(
+                    &raw, &parsed.mdat_bounds, meta.idat.as_deref(), &sato_extents, parsed.truncated,
+                )?;
+                // A malformed expression drops the transform; the primary item is its alternative
+                let expression = match parse_sample_transform(&sato_data, inputs.len()) {
+                    Ok(expression) => expression,
+                    Err(e) if is_malformed_data(e.error()) => {
+                        warn!("ignoring sample transform item {sato_id}: {}", e.error());
+                        let warning = ParseWarning { kind: ParseWarningKind::MalformedSampleTransform, fourcc: *b"sato", offset: 0 };
+                        warnings.push(warning).map_err(|e| at!(Error::from(e)))?;
+                        None
+                    }
+                    Err(e) => return Err(e),
+                };
+                tracker.release(sato_len);
+
+                match expression {
+                    Some((bit_depth, tokens)) => {
+                        let mut input_extents = TryVec::new();
+                        for &(input_id, _) in inputs.iter() {
+                            input_extents.push(Self::get_item_extents(&meta, input_id)?).map_err(|e| at!(Error::from(e)))?;
+                        }
+                        let input_item_ids = inputs.iter().map(|&(id, _)| id).collect();
+                        (Some(SampleTransform { item_id: sato_id, bit_depth, tokens, input_item_ids }), input_extents)
+                    }
+                    None => (None, TryVec::new()),
+                }
             }
+            None => (None, TryVec::new()),
         };
 
         // Extract properties for the primary item
@@ -2071,7 +4804,7 @@ impl<'data> AvifParser<'data> {
             ($variant:ident) => {
                 meta.properties.iter().find_map(|p| {
                     if p.item_id == meta.primary_item_id {
-                        match &p.property {
+                        match meta.property(p) {
                             ItemProperty::$variant(c) => Some(c.clone()),
                             _ => None,
                         }
@@ -2086,30 +4819,97 @@ impl<'data> AvifParser<'data> {
         let spatial_extents = find_prop!(ImageSpatialExtents);
         let av1_config = find_prop!(AV1Config)
             .or_else(|| track_config.and_then(|c| c.av1_config.clone()));
+        #[cfg(feature = "heif")]
+        let hevc_config = find_prop!(HevcConfig);
         let color_info = find_prop!(ColorInformation)
             .or_else(|| track_config.and_then(|c| c.color_info.clone()));
+        let icc_profile = primary_icc_profile(&meta)
+            .or_else(|| track_config.and_then(|c| c.icc_profile.clone()));
         let rotation = find_prop!(Rotation);
         let mirror = find_prop!(Mirror);
         let clean_aperture = find_prop!(CleanAperture);
-        let pixel_aspect_ratio = find_prop!(PixelAspectRatio);
-        let content_light_level = find_prop!(ContentLightLevel);
-        let mastering_display = find_prop!(MasteringDisplayColourVolume);
-        let content_colour_volume = find_prop!(ContentColourVolume);
-        let ambient_viewing = find_prop!(AmbientViewingEnvironment);
+        let pixel_aspect_ratio = find_prop!(PixelAspectRatio)
+            .or_else(|| track_config.and_then(|c| c.pixel_aspect_ratio));
+        let content_light_level = find_prop!(ContentLightLevel)
+            .or_else(|| track_config.and_then(|c| c.content_light_level));
+        let mastering_display = find_prop!(MasteringDisplayColourVolume)
+            .or_else(|| track_config.and_then(|c| c.mastering_display));
+        let content_colour_volume = find_prop!(ContentColourVolume)
+            .or_else(|| track_config.and_then(|c| c.content_colour_volume));
+        let ambient_viewing = find_prop!(AmbientViewingEnvironment)
+            .or_else(|| track_config.and_then(|c| c.ambient_viewing));
         let operating_point = find_prop!(OperatingPointSelector);
         let layer_selector = find_prop!(LayerSelector);
         let layered_image_indexing = find_prop!(AV1LayeredImageIndexing);
+        let user_descriptions = primary_user_descriptions(&meta)?;
+        let pixel_information = find_prop!(PixelInformation);
+        let alpha_pixel_information = alpha_item_id.and_then(|alpha_id| {
+            meta.properties.iter().find_map(|p| match meta.property(p) {
+                ItemProperty::PixelInformation(c) if p.item_id == alpha_id => Some(c.clone()),
+                _ => None,
+            })
+        });
+
+        let alpha_av1_config = alpha_item_id.and_then(|alpha_id| {
+            property_for(&meta, alpha_id, |p| match p {
+                ItemProperty::AV1Config(c) => Some(c.clone()),
+                _ => None,
+            })
+        });
 
-        // Clone idat
-        let idat = if let Some(ref idat_data) = meta.idat {
-            let mut cloned = TryVec::new();
-            cloned.extend_from_slice(idat_data).map_err(|e| at!(Error::from(e)))?;
-            Some(cloned)
-        } else {
-            None
-        };
+        // The alpha item must be the same size and depth as the color item
+        if let Some(alpha_id) = alpha_item_id {
+            let alpha_ispe = meta.properties.iter().find_map(|p| match meta.property(p) {
+                ItemProperty::ImageSpatialExtents(e) if p.item_id == alpha_id => Some(*e),
+                _ => None,
+            });
+            let alpha_depth = alpha_av1_config.as_ref().map(|c| c.bit_depth);
+            if let (Some(color), Some(alpha)) = (spatial_extents, alpha_ispe) {
+                check_alpha_consistent(color == alpha, b"ispe", "alpha item size differs from the color item", config, &mut warnings)?;
+            }
+            if let (Some(color), Some(alpha)) = (&av1_config, alpha_depth) {
+                check_alpha_consistent(color.bit_depth == alpha, b"av1C", "alpha item bit depth differs from the color item", config, &mut warnings)?;
+            }
+            if let (Some(color), Some(alpha)) = (&pixel_information, &alpha_pixel_information) {
+                let same_depth = color.bits_per_channel.first() == alpha.bits_per_channel.first();
+                check_alpha_consistent(same_depth, b"pixi", "alpha item bit depth differs from the color item", config, &mut warnings)?;
+            }
+        }
 
-        Ok(Self {
+        let alternatives = meta
+            .entity_groups
+            .iter()
+            .filter(|g| g.group_type == b"altr")
+            .map(|g| AlternativeGroup { group_id: g.group_id, entity_ids: g.entity_ids.to_vec() })
+            .collect();
+
+        // The idat copy is kept for the parser's lifetime; items split across
+        // separate extents are concatenated on each access, so only the
+        // largest one counts.
+        let idat_data = meta.idat.as_deref();
+        tracker.reserve(idat_data.map_or(0, |idat| idat.len().to_u64()))?;
+        let largest_owned_item = [Some(&primary), alpha.as_ref(), exif_item.as_ref(), xmp_item.as_ref()]
+            .into_iter()
+            .chain([thumbnail.as_ref(), gain_map.as_ref(), depth_item.as_ref()])
+            .flatten()
+            .chain(tiles.iter())
+            .chain(overlay_inputs.iter())
+            .map(|item| Self::owned_item_len(&raw, &parsed.mdat_bounds, idat_data, item))
+            .max()
+            .unwrap_or(0);
+        tracker.reserve(largest_owned_item)?;
+
+        let items = Self::item_records(&meta)?;
+        let auxiliary_items = auxiliary_items(&meta)?;
+        let mut references = alloc::vec::Vec::new();
+        references.try_reserve_exact(meta.item_references.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        references.extend(meta.item_references.iter().map(SingleItemTypeReferenceBox::to_item_reference));
+
+        // The meta structure is dropped once the parser is built, so its idat
+        // buffer moves over instead of being duplicated
+        let idat = meta.idat.take();
+
+        Ok(Self::new(ParserState {
             raw,
             mdat_bounds: parsed.mdat_bounds,
             idat,
@@ -2117,11 +4917,22 @@ impl<'data> AvifParser<'data> {
             alpha,
             grid_config,
             tiles,
+            tile_spatial_extents,
+            overlay_config,
+            overlay_inputs,
+            sample_transform,
+            sample_transform_inputs,
+            alternatives,
             animation_data,
             premultiplied_alpha,
             spatial_extents,
             av1_config,
+            alpha_av1_config,
+            tile_av1_configs,
+            #[cfg(feature = "heif")]
+            hevc_config,
             color_info,
+            icc_profile,
             rotation,
             mirror,
             clean_aperture,
@@ -2133,11 +4944,20 @@ impl<'data> AvifParser<'data> {
             operating_point,
             layer_selector,
             layered_image_indexing,
+            user_descriptions,
+            pixel_information,
+            alpha_pixel_information,
             exif_item,
+            exif_headerless: meta.headerless_exif,
             xmp_item,
+            thumbnail,
+            thumbnail_spatial_extents,
+            thumbnail_av1_config,
             gain_map_metadata,
             gain_map,
             gain_map_color_info,
+            gain_map_spatial_extents,
+            gain_map_av1_config,
             depth_item,
             depth_width,
             depth_height,
@@ -2145,13 +4965,36 @@ impl<'data> AvifParser<'data> {
             depth_color_info,
             major_brand: parsed.major_brand,
             compatible_brands: parsed.compatible_brands,
-        })
+            items,
+            auxiliary_items,
+            references,
+            uuid_boxes,
+            protected_items: meta.protected_items,
+            layout,
+            truncated: parsed.truncated,
+            warnings,
+            stats: ParseStats {
+                peak_memory: tracker.peak_memory,
+                parse_duration: parsed.started.map(|started| started.elapsed()),
+                ..stats
+            },
+        }))
     }
 
     // ========================================
     // Internal helpers
     // ========================================
 
+    /// The first `sato` item in an `altr` group with the primary item.
+    fn sample_transform_item(meta: &AvifInternalMeta) -> Option<u32> {
+        meta.entity_groups
+            .iter()
+            .filter(|g| g.group_type == b"altr" && g.entity_ids.contains(&meta.primary_item_id))
+            .flat_map(|g| g.entity_ids.iter())
+            .copied()
+            .find(|&id| meta.item_infos.iter().any(|x| x.item_id == id && x.item_type == b"sato"))
+    }
+
     /// Get item extents (construction method + ranges) from metadata.
     fn get_item_extents(meta: &AvifInternalMeta, item_id: u32) -> Result<ItemExtents> {
         let item = meta
@@ -2170,17 +5013,73 @@ impl<'data> AvifParser<'data> {
         })
     }
 
+    /// The `iinf` entries with their `iloc`, `ipma` and `iref` data, after
+    /// `iden` and `item_offset` constructions have been resolved.
+    fn item_records(meta: &AvifInternalMeta) -> Result<alloc::vec::Vec<ItemRecord>> {
+        meta.item_infos.iter().map(|info| -> Result<ItemRecord> {
+            let location = meta.iloc_items.iter().any(|item| item.item_id == info.item_id)
+                .then(|| Self::get_item_extents(meta, info.item_id))
+                .transpose()?;
+            let properties = meta.properties.iter()
+                .filter(|prop| prop.item_id == info.item_id)
+                .map(|prop| PropertySummary {
+                    fourcc: fourcc_string(&prop.fourcc.value),
+                    essential: prop.essential,
+                    supported: *meta.property(prop) != ItemProperty::Unsupported,
+                })
+                .collect();
+            let references = meta.item_references.iter()
+                .filter(|r| r.from_item_id == info.item_id)
+                .map(SingleItemTypeReferenceBox::to_item_reference)
+                .collect();
+            Ok(ItemRecord {
+                summary: ItemSummary {
+                    id: info.item_id,
+                    primary: info.item_id == meta.primary_item_id,
+                    item_type: fourcc_string(&info.item_type.value),
+                    content_type: (!info.content_type.is_empty())
+                        .then(|| alloc::string::String::from_utf8_lossy(&info.content_type).into_owned()),
+                    in_idat: location.as_ref().is_some_and(|l| l.construction_method == ConstructionMethod::Idat),
+                    size: 0,
+                    byte_ranges: alloc::vec::Vec::new(),
+                    properties,
+                    references,
+                },
+                location,
+            })
+        }).collect()
+    }
+
     /// Resolve file-based item extents from a raw buffer during `build()`,
     /// before `self` exists. Returns owned data (small payloads like tmap).
     fn resolve_extents_from_raw(
-        raw: &[u8],
+        raw: &RawData<'_>,
         mdat_bounds: &[MdatBounds],
+        idat: Option<&[u8]>,
         item: &ItemExtents,
-    ) -> Result<std::vec::Vec<u8>> {
+        truncated: bool,
+    ) -> Result<alloc::vec::Vec<u8>> {
+        // Derived item payloads are small and often stored in idat
+        if item.construction_method == ConstructionMethod::Idat {
+            let idat = idat.ok_or_else(|| at!(Error::InvalidData("idat box missing but construction_method is Idat")))?;
+            let mut data = alloc::vec::Vec::new();
+            for extent in &item.extents {
+                let start = usize::try_from(extent.start()).map_err(|e| at!(Error::from(e)))?;
+                let slice = match extent {
+                    ExtentRange::WithLength(range) => {
+                        let len = usize::try_from(range.end - range.start).map_err(|e| at!(Error::from(e)))?;
+                        start.checked_add(len).and_then(|end| idat.get(start..end))
+                    }
+                    ExtentRange::ToEnd(_) => idat.get(start..),
+                };
+                data.extend_from_slice(slice.ok_or_else(|| at!(Error::InvalidData("idat extent out of bounds")))?);
+            }
+            return Ok(data);
+        }
         if item.construction_method != ConstructionMethod::File {
-            return Err(at!(Error::Unsupported("tmap item must use file construction method")));
+            return Err(at!(Error::Unsupported("derived item must use file or idat construction method")));
         }
-        let mut data = std::vec::Vec::new();
+        let mut data = alloc::vec::Vec::new();
         for extent in &item.extents {
             let file_offset = extent.start();
             let start = usize::try_from(file_offset).map_err(|e| at!(Error::from(e)))?;
@@ -2193,7 +5092,7 @@ impl<'data> AvifParser<'data> {
                 }
                 ExtentRange::ToEnd(_) => {
                     // Find the mdat that contains this offset
-                    let mut found_end = raw.len();
+                    let mut found_end = usize::try_from(raw.len()).map_err(|e| at!(Error::from(e)))?;
                     for mdat in mdat_bounds {
                         if file_offset >= mdat.offset && file_offset < mdat.offset + mdat.length {
                             found_end = usize::try_from(mdat.offset + mdat.length).map_err(|e| at!(Error::from(e)))?;
@@ -2203,41 +5102,221 @@ impl<'data> AvifParser<'data> {
                     found_end
                 }
             };
-            let slice = raw.get(start..end)
-                .ok_or_else(|| at!(Error::InvalidData("tmap extent out of bounds")))?;
-            data.extend_from_slice(slice);
+            if truncated && end.to_u64() > raw.len() {
+                return Err(at!(Error::DataNotAvailable));
+            }
+            let slice = raw.read_range(start, end)
+                .map_err(|_| at!(Error::InvalidData("derived item extent out of bounds")))?;
+            data.extend_from_slice(&slice);
         }
         Ok(data)
     }
 
+    /// Enforce `total_megapixels_limit` on an image item before anything
+    /// decodes it, using its `ispe` and, for a coded `av01` item, the maximum
+    /// frame size in the sequence header at the start of its bitstream. A
+    /// bitstream that cannot be read here is left for the decoder to reject.
+    fn validate_image_size(
+        tracker: &ResourceTracker<'_>,
+        meta: &AvifInternalMeta,
+        raw: &RawData<'_>,
+        item_id: u32,
+        item: &ItemExtents,
+    ) -> Result<()> {
+        // Far more than a sequence header with every operating point needs
+        const SEQUENCE_HEADER_PREFIX: u64 = 1024;
+
+        if let Some(ispe) = property_for(meta, item_id, |p| match p {
+            ItemProperty::ImageSpatialExtents(e) => Some(*e),
+            _ => None,
+        }) {
+            tracker.validate_total_megapixels(ispe.width, ispe.height)?;
+        }
+
+        let is_coded = meta.item_infos.iter().any(|info| info.item_id == item_id && info.item_type == b"av01");
+        let Some(extent) = item.extents.first() else {
+            return Ok(());
+        };
+        if !is_coded || item.construction_method != ConstructionMethod::File {
+            return Ok(());
+        }
+        let end = match extent {
+            ExtentRange::WithLength(range) => range.end,
+            ExtentRange::ToEnd(_) => raw.len(),
+        };
+        let end = end.min(raw.len()).min(extent.start().saturating_add(SEQUENCE_HEADER_PREFIX));
+        let (Ok(start), Ok(end)) = (usize::try_from(extent.start()), usize::try_from(end)) else {
+            return Ok(());
+        };
+        if start < end
+            && let Ok(prefix) = raw.read_range(start, end)
+            && let Ok(seq) = obu::parse_leading_sequence_header(&prefix) {
+                tracker.validate_total_megapixels(seq.max_frame_width.get(), seq.max_frame_height.get())?;
+            }
+        Ok(())
+    }
+
+    /// Payload length of `item`, with open-ended extents running to the end
+    /// of their `mdat` (or of `idat`). Out-of-range extents are not checked
+    /// here; resolving them fails later.
+    fn item_len(raw: &RawData<'_>, mdat_bounds: &[MdatBounds], idat: Option<&[u8]>, item: &ItemExtents) -> u64 {
+        item.extents.iter().fold(0u64, |total, extent| {
+            let len = match extent {
+                ExtentRange::WithLength(range) => range.end.saturating_sub(range.start),
+                ExtentRange::ToEnd(range) => {
+                    let end = if item.construction_method == ConstructionMethod::Idat {
+                        idat.map_or(0, |idat| idat.len().to_u64())
+                    } else {
+                        mdat_bounds
+                            .iter()
+                            .find(|mdat| range.start >= mdat.offset && range.start < mdat.offset + mdat.length)
+                            .map_or(raw.len(), |mdat| mdat.offset + mdat.length)
+                    };
+                    end.saturating_sub(range.start)
+                }
+            };
+            total.saturating_add(len)
+        })
+    }
+
+    /// Bytes [`resolve_item`](Self::resolve_item) allocates to return `item`:
+    /// zero when contiguous extents can be borrowed, the whole payload otherwise.
+    fn owned_item_len(raw: &RawData<'_>, mdat_bounds: &[MdatBounds], idat: Option<&[u8]>, item: &ItemExtents) -> u64 {
+        let borrowed = ExtentRange::contiguous(&item.extents)
+            && (item.construction_method == ConstructionMethod::Idat || !raw.is_seekable());
+        if borrowed { 0 } else { Self::item_len(raw, mdat_bounds, idat, item) }
+    }
+
     /// Resolve an item's data from the raw buffer, returning `Cow::Borrowed`
-    /// for single-extent file items and `Cow::Owned` for multi-extent or idat.
+    /// when the extents are contiguous and `Cow::Owned` when they must be joined.
     fn resolve_item(&self, item: &ItemExtents) -> Result<Cow<'_, [u8]>> {
         match item.construction_method {
             ConstructionMethod::Idat => self.resolve_idat_extents(&item.extents),
             ConstructionMethod::File => self.resolve_file_extents(&item.extents),
-            ConstructionMethod::Item => Err(at!(Error::Unsupported("construction_method 'item' not supported"))),
+            // Flattened by `flatten_item_constructions` during parsing
+            ConstructionMethod::Item => Err(at!(Error::InvalidData("unresolved item_offset construction"))),
+        }
+    }
+
+    /// Read `start..end` of the file, reporting [`Error::DataNotAvailable`]
+    /// for ranges past the end of truncated input.
+    fn read_raw(&self, start: usize, end: usize) -> Result<Cow<'_, [u8]>> {
+        if self.inner.truncated && end.to_u64() > self.inner.raw.len() {
+            return Err(at!(Error::DataNotAvailable));
+        }
+        self.inner.raw.read_range(start, end)
+    }
+
+    /// [`read_raw`](Self::read_raw) for a range of file offsets.
+    fn read_file_range(&self, range: &Range<u64>) -> Result<Cow<'_, [u8]>> {
+        let start = usize::try_from(range.start).map_err(|e| at!(Error::from(e)))?;
+        let end = usize::try_from(range.end).map_err(|e| at!(Error::from(e)))?;
+        self.read_raw(start, end)
+    }
+
+    /// [`read_raw`](Self::read_raw), appending to `out`.
+    fn read_raw_into(&self, start: usize, end: usize, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        if self.inner.truncated && end.to_u64() > self.inner.raw.len() {
+            return Err(at!(Error::DataNotAvailable));
+        }
+        self.inner.raw.read_range_into(start, end, out)
+    }
+
+    /// Append an item's data to `out`; [`resolve_item`](Self::resolve_item)
+    /// without the allocation for multi-extent items.
+    fn resolve_item_into(&self, item: &ItemExtents, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        match item.construction_method {
+            ConstructionMethod::Idat => {
+                let data = self.resolve_idat_extents(&item.extents)?;
+                out.try_reserve(data.len()).map_err(|_| at!(Error::OutOfMemory))?;
+                out.extend_from_slice(&data);
+            }
+            ConstructionMethod::File => {
+                let old_len = out.len();
+                for extent in &item.extents {
+                    let read = self.extent_byte_range(extent)
+                        .and_then(|(start, end)| self.read_raw_into(start, end, out));
+                    if let Err(e) = read {
+                        out.truncate(old_len);
+                        return Err(e);
+                    }
+                }
+            }
+            // Flattened by `flatten_item_constructions` during parsing
+            ConstructionMethod::Item => return Err(at!(Error::InvalidData("unresolved item_offset construction"))),
+        }
+        Ok(())
+    }
+
+    /// Append sample `index` of `st` to `out`.
+    fn resolve_sample_into(&self, st: &SampleTable, index: usize, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        if index >= st.sample_sizes.len() {
+            return Err(at!(Error::InvalidData("frame index out of bounds")));
+        }
+        let (offset, size) = self.calculate_sample_location(st, index)?;
+        let start = usize::try_from(offset).map_err(|e| at!(Error::from(e)))?;
+        let end = start.checked_add(size as usize)
+            .ok_or_else(|| at!(Error::InvalidData("frame end overflow")))?;
+        self.read_raw_into(start, end, out)
+            .map_err(|e| Self::missing_or(e, "frame not found in raw buffer"))
+    }
+
+    /// [`read_raw`](Self::read_raw) as `Bytes`.
+    #[cfg(feature = "bytes")]
+    fn read_raw_shared(&self, start: usize, end: usize) -> Result<bytes::Bytes> {
+        if self.inner.truncated && end.to_u64() > self.inner.raw.len() {
+            return Err(at!(Error::DataNotAvailable));
+        }
+        self.inner.raw.read_range_shared(start, end)
+    }
+
+    /// [`resolve_item`](Self::resolve_item) as `Bytes`: a slice of shared
+    /// input when the extents are contiguous, otherwise the joined copy.
+    #[cfg(feature = "bytes")]
+    fn resolve_item_shared(&self, item: &ItemExtents) -> Result<bytes::Bytes> {
+        if item.construction_method == ConstructionMethod::File && ExtentRange::contiguous(&item.extents) {
+            let (start, _) = self.extent_byte_range(&item.extents[0])?;
+            let (_, end) = self.extent_byte_range(&item.extents[item.extents.len() - 1])?;
+            return self.read_raw_shared(start, end);
+        }
+        self.resolve_item(item).map(cow_to_bytes)
+    }
+
+    /// Sample `index` of `st` as `Bytes`.
+    #[cfg(feature = "bytes")]
+    fn resolve_sample_shared(&self, st: &SampleTable, index: usize) -> Result<bytes::Bytes> {
+        if index >= st.sample_sizes.len() {
+            return Err(at!(Error::InvalidData("frame index out of bounds")));
         }
+        let (offset, size) = self.calculate_sample_location(st, index)?;
+        let start = usize::try_from(offset).map_err(|e| at!(Error::from(e)))?;
+        let end = start.checked_add(size as usize)
+            .ok_or_else(|| at!(Error::InvalidData("frame end overflow")))?;
+        self.read_raw_shared(start, end)
+            .map_err(|e| Self::missing_or(e, "frame not found in raw buffer"))
+    }
+
+    /// Keep [`Error::DataNotAvailable`], replace anything else with `msg`.
+    fn missing_or(e: At<Error>, msg: &'static str) -> At<Error> {
+        if matches!(e.error(), Error::DataNotAvailable) { e } else { at!(Error::InvalidData(msg)) }
     }
 
     /// Resolve file-based extents from the raw buffer.
     fn resolve_file_extents(&self, extents: &[ExtentRange]) -> Result<Cow<'_, [u8]>> {
-        let raw = self.raw.as_ref();
-
-        // Fast path: single extent → borrow directly from raw
-        if extents.len() == 1 {
-            let extent = &extents[0];
-            let (start, end) = self.extent_byte_range(extent)?;
-            let slice = raw.get(start..end).ok_or_else(|| at!(Error::InvalidData("extent out of bounds in raw buffer")))?;
-            return Ok(Cow::Borrowed(slice));
+        // Fast path: one extent, or several that follow each other in the
+        // file → borrow the whole span directly from raw
+        if ExtentRange::contiguous(extents) {
+            let (start, _) = self.extent_byte_range(&extents[0])?;
+            let (_, end) = self.extent_byte_range(&extents[extents.len() - 1])?;
+            return self.read_raw(start, end);
         }
 
         // Multi-extent: concatenate into owned buffer
         let mut data = TryVec::new();
         for extent in extents {
             let (start, end) = self.extent_byte_range(extent)?;
-            let slice = raw.get(start..end).ok_or_else(|| at!(Error::InvalidData("extent out of bounds in raw buffer")))?;
-            data.extend_from_slice(slice).map_err(|e| at!(Error::from(e)))?;
+            let slice = self.read_raw(start, end)?;
+            data.extend_from_slice(&slice).map_err(|e| at!(Error::from(e)))?;
         }
         Ok(Cow::Owned(data.into_iter().collect()))
     }
@@ -2257,30 +5336,29 @@ impl<'data> AvifParser<'data> {
             }
             ExtentRange::ToEnd(_) => {
                 // Find the mdat that contains this offset and use its bounds
-                for mdat in &self.mdat_bounds {
+                for mdat in &self.inner.mdat_bounds {
                     if file_offset >= mdat.offset && file_offset < mdat.offset + mdat.length {
                         let end = usize::try_from(mdat.offset + mdat.length).map_err(|e| at!(Error::from(e)))?;
                         return Ok((start, end));
                     }
                 }
                 // Fall back to end of raw buffer
-                Ok((start, self.raw.len()))
+                Ok((start, usize::try_from(self.inner.raw.len()).map_err(|e| at!(Error::from(e)))?))
             }
         }
     }
 
     /// Resolve idat-based extents.
     fn resolve_idat_extents(&self, extents: &[ExtentRange]) -> Result<Cow<'_, [u8]>> {
-        let idat_data = self.idat.as_ref()
+        let idat_data = self.inner.idat.as_ref()
             .ok_or_else(|| at!(Error::InvalidData("idat box missing but construction_method is Idat")))?;
 
-        if extents.len() == 1 {
-            let extent = &extents[0];
-            let start = usize::try_from(extent.start()).map_err(|e| at!(Error::from(e)))?;
-            let slice = match extent {
+        if ExtentRange::contiguous(extents) {
+            let start = usize::try_from(extents[0].start()).map_err(|e| at!(Error::from(e)))?;
+            let slice = match &extents[extents.len() - 1] {
                 ExtentRange::WithLength(range) => {
-                    let len = usize::try_from(range.end - range.start).map_err(|e| at!(Error::from(e)))?;
-                    idat_data.get(start..start + len)
+                    let end = usize::try_from(range.end).map_err(|e| at!(Error::from(e)))?;
+                    idat_data.get(start..end)
                         .ok_or_else(|| at!(Error::InvalidData("idat extent out of bounds")))?
                 }
                 ExtentRange::ToEnd(_) => {
@@ -2313,23 +5391,10 @@ impl<'data> AvifParser<'data> {
 
     /// Resolve a single animation frame from the raw buffer.
     fn resolve_frame(&self, index: usize) -> Result<FrameRef<'_>> {
-        let anim = self.animation_data.as_ref()
+        let anim = self.inner.animation_data.as_ref()
             .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
 
-        if index >= anim.sample_table.sample_sizes.len() {
-            return Err(at!(Error::InvalidData("frame index out of bounds")));
-        }
-
-        let duration_ms = self.calculate_frame_duration(&anim.sample_table, anim.media_timescale, index)?;
-        let (offset, size) = self.calculate_sample_location(&anim.sample_table, index)?;
-
-        let start = usize::try_from(offset).map_err(|e| at!(Error::from(e)))?;
-        let end = start.checked_add(size as usize)
-            .ok_or_else(|| at!(Error::InvalidData("frame end overflow")))?;
-
-        let raw = self.raw.as_ref();
-        let slice = raw.get(start..end)
-            .ok_or_else(|| at!(Error::InvalidData("frame not found in raw buffer")))?;
+        let mut frame = self.resolve_sample(&anim.sample_table, anim.media_timescale, index)?;
 
         // Resolve alpha frame if alpha track exists and has this index
         let alpha_data = if let Some(ref alpha_st) = anim.alpha_sample_table {
@@ -2339,10 +5404,10 @@ impl<'data> AvifParser<'data> {
                 let a_start = usize::try_from(a_offset).map_err(|e| at!(Error::from(e)))?;
                 let a_end = a_start.checked_add(a_size as usize)
                     .ok_or_else(|| at!(Error::InvalidData("alpha frame end overflow")))?;
-                let a_slice = raw.get(a_start..a_end)
-                    .ok_or_else(|| at!(Error::InvalidData("alpha frame not found in raw buffer")))?;
+                let a_data = self.read_raw(a_start, a_end)
+                    .map_err(|e| Self::missing_or(e, "alpha frame not found in raw buffer"))?;
                 let _ = alpha_timescale; // timescale used for duration, which comes from color track
-                Some(Cow::Borrowed(a_slice))
+                Some(a_data)
             } else {
                 warn!("alpha track has fewer frames than color track (index {})", index);
                 None
@@ -2351,175 +5416,711 @@ impl<'data> AvifParser<'data> {
             None
         };
 
+        frame.alpha_data = alpha_data;
+        Ok(frame)
+    }
+
+    /// Resolve sample `index` of any track, without alpha.
+    fn resolve_sample(&self, st: &SampleTable, timescale: u32, index: usize) -> Result<FrameRef<'_>> {
+        if index >= st.sample_sizes.len() {
+            return Err(at!(Error::InvalidData("frame index out of bounds")));
+        }
+
+        let duration_ms = self.calculate_frame_duration(st, timescale, index)?;
+        let (offset, size) = self.calculate_sample_location(st, index)?;
+
+        let start = usize::try_from(offset).map_err(|e| at!(Error::from(e)))?;
+        let end = start.checked_add(size as usize)
+            .ok_or_else(|| at!(Error::InvalidData("frame end overflow")))?;
+
+        let data = self.read_raw(start, end)
+            .map_err(|e| Self::missing_or(e, "frame not found in raw buffer"))?;
+
         Ok(FrameRef {
-            data: Cow::Borrowed(slice),
-            alpha_data,
+            data,
+            alpha_data: None,
             duration_ms,
+            timestamp_ms: st.presentation_time_ms(index, timescale),
+            timestamp: st.presentation_time(index),
+            duration: st.sample_delta(index).unwrap_or(0),
+            timescale,
+            is_keyframe: st.is_sync(index),
+        })
+    }
+
+    /// Calculate grid configuration from metadata and the grid item's payload.
+    fn calculate_grid_config(
+        meta: &AvifInternalMeta,
+        tile_ids: &[u32],
+        payload: Option<&[u8]>,
+        allow_fallback: bool,
+    ) -> Result<GridConfig> {
+        // Try explicit grid property first
+        for prop in &meta.properties {
+            if prop.item_id == meta.primary_item_id
+                && let ItemProperty::ImageGrid(grid) = meta.property(prop) {
+                    return Ok(grid.clone());
+                }
+        }
+
+        // Then the ImageGrid payload; a malformed one falls through to the ispe heuristic
+        if let Some(grid) = payload.and_then(|data| parse_image_grid(data).ok()) {
+            return Ok(grid);
+        }
+
+        match grid_layout_from_ispe(meta, tile_ids) {
+            Ok(grid) => Ok(grid),
+            Err(e) if !allow_fallback => Err(e),
+            Err(_) => Ok(fallback_grid_layout(tile_ids.len())),
+        }
+    }
+
+    /// Calculate frame duration from sample table.
+    fn calculate_frame_duration(
+        &self,
+        st: &SampleTable,
+        timescale: u32,
+        index: usize,
+    ) -> Result<u32> {
+        // `sample_delta` accumulates the attacker-controlled `sample_count`s
+        // in saturating u64, so a crafted stts cannot overflow on 32-bit targets.
+        let duration_ms = match st.sample_delta(index) {
+            Some(delta) if timescale > 0 => u64::from(delta) * 1000 / u64::from(timescale),
+            _ => 0,
+        };
+        Ok(u32::try_from(duration_ms).unwrap_or(u32::MAX))
+    }
+
+    /// Look up precomputed sample location (offset and size) from sample table.
+    fn calculate_sample_location(&self, st: &SampleTable, index: usize) -> Result<(u64, u32)> {
+        let offset = *st
+            .sample_offsets
+            .get(index)
+            .ok_or_else(|| at!(Error::InvalidData("sample index out of bounds")))?;
+        let size = st
+            .sample_sizes
+            .get(index)
+            .ok_or_else(|| at!(Error::InvalidData("sample index out of bounds")))?;
+        Ok((offset, size))
+    }
+
+    // ========================================
+    // Public data access API (one way each)
+    // ========================================
+
+    /// Get primary item data.
+    ///
+    /// Returns `Cow::Borrowed` when the item's extents are contiguous (a single
+    /// extent, or several that follow each other), `Cow::Owned` otherwise.
+    ///
+    /// For an animation without a still image item, this is frame 0.
+    pub fn primary_data(&self) -> Result<Cow<'_, [u8]>> {
+        if self.is_sequence_only() {
+            return Ok(self.frame(0)?.data);
+        }
+        self.resolve_item(&self.inner.primary)
+    }
+
+    /// Get the primary item as a decoder expects it: the `av1C` configOBUs
+    /// followed by the item data, which is what dav1d and libaom take.
+    ///
+    /// Same as [`primary_data()`](Self::primary_data), including zero-copy
+    /// for single-extent items, when `av1C` has no configOBUs. For grids,
+    /// whose tiles carry their own `av1C`, use [`tile_data()`](Self::tile_data).
+    pub fn primary_decoder_payload(&self) -> Result<Cow<'_, [u8]>> {
+        let data = self.primary_data()?;
+        let config_obus = self.inner.av1_config.as_ref().map_or(&[][..], |c| &c.config_obus[..]);
+        if config_obus.is_empty() {
+            return Ok(data);
+        }
+        let mut payload = alloc::vec::Vec::new();
+        payload.try_reserve_exact(config_obus.len() + data.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        payload.extend_from_slice(config_obus);
+        payload.extend_from_slice(&data);
+        Ok(Cow::Owned(payload))
+    }
+
+    /// Get alpha item data, if present; for an animation without a still
+    /// image item, frame 0 of the alpha track.
+    pub fn alpha_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
+        if self.is_sequence_only() {
+            let anim = self.inner.animation_data.as_ref()?;
+            anim.alpha_sample_table.as_ref()?;
+            return match self.frame(0) {
+                Ok(frame) => frame.alpha_data.map(Ok),
+                Err(e) => Some(Err(e)),
+            };
+        }
+        self.inner.alpha.as_ref().map(|item| self.resolve_item(item))
+    }
+
+    /// Get grid tile data by index.
+    pub fn tile_data(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        let item = self.inner.tiles.get(index)
+            .ok_or_else(|| at!(Error::InvalidData("tile index out of bounds")))?;
+        self.resolve_item(item)
+    }
+
+    /// Append the primary item's data to `out`, e.g. a buffer reused across
+    /// files; [`primary_data()`](Self::primary_data) without allocating for
+    /// multi-extent items. `out` is left as it was on error.
+    pub fn primary_data_into(&self, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        if self.is_sequence_only() {
+            return self.frame_into(0, out);
+        }
+        self.resolve_item_into(&self.inner.primary, out)
+    }
+
+    /// Append the alpha item's data to `out`; `false` if there is none.
+    pub fn alpha_data_into(&self, out: &mut alloc::vec::Vec<u8>) -> Result<bool> {
+        if self.is_sequence_only() {
+            return self.alpha_frame_into(0, out);
+        }
+        let Some(item) = &self.inner.alpha else {
+            return Ok(false);
+        };
+        self.resolve_item_into(item, out)?;
+        Ok(true)
+    }
+
+    /// Append grid tile `index` to `out`.
+    pub fn tile_data_into(&self, index: usize, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        let item = self.inner.tiles.get(index)
+            .ok_or_else(|| at!(Error::InvalidData("tile index out of bounds")))?;
+        self.resolve_item_into(item, out)
+    }
+
+    /// Append the data of animation frame `index` to `out`. With one buffer
+    /// cleared and refilled per frame, playback from a seekable source
+    /// allocates nothing once the buffer has grown to the largest frame.
+    /// In-memory input needs no copy: [`frame()`](Self::frame) borrows it.
+    pub fn frame_into(&self, index: usize, out: &mut alloc::vec::Vec<u8>) -> Result<()> {
+        let anim = self.inner.animation_data.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        self.resolve_sample_into(&anim.sample_table, index, out)
+    }
+
+    /// Append the alpha track's sample for frame `index` to `out`; `false`
+    /// if the animation has no alpha track or it has no such sample.
+    pub fn alpha_frame_into(&self, index: usize, out: &mut alloc::vec::Vec<u8>) -> Result<bool> {
+        match self.alpha_sample_table(index)? {
+            Some(st) => {
+                self.resolve_sample_into(st, index, out)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The alpha track's sample table if it has a sample for frame `index`.
+    /// Like the color accessors, fails for still images and for indices
+    /// past the last frame.
+    fn alpha_sample_table(&self, index: usize) -> Result<Option<&SampleTable>> {
+        let anim = self.inner.animation_data.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        if index >= anim.sample_table.sample_sizes.len() {
+            return Err(at!(Error::InvalidData("frame index out of bounds")));
+        }
+        Ok(anim.alpha_sample_table.as_ref().filter(|st| index < st.sample_sizes.len()))
+    }
+
+    /// The primary item's data as `Bytes`, or frame 0 for an animation
+    /// without a still image item. A slice of the input for parsers built
+    /// with [`from_bytes_shared`](Self::from_bytes_shared) and contiguous
+    /// extents, a copy otherwise.
+    #[cfg(feature = "bytes")]
+    pub fn primary_bytes(&self) -> Result<bytes::Bytes> {
+        if self.is_sequence_only() {
+            return self.frame_bytes(0);
+        }
+        self.resolve_item_shared(&self.inner.primary)
+    }
+
+    /// The alpha item's data as `Bytes`, if present; like
+    /// [`alpha_data`](Self::alpha_data), frame 0 of the alpha track for an
+    /// animation without a still image item.
+    #[cfg(feature = "bytes")]
+    pub fn alpha_bytes(&self) -> Option<Result<bytes::Bytes>> {
+        if self.is_sequence_only() {
+            return self.alpha_frame_bytes(0).transpose();
+        }
+        self.inner.alpha.as_ref().map(|item| self.resolve_item_shared(item))
+    }
+
+    /// Grid tile `index` as `Bytes`.
+    #[cfg(feature = "bytes")]
+    pub fn tile_bytes(&self, index: usize) -> Result<bytes::Bytes> {
+        let item = self.inner.tiles.get(index)
+            .ok_or_else(|| at!(Error::InvalidData("tile index out of bounds")))?;
+        self.resolve_item_shared(item)
+    }
+
+    /// The data of animation frame `index` as `Bytes`; it stays valid after
+    /// the parser is dropped.
+    #[cfg(feature = "bytes")]
+    pub fn frame_bytes(&self, index: usize) -> Result<bytes::Bytes> {
+        let anim = self.inner.animation_data.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        self.resolve_sample_shared(&anim.sample_table, index)
+    }
+
+    /// The alpha track's sample for frame `index` as `Bytes`; `None` if the
+    /// animation has no alpha track or it has no such sample.
+    #[cfg(feature = "bytes")]
+    pub fn alpha_frame_bytes(&self, index: usize) -> Result<Option<bytes::Bytes>> {
+        self.alpha_sample_table(index)?
+            .map(|st| self.resolve_sample_shared(st, index))
+            .transpose()
+    }
+
+    /// Get a single animation frame by index.
+    pub fn frame(&self, index: usize) -> Result<FrameRef<'_>> {
+        self.resolve_frame(index)
+    }
+
+    /// Indices of the frames decoding can start at, ascending: the sync
+    /// samples of the color track, or every frame if it has no `stss`.
+    /// Empty for still images.
+    pub fn keyframes(&self) -> alloc::vec::Vec<usize> {
+        self.inner.animation_data.as_ref().map_or_else(alloc::vec::Vec::new, |anim| {
+            let st = &anim.sample_table;
+            (0..st.sample_sizes.len()).filter(|&index| st.is_sync(index)).collect()
         })
     }
 
-    /// Calculate grid configuration from metadata.
-    fn calculate_grid_config(meta: &AvifInternalMeta, tile_ids: &[u32]) -> Result<GridConfig> {
-        // Try explicit grid property first
-        for prop in &meta.properties {
-            if prop.item_id == meta.primary_item_id
-                && let ItemProperty::ImageGrid(grid) = &prop.property {
-                    return Ok(grid.clone());
-                }
-        }
+    /// The frame displayed at `time` into the animation, and the keyframe
+    /// decoding must start from to show it. Times before the first frame
+    /// give the first frame and times past the end the last; `None` for
+    /// still images and empty tracks.
+    ///
+    /// Looks the time up in the sample tables without reading frame data,
+    /// so a viewer can scrub without iterating the frames.
+    pub fn frame_index_at(&self, time: core::time::Duration) -> Option<FramePosition> {
+        let anim = self.inner.animation_data.as_ref()?;
+        let st = &anim.sample_table;
+        if st.sample_sizes.len() == 0 {
+            return None;
+        }
+        let ticks = time.as_nanos() * u128::from(anim.media_timescale) / 1_000_000_000;
+        let index = st.sample_at(u64::try_from(ticks).unwrap_or(u64::MAX));
+        Some(FramePosition { index, keyframe_index: st.sync_at_or_before(index) })
+    }
+
+    /// The frame displayed at `time`; see [`frame_index_at`](Self::frame_index_at).
+    /// Decoders that cannot start at any frame must decode from
+    /// [`FramePosition::keyframe_index`] instead.
+    pub fn frame_at_time(&self, time: core::time::Duration) -> Result<FrameRef<'_>> {
+        let position = self.frame_index_at(time)
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        self.frame(position.index)
+    }
+
+    /// Every track of the image sequence, in file order; empty for still
+    /// images. [`frame`](Self::frame) and [`frames`](Self::frames) read the
+    /// one marked [`selected`](TrackInfo::selected), which
+    /// [`DecodeConfig::with_track`] chooses.
+    pub fn tracks(&self) -> &[TrackInfo] {
+        self.inner.animation_data.as_ref().map(|anim| &anim.tracks[..]).unwrap_or_default()
+    }
+
+    /// Sample `index` of the track with `track_id`, whatever its role.
+    /// `alpha_data` is set only for the selected track, as in
+    /// [`frame`](Self::frame).
+    pub fn track_frames(&self, track_id: u32, index: usize) -> Result<FrameRef<'_>> {
+        let anim = self.inner.animation_data.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        if track_id == anim.track_id {
+            return self.frame(index);
+        }
+        if anim.alpha_track_id == Some(track_id)
+            && let Some(alpha_st) = &anim.alpha_sample_table
+        {
+            let timescale = anim.alpha_media_timescale.unwrap_or(anim.media_timescale);
+            return self.resolve_sample(alpha_st, timescale, index);
+        }
+        let track = anim.other_tracks.iter().find(|t| t.track_id == track_id)
+            .ok_or_else(|| at!(Error::InvalidData("no track with this track_id")))?;
+        self.resolve_sample(&track.sample_table, track.media_timescale, index)
+    }
+
+    /// Iterate over all animation frames.
+    pub fn frames(&self) -> FrameIterator<'_> {
+        let count = self
+            .animation_info()
+            .map(|info| info.frame_count)
+            .unwrap_or(0);
+        FrameIterator { parser: self, index: 0, count }
+    }
+
+    /// Iterate over the animation frames from `start` on, e.g. to resume
+    /// playback without resolving the earlier frames. Empty if `start` is
+    /// past the last frame.
+    pub fn frames_from(&self, start: usize) -> FrameIterator<'_> {
+        self.frames_range(start..usize::MAX)
+    }
+
+    /// Iterate over the animation frames in `range`, clamped to the frame count.
+    pub fn frames_range(&self, range: Range<usize>) -> FrameIterator<'_> {
+        let mut frames = self.frames();
+        frames.count = frames.count.min(range.end);
+        frames.index = range.start.min(frames.count);
+        frames
+    }
+
+    // ========================================
+    // Consuming accessors
+    // ========================================
 
-        // Fall back to ispe calculation
-        let grid_dims = meta
-            .properties
-            .iter()
-            .find(|p| p.item_id == meta.primary_item_id)
-            .and_then(|p| match &p.property {
-                ItemProperty::ImageSpatialExtents(e) => Some(e),
-                _ => None,
-            });
+    /// Consume the parser and return the primary item's data.
+    ///
+    /// For a parser built with [`from_owned`](Self::from_owned) (or a reader
+    /// constructor) and not shared with clones, the input buffer itself is
+    /// handed back: the item's extents are moved to its front and the rest
+    /// is cut off, without allocating. Otherwise the data is copied out.
+    /// The returned `Vec` may keep the input's capacity; call
+    /// `shrink_to_fit` if that matters. For an animation without a still
+    /// image this is the first frame, as with [`primary_data`](Self::primary_data).
+    pub fn into_primary(self) -> Result<alloc::vec::Vec<u8>> {
+        let spans = match &self.inner.animation_data {
+            Some(anim) if self.is_sequence_only() => self.sample_span(&anim.sample_table, 0).map(|span| alloc::vec![span]),
+            _ => self.file_spans(&self.inner.primary),
+        };
+        let parser = match self.into_compacted(spans) {
+            Ok(data) => return Ok(data),
+            Err(parser) => parser,
+        };
+        let mut data = alloc::vec::Vec::new();
+        parser.primary_data_into(&mut data)?;
+        Ok(data)
+    }
 
-        let tile_dims = tile_ids.first().and_then(|&tile_id| {
-            meta.properties
-                .iter()
-                .find(|p| p.item_id == tile_id)
-                .and_then(|p| match &p.property {
-                    ItemProperty::ImageSpatialExtents(e) => Some(e),
-                    _ => None,
-                })
+    /// Consume the parser and return the color track's frames in one buffer.
+    ///
+    /// Like [`into_primary`](Self::into_primary), an unshared owned input is
+    /// reused in place when the frames are stored in order, which encoders
+    /// do; otherwise the frames are copied into a new buffer. Alpha track
+    /// samples are not included. Empty for still images.
+    pub fn into_frames(self) -> Result<OwnedFrames> {
+        let Some(anim) = &self.inner.animation_data else {
+            return Ok(OwnedFrames { data: alloc::vec::Vec::new(), ends: alloc::vec::Vec::new() });
+        };
+        let st = &anim.sample_table;
+        let spans: Option<alloc::vec::Vec<(usize, usize)>> = (0..st.sample_sizes.len())
+            .map(|index| self.sample_span(st, index))
+            .collect();
+        let ends = spans.as_ref().map(|spans| {
+            spans.iter().scan(0, |end, (start, stop)| {
+                *end += stop - start;
+                Some(*end)
+            }).collect()
         });
+        let parser = match self.into_compacted(spans) {
+            Ok(data) => return Ok(OwnedFrames { data, ends: ends.unwrap_or_default() }),
+            Err(parser) => parser,
+        };
 
-        if let (Some(grid), Some(tile)) = (grid_dims, tile_dims)
-            && tile.width != 0
-                && tile.height != 0
-                && grid.width % tile.width == 0
-                && grid.height % tile.height == 0
-            {
-                let columns = grid.width / tile.width;
-                let rows = grid.height / tile.height;
-
-                if columns <= 255 && rows <= 255 {
-                    return Ok(GridConfig {
-                        rows: rows as u8,
-                        columns: columns as u8,
-                        output_width: grid.width,
-                        output_height: grid.height,
-                    });
-                }
+        let mut frames = OwnedFrames { data: alloc::vec::Vec::new(), ends: alloc::vec::Vec::new() };
+        if let Some(anim) = &parser.inner.animation_data {
+            let st = &anim.sample_table;
+            frames.ends.try_reserve_exact(st.sample_sizes.len()).map_err(|_| at!(Error::OutOfMemory))?;
+            for index in 0..st.sample_sizes.len() {
+                parser.resolve_sample_into(st, index, &mut frames.data)?;
+                frames.ends.push(frames.data.len());
             }
+        }
+        Ok(frames)
+    }
 
-        let tile_count = tile_ids.len();
-        Ok(GridConfig {
-            rows: tile_count.min(255) as u8,
-            columns: 1,
-            output_width: 0,
-            output_height: 0,
-        })
+    /// Byte range of sample `index` in the input, if it can be located.
+    fn sample_span(&self, st: &SampleTable, index: usize) -> Option<(usize, usize)> {
+        let (offset, size) = self.calculate_sample_location(st, index).ok()?;
+        let start = usize::try_from(offset).ok()?;
+        Some((start, start.checked_add(size as usize)?))
     }
 
-    /// Calculate frame duration from sample table.
-    fn calculate_frame_duration(
-        &self,
-        st: &SampleTable,
-        timescale: u32,
-        index: usize,
-    ) -> Result<u32> {
-        let mut current_sample: usize = 0;
-        for entry in &st.time_to_sample {
-            // `sample_count` is attacker-controlled (stts box). Use saturating
-            // adds so a crafted table whose counts sum past usize::MAX cannot
-            // overflow on 32-bit targets (i686/wasm32). Saturation is correct
-            // here: sample indices are monotonic, so a saturated accumulator is
-            // still `> index` and the comparison stays well-defined.
-            if current_sample.saturating_add(entry.sample_count as usize) > index {
-                let duration_ms = if timescale > 0 {
-                    ((entry.sample_delta as u64) * 1000) / (timescale as u64)
-                } else {
-                    0
-                };
-                return Ok(u32::try_from(duration_ms).unwrap_or(u32::MAX));
-            }
-            current_sample = current_sample.saturating_add(entry.sample_count as usize);
+    /// File byte ranges of a file-stored item, `None` if it has none or they
+    /// do not fit in `usize`.
+    fn file_spans(&self, item: &ItemExtents) -> Option<alloc::vec::Vec<(usize, usize)>> {
+        if item.construction_method != ConstructionMethod::File {
+            return None;
         }
-        Ok(0)
+        item.extents.iter().map(|extent| self.extent_byte_range(extent).ok()).collect()
     }
 
-    /// Look up precomputed sample location (offset and size) from sample table.
-    fn calculate_sample_location(&self, st: &SampleTable, index: usize) -> Result<(u64, u32)> {
-        let offset = *st
-            .sample_offsets
-            .get(index)
-            .ok_or_else(|| at!(Error::InvalidData("sample index out of bounds")))?;
-        let size = st
-            .sample_sizes
-            .get(index)
-            .ok_or_else(|| at!(Error::InvalidData("sample index out of bounds")))?;
-        Ok((offset, size))
+    /// Take the owned input buffer and shift `spans` down to its front, in
+    /// order. Gives the parser back when the buffer is borrowed, shared with
+    /// clones, or the spans are out of order or out of bounds.
+    fn into_compacted(self, spans: Option<alloc::vec::Vec<(usize, usize)>>) -> core::result::Result<alloc::vec::Vec<u8>, Self> {
+        let Some(spans) = spans else {
+            return Err(self);
+        };
+        let mut state = alloc::sync::Arc::try_unwrap(self.inner).map_err(|inner| Self { inner })?;
+        let RawData::Bytes(Cow::Owned(data)) = &mut state.raw else {
+            return Err(Self::new(state));
+        };
+        // Moving a span down must not overwrite one still to be moved
+        let mut prev_end = 0;
+        for &(start, end) in &spans {
+            if start < prev_end || end < start || end > data.len() {
+                return Err(Self::new(state));
+            }
+            prev_end = end;
+        }
+        let mut len = 0;
+        for (start, end) in spans {
+            data.copy_within(start..end, len);
+            len += end - start;
+        }
+        data.truncate(len);
+        Ok(core::mem::take(data))
     }
 
     // ========================================
-    // Public data access API (one way each)
+    // Byte ranges (for range requests)
     // ========================================
 
-    /// Get primary item data.
+    /// Absolute file byte ranges holding the primary item's data, in order.
     ///
-    /// Returns `Cow::Borrowed` for single-extent items, `Cow::Owned` for multi-extent.
-    pub fn primary_data(&self) -> Result<Cow<'_, [u8]>> {
-        self.resolve_item(&self.primary)
+    /// Items stored in `idat` return an empty list: their bytes live inside
+    /// the `meta` box, which has already been fetched to parse the file.
+    pub fn primary_byte_ranges(&self) -> Result<alloc::vec::Vec<Range<u64>>> {
+        self.item_byte_ranges(&self.inner.primary)
     }
 
-    /// Get alpha item data, if present.
-    pub fn alpha_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
-        self.alpha.as_ref().map(|item| self.resolve_item(item))
+    /// Absolute file byte ranges holding the alpha item's data, if present.
+    pub fn alpha_byte_ranges(&self) -> Option<Result<alloc::vec::Vec<Range<u64>>>> {
+        self.inner.alpha.as_ref().map(|item| self.item_byte_ranges(item))
     }
 
-    /// Get grid tile data by index.
-    pub fn tile_data(&self, index: usize) -> Result<Cow<'_, [u8]>> {
-        let item = self.tiles.get(index)
+    /// Absolute file byte ranges holding a grid tile's data.
+    pub fn tile_byte_ranges(&self, index: usize) -> Result<alloc::vec::Vec<Range<u64>>> {
+        let item = self.inner.tiles.get(index)
             .ok_or_else(|| at!(Error::InvalidData("tile index out of bounds")))?;
-        self.resolve_item(item)
+        self.item_byte_ranges(item)
+    }
+
+    /// Absolute file byte ranges holding an animation frame: the color
+    /// sample, followed by the alpha sample if the animation has one.
+    pub fn frame_byte_ranges(&self, index: usize) -> Result<alloc::vec::Vec<Range<u64>>> {
+        let mut ranges = alloc::vec::Vec::new();
+        let (offset, size) = self.frame_location(index)?;
+        ranges.push(offset..offset.checked_add(u64::from(size)).ok_or_else(|| at!(Error::InvalidData("frame end overflow")))?);
+        if let Some((a_offset, a_size)) = self.alpha_frame_location(index)? {
+            ranges.push(a_offset..a_offset.checked_add(u64::from(a_size))
+                .ok_or_else(|| at!(Error::InvalidData("alpha frame end overflow")))?);
+        }
+        Ok(ranges)
     }
 
-    /// Get a single animation frame by index.
-    pub fn frame(&self, index: usize) -> Result<FrameRef<'_>> {
-        self.resolve_frame(index)
+    /// File offset and size of the color sample of animation frame `index`,
+    /// for callers that read frames with their own I/O (`pread`, range
+    /// requests) instead of through the parser.
+    pub fn frame_location(&self, index: usize) -> Result<(u64, u32)> {
+        let anim = self.inner.animation_data.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not an animated AVIF")))?;
+        if index >= anim.sample_table.sample_sizes.len() {
+            return Err(at!(Error::InvalidData("frame index out of bounds")));
+        }
+        self.calculate_sample_location(&anim.sample_table, index)
     }
 
-    /// Iterate over all animation frames.
-    pub fn frames(&self) -> FrameIterator<'_> {
-        let count = self
-            .animation_info()
-            .map(|info| info.frame_count)
-            .unwrap_or(0);
-        FrameIterator { parser: self, index: 0, count }
+    /// File offset and size of the alpha track's sample for frame `index`;
+    /// `None` without an alpha track or past its last sample. Fails like
+    /// [`frame_location`](Self::frame_location) for still images and for
+    /// indices past the last frame.
+    pub fn alpha_frame_location(&self, index: usize) -> Result<Option<(u64, u32)>> {
+        self.alpha_sample_table(index)?
+            .map(|st| self.calculate_sample_location(st, index))
+            .transpose()
+    }
+
+    /// Absolute file byte ranges of the payload of each `mdat` box (and of
+    /// the data of a `mini` box), in file order. For truncated input the
+    /// last range ends where the input does.
+    pub fn mdat_ranges(&self) -> alloc::vec::Vec<Range<u64>> {
+        self.inner.mdat_bounds.iter().map(|mdat| mdat.offset..mdat.offset + mdat.length).collect()
+    }
+
+    /// Payload of the `meta` box's `idat`, which items with construction
+    /// method 1 are stored in, if the file has one.
+    pub fn idat(&self) -> Option<&[u8]> {
+        self.inner.idat.as_deref()
+    }
+
+    fn item_byte_ranges(&self, item: &ItemExtents) -> Result<alloc::vec::Vec<Range<u64>>> {
+        match item.construction_method {
+            ConstructionMethod::Idat => Ok(alloc::vec::Vec::new()),
+            ConstructionMethod::File => item
+                .extents
+                .iter()
+                .map(|extent| self.extent_byte_range(extent).map(|(start, end)| start.to_u64()..end.to_u64()))
+                .collect(),
+            // Flattened by `flatten_item_constructions` during parsing
+            ConstructionMethod::Item => Err(at!(Error::InvalidData("unresolved item_offset construction"))),
+        }
     }
 
     // ========================================
     // Metadata (no data access)
     // ========================================
 
+    /// Whether the input contained every top-level box in full.
+    ///
+    /// Always true unless the parser was created with
+    /// [`DecodeConfig::allow_truncated`] and the input was cut short.
+    pub fn is_complete(&self) -> bool {
+        !self.inner.truncated
+    }
+
+    /// Spec violations that [`Strictness::Permissive`] parsing tolerated.
+    ///
+    /// Always empty in strict mode, where each of these is an error instead,
+    /// except for the [`ParseWarningKind::OverlappingExtents`] reported by
+    /// [`DecodeConfig::detect_overlapping_extents`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.inner.warnings
+    }
+
+    /// Counters and peak tracked memory gathered while parsing.
+    pub fn stats(&self) -> ParseStats {
+        self.inner.stats
+    }
+
     /// Get animation metadata (if animated).
     pub fn animation_info(&self) -> Option<AnimationInfo> {
-        self.animation_data.as_ref().map(|data| AnimationInfo {
+        self.inner.animation_data.as_ref().map(|data| AnimationInfo {
             frame_count: data.sample_table.sample_sizes.len(),
             loop_count: data.loop_count,
             has_alpha: data.alpha_sample_table.is_some(),
             timescale: data.media_timescale,
+            duration: data.duration,
         })
     }
 
     /// Get grid configuration (if grid image).
     pub fn grid_config(&self) -> Option<&GridConfig> {
-        self.grid_config.as_ref()
+        self.inner.grid_config.as_ref()
     }
 
     /// Get number of grid tiles.
     pub fn grid_tile_count(&self) -> usize {
-        self.tiles.len()
+        self.inner.tiles.len()
+    }
+
+    /// Resolve every grid tile at once, on the rayon thread pool. Indexed
+    /// like [`tile_data()`](Self::tile_data). If several tiles fail, the
+    /// error returned is from whichever the pool reached first, not
+    /// necessarily the lowest index. Worth it for large grids, where joining
+    /// multi-extent tiles or reading them from a seekable source dominates.
+    #[cfg(feature = "rayon")]
+    pub fn all_tiles_parallel(&self) -> Result<alloc::vec::Vec<Cow<'_, [u8]>>> {
+        use rayon::prelude::*;
+        (0..self.inner.tiles.len()).into_par_iter().map(|index| self.tile_data(index)).collect()
+    }
+
+    /// Resolve every animation frame at once, on the rayon thread pool.
+    /// Empty for still images. Errors are reported like
+    /// [`all_tiles_parallel()`](Self::all_tiles_parallel).
+    #[cfg(feature = "rayon")]
+    pub fn all_frames_parallel(&self) -> Result<alloc::vec::Vec<FrameRef<'_>>> {
+        use rayon::prelude::*;
+        let count = self.animation_info().map_or(0, |info| info.frame_count);
+        (0..count).into_par_iter().map(|index| self.frame(index)).collect()
+    }
+
+    /// Where each grid tile goes in the output image, indexed like
+    /// [`tile_data()`](Self::tile_data).
+    ///
+    /// Tiles are laid out in row-major order. Tiles in the last column and
+    /// row are cropped to the output size, so their `width`/`height` can be
+    /// smaller than the tile's `ispe`. Fails if the primary item is not a
+    /// grid, the first tile has no `ispe`, or the tiles do not cover the
+    /// output exactly as ISO 23008-12 § 6.6.2.3 requires.
+    pub fn tile_layout(&self) -> Result<alloc::vec::Vec<TilePlacement>> {
+        let grid = self.inner.grid_config.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("not a grid image")))?;
+        let tile = self.inner.tile_spatial_extents
+            .ok_or_else(|| at!(Error::InvalidData("grid tile has no ispe property")))?;
+        let (rows, columns) = (u32::from(grid.rows), u32::from(grid.columns));
+        if self.inner.tiles.len() as u64 != u64::from(rows) * u64::from(columns) {
+            return Err(at!(Error::InvalidData("grid tile count does not match rows × columns")));
+        }
+        let covers = |tile_size: u32, count: u32, output: u32| {
+            let span = u64::from(tile_size) * u64::from(count);
+            tile_size != 0 && span >= u64::from(output) && span - u64::from(tile_size) < u64::from(output)
+        };
+        // An output size of 0 means the full tile span (see GridConfig)
+        let output_width = if grid.output_width == 0 { tile.width.saturating_mul(columns) } else { grid.output_width };
+        let output_height = if grid.output_height == 0 { tile.height.saturating_mul(rows) } else { grid.output_height };
+        if !covers(tile.width, columns, output_width) || !covers(tile.height, rows, output_height) {
+            return Err(at!(Error::InvalidData("grid tiles do not cover the output image")));
+        }
+
+        let mut layout = alloc::vec::Vec::new();
+        layout.try_reserve_exact(self.inner.tiles.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        for row in 0..rows {
+            for column in 0..columns {
+                // Bounded by output_width/output_height, checked above
+                let x = column * tile.width;
+                let y = row * tile.height;
+                layout.push(TilePlacement {
+                    row,
+                    column,
+                    x,
+                    y,
+                    width: tile.width.min(output_width - x),
+                    height: tile.height.min(output_height - y),
+                });
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Get overlay configuration (if the primary item is an `iovl` overlay).
+    pub fn overlay_config(&self) -> Option<&OverlayConfig> {
+        self.inner.overlay_config.as_ref()
+    }
+
+    /// Get number of overlay inputs.
+    pub fn overlay_input_count(&self) -> usize {
+        self.inner.overlay_inputs.len()
+    }
+
+    /// Get overlay input data by index, in the order of [`OverlayConfig::offsets`].
+    pub fn overlay_input_data(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        let item = self.inner.overlay_inputs.get(index)
+            .ok_or_else(|| at!(Error::InvalidData("overlay input index out of bounds")))?;
+        self.resolve_item(item)
+    }
+
+    /// Get the sample transform (`sato`) item that is an alternative to the primary item.
+    ///
+    /// When a `sato` item is the primary item, the parser falls back to the first
+    /// alternative it supports; the transform's inputs are available through
+    /// [`sample_transform_input_data`](Self::sample_transform_input_data).
+    pub fn sample_transform(&self) -> Option<&SampleTransform> {
+        self.inner.sample_transform.as_ref()
+    }
+
+    /// Get sample transform input data by index, in the order of
+    /// [`SampleTransform::input_item_ids`].
+    pub fn sample_transform_input_data(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        let item = self.inner.sample_transform_inputs.get(index)
+            .ok_or_else(|| at!(Error::InvalidData("sample transform input index out of bounds")))?;
+        self.resolve_item(item)
+    }
+
+    /// Get the alternative groups (`altr`) declared in the `grpl` box.
+    ///
+    /// The primary item may be one of the alternatives; see
+    /// [`DecodeConfig::with_select_alternative`] to pick the first supported one automatically.
+    pub fn alternatives(&self) -> &[AlternativeGroup] {
+        &self.inner.alternatives
     }
 
     /// Check if alpha channel uses premultiplied alpha.
     pub fn premultiplied_alpha(&self) -> bool {
-        self.premultiplied_alpha
+        self.inner.premultiplied_alpha
     }
 
     /// Get the primary item's dimensions from its `ispe` property, if present.
@@ -2528,14 +6129,62 @@ impl<'data> AvifParser<'data> {
     /// container. It does not parse the AV1 bitstream or fall back to dimensions
     /// found there.
     pub fn spatial_extents(&self) -> Option<&ImageSpatialExtents> {
-        self.spatial_extents.as_ref()
+        self.inner.spatial_extents.as_ref()
+    }
+
+    /// The primary item's stored width and height, from `ispe`.
+    ///
+    /// Only container metadata is read, so this is the cheap way to get an
+    /// image's size; [`primary_metadata()`](Self::primary_metadata) parses
+    /// the AV1 sequence header. The size is before `clap` and `irot`; see
+    /// [`display_dimensions()`](Self::display_dimensions) for the size the
+    /// image is shown at.
+    pub fn dimensions(&self) -> Option<ImageSpatialExtents> {
+        self.inner.spatial_extents
+    }
+
+    /// The size the primary item is displayed at: its `ispe` dimensions
+    /// cropped by `clap`, then swapped by a 90° or 270° `irot`.
+    ///
+    /// `None` without `ispe`, or if `clap` does not describe a whole-pixel
+    /// rectangle inside the image.
+    pub fn display_dimensions(&self) -> Option<ImageSpatialExtents> {
+        self.display_transform().map(|t| ImageSpatialExtents { width: t.width, height: t.height })
+    }
+
+    /// The primary item's `clap`, `irot` and `imir` as one [`Transform`],
+    /// in the order they must be applied, with the displayed size.
+    ///
+    /// `None` without `ispe`, or if `clap` does not describe a whole-pixel
+    /// rectangle inside the image.
+    pub fn display_transform(&self) -> Option<Transform> {
+        let ImageSpatialExtents { width, height } = self.inner.spatial_extents?;
+        let crop = match &self.inner.clean_aperture {
+            Some(clap) => clap.pixel_rect(width, height)?,
+            None => Rect { x: 0, y: 0, width, height },
+        };
+        let rotation = self.inner.rotation.map_or(0, |rotation| rotation.angle);
+        let flip = self.inner.mirror.map(|mirror| if mirror.axis == 0 { Axis::Vertical } else { Axis::Horizontal });
+        let (width, height) = if rotation % 180 == 90 { (crop.height, crop.width) } else { (crop.width, crop.height) };
+        Some(Transform { crop, rotation, flip, width, height })
     }
 
     /// Get the AV1 codec configuration for the primary item, if present.
     ///
     /// This is parsed from the `av1C` property box in the container.
     pub fn av1_config(&self) -> Option<&AV1Config> {
-        self.av1_config.as_ref()
+        self.inner.av1_config.as_ref()
+    }
+
+    /// Get the HEVC codec configuration for an `hvc1` or `hev1` primary item,
+    /// if present.
+    ///
+    /// This is parsed from the `hvcC` property box. The item's payload, from
+    /// [`primary_data()`](Self::primary_data), is a sequence of NAL units, each
+    /// with a big-endian length prefix of [`HevcConfig::length_size`] bytes.
+    #[cfg(feature = "heif")]
+    pub fn hevc_config(&self) -> Option<&HevcConfig> {
+        self.inner.hevc_config.as_ref()
     }
 
     /// Get colour information for the primary item, if present.
@@ -2544,79 +6193,195 @@ impl<'data> AvifParser<'data> {
     /// For CICP/nclx values, this is the authoritative source and may
     /// differ from values in the AV1 bitstream sequence header.
     pub fn color_info(&self) -> Option<&ColorInformation> {
-        self.color_info.as_ref()
+        self.inner.color_info.as_ref()
+    }
+
+    /// Get the ICC profile for the primary item, if present.
+    ///
+    /// Taken from a `colr` box of type `prof` or `rICC`. An item may carry both
+    /// an `nclx` and an ICC `colr` box; in that case [`color_info()`](Self::color_info)
+    /// returns whichever was associated first, while this always returns the ICC data.
+    pub fn icc_profile(&self) -> Option<Cow<'_, [u8]>> {
+        self.inner.icc_profile.as_deref().map(Cow::Borrowed)
+    }
+
+    /// The primary item's color space, if its CICP values name a common one.
+    ///
+    /// Taken from the `nclx` `colr` property, or from the sequence header of
+    /// the AV1 bitstream when the item has no `colr` (MIAF § 7.3.6.4), which
+    /// parses the primary item's data. `None` when the color is described by
+    /// an ICC profile, when the values are not one of [`KnownColorSpace`], or
+    /// when the fallback finds no parsable sequence header.
+    pub fn known_color_space(&self) -> Option<KnownColorSpace> {
+        let (primaries, transfer) = self.cicp_primaries_and_transfer()?;
+        KnownColorSpace::from_cicp(primaries, transfer)
+    }
+
+    /// Classify the primary image for HDR routing.
+    ///
+    /// A PQ or HLG transfer (from `nclx`, or the sequence header without
+    /// `colr`) gives [`HdrKind::Pq`] or [`HdrKind::Hlg`]. Otherwise a gain
+    /// map gives [`HdrKind::GainMap`]. When no transfer is known, `mdcv` or
+    /// `clli` on an image deeper than 8 bits is taken as HDR10, which is PQ.
+    /// Everything else is [`HdrKind::Sdr`].
+    pub fn hdr_kind(&self) -> HdrKind {
+        let transfer = self.cicp_primaries_and_transfer().map(|(_, transfer)| transfer);
+        match transfer {
+            Some(16) => return HdrKind::Pq,
+            Some(18) => return HdrKind::Hlg,
+            _ => {}
+        }
+        if self.inner.gain_map_metadata.is_some() {
+            return HdrKind::GainMap;
+        }
+        let hdr10_metadata = self.inner.mastering_display.is_some() || self.inner.content_light_level.is_some();
+        let high_bit_depth = self.inner.av1_config.as_ref().is_some_and(|config| config.bit_depth > 8);
+        if transfer.is_none() && hdr10_metadata && high_bit_depth {
+            return HdrKind::Pq;
+        }
+        HdrKind::Sdr
+    }
+
+    /// Colour primaries and transfer characteristics of the primary item:
+    /// its `nclx`, or the sequence header's when it has no `colr`. `None`
+    /// for ICC color or an unparsable bitstream.
+    fn cicp_primaries_and_transfer(&self) -> Option<(u16, u16)> {
+        match &self.inner.color_info {
+            Some(ColorInformation::Nclx { color_primaries, transfer_characteristics, .. }) => {
+                Some((*color_primaries, *transfer_characteristics))
+            }
+            Some(ColorInformation::IccProfile(_)) => None,
+            None => {
+                let seq = self.primary_metadata().ok()?;
+                Some((seq.color_primaries.into(), seq.transfer_characteristics.into()))
+            }
+        }
     }
 
     /// Get rotation for the primary item, if present.
     pub fn rotation(&self) -> Option<&ImageRotation> {
-        self.rotation.as_ref()
+        self.inner.rotation.as_ref()
     }
 
     /// Get mirror for the primary item, if present.
     pub fn mirror(&self) -> Option<&ImageMirror> {
-        self.mirror.as_ref()
+        self.inner.mirror.as_ref()
     }
 
     /// Get clean aperture (crop) for the primary item, if present.
     pub fn clean_aperture(&self) -> Option<&CleanAperture> {
-        self.clean_aperture.as_ref()
+        self.inner.clean_aperture.as_ref()
     }
 
     /// Get pixel aspect ratio for the primary item, if present.
     pub fn pixel_aspect_ratio(&self) -> Option<&PixelAspectRatio> {
-        self.pixel_aspect_ratio.as_ref()
+        self.inner.pixel_aspect_ratio.as_ref()
     }
 
     /// Get content light level info for the primary item, if present.
     pub fn content_light_level(&self) -> Option<&ContentLightLevel> {
-        self.content_light_level.as_ref()
+        self.inner.content_light_level.as_ref()
     }
 
     /// Get mastering display colour volume for the primary item, if present.
     pub fn mastering_display(&self) -> Option<&MasteringDisplayColourVolume> {
-        self.mastering_display.as_ref()
+        self.inner.mastering_display.as_ref()
     }
 
     /// Get content colour volume for the primary item, if present.
     pub fn content_colour_volume(&self) -> Option<&ContentColourVolume> {
-        self.content_colour_volume.as_ref()
+        self.inner.content_colour_volume.as_ref()
+    }
+
+    /// Get the ambient viewing environment (`amve`) for the primary item,
+    /// or from the animation's sample entry, if present.
+    pub fn ambient_viewing_environment(&self) -> Option<&AmbientViewingEnvironment> {
+        self.inner.ambient_viewing.as_ref()
     }
 
     /// Get ambient viewing environment for the primary item, if present.
+    #[deprecated(since = "0.7.0", note = "Use `ambient_viewing_environment()` instead")]
     pub fn ambient_viewing(&self) -> Option<&AmbientViewingEnvironment> {
-        self.ambient_viewing.as_ref()
+        self.ambient_viewing_environment()
     }
 
     /// Get operating point selector for the primary item, if present.
     pub fn operating_point(&self) -> Option<&OperatingPointSelector> {
-        self.operating_point.as_ref()
+        self.inner.operating_point.as_ref()
     }
 
     /// Get layer selector for the primary item, if present.
     pub fn layer_selector(&self) -> Option<&LayerSelector> {
-        self.layer_selector.as_ref()
+        self.inner.layer_selector.as_ref()
     }
 
     /// Get AV1 layered image indexing for the primary item, if present.
     pub fn layered_image_indexing(&self) -> Option<&AV1LayeredImageIndexing> {
-        self.layered_image_indexing.as_ref()
+        self.inner.layered_image_indexing.as_ref()
+    }
+
+    /// Get the user descriptions (`udes`) of the primary item, one per
+    /// language, in association order.
+    pub fn user_descriptions(&self) -> &[UserDescription] {
+        &self.inner.user_descriptions
+    }
+
+    /// Bits per channel of the primary item, one entry per channel, from its
+    /// `pixi` property, if present.
+    ///
+    /// MIAF requires `pixi` on every image item, but many files omit it;
+    /// the AV1 configuration's [`bit_depth`](AV1Config::bit_depth) is then
+    /// the fallback.
+    pub fn bits_per_channel(&self) -> Option<&[u8]> {
+        self.inner.pixel_information.as_ref().map(|pixi| pixi.bits_per_channel.as_slice())
+    }
+
+    /// Bits per channel of the alpha item, from its `pixi` property, if
+    /// present. Alpha planes have a single channel, whose depth should match
+    /// the color item's.
+    pub fn alpha_bits_per_channel(&self) -> Option<&[u8]> {
+        self.inner.alpha_pixel_information.as_ref().map(|pixi| pixi.bits_per_channel.as_slice())
+    }
+
+    /// The primary item's `pixi` property, with the per-channel details of
+    /// version 1, if present. See also [`bits_per_channel()`](Self::bits_per_channel).
+    pub fn pixel_information(&self) -> Option<&PixelInformation> {
+        self.inner.pixel_information.as_ref()
+    }
+
+    /// The alpha item's `pixi` property, if present.
+    pub fn alpha_pixel_information(&self) -> Option<&PixelInformation> {
+        self.inner.alpha_pixel_information.as_ref()
     }
 
     /// Get EXIF metadata for the primary item, if present.
     ///
-    /// Returns raw EXIF data (TIFF header onwards), with the 4-byte AVIF offset prefix stripped.
+    /// Alias for [`exif_data()`](Self::exif_data).
     pub fn exif(&self) -> Option<Result<Cow<'_, [u8]>>> {
-        self.exif_item.as_ref().map(|item| {
+        self.exif_data()
+    }
+
+    /// Get the EXIF payload of the `cdsc`-linked `Exif` item, if present.
+    ///
+    /// Per ISO 23008-12 Annex A.2.1 the item starts with a 4-byte big-endian
+    /// `exif_tiff_header_offset`, counted from the end of that field; a `mini`
+    /// box stores the TIFF data without it. The returned data begins at the
+    /// TIFF header (`II*\0` / `MM\0*`). The slice is borrowed from the input
+    /// when the item is stored in contiguous extents.
+    pub fn exif_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
+        self.inner.exif_item.as_ref().map(|item| {
             let raw = self.resolve_item(item)?;
+            if self.inner.exif_headerless {
+                return Ok(raw);
+            }
             // AVIF EXIF items start with a 4-byte big-endian offset to the TIFF header
             if raw.len() <= 4 {
                 return Err(at!(Error::InvalidData("EXIF item too short")));
             }
             let offset = u32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize;
-            let start = 4 + offset;
-            if start >= raw.len() {
-                return Err(at!(Error::InvalidData("EXIF offset exceeds item size")));
-            }
+            let start = 4usize.checked_add(offset)
+                .filter(|&start| start < raw.len())
+                .ok_or_else(|| at!(Error::InvalidData("EXIF offset exceeds item size")))?;
             match raw {
                 Cow::Borrowed(slice) => Ok(Cow::Borrowed(&slice[start..])),
                 Cow::Owned(vec) => Ok(Cow::Owned(vec[start..].to_vec())),
@@ -2626,9 +6391,73 @@ impl<'data> AvifParser<'data> {
 
     /// Get XMP metadata for the primary item, if present.
     ///
-    /// Returns raw XMP/XML data.
+    /// Alias for [`xmp_data()`](Self::xmp_data).
     pub fn xmp(&self) -> Option<Result<Cow<'_, [u8]>>> {
-        self.xmp_item.as_ref().map(|item| self.resolve_item(item))
+        self.xmp_data()
+    }
+
+    /// Get the XMP packet of the `cdsc`-linked `mime` item with content type
+    /// `application/rdf+xml`, if present, else the payload of the first
+    /// [`UuidBox::XMP`] box.
+    ///
+    /// Returns the raw XMP/XML bytes (UTF-8). `mime` items with other content
+    /// types are ignored.
+    pub fn xmp_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
+        if let Some(item) = &self.inner.xmp_item {
+            return Some(self.resolve_item(item));
+        }
+        Some(self.read_file_range(&self.xmp_uuid_box()?.payload))
+    }
+
+    /// The first XMP `uuid` box, at the top level or in `meta`.
+    fn xmp_uuid_box(&self) -> Option<&UuidBox> {
+        self.inner.uuid_boxes.iter().find(|b| b.uuid == UuidBox::XMP)
+    }
+
+    /// The `uuid` boxes at the top level and directly in `meta`, in file
+    /// order. Their payloads are not read; see [`UuidBox::payload`].
+    pub fn uuid_boxes(&self) -> &[UuidBox] {
+        &self.inner.uuid_boxes
+    }
+
+    /// Items skipped because they are protected (e.g. encrypted). A file
+    /// whose primary image needs a protected item fails with
+    /// [`Error::ProtectedItem`] instead.
+    pub fn protected_items(&self) -> &[ProtectedItem] {
+        &self.inner.protected_items
+    }
+
+    /// Where every box is in the file, if parsed with
+    /// [`DecodeConfig::preserve_layout`].
+    pub fn layout(&self) -> Option<&FileLayout> {
+        self.inner.layout.as_ref()
+    }
+
+    /// Get the raw AV1 bitstream of the primary item's thumbnail, if present.
+    ///
+    /// The thumbnail is the first `av01` item with a `thmb` reference to the
+    /// primary item. It is usually much smaller than the primary image, so
+    /// galleries can decode it for previews.
+    pub fn thumbnail_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
+        self.inner.thumbnail.as_ref().map(|item| self.resolve_item(item))
+    }
+
+    /// Parse AV1 metadata from the thumbnail item, if present.
+    pub fn thumbnail_metadata(&self) -> Option<Result<AV1Metadata>> {
+        self.inner.thumbnail.as_ref().map(|item| {
+            let data = self.resolve_item(item)?;
+            AV1Metadata::parse_av1_bitstream(&data)
+        })
+    }
+
+    /// Dimensions of the thumbnail item, from its `ispe` property.
+    pub fn thumbnail_spatial_extents(&self) -> Option<ImageSpatialExtents> {
+        self.inner.thumbnail_spatial_extents
+    }
+
+    /// AV1 codec configuration of the thumbnail item, from its `av1C` property.
+    pub fn thumbnail_av1_config(&self) -> Option<&AV1Config> {
+        self.inner.thumbnail_av1_config.as_ref()
     }
 
     /// Gain map metadata, if a `tmap` derived image item is present.
@@ -2636,12 +6465,12 @@ impl<'data> AvifParser<'data> {
     /// Describes how to apply a gain map to reconstruct an HDR rendition
     /// from the SDR base image. See ISO 21496-1.
     pub fn gain_map_metadata(&self) -> Option<&GainMapMetadata> {
-        self.gain_map_metadata.as_ref()
+        self.inner.gain_map_metadata.as_ref()
     }
 
     /// Gain map image data (AV1-encoded), if present.
     pub fn gain_map_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
-        self.gain_map.as_ref().map(|item| self.resolve_item(item))
+        self.inner.gain_map.as_ref().map(|item| self.resolve_item(item))
     }
 
     /// Color information for the alternate (typically HDR) rendition.
@@ -2649,7 +6478,19 @@ impl<'data> AvifParser<'data> {
     /// This comes from the `tmap` item's `colr` property and describes
     /// the colour space of the tone-mapped output.
     pub fn gain_map_color_info(&self) -> Option<&ColorInformation> {
-        self.gain_map_color_info.as_ref()
+        self.inner.gain_map_color_info.as_ref()
+    }
+
+    /// Dimensions of the gain map image, from its `ispe` property.
+    ///
+    /// Gain maps are commonly stored at a lower resolution than the base image.
+    pub fn gain_map_spatial_extents(&self) -> Option<ImageSpatialExtents> {
+        self.inner.gain_map_spatial_extents
+    }
+
+    /// AV1 codec configuration of the gain map image, from its `av1C` property.
+    pub fn gain_map_av1_config(&self) -> Option<&AV1Config> {
+        self.inner.gain_map_av1_config.as_ref()
     }
 
     /// Get the full gain map bundle, if a `tmap` derived image item is present.
@@ -2658,9 +6499,9 @@ impl<'data> AvifParser<'data> {
     /// and alternate rendition color info. Returns `None` if no gain map
     /// is present, or `Some(Err(..))` if the gain map data cannot be resolved.
     pub fn gain_map(&self) -> Option<Result<AvifGainMap>> {
-        let metadata = self.gain_map_metadata.as_ref()?.clone();
-        let data_extents = self.gain_map.as_ref()?;
-        let alt_color_info = self.gain_map_color_info.clone();
+        let metadata = self.inner.gain_map_metadata.as_ref()?.clone();
+        let data_extents = self.inner.gain_map.as_ref()?;
+        let alt_color_info = self.inner.gain_map_color_info.clone();
 
         Some(self.resolve_item(data_extents).map(|data| AvifGainMap {
             metadata,
@@ -2674,12 +6515,12 @@ impl<'data> AvifParser<'data> {
     /// Returns `true` if the AVIF container has an `auxl`-linked item with
     /// a depth auxiliary type URN.
     pub fn has_depth_map(&self) -> bool {
-        self.depth_item.is_some()
+        self.inner.depth_item.is_some()
     }
 
     /// Get the raw AV1 bitstream of the depth auxiliary image, if present.
     pub fn depth_map_data(&self) -> Option<Result<Cow<'_, [u8]>>> {
-        self.depth_item.as_ref().map(|item| self.resolve_item(item))
+        self.inner.depth_item.as_ref().map(|item| self.resolve_item(item))
     }
 
     /// Get the full depth map bundle, if a depth auxiliary image is present.
@@ -2698,11 +6539,11 @@ impl<'data> AvifParser<'data> {
     /// }
     /// ```
     pub fn depth_map(&self) -> Option<Result<AvifDepthMap>> {
-        let data_extents = self.depth_item.as_ref()?;
-        let av1_config = self.depth_av1_config.clone();
-        let color_info = self.depth_color_info.clone();
-        let width = self.depth_width;
-        let height = self.depth_height;
+        let data_extents = self.inner.depth_item.as_ref()?;
+        let av1_config = self.inner.depth_av1_config.clone();
+        let color_info = self.inner.depth_color_info.clone();
+        let width = self.inner.depth_width;
+        let height = self.inner.depth_height;
 
         Some(self.resolve_item(data_extents).map(|data| AvifDepthMap {
             data: data.into_owned(),
@@ -2715,23 +6556,288 @@ impl<'data> AvifParser<'data> {
 
     /// Get the major brand from the `ftyp` box (e.g., `*b"avif"` or `*b"avis"`).
     pub fn major_brand(&self) -> &[u8; 4] {
-        &self.major_brand
+        &self.inner.major_brand
     }
 
     /// Get the compatible brands from the `ftyp` box.
     pub fn compatible_brands(&self) -> &[[u8; 4]] {
-        &self.compatible_brands
+        &self.inner.compatible_brands
+    }
+
+    /// Parse AV1 metadata from the primary item.
+    pub fn primary_metadata(&self) -> Result<AV1Metadata> {
+        let data = self.primary_data()?;
+        AV1Metadata::parse_av1_bitstream(&data)
+    }
+
+    /// Cross-check the primary item's `av1C` against its sequence header.
+    ///
+    /// Compares profile, level, tier, bit depth, monochrome and chroma
+    /// subsampling, returning one [`CodecConfigMismatch`] per differing field
+    /// (empty if they agree). AV1-ISOBMFF § 2.3.4 requires them to match, and
+    /// decoders differ on which one they trust, so a mismatch usually means a
+    /// muxer bug. Fails if the primary item has no `av1C`, as for grids and
+    /// overlays, or its bitstream has no parsable sequence header.
+    pub fn verify_codec_config(&self) -> Result<alloc::vec::Vec<CodecConfigMismatch>> {
+        let config = self.inner.av1_config.as_ref()
+            .ok_or_else(|| at!(Error::InvalidData("primary item has no av1C property")))?;
+        let seq = AV1Metadata::parse_av1_bitstream(&self.first_av1_payload()?)?;
+
+        let fields = [
+            ("seq_profile", config.profile, seq.seq_profile),
+            ("seq_level_idx_0", config.level, seq.seq_level_idx),
+            ("seq_tier_0", config.tier, seq.seq_tier),
+            ("bit_depth", config.bit_depth, seq.bit_depth),
+            ("monochrome", u8::from(config.monochrome), u8::from(seq.monochrome)),
+            ("chroma_subsampling_x", config.chroma_subsampling_x, u8::from(seq.chroma_subsampling.horizontal)),
+            ("chroma_subsampling_y", config.chroma_subsampling_y, u8::from(seq.chroma_subsampling.vertical)),
+        ];
+        let mut mismatches = alloc::vec::Vec::new();
+        for (field, av1c, sequence_header) in fields {
+            if av1c != sequence_header {
+                mismatches.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                mismatches.push(CodecConfigMismatch { field, av1c, sequence_header });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Check the file against the AVIF profile brands it advertises, as its
+    /// major or a compatible brand.
+    ///
+    /// `MA1B` (Baseline) requires AV1 Main profile at level 5.1 or lower and
+    /// `MA1A` (Advanced) High profile at level 6.0 or lower, both at most 10
+    /// bits, Main also 4:2:0 or monochrome; these are checked against the
+    /// `av1C` of the primary image, each of its grid tiles, and the alpha,
+    /// thumbnail, gain map and depth images, and of each track. `avio`
+    /// (intra-only) requires every sample of the image sequence to be a sync
+    /// sample. Returns one [`ProfileViolation`] per broken requirement, empty
+    /// if the file complies or advertises none of them.
+    /// See AVIF § 8.2 and § 8.3.
+    pub fn profile_compliance(&self) -> Result<alloc::vec::Vec<ProfileViolation>> {
+        let advertises = |brand: &[u8; 4]| self.inner.major_brand == *brand || self.inner.compatible_brands.contains(brand);
+        let mut violations = alloc::vec::Vec::new();
+        let mut report = |brand: &[u8; 4], image: &'static str, field: &'static str, value: u32, limit: u32| -> Result<()> {
+            if value > limit {
+                violations.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                violations.push(ProfileViolation { brand: *brand, image, field, value, limit });
+            }
+            Ok(())
+        };
+
+        let mut configs = alloc::vec::Vec::new();
+        let images = [
+            ("primary", &self.inner.av1_config),
+            ("alpha", &self.inner.alpha_av1_config),
+            ("thumbnail", &self.inner.thumbnail_av1_config),
+            ("gain map", &self.inner.gain_map_av1_config),
+            ("depth", &self.inner.depth_av1_config),
+        ];
+        for (image, config) in images {
+            if let Some(config) = config {
+                configs.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                configs.push((image, config));
+            }
+        }
+        for config in &self.inner.tile_av1_configs {
+            configs.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+            configs.push(("grid tile", config));
+        }
+        for track in self.inner.animation_data.iter().flat_map(|anim| anim.tracks.iter()) {
+            if let Some(config) = &track.av1_config {
+                configs.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                configs.push(("track", config));
+            }
+        }
+
+        // (brand, seq_profile, seq_level_idx_0)
+        for (brand, max_profile, max_level) in [(b"MA1B", 0, 13), (b"MA1A", 1, 16)] {
+            if !advertises(brand) {
+                continue;
+            }
+            for &(image, config) in &configs {
+                report(brand, image, "seq_profile", config.profile.into(), max_profile)?;
+                report(brand, image, "seq_level_idx_0", config.level.into(), max_level)?;
+                report(brand, image, "bit_depth", config.bit_depth.into(), 10)?;
+                let chroma = match (config.chroma_subsampling_x, config.chroma_subsampling_y) {
+                    _ if config.monochrome => 420,
+                    (1, 1) => 420,
+                    (1, 0) => 422,
+                    _ => 444,
+                };
+                let max_chroma = if max_profile == 0 { 420 } else { 444 };
+                report(brand, image, "chroma_subsampling", chroma, max_chroma)?;
+            }
+        }
+
+        if advertises(b"avio")
+            && let Some(anim) = &self.inner.animation_data {
+                let tables = [Some(&anim.sample_table), anim.alpha_sample_table.as_ref()];
+                for table in tables.into_iter().flatten() {
+                    let non_sync = (0..table.sample_sizes.len()).filter(|&index| !table.is_sync(index)).count();
+                    report(b"avio", "track", "non_sync_samples", u32::try_from(non_sync).unwrap_or(u32::MAX), 0)?;
+                }
+            }
+        Ok(violations)
+    }
+
+    /// HDR metadata OBUs from the primary image's AV1 bitstream.
+    ///
+    /// Complements [`content_light_level()`](Self::content_light_level) and
+    /// [`mastering_display()`](Self::mastering_display), which only report
+    /// the container properties. Grids and overlays are read from their first
+    /// input image, pure sequences from their first frame. Malformed metadata
+    /// OBUs are skipped.
+    pub fn bitstream_hdr_metadata(&self) -> Result<AV1HdrMetadata> {
+        Ok(obu::parse_metadata_obus(&self.first_av1_payload()?).hdr)
+    }
+
+    /// Whether the primary item has an `auxl` alpha item.
+    fn has_alpha_item(&self) -> bool {
+        self.inner.alpha.is_some()
+    }
+
+    /// Whether the file is an animation without a still image item, whose
+    /// first frame stands in for the primary item.
+    fn is_sequence_only(&self) -> bool {
+        self.inner.primary.extents.is_empty() && self.inner.animation_data.is_some()
+    }
+
+    /// The AV1 bitstream that stands for the primary image: the first tile
+    /// or overlay input of a derived image, the first frame of a pure
+    /// sequence, or the primary item itself.
+    fn first_av1_payload(&self) -> Result<Cow<'_, [u8]>> {
+        if self.inner.grid_config.is_some() {
+            self.tile_data(0)
+        } else if let Some(input) = self.inner.overlay_inputs.first() {
+            self.resolve_item(input)
+        } else {
+            self.primary_data()
+        }
+    }
+
+    /// Describe the file in one value, e.g. to log or serialize (with the
+    /// `serde` feature) what an upload contains: the primary image's
+    /// properties, every item with its properties, references and byte
+    /// ranges, and the size and duration of every frame.
+    ///
+    /// Only the sequence header is read from the bitstream; everything else
+    /// comes from the container.
+    pub fn summary(&self) -> AvifSummary {
+        let items = self.inner.items.iter().map(|record| {
+            let mut item = record.summary.clone();
+            if let Some(location) = &record.location {
+                item.size = Self::item_len(&self.inner.raw, &self.inner.mdat_bounds, self.inner.idat.as_deref(), location);
+                item.byte_ranges = self.item_byte_ranges(location).unwrap_or_default();
+            }
+            item
+        }).collect();
+        let frames = self.inner.animation_data.as_ref().map_or_else(alloc::vec::Vec::new, |anim| {
+            (0..anim.sample_table.sample_sizes.len()).map(|index| FrameSummary {
+                size: anim.sample_table.sample_sizes.get(index).unwrap_or(0),
+                alpha_size: anim.alpha_sample_table.as_ref().and_then(|st| st.sample_sizes.get(index)),
+                duration_ms: self.calculate_frame_duration(&anim.sample_table, anim.media_timescale, index).unwrap_or(0),
+                timestamp_ms: anim.sample_table.presentation_time_ms(index, anim.media_timescale),
+                timestamp: anim.sample_table.presentation_time(index),
+                duration: anim.sample_table.sample_delta(index).unwrap_or(0),
+                is_keyframe: anim.sample_table.is_sync(index),
+            }).collect()
+        });
+        AvifSummary {
+            major_brand: fourcc_string(&self.inner.major_brand),
+            compatible_brands: self.inner.compatible_brands.iter().map(fourcc_string).collect(),
+            spatial_extents: self.inner.spatial_extents,
+            av1_config: self.inner.av1_config.clone(),
+            sequence_header: self.first_av1_payload().and_then(|data| AV1Metadata::parse_av1_bitstream(&data)).ok(),
+            nclx: self.inner.color_info.clone().filter(|c| matches!(c, ColorInformation::Nclx { .. })),
+            icc_profile_size: self.inner.icc_profile.as_ref().map(|icc| icc.len()),
+            has_alpha: self.has_alpha_item() || self.animation_info().is_some_and(|a| a.has_alpha),
+            premultiplied_alpha: self.inner.premultiplied_alpha,
+            rotation: self.inner.rotation,
+            mirror: self.inner.mirror,
+            clean_aperture: self.inner.clean_aperture,
+            pixel_aspect_ratio: self.inner.pixel_aspect_ratio,
+            content_light_level: self.inner.content_light_level,
+            mastering_display: self.inner.mastering_display,
+            content_colour_volume: self.inner.content_colour_volume,
+            ambient_viewing_environment: self.inner.ambient_viewing,
+            grid: self.inner.grid_config.clone(),
+            overlay: self.inner.overlay_config.clone(),
+            animation: self.animation_info(),
+            has_exif: self.inner.exif_item.is_some(),
+            has_xmp: self.inner.xmp_item.is_some() || self.xmp_uuid_box().is_some(),
+            has_thumbnail: self.inner.thumbnail.is_some(),
+            gain_map: self.inner.gain_map_metadata.clone(),
+            has_depth_map: self.inner.depth_item.is_some(),
+            items,
+            frames,
+            tracks: self.tracks().to_vec(),
+            warnings: self.inner.warnings.to_vec(),
+        }
+    }
+
+    /// The properties associated with an item, in `ipma` order, with their
+    /// essential flags. `None` if the file has no such item.
+    ///
+    /// HEIF § 9.3.1 forbids displaying an item with an essential property
+    /// the reader does not support: check [`PropertySummary::supported`] on
+    /// essential entries when parsing permissively.
+    pub fn item_properties(&self, item_id: u32) -> Option<&[PropertySummary]> {
+        self.inner.items.iter()
+            .find(|record| record.summary.id == item_id)
+            .map(|record| record.summary.properties.as_slice())
+    }
+
+    /// The region items (`rgan`) in the file, in `iinf` order, with their
+    /// geometry read from the item data.
+    ///
+    /// A region item applies to the items in its
+    /// [`described_items`](RegionItem::described_items), usually the
+    /// primary item.
+    pub fn regions(&self) -> Result<alloc::vec::Vec<RegionItem>> {
+        let mut regions = alloc::vec::Vec::new();
+        for record in self.inner.items.iter().filter(|record| record.summary.item_type == "rgan") {
+            let Some(location) = &record.location else {
+                continue;
+            };
+            let (reference_width, reference_height, geometry) = parse_region_item(&self.resolve_item(location)?)?;
+            let described_items = record.summary.references.iter()
+                .filter(|r| &r.reference_type == b"cdsc")
+                .map(|r| r.to_item_id)
+                .collect();
+            regions.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+            regions.push(RegionItem {
+                item_id: record.summary.id,
+                reference_width,
+                reference_height,
+                regions: geometry,
+                described_items,
+            });
+        }
+        Ok(regions)
+    }
+
+    /// Every item reference in `iref`, in file order, whether or not this
+    /// crate interprets its type.
+    ///
+    /// Use it to follow relationships the parser does not model, e.g. all
+    /// items with a `cdsc` reference to some item.
+    pub fn references(&self) -> &[ItemReference] {
+        &self.inner.references
     }
 
-    /// Parse AV1 metadata from the primary item.
-    pub fn primary_metadata(&self) -> Result<AV1Metadata> {
-        let data = self.primary_data()?;
-        AV1Metadata::parse_av1_bitstream(&data)
+    /// The items with an `auxC` property, in `ipma` order, with their raw
+    /// auxiliary type URN and subtype.
+    ///
+    /// Alpha and depth are recognized by [`AvifParser::alpha_data()`] and
+    /// [`AvifParser::depth_map_data()`]; use this for other auxiliary images.
+    pub fn auxiliary_items(&self) -> &[AuxiliaryItem] {
+        &self.inner.auxiliary_items
     }
 
     /// Parse AV1 metadata from the alpha item, if present.
     pub fn alpha_metadata(&self) -> Option<Result<AV1Metadata>> {
-        self.alpha.as_ref().map(|item| {
+        self.inner.alpha.as_ref().map(|item| {
             let data = self.resolve_item(item)?;
             AV1Metadata::parse_av1_bitstream(&data)
         })
@@ -2771,6 +6877,14 @@ impl<'data> AvifParser<'data> {
             grid_tiles.push(v).map_err(|e| at!(Error::from(e)))?;
         }
 
+        let mut overlay_inputs = TryVec::new();
+        for i in 0..self.overlay_input_count() {
+            let data = self.overlay_input_data(i)?;
+            let mut v = TryVec::new();
+            v.extend_from_slice(&data).map_err(|e| at!(Error::from(e)))?;
+            overlay_inputs.push(v).map_err(|e| at!(Error::from(e)))?;
+        }
+
         let animation = if let Some(info) = self.animation_info() {
             let mut frames = TryVec::new();
             for i in 0..info.frame_count {
@@ -2790,58 +6904,138 @@ impl<'data> AvifParser<'data> {
         Ok(AvifData {
             primary_item,
             alpha_item,
-            premultiplied_alpha: self.premultiplied_alpha,
-            grid_config: self.grid_config.clone(),
+            premultiplied_alpha: self.inner.premultiplied_alpha,
+            grid_config: self.inner.grid_config.clone(),
             grid_tiles,
+            overlay_config: self.inner.overlay_config.clone(),
+            overlay_inputs,
             animation,
-            av1_config: self.av1_config.clone(),
-            color_info: self.color_info.clone(),
-            rotation: self.rotation,
-            mirror: self.mirror,
-            clean_aperture: self.clean_aperture,
-            pixel_aspect_ratio: self.pixel_aspect_ratio,
-            content_light_level: self.content_light_level,
-            mastering_display: self.mastering_display,
-            content_colour_volume: self.content_colour_volume,
-            ambient_viewing: self.ambient_viewing,
-            operating_point: self.operating_point,
-            layer_selector: self.layer_selector,
-            layered_image_indexing: self.layered_image_indexing,
-            exif: self.exif().and_then(|r| r.ok()).map(|c| {
+            av1_config: self.inner.av1_config.clone(),
+            color_info: self.inner.color_info.clone(),
+            icc_profile: self.inner.icc_profile.clone(),
+            rotation: self.inner.rotation,
+            mirror: self.inner.mirror,
+            clean_aperture: self.inner.clean_aperture,
+            pixel_aspect_ratio: self.inner.pixel_aspect_ratio,
+            content_light_level: self.inner.content_light_level,
+            mastering_display: self.inner.mastering_display,
+            content_colour_volume: self.inner.content_colour_volume,
+            ambient_viewing: self.inner.ambient_viewing,
+            operating_point: self.inner.operating_point,
+            layer_selector: self.inner.layer_selector,
+            layered_image_indexing: self.inner.layered_image_indexing,
+            user_descriptions: self.inner.user_descriptions.clone(),
+            exif: self.exif_data().and_then(|r| r.ok()).map(|c| {
                 let mut v = TryVec::new();
                 let _ = v.extend_from_slice(&c);
                 v
             }),
-            xmp: self.xmp().and_then(|r| r.ok()).map(|c| {
+            xmp: self.xmp_data().and_then(|r| r.ok()).map(|c| {
                 let mut v = TryVec::new();
                 let _ = v.extend_from_slice(&c);
                 v
             }),
-            gain_map_metadata: self.gain_map_metadata.clone(),
+            gain_map_metadata: self.inner.gain_map_metadata.clone(),
             gain_map_item: self.gain_map_data().and_then(|r| r.ok()).map(|c| {
                 let mut v = TryVec::new();
                 let _ = v.extend_from_slice(&c);
                 v
             }),
-            gain_map_color_info: self.gain_map_color_info.clone(),
+            gain_map_color_info: self.inner.gain_map_color_info.clone(),
             depth_item: self.depth_map_data().and_then(|r| r.ok()).map(|c| {
                 let mut v = TryVec::new();
                 let _ = v.extend_from_slice(&c);
                 v
             }),
-            depth_width: self.depth_width,
-            depth_height: self.depth_height,
-            depth_av1_config: self.depth_av1_config.clone(),
-            depth_color_info: self.depth_color_info.clone(),
-            major_brand: self.major_brand,
-            compatible_brands: self.compatible_brands.clone(),
+            depth_width: self.inner.depth_width,
+            depth_height: self.inner.depth_height,
+            depth_av1_config: self.inner.depth_av1_config.clone(),
+            depth_color_info: self.inner.depth_color_info.clone(),
+            major_brand: self.inner.major_brand,
+            compatible_brands: self.inner.compatible_brands.clone(),
         })
     }
 }
 
+/// A track of an image sequence, from [`AvifParser::tracks()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct TrackInfo {
+    /// `tkhd` track ID, for [`AvifParser::track_frames`] and
+    /// [`DecodeConfig::with_track`].
+    pub track_id: u32,
+    /// `hdlr` handler type, e.g. `"pict"`, `"auxv"` or `"soun"`.
+    pub handler_type: alloc::string::String,
+    /// Role of the track, from its handler and `tref`.
+    pub kind: TrackKind,
+    /// Presentation width from `tkhd`, in pixels.
+    pub width: u32,
+    /// Presentation height from `tkhd`, in pixels.
+    pub height: u32,
+    /// Type of the first `stsd` sample entry, e.g. `"av01"`.
+    pub codec: Option<alloc::string::String>,
+    /// The `av1C` of the first `av01` sample entry, including the
+    /// `configOBUs` a decoder needs before the first sample.
+    pub av1_config: Option<AV1Config>,
+    /// The first `colr` of that sample entry.
+    pub color_info: Option<ColorInformation>,
+    /// The `pasp` of that sample entry.
+    pub pixel_aspect_ratio: Option<PixelAspectRatio>,
+    /// Number of samples.
+    pub frame_count: usize,
+    /// Media timescale (ticks per second).
+    pub timescale: u32,
+    /// Whether [`AvifParser::frame`] reads this track.
+    pub selected: bool,
+}
+
+/// Role of a track, from [`TrackInfo::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum TrackKind {
+    /// An image sequence (`pict` handler).
+    Picture,
+    /// A video (`vide` handler).
+    Video,
+    /// An auxiliary image sequence such as alpha or depth: the `auxv`
+    /// handler, or a `pict`/`vide` track with an `auxl` track reference.
+    Auxiliary,
+    /// Audio (`soun` handler).
+    Audio,
+    /// Any other handler, e.g. timed metadata.
+    Other,
+}
+
+impl TrackKind {
+    fn classify(handler_type: &FourCC, references: &[TrackReference]) -> Self {
+        let auxiliary = references.iter().any(|r| r.reference_type == b"auxl");
+        match &handler_type.value {
+            b"auxv" => Self::Auxiliary,
+            b"pict" | b"vide" if auxiliary => Self::Auxiliary,
+            b"pict" => Self::Picture,
+            b"vide" => Self::Video,
+            b"soun" => Self::Audio,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// A frame found by [`AvifParser::frame_index_at()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FramePosition {
+    /// The frame displayed at the requested time.
+    pub index: usize,
+    /// The nearest keyframe at or before `index`, where decoding starts.
+    pub keyframe_index: usize,
+}
+
 /// Iterator over animation frames.
 ///
-/// Created by [`AvifParser::frames()`]. Yields [`FrameRef`] on demand.
+/// Created by [`AvifParser::frames()`], [`frames_from()`](AvifParser::frames_from)
+/// and [`frames_range()`](AvifParser::frames_range). Yields [`FrameRef`] on demand.
 pub struct FrameIterator<'a> {
     parser: &'a AvifParser<'a>,
     index: usize,
@@ -2872,15 +7066,70 @@ impl ExactSizeIterator for FrameIterator<'_> {
     }
 }
 
+/// Animation frames in one owned buffer, from [`AvifParser::into_frames()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedFrames {
+    data: alloc::vec::Vec<u8>,
+    /// End offset of each frame in `data`; frame `i` starts where `i - 1` ends.
+    ends: alloc::vec::Vec<usize>,
+}
+
+impl OwnedFrames {
+    /// Number of frames.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether there are no frames (a still image).
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The data of frame `index`.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let end = *self.ends.get(index)?;
+        let start = index.checked_sub(1).map_or(0, |prev| self.ends[prev]);
+        self.data.get(start..end)
+    }
+
+    /// Iterate over the frames in order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[u8]> + '_ {
+        (0..self.len()).map(|index| self.get(index).unwrap_or_default())
+    }
+
+    /// The concatenated frames and the end offset of each.
+    pub fn into_parts(self) -> (alloc::vec::Vec<u8>, alloc::vec::Vec<usize>) {
+        (self.data, self.ends)
+    }
+}
+
 struct AvifInternalMeta {
     item_references: TryVec<SingleItemTypeReferenceBox>,
+    /// Every `ipco` property, once, however many items it is associated with.
+    property_store: TryVec<ItemProperty>,
+    /// The `ipma` associations, pointing into `property_store`.
     properties: TryVec<AssociatedProperty>,
     primary_item_id: u32,
     iloc_items: TryVec<ItemLocationBoxItem>,
     item_infos: TryVec<ItemInfoEntry>,
     idat: Option<TryVec<u8>>,
-    #[allow(dead_code)] // Parsed for future altr group support
     entity_groups: TryVec<EntityGroup>,
+    uuid_boxes: TryVec<UuidBox>,
+    /// Items with an `ipro` entry, which are never read.
+    protected_items: TryVec<ProtectedItem>,
+    /// Read from a `mini` box, whose Exif has no `exif_tiff_header_offset`.
+    headerless_exif: bool,
+}
+
+impl AvifInternalMeta {
+    /// The property an association refers to.
+    fn property(&self, association: &AssociatedProperty) -> &ItemProperty {
+        &self.property_store[association.property_index]
+    }
+
+    fn is_protected(&self, item_id: u32) -> bool {
+        self.protected_items.iter().any(|item| item.item_id == item_id)
+    }
 }
 
 /// A Media Data Box
@@ -2957,7 +7206,19 @@ impl MediaDataBox {
 #[derive(Debug)]
 struct ItemInfoEntry {
     item_id: u32,
+    /// 0 if unprotected, otherwise a 1-based index into `ipro`
+    protection_index: u16,
     item_type: FourCC,
+    /// `content_type` of a `mime` item (without the NUL terminator), empty otherwise.
+    content_type: TryVec<u8>,
+}
+
+impl ItemInfoEntry {
+    /// XMP packets are stored as `mime` items with content type `application/rdf+xml`
+    /// (ISO 23008-12 Annex A.2.2).
+    fn is_xmp(&self) -> bool {
+        self.item_type == b"mime" && &self.content_type[..] == b"application/rdf+xml"
+    }
 }
 
 /// See ISO 14496-12:2015 § 8.11.12
@@ -2971,6 +7232,17 @@ struct SingleItemTypeReferenceBox {
     reference_index: u16,
 }
 
+impl SingleItemTypeReferenceBox {
+    fn to_item_reference(&self) -> ItemReference {
+        ItemReference {
+            reference_type: self.item_type.value,
+            from_item_id: self.from_item_id,
+            to_item_id: self.to_item_id,
+            index: self.reference_index,
+        }
+    }
+}
+
 /// Potential sizes (in bytes) of variable-sized fields of the 'iloc' box
 /// See ISO 14496-12:2015 § 8.11.3
 #[derive(Debug)]
@@ -3026,11 +7298,12 @@ impl TryFrom<u8> for IlocVersion {
 /// Used for 'iloc' boxes
 /// See ISO 14496-12:2015 § 8.11.3
 /// `base_offset` is omitted since it is integrated into the ranges in `extents`
-/// `data_reference_index` is omitted, since only 0 (i.e., this file) is supported
 #[derive(Debug)]
 struct ItemLocationBoxItem {
     item_id: u32,
     construction_method: ConstructionMethod,
+    /// 0 for this file, otherwise a 1-based index into the `meta` box's `dref`
+    data_reference_index: u16,
     /// Unused for `ConstructionMethod::Idat`
     extents: TryVec<ItemLocationBoxExtent>,
 }
@@ -3039,14 +7312,14 @@ struct ItemLocationBoxItem {
 enum ConstructionMethod {
     File,
     Idat,
-    #[allow(dead_code)] // TODO: see https://github.com/mozilla/mp4parse-rust/issues/196
     Item,
 }
 
-/// `extent_index` is omitted since it's only used for `ConstructionMethod::Item` which
-/// is currently not implemented.
+/// `extent_index` is only meaningful for `ConstructionMethod::Item`, where it selects
+/// (1-based) the source item among this item's `iloc` references.
 #[derive(Clone, Debug)]
 struct ItemLocationBoxExtent {
+    extent_index: Option<u64>,
     extent_range: ExtentRange,
 }
 
@@ -3063,31 +7336,63 @@ impl ExtentRange {
             Self::ToEnd(r) => r.start,
         }
     }
+
+    /// Whether `extents` describe one unbroken span: each ends where the
+    /// next begins, so the item can be borrowed rather than concatenated.
+    fn contiguous(extents: &[Self]) -> bool {
+        !extents.is_empty()
+            && extents.windows(2).all(|pair| matches!(&pair[0], Self::WithLength(r) if r.end == pair[1].start()))
+    }
 }
 
 /// See ISO 14496-12:2015 § 4.2
 struct BMFFBox<'a, T> {
     head: BoxHeader,
     content: Take<&'a mut T>,
+    /// Nesting level: 1 for top-level boxes.
+    depth: u32,
+    /// Deepest level children may be at (see [`DecodeConfig::max_box_depth`]).
+    max_depth: u32,
+    /// The [`BoxIter`] state, passed on to the iterator over the children.
+    walk: alloc::rc::Rc<BoxWalk>,
+    /// Offset of the end of the (clamped) content, in the [`BoxIter`]'s
+    /// coordinates: file offsets, except under [`BoxIter::new`].
+    end: u64,
+}
+
+/// State shared by a [`BoxIter`] and the iterators over the children.
+#[derive(Default, Clone)]
+struct BoxWalk {
+    /// Box headers read.
+    count: core::cell::Cell<u64>,
+    /// See [`DecodeConfig::max_total_boxes`].
+    limit: Option<u64>,
+    /// See [`DecodeConfig::box_handler`].
+    handler: Option<alloc::sync::Arc<dyn BoxHandler>>,
+    /// Types of the last box read at each level; only kept with a handler.
+    path: core::cell::RefCell<alloc::vec::Vec<[u8; 4]>>,
+    /// Every box read, in file order; see [`DecodeConfig::preserve_layout`].
+    layout: Option<core::cell::RefCell<alloc::vec::Vec<BoxLayout>>>,
 }
 
 impl<T: Read> BMFFBox<'_, T> {
-    fn read_into_try_vec(&mut self) -> std::io::Result<TryVec<u8>> {
+    fn read_into_try_vec(&mut self) -> io::Result<TryVec<u8>> {
         let limit = self.content.limit();
-        // For size=0 boxes, size is set to u64::MAX, but after subtracting offset
-        // (8 or 16 bytes), the limit will be slightly less. Check for values very
-        // close to u64::MAX to detect these cases.
+        // For size=0 boxes read from a stream of unknown length, size is set to
+        // u64::MAX, but after subtracting offset (8 or 16 bytes), the limit will
+        // be slightly less. Check for values very close to u64::MAX to detect
+        // these cases.
         // Cap pre-allocation to 256 MB — the actual read_to_end will
         // grow as needed if the box really is larger, and return early
         // if the underlying reader has less data than claimed.
         const MAX_PREALLOC: u64 = 256 * 1024 * 1024;
         let mut vec = if limit >= u64::MAX - BoxHeader::MIN_LARGE_SIZE {
             // Unknown size (size=0 box), read without pre-allocation
-            std::vec::Vec::new()
+            alloc::vec::Vec::new()
         } else {
-            let mut v = std::vec::Vec::new();
+            let mut v = alloc::vec::Vec::new();
             v.try_reserve_exact(limit.min(MAX_PREALLOC) as usize)
-                .map_err(|_| std::io::ErrorKind::OutOfMemory)?;
+                .map_err(|_| io::ErrorKind::OutOfMemory)?;
             v
         };
         self.content.read_to_end(&mut vec)?; // The default impl
@@ -3101,6 +7406,10 @@ fn box_read_to_end() {
     let mut src = BMFFBox {
         head: BoxHeader { name: BoxType::FileTypeBox, size: 5, offset: 0, uuid: None },
         content: <_ as Read>::take(tmp, 5),
+        depth: 1,
+        max_depth: u32::MAX,
+        walk: Default::default(),
+        end: 5,
     };
     let buf = src.read_into_try_vec().unwrap();
     assert_eq!(buf.len(), 5);
@@ -3115,6 +7424,10 @@ fn box_read_to_end_large_claim() {
     let mut src = BMFFBox {
         head: BoxHeader { name: BoxType::FileTypeBox, size: 5, offset: 0, uuid: None },
         content: <_ as Read>::take(tmp, u64::MAX / 2),
+        depth: 1,
+        max_depth: u32::MAX,
+        walk: Default::default(),
+        end: u64::MAX / 2,
     };
     let buf = src.read_into_try_vec().unwrap();
     assert_eq!(buf.len(), 10);
@@ -3128,23 +7441,89 @@ struct BoxIter<'a, T> {
     /// (e.g. claiming 4 GB when only 26 bytes remain) does not cause
     /// multi-gigabyte allocations based on [`BMFFBox::bytes_left`].
     max_remaining: u64,
+    /// Nesting level of the boxes this iterator yields.
+    depth: u32,
+    max_depth: u32,
+    /// Box headers read, shared with the iterators over their children.
+    walk: alloc::rc::Rc<BoxWalk>,
+    /// Offset of the end of the data, so the next box starts at
+    /// `end - max_remaining`.
+    end: u64,
 }
 
 impl<T: Read> BoxIter<'_, T> {
     /// Create a BoxIter without a known data bound (used by streaming readers).
     #[cfg(feature = "eager")]
     fn new(src: &mut T) -> BoxIter<'_, T> {
-        BoxIter { src, max_remaining: u64::MAX }
+        BoxIter { src, max_remaining: u64::MAX, depth: 1, max_depth: u32::MAX, walk: Default::default(), end: u64::MAX }
     }
 
     fn with_max_remaining(src: &mut T, max_remaining: u64) -> BoxIter<'_, T> {
-        BoxIter { src, max_remaining }
+        BoxIter { src, max_remaining, depth: 1, max_depth: u32::MAX, walk: Default::default(), end: max_remaining }
+    }
+
+    /// Report offsets as if the source started at `offset` in the file.
+    fn starting_at(mut self, offset: u64) -> Self {
+        self.end = offset.saturating_add(self.max_remaining);
+        self
+    }
+
+    /// Boxes read so far by this iterator and those over their children.
+    fn box_count(&self) -> u64 {
+        self.walk.count.get()
+    }
+
+    /// Reject the box header after `limit` of them have been read, here and
+    /// in the iterators over the children. Call before reading any box.
+    fn with_max_boxes(mut self, limit: Option<u64>) -> Self {
+        alloc::rc::Rc::make_mut(&mut self.walk).limit = limit;
+        self
+    }
+
+    /// Offer the unknown boxes skipped here and in the iterators over the
+    /// children to `handler`. Call before reading any box.
+    fn with_handler(mut self, handler: Option<alloc::sync::Arc<dyn BoxHandler>>) -> Self {
+        alloc::rc::Rc::make_mut(&mut self.walk).handler = handler;
+        self
+    }
+
+    /// Record every box read here and in the iterators over the children,
+    /// for [`BoxIter::take_layout`]. Call before reading any box.
+    fn with_layout(mut self, record: bool) -> Self {
+        alloc::rc::Rc::make_mut(&mut self.walk).layout = record.then(Default::default);
+        self
+    }
+
+    /// The boxes recorded since [`BoxIter::with_layout`], if enabled.
+    fn take_layout(&self) -> Option<alloc::vec::Vec<BoxLayout>> {
+        self.walk.layout.as_ref().map(|layout| layout.take())
+    }
+
+    /// Reject boxes nested deeper than `limit` (top-level boxes are level 1).
+    fn with_max_depth(mut self, limit: Option<u32>) -> Self {
+        self.max_depth = limit.unwrap_or(u32::MAX);
+        self
+    }
+
+    /// Upper bound on the bytes not yet read; exact for a fresh iterator
+    /// over a complete buffer.
+    fn remaining(&self) -> u64 {
+        self.max_remaining
     }
 
     fn next_box(&mut self) -> Result<Option<BMFFBox<'_, T>>> {
         let r = read_box_header(self.src);
         match r {
-            Ok(h) => {
+            Ok(mut h) => {
+                let start = self.end.saturating_sub(self.max_remaining);
+                // A top-level size=0 box extends to the end of the input; when
+                // its length is known, give the box its exact size
+                if h.size == u64::MAX && self.depth == 1 && self.end != u64::MAX {
+                    h.size = self.max_remaining.max(h.offset);
+                }
+                if self.depth > self.max_depth {
+                    return Err(at!(Error::ResourceLimitExceeded("box nesting exceeds max_box_depth")));
+                }
                 let claimed = h.size - h.offset;
                 // Clamp the Take limit so that allocations based on
                 // bytes_left() cannot exceed the actual data available.
@@ -3159,9 +7538,28 @@ impl<T: Read> BoxIter<'_, T> {
                 // Decrease our remaining budget by the clamped content
                 // size plus the header bytes already consumed.
                 self.max_remaining = self.max_remaining.saturating_sub(clamped.saturating_add(h.offset));
+                let count = self.walk.count.get().saturating_add(1);
+                if self.walk.limit.is_some_and(|limit| count > limit) {
+                    return Err(at!(Error::ResourceLimitExceeded("box count exceeds max_total_boxes")));
+                }
+                self.walk.count.set(count);
+                if self.walk.handler.is_some() {
+                    let mut path = self.walk.path.borrow_mut();
+                    path.truncate(self.depth.saturating_sub(1) as usize);
+                    path.push(FourCC::from(h.name).value);
+                }
+                if let Some(layout) = &self.walk.layout {
+                    let mut layout = layout.borrow_mut();
+                    layout.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                    layout.push(BoxLayout::new(&h, self.depth, start, clamped.saturating_add(h.offset)));
+                }
                 Ok(Some(BMFFBox {
                     head: h,
                     content: self.src.take(clamped),
+                    depth: self.depth,
+                    max_depth: self.max_depth,
+                    walk: self.walk.clone(),
+                    end: self.end.saturating_sub(self.max_remaining),
                 }))
             }
             Err(e) if matches!(e.error(), Error::UnexpectedEOF) => Ok(None),
@@ -3171,7 +7569,7 @@ impl<T: Read> BoxIter<'_, T> {
 }
 
 impl<T: Read> Read for BMFFBox<'_, T> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.content.read(buf)
     }
 }
@@ -3182,6 +7580,60 @@ impl<T: Offset> Offset for BMFFBox<'_, T> {
     }
 }
 
+/// Run `f` on box `b`, annotating data errors with the box's FourCC and offset.
+#[cfg(feature = "rich-errors")]
+fn in_box<'a, T: Read + Offset, R>(b: &mut BMFFBox<'a, T>, f: impl FnOnce(&mut BMFFBox<'a, T>) -> Result<R>) -> Result<R> {
+    let (name, offset) = (b.head.name, b.start_offset());
+    f(b).map_err(|e| add_box_context(e, name, offset))
+}
+
+/// Run `f` on box `b` (errors carry no box context without `rich-errors`).
+#[cfg(not(feature = "rich-errors"))]
+fn in_box<'a, T: Read + Offset, R>(b: &mut BMFFBox<'a, T>, f: impl FnOnce(&mut BMFFBox<'a, T>) -> Result<R>) -> Result<R> {
+    f(b)
+}
+
+/// Prepend a box to an error's path; the innermost box sets the offset.
+///
+/// Limits, I/O failures and cancellation are not about file content and pass through unchanged.
+#[cfg(feature = "rich-errors")]
+#[cold]
+fn add_box_context(e: At<Error>, name: BoxType, offset: u64) -> At<Error> {
+    let is_data_error = matches!(
+        e.error(),
+        Error::InvalidData(_)
+            | Error::Unsupported(_)
+            | Error::ExternalDataReference { .. }
+            | Error::ProtectedItem { .. }
+            | Error::UnexpectedEOF
+            | Error::WithContext(_)
+    );
+    if !is_data_error {
+        return e;
+    }
+    let fourcc = FourCC::from(name).value;
+    match e.decompose().0 {
+        Error::WithContext(mut inner) => {
+            // Past eight levels the outermost boxes are dropped
+            let _ = inner.1.box_path.try_insert(0, fourcc);
+            at!(Error::WithContext(inner))
+        }
+        error => {
+            let mut box_path = ArrayVec::new();
+            box_path.push(fourcc);
+            at!(Error::WithContext(alloc::boxed::Box::new((error, ErrorContext { offset, box_path }))))
+        }
+    }
+}
+
+impl<T: Read + Offset> BMFFBox<'_, T> {
+    /// Absolute offset of this box's header.
+    fn start_offset(&self) -> u64 {
+        (self.offset() + self.bytes_left()).checked_sub(self.head.size)
+            .unwrap_or_else(|| self.offset().saturating_sub(self.head.offset))
+    }
+}
+
 impl<T: Read> BMFFBox<'_, T> {
     fn bytes_left(&self) -> u64 {
         self.content.limit()
@@ -3192,7 +7644,9 @@ impl<T: Read> BMFFBox<'_, T> {
     }
 
     fn box_iter(&mut self) -> BoxIter<'_, Self> {
-        BoxIter::with_max_remaining(self, self.bytes_left())
+        let (max_remaining, depth, max_depth) = (self.bytes_left(), self.depth.saturating_add(1), self.max_depth);
+        let (walk, end) = (self.walk.clone(), self.end);
+        BoxIter { src: self, max_remaining, depth, max_depth, walk, end }
     }
 }
 
@@ -3277,11 +7731,12 @@ fn read_fullbox_extra<T: ReadBytesExt>(src: &mut T) -> Result<(u8, u32)> {
 }
 
 // Parse the extra fields for a full box whose flag fields must be zero.
-fn read_fullbox_version_no_flags<T: ReadBytesExt>(src: &mut T, options: &ParseOptions) -> Result<u8> {
+fn read_fullbox_version_no_flags<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<u8> {
     let (version, flags) = read_fullbox_extra(src)?;
 
-    if flags != 0 && !options.lenient {
-        return Err(at!(Error::Unsupported("expected flags to be 0")));
+    if flags != 0 {
+        let fourcc: FourCC = src.head.name.into();
+        options.tolerate(ParseWarningKind::NonZeroFlags, &fourcc, src.start_offset(), at!(Error::Unsupported("expected flags to be 0")))?;
     }
 
     Ok(version)
@@ -3295,8 +7750,8 @@ fn skip_box_content<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<()> {
         debug!("{header:?} (skipped)");
         if header.size == u64::MAX {
             // ISOBMFF size==0: the box extends to end-of-file (stored
-            // as the u64::MAX sentinel; valid for the last top-level
-            // box, usually `mdat`). `BoxIter::next_box` clamps the
+            // as the u64::MAX sentinel when the input length is unknown;
+            // valid for the last top-level box, usually `mdat`). `BoxIter::next_box` clamps the
             // reader to the bytes actually available (the OOM guard),
             // so the sentinel-derived length must not be compared
             // against `bytes_left()` — the remaining bytes ARE the
@@ -3314,11 +7769,13 @@ fn skip_box_content<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<()> {
     if to_skip != src.bytes_left() {
         return Err(at!(Error::InvalidData("box content size mismatch")));
     }
-    skip(src, to_skip)
+    offer_to_handler(src)?;
+    skip(src, src.bytes_left())
 }
 
 /// Skip over the remain data of a box.
 fn skip_box_remain<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<()> {
+    offer_to_handler(src)?;
     let remain = {
         let header = src.get_header();
         let len = src.bytes_left();
@@ -3328,11 +7785,23 @@ fn skip_box_remain<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<()> {
     skip(src, remain)
 }
 
+/// Pass an unknown or `uuid` box about to be skipped to the
+/// [`DecodeConfig::box_handler`], if any.
+fn offer_to_handler<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<()> {
+    if !matches!(src.head.name, BoxType::UnknownBox(_) | BoxType::UuidBox) {
+        return Ok(());
+    }
+    let Some(handler) = src.walk.handler.clone() else {
+        return Ok(());
+    };
+    let path = src.walk.path.borrow().clone();
+    let uuid = src.head.uuid;
+    handler.unknown_box(&path, uuid.as_ref(), src).map_err(|e| at!(Error::from(e)))
+}
+
 struct ResourceTracker<'a> {
     config: &'a DecodeConfig,
-    #[cfg(feature = "eager")]
     current_memory: u64,
-    #[cfg(feature = "eager")]
     peak_memory: u64,
 }
 
@@ -3340,14 +7809,11 @@ impl<'a> ResourceTracker<'a> {
     fn new(config: &'a DecodeConfig) -> Self {
         Self {
             config,
-            #[cfg(feature = "eager")]
             current_memory: 0,
-            #[cfg(feature = "eager")]
             peak_memory: 0,
         }
     }
 
-    #[cfg(feature = "eager")]
     fn reserve(&mut self, bytes: u64) -> Result<()> {
         self.current_memory = self.current_memory.saturating_add(bytes);
         self.peak_memory = self.peak_memory.max(self.current_memory);
@@ -3360,7 +7826,6 @@ impl<'a> ResourceTracker<'a> {
         Ok(())
     }
 
-    #[cfg(feature = "eager")]
     fn release(&mut self, bytes: u64) {
         self.current_memory = self.current_memory.saturating_sub(bytes);
     }
@@ -3397,6 +7862,37 @@ impl<'a> ResourceTracker<'a> {
 
         Ok(())
     }
+
+    fn validate_item_construction_depth(&self, depth: u32) -> Result<()> {
+        if let Some(limit) = self.config.max_item_construction_depth
+            && depth > limit {
+                return Err(at!(Error::ResourceLimitExceeded("item construction depth limit exceeded")));
+            }
+
+        Ok(())
+    }
+
+    /// Cap on the extents produced by flattening all `item_offset` constructions,
+    /// which can multiply the extents of the items they are built from.
+    fn validate_flattened_extent_count(&self, count: u64) -> Result<()> {
+        let limit = self.config.max_extents_per_item.map_or(u64::from(u16::MAX), u64::from);
+        if count > limit {
+            return Err(at!(Error::ResourceLimitExceeded("extent count limit exceeded")));
+        }
+
+        Ok(())
+    }
+
+    /// Size guard for item-constructed data, which is concatenated into a
+    /// single owned buffer when resolved.
+    fn validate_constructed_item_size(&self, bytes: u64) -> Result<()> {
+        if let Some(limit) = self.config.peak_memory_limit
+            && bytes > limit {
+                return Err(at!(Error::ResourceLimitExceeded("peak memory limit exceeded")));
+            }
+
+        Ok(())
+    }
 }
 
 /// Read the contents of an AVIF file with resource limits and cancellation support
@@ -3416,20 +7912,37 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
     f: &mut T,
     config: &DecodeConfig,
     stop: &dyn Stop,
+) -> Result<AvifData> {
+    let budget = TimeBudget::new(stop, config.time_budget)?;
+    read_avif_within(f, config, &budget).map_err(|e| budget.classify(e))
+}
+
+#[cfg(feature = "eager")]
+#[allow(deprecated)]
+fn read_avif_within<T: Read + ?Sized>(
+    f: &mut T,
+    config: &DecodeConfig,
+    stop: &dyn Stop,
 ) -> Result<AvifData> {
     let mut tracker = ResourceTracker::new(config);
     let mut f = OffsetReader::new(f);
 
-    let mut iter = BoxIter::new(&mut f);
+    let mut iter = BoxIter::new(&mut f)
+        .with_max_depth(config.max_box_depth)
+        .with_max_boxes(config.max_total_boxes)
+        .with_handler(config.box_handler.clone());
 
     // 'ftyp' box must occur first; see ISO 14496-12:2015 § 4.3.1
     let (major_brand, compatible_brands) = if let Some(mut b) = iter.next_box()? {
         if b.head.name == BoxType::FileTypeBox {
             let ftyp = read_ftyp(&mut b)?;
-            // Accept both 'avif' (single-frame) and 'avis' (animated) brands
-            if ftyp.major_brand != b"avif" && ftyp.major_brand != b"avis" {
+            // 'avif' (single-frame) or 'avis' (animated), as the major or a compatible brand
+            if !is_supported_major_brand(&ftyp) {
                 warn!("major_brand: {}", ftyp.major_brand);
-                return Err(at!(Error::InvalidData("ftyp must be 'avif' or 'avis'")));
+                return Err(at!(Error::InvalidData(UNSUPPORTED_BRAND)));
+            }
+            if config.effective_strictness() == Strictness::Pedantic {
+                check_structural_brand(&ftyp)?;
             }
             let major = ftyp.major_brand.value;
             let compat = ftyp.compatible_brands.iter().map(|b| b.value).collect();
@@ -3442,25 +7955,27 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
     };
 
     let mut meta = None;
+    let mut itemless_meta = false;
     let mut mdats = TryVec::new();
     let mut animation_data: Option<ParsedAnimationData> = None;
 
-    let parse_opts = ParseOptions { lenient: config.lenient };
+    let parse_opts = ParseContext::new(config);
 
     while let Some(mut b) = iter.next_box()? {
         stop.check().map_err(|e| at!(Error::from(e)))?;
 
         match b.head.name {
             BoxType::MetadataBox => {
-                if meta.is_some() {
+                if meta.is_some() || itemless_meta {
                     return Err(at!(Error::InvalidData("There should be zero or one meta boxes per ISO 14496-12:2015 § 8.11.1.1")));
                 }
-                meta = Some(read_avif_meta(&mut b, &parse_opts)?);
+                meta = in_box(&mut b, |b| read_avif_meta(b, &parse_opts, config.select_alternative, stop))?;
+                itemless_meta = meta.is_none();
             },
             BoxType::MovieBox => {
-                let tracks = read_moov(&mut b, stop)?;
+                let tracks = in_box(&mut b, |b| read_moov(b, &parse_opts, stop))?;
                 if !tracks.is_empty() {
-                    animation_data = Some(associate_tracks(tracks)?);
+                    animation_data = Some(associate_tracks(tracks, config.track_id)?);
                 }
             },
             BoxType::MediaDataBox => {
@@ -3481,14 +7996,25 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
 
     // meta is required for still images; pure sequences can have only moov+mdat
     if meta.is_none() && animation_data.is_none() {
-        return Err(at!(Error::InvalidData("missing meta")));
+        return Err(at!(Error::InvalidData(if itemless_meta { ITEMLESS_META } else { "missing meta" })));
     }
-    let Some(meta) = meta else {
+    let Some(mut meta) = meta else {
         // Pure sequence: return minimal AvifData with no items
         return Ok(AvifData {
             ..Default::default()
         });
     };
+    flatten_item_constructions(&mut meta, &tracker)?;
+
+    // A single coded image can be as large as a grid; grid output is checked again below
+    for item_id in [Some(meta.primary_item_id), alpha_item_id(&meta)].into_iter().flatten() {
+        if let Some(ispe) = property_for(&meta, item_id, |p| match p {
+            ItemProperty::ImageSpatialExtents(e) => Some(*e),
+            _ => None,
+        }) {
+            tracker.validate_total_megapixels(ispe.width, ispe.height)?;
+        }
+    }
 
     // Check if primary item is a grid (tiled image)
     let is_grid = meta
@@ -3502,6 +8028,11 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
             }
             is_g
         });
+    let is_overlay = meta
+        .item_infos
+        .iter()
+        .find(|x| x.item_id == meta.primary_item_id)
+        .is_some_and(|info| info.item_type == b"iovl");
 
     // Extract grid configuration if this is a grid image
     let mut grid_config = if is_grid {
@@ -3509,9 +8040,9 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
             .iter()
             .find(|prop| {
                 prop.item_id == meta.primary_item_id
-                    && matches!(prop.property, ItemProperty::ImageGrid(_))
+                    && matches!(meta.property(prop), ItemProperty::ImageGrid(_))
             })
-            .and_then(|prop| match &prop.property {
+            .and_then(|prop| match meta.property(prop) {
                 ItemProperty::ImageGrid(config) => {
                     log::debug!("Grid: found explicit ImageGrid property: {:?}", config);
                     Some(config.clone())
@@ -3522,6 +8053,18 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
         None
     };
 
+    // Then the grid item's ImageGrid payload, usually stored in idat
+    if is_grid
+        && grid_config.is_none()
+        && let Some(loc) = meta.iloc_items.iter().find(|loc| loc.item_id == meta.primary_item_id)
+    {
+        let mut payload = TryVec::new();
+        let mut extractor = ItemDataExtractor { mdats: &mut mdats, idat: meta.idat.as_ref() };
+        if extractor.extract(loc, &mut payload).is_ok() {
+            grid_config = parse_image_grid(&payload).ok();
+        }
+    }
+
     // Find tile item IDs if this is a grid
     let tile_item_ids: TryVec<u32> = if is_grid {
         // Collect tiles with their reference index
@@ -3549,63 +8092,15 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
 
         // If no ImageGrid property found, calculate grid layout from ispe dimensions
         if grid_config.is_none() && !ids.is_empty() {
-            // Try to calculate grid dimensions from ispe properties
-            let grid_dims = meta.properties.iter()
-                .find(|p| p.item_id == meta.primary_item_id)
-                .and_then(|p| match &p.property {
-                    ItemProperty::ImageSpatialExtents(e) => Some(e),
-                    _ => None,
-                });
-
-            let tile_dims = ids.first().and_then(|&tile_id| {
-                meta.properties.iter()
-                    .find(|p| p.item_id == tile_id)
-                    .and_then(|p| match &p.property {
-                        ItemProperty::ImageSpatialExtents(e) => Some(e),
-                        _ => None,
-                    })
-            });
-
-            if let (Some(grid), Some(tile)) = (grid_dims, tile_dims) {
-                // Validate grid output dimensions
-                tracker.validate_total_megapixels(grid.width, grid.height)?;
-
-                // Validate tile dimensions are non-zero (already validated in read_ispe, but defensive)
-                if tile.width == 0 || tile.height == 0 {
-                    log::warn!("Grid: tile has zero dimensions, using fallback");
-                } else if grid.width % tile.width == 0 && grid.height % tile.height == 0 {
-                    // Calculate grid layout: grid_dims ÷ tile_dims
-                    let columns = grid.width / tile.width;
-                    let rows = grid.height / tile.height;
-
-                    // Validate grid dimensions fit in u8 (max 255×255 grid)
-                    if columns > 255 || rows > 255 {
-                        log::warn!("Grid: calculated dimensions {}×{} exceed 255, using fallback", rows, columns);
-                    } else {
-                        log::debug!("Grid: calculated {}×{} layout from ispe dimensions", rows, columns);
-                        grid_config = Some(GridConfig {
-                            rows: rows as u8,
-                            columns: columns as u8,
-                            output_width: grid.width,
-                            output_height: grid.height,
-                        });
-                    }
-                } else {
-                    log::warn!("Grid: dimension mismatch - grid {}×{} not evenly divisible by tile {}×{}, using fallback",
-                              grid.width, grid.height, tile.width, tile.height);
-                }
-            }
-
-            // Fallback: if calculation failed or ispe not available, use N×1 inference
-            if grid_config.is_none() {
-                log::debug!("Grid: using fallback {}×1 layout inference", ids.len());
-                grid_config = Some(GridConfig {
-                    rows: ids.len() as u8,  // Changed: vertical stack
-                    columns: 1,              // Changed: single column
-                    output_width: 0,  // Will be calculated from tiles
-                    output_height: 0, // Will be calculated from tiles
-                });
+            let grid = match grid_layout_from_ispe(&meta, &ids) {
+                Ok(grid) => grid,
+                Err(e) if !config.allow_grid_fallback => return Err(e),
+                Err(_) => fallback_grid_layout(ids.len()),
+            };
+            if grid.output_width != 0 {
+                tracker.validate_total_megapixels(grid.output_width, grid.output_height)?;
             }
+            grid_config = Some(grid);
         }
 
         ids
@@ -3613,35 +8108,14 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
         TryVec::new()
     };
 
-    let alpha_item_id = meta
-        .item_references
-        .iter()
-        // Auxiliary image for the primary image
-        .filter(|iref| {
-            iref.to_item_id == meta.primary_item_id
-                && iref.from_item_id != meta.primary_item_id
-                && iref.item_type == b"auxl"
-        })
-        .map(|iref| iref.from_item_id)
-        // which has the alpha property
-        .find(|&item_id| {
-            meta.properties.iter().any(|prop| {
-                prop.item_id == item_id
-                    && match &prop.property {
-                        ItemProperty::AuxiliaryType(urn) => {
-                            urn.type_subtype().0 == b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha"
-                        }
-                        _ => false,
-                    }
-            })
-        });
+    let alpha_item_id = alpha_item_id(&meta);
 
     // Extract properties for the primary item
     macro_rules! find_prop {
         ($variant:ident) => {
             meta.properties.iter().find_map(|p| {
                 if p.item_id == meta.primary_item_id {
-                    match &p.property {
+                    match meta.property(p) {
                         ItemProperty::$variant(c) => Some(c.clone()),
                         _ => None,
                     }
@@ -3654,6 +8128,7 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
 
     let av1_config = find_prop!(AV1Config);
     let color_info = find_prop!(ColorInformation);
+    let icc_profile = primary_icc_profile(&meta);
     let rotation = find_prop!(Rotation);
     let mirror = find_prop!(Mirror);
     let clean_aperture = find_prop!(CleanAperture);
@@ -3665,6 +8140,7 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
     let operating_point = find_prop!(OperatingPointSelector);
     let layer_selector = find_prop!(LayerSelector);
     let layered_image_indexing = find_prop!(AV1LayeredImageIndexing);
+    let user_descriptions = primary_user_descriptions(&meta)?;
 
     let mut context = AvifData {
         premultiplied_alpha: alpha_item_id.is_some_and(|alpha_item_id| {
@@ -3676,6 +8152,7 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
         }),
         av1_config,
         color_info,
+        icc_profile,
         rotation,
         mirror,
         clean_aperture,
@@ -3687,6 +8164,7 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
         operating_point,
         layer_selector,
         layered_image_indexing,
+        user_descriptions,
         major_brand,
         compatible_brands,
         ..Default::default()
@@ -3712,6 +8190,42 @@ pub fn read_avif_with_config<T: Read + ?Sized>(
             context.grid_tiles.push(tile_data).map_err(|e| at!(Error::from(e)))?;
         }
         context.grid_config = grid_config;
+    } else if is_overlay {
+        // Overlay: the primary item's payload is the iovl descriptor (kept in primary_item,
+        // matching AvifParser::primary_data), inputs come via dimg
+        let mut inputs_with_index: TryVec<(u32, u16)> = TryVec::new();
+        for iref in meta.item_references.iter() {
+            if iref.from_item_id == meta.primary_item_id && iref.item_type == b"dimg" {
+                inputs_with_index.push((iref.to_item_id, iref.reference_index)).map_err(|e| at!(Error::from(e)))?;
+            }
+        }
+        tracker.validate_grid_tiles(inputs_with_index.len() as u32)?;
+        inputs_with_index.sort_by_key(|&(_, idx)| idx);
+
+        let loc = meta
+            .iloc_items
+            .iter()
+            .find(|loc| loc.item_id == meta.primary_item_id)
+            .ok_or_else(|| at!(Error::InvalidData("iovl item not found in iloc")))?;
+        extractor.extract(loc, &mut context.primary_item)?;
+        let overlay_config = parse_overlay(&context.primary_item, inputs_with_index.len())?;
+        tracker.validate_total_megapixels(overlay_config.output_width, overlay_config.output_height)?;
+
+        for (idx, &(input_id, _)) in inputs_with_index.iter().enumerate() {
+            if idx % 16 == 0 {
+                stop.check().map_err(|e| at!(Error::from(e)))?;
+            }
+
+            let mut input_data = TryVec::new();
+            let loc = meta
+                .iloc_items
+                .iter()
+                .find(|loc| loc.item_id == input_id)
+                .ok_or_else(|| at!(Error::InvalidData("overlay input not found in iloc")))?;
+            extractor.extract(loc, &mut input_data)?;
+            context.overlay_inputs.push(input_data).map_err(|e| at!(Error::from(e)))?;
+        }
+        context.overlay_config = Some(overlay_config);
     } else {
         // Standard single-frame AVIF: load primary_item and optional alpha_item
         for loc in meta.iloc_items.iter() {
@@ -3754,7 +8268,8 @@ impl ItemDataExtractor<'_> {
         match loc.construction_method {
             ConstructionMethod::File => self.extract_from_mdat(loc, buf),
             ConstructionMethod::Idat => self.extract_from_idat(loc, buf),
-            ConstructionMethod::Item => Err(at!(Error::Unsupported("construction_method 'item' not supported"))),
+            // Flattened by `flatten_item_constructions` during parsing
+            ConstructionMethod::Item => Err(at!(Error::InvalidData("unresolved item_offset construction"))),
         }
     }
 
@@ -3828,21 +8343,24 @@ fn extract_metadata_sidecars(
         let Some(info) = meta.item_infos.iter().find(|i| i.item_id == desc_item_id) else {
             continue;
         };
+        if meta.is_protected(desc_item_id) {
+            continue;
+        }
         if info.item_type == b"Exif" {
             if let Some(loc) = meta.iloc_items.iter().find(|l| l.item_id == desc_item_id) {
                 let mut raw = TryVec::new();
                 extractor.extract(loc, &mut raw)?;
                 if raw.len() > 4 {
                     let offset = u32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize;
-                    let start = 4 + offset;
-                    if start < raw.len() {
+                    if let Some(start) = 4usize.checked_add(offset).filter(|&start| start < raw.len()) {
                         let mut exif = TryVec::new();
                         exif.extend_from_slice(&raw[start..]).map_err(|e| at!(Error::from(e)))?;
                         context.exif = Some(exif);
                     }
                 }
             }
-        } else if info.item_type == b"mime"
+        } else if info.is_xmp()
+            && context.xmp.is_none()
             && let Some(loc) = meta.iloc_items.iter().find(|l| l.item_id == desc_item_id)
         {
             let mut xmp = TryVec::new();
@@ -3862,7 +8380,7 @@ fn extract_gain_map(
     context: &mut AvifData,
     extractor: &mut ItemDataExtractor<'_>,
 ) -> Result<()> {
-    let Some(tmap_info) = meta.item_infos.iter().find(|info| info.item_type == b"tmap") else {
+    let Some(tmap_info) = meta.item_infos.iter().find(|info| info.item_type == b"tmap" && !meta.is_protected(info.item_id)) else {
         return Ok(());
     };
     let tmap_id = tmap_info.item_id;
@@ -3875,7 +8393,7 @@ fn extract_gain_map(
     }
     inputs.sort_by_key(|&(_, idx)| idx);
 
-    if inputs.len() < 2 || inputs[0].0 != meta.primary_item_id {
+    if inputs.len() < 2 || inputs[0].0 != meta.primary_item_id || meta.is_protected(inputs[1].0) {
         return Ok(());
     }
     let gmap_item_id = inputs[1].0;
@@ -3949,12 +8467,12 @@ fn find_depth_aux_item(meta: &AvifInternalMeta, alpha_item_id: Option<u32>) -> O
         })
         .map(|iref| iref.from_item_id)
         .find(|&item_id| {
-            if alpha_item_id == Some(item_id) {
+            if alpha_item_id == Some(item_id) || meta.is_protected(item_id) {
                 return false;
             }
             meta.properties.iter().any(|prop| {
                 prop.item_id == item_id
-                    && match &prop.property {
+                    && match meta.property(prop) {
                         ItemProperty::AuxiliaryType(urn) => {
                             is_depth_auxiliary_urn(urn.type_subtype().0)
                         }
@@ -3966,12 +8484,207 @@ fn find_depth_aux_item(meta: &AvifInternalMeta, alpha_item_id: Option<u32>) -> O
 
 /// Return the first property of the given item that matches `pick`, ignoring properties for
 /// other items.
-#[cfg(feature = "eager")]
 fn property_for<T>(meta: &AvifInternalMeta, item_id: u32, pick: impl Fn(&ItemProperty) -> Option<T>) -> Option<T> {
     meta.properties
         .iter()
         .filter(|p| p.item_id == item_id)
-        .find_map(|p| pick(&p.property))
+        .find_map(|p| pick(meta.property(p)))
+}
+
+/// A mismatch between the alpha and color images fails with `err`, unless
+/// parsing is permissive, which records an [`ParseWarningKind::AlphaMismatch`].
+fn check_alpha_consistent(
+    consistent: bool,
+    fourcc: &[u8; 4],
+    err: &'static str,
+    config: &DecodeConfig,
+    warnings: &mut TryVec<ParseWarning>,
+) -> Result<()> {
+    if consistent {
+        return Ok(());
+    }
+    if config.effective_strictness() != Strictness::Permissive {
+        return Err(at!(Error::InvalidData(err)));
+    }
+    let warning = ParseWarning { kind: ParseWarningKind::AlphaMismatch, fourcc: *fourcc, offset: 0 };
+    warnings.push(warning).map_err(|e| at!(Error::from(e)))
+}
+
+/// [`DecodeConfig::validate_extents`]: file extents must lie within one
+/// `mdat`, `idat` extents within `idat`, and file extents may only overlap
+/// when they are identical. In truncated input, extents starting in or past
+/// the last `mdat` are left to fail when read.
+fn validate_item_extents(meta: &AvifInternalMeta, mdat_bounds: &[MdatBounds], truncated: bool) -> Result<()> {
+    let idat_len = meta.idat.as_ref().map_or(0, |idat| idat.len().to_u64());
+    let mut ranges = TryVec::new();
+    for item in meta.iloc_items.iter() {
+        for extent in item.extents.iter() {
+            let start = extent.extent_range.start();
+            match item.construction_method {
+                ConstructionMethod::Idat => {
+                    let end = match &extent.extent_range {
+                        ExtentRange::WithLength(range) => range.end,
+                        ExtentRange::ToEnd(_) => idat_len,
+                    };
+                    if start > end || end > idat_len {
+                        return Err(at!(Error::InvalidData("item extent outside idat")));
+                    }
+                }
+                ConstructionMethod::File => {
+                    if truncated && mdat_bounds.last().is_some_and(|last| start >= last.offset) {
+                        continue;
+                    }
+                    let mdat_end = mdat_bounds
+                        .iter()
+                        .find(|mdat| start >= mdat.offset && start - mdat.offset < mdat.length)
+                        .map(|mdat| mdat.offset + mdat.length)
+                        .ok_or_else(|| at!(Error::InvalidData("item extent outside mdat")))?;
+                    let end = match &extent.extent_range {
+                        ExtentRange::WithLength(range) => range.end,
+                        ExtentRange::ToEnd(_) => mdat_end,
+                    };
+                    if end > mdat_end {
+                        return Err(at!(Error::InvalidData("item extent outside mdat")));
+                    }
+                    if start < end {
+                        ranges.push(start..end).map_err(|e| at!(Error::from(e)))?;
+                    }
+                }
+                // Checked against their source items by `flatten_item_constructions`
+                ConstructionMethod::Item => {}
+            }
+        }
+    }
+
+    ranges.sort_by_key(|range| (range.start, range.end));
+    let mut furthest: Option<&Range<u64>> = None;
+    for range in ranges.iter() {
+        match furthest {
+            Some(prev) if range == prev => {}
+            Some(prev) if range.start < prev.end => {
+                return Err(at!(Error::InvalidData("item extents overlap")));
+            }
+            _ => furthest = Some(range),
+        }
+    }
+    Ok(())
+}
+
+/// The owner of a file range: `(b"iloc", item_id)` or `(b"trak", track_id)`.
+type ExtentOwner = ([u8; 4], u32);
+
+/// [`DecodeConfig::detect_overlapping_extents`]: warn about each file extent
+/// or track sample that overlaps an earlier one owned by another item or
+/// track, at its start.
+fn report_overlapping_extents(
+    meta: Option<&AvifInternalMeta>,
+    animation: Option<&AnimationParserData>,
+    mdat_bounds: &[MdatBounds],
+    input_len: u64,
+    warnings: &mut TryVec<ParseWarning>,
+) -> Result<()> {
+    let mut ranges: TryVec<(Range<u64>, ExtentOwner)> = TryVec::new();
+    for item in meta.iter().flat_map(|meta| meta.iloc_items.iter()) {
+        if item.construction_method != ConstructionMethod::File {
+            continue;
+        }
+        for extent in item.extents.iter() {
+            let start = extent.extent_range.start();
+            let end = match &extent.extent_range {
+                ExtentRange::WithLength(range) => range.end,
+                ExtentRange::ToEnd(_) => mdat_bounds
+                    .iter()
+                    .find(|mdat| start >= mdat.offset && start - mdat.offset < mdat.length)
+                    .map_or(input_len, |mdat| mdat.offset + mdat.length),
+            };
+            if start < end {
+                ranges.push((start..end, (*b"iloc", item.item_id))).map_err(|e| at!(Error::from(e)))?;
+            }
+        }
+    }
+    if let Some(anim) = animation {
+        let alpha = anim.alpha_track_id.zip(anim.alpha_sample_table.as_ref());
+        for (track_id, table) in core::iter::once((anim.track_id, &anim.sample_table)).chain(alpha) {
+            for (index, &start) in table.sample_offsets.iter().enumerate() {
+                let end = start.saturating_add(table.sample_sizes.get(index).unwrap_or(0).into());
+                if start < end {
+                    ranges.push((start..end, (*b"trak", track_id))).map_err(|e| at!(Error::from(e)))?;
+                }
+            }
+        }
+    }
+
+    ranges.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut furthest: Option<&(Range<u64>, ExtentOwner)> = None;
+    for entry in ranges.iter() {
+        let (range, owner) = entry;
+        if let Some((prev_range, prev_owner)) = furthest {
+            // An item stored as a track sample shows the same image either way
+            let same_image = range == prev_range && owner.0 != prev_owner.0;
+            if range.start < prev_range.end && owner != prev_owner && !same_image {
+                let warning = ParseWarning { kind: ParseWarningKind::OverlappingExtents, fourcc: owner.0, offset: range.start };
+                warnings.push(warning).map_err(|e| at!(Error::from(e)))?;
+            }
+            if range.end <= prev_range.end {
+                continue;
+            }
+        }
+        furthest = Some(entry);
+    }
+    Ok(())
+}
+
+/// Find the alpha auxiliary image of the primary item: an `auxl` reference to
+/// the primary item from an item whose `auxC` has the alpha URN.
+fn alpha_item_id(meta: &AvifInternalMeta) -> Option<u32> {
+    meta.item_references
+        .iter()
+        // Auxiliary image for the primary image
+        .filter(|iref| {
+            iref.to_item_id == meta.primary_item_id
+                && iref.from_item_id != meta.primary_item_id
+                && iref.item_type == b"auxl"
+        })
+        .map(|iref| iref.from_item_id)
+        // which has the alpha property
+        .find(|&item_id| {
+            meta.properties.iter().any(|prop| {
+                prop.item_id == item_id
+                    && match meta.property(prop) {
+                        ItemProperty::AuxiliaryType(urn) => {
+                            urn.type_subtype().0 == b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha"
+                        }
+                        _ => false,
+                    }
+            })
+        })
+}
+
+/// The items with an `auxC` property, for [`AvifParser::auxiliary_items()`].
+fn auxiliary_items(meta: &AvifInternalMeta) -> Result<alloc::vec::Vec<AuxiliaryItem>> {
+    let mut items: alloc::vec::Vec<AuxiliaryItem> = alloc::vec::Vec::new();
+    for prop in meta.properties.iter() {
+        let ItemProperty::AuxiliaryType(auxc) = meta.property(prop) else {
+            continue;
+        };
+        // Only the first auxC of an item counts
+        if items.iter().any(|item| item.item_id == prop.item_id) {
+            continue;
+        }
+        let (aux_type, aux_subtype) = auxc.type_subtype();
+        let auxiliary_for = meta.item_references.iter()
+            .filter(|iref| iref.from_item_id == prop.item_id && iref.item_type == b"auxl")
+            .map(|iref| iref.to_item_id)
+            .collect();
+        items.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+        items.push(AuxiliaryItem {
+            item_id: prop.item_id,
+            aux_type: alloc::string::String::from_utf8_lossy(aux_type).into_owned(),
+            aux_subtype: aux_subtype.to_vec(),
+            auxiliary_for,
+        });
+    }
+    Ok(items)
 }
 
 /// Decode the animation sample table into per-frame buffers + duration. Sample-size count is
@@ -4015,10 +8728,11 @@ fn extract_animation(
 /// * `f` - Reader for the AVIF file
 /// * `options` - Parsing options (e.g., lenient mode)
 #[cfg(feature = "eager")]
-#[deprecated(since = "1.5.0", note = "Use `AvifParser::from_reader_with_config()` with `DecodeConfig::lenient()` instead")]
+#[deprecated(since = "1.5.0", note = "Use `AvifParser::from_reader_with_config()` with `DecodeConfig::with_strictness()` instead")]
 #[allow(deprecated)]
 pub fn read_avif_with_options<T: Read + ?Sized>(f: &mut T, options: &ParseOptions) -> Result<AvifData> {
-    let config = DecodeConfig::unlimited().lenient(options.lenient);
+    let strictness = if options.lenient { Strictness::Permissive } else { Strictness::Standard };
+    let config = DecodeConfig::unlimited().with_strictness(strictness);
     read_avif_with_config(f, &config, &Unstoppable)
 }
 
@@ -4039,7 +8753,6 @@ pub fn read_avif<T: Read + ?Sized>(f: &mut T) -> Result<AvifData> {
 /// An entity group from a GroupsListBox (`grpl`).
 ///
 /// See ISO 14496-12:2024 § 8.15.3.
-#[allow(dead_code)] // Parsed for future altr group support
 struct EntityGroup {
     group_type: FourCC,
     group_id: u32,
@@ -4086,6 +8799,268 @@ fn read_grpl<T: Read + Offset>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Entity
     Ok(groups)
 }
 
+/// Derive a grid layout by dividing the grid item's `ispe` by its first tile's.
+///
+/// For grids without an ImageGrid descriptor; the error names the input that
+/// is missing or unusable.
+fn grid_layout_from_ispe(meta: &AvifInternalMeta, tile_ids: &[u32]) -> Result<GridConfig> {
+    let ispe = |item_id| property_for(meta, item_id, |p| match p {
+        ItemProperty::ImageSpatialExtents(e) => Some(*e),
+        _ => None,
+    });
+    let grid = ispe(meta.primary_item_id)
+        .ok_or_else(|| at!(Error::InvalidData("grid layout unknown: no ImageGrid descriptor and no ispe on the grid item")))?;
+    let first_tile = *tile_ids.first().ok_or_else(|| at!(Error::InvalidData("grid item has no dimg tiles")))?;
+    let tile = ispe(first_tile)
+        .ok_or_else(|| at!(Error::InvalidData("grid layout unknown: no ImageGrid descriptor and no ispe on the first tile")))?;
+    if tile.width == 0 || tile.height == 0 || grid.width % tile.width != 0 || grid.height % tile.height != 0 {
+        warn!(
+            "grid {}×{} is not a multiple of its {}×{} tiles",
+            grid.width, grid.height, tile.width, tile.height
+        );
+        return Err(at!(Error::InvalidData(
+            "grid layout unknown: no ImageGrid descriptor and the grid ispe is not a multiple of the tile ispe",
+        )));
+    }
+    let (columns, rows) = (grid.width / tile.width, grid.height / tile.height);
+    // The ImageGrid descriptor stores rows and columns minus one in a byte
+    if columns > 256 || rows > 256 {
+        return Err(at!(Error::InvalidData("grid ispe implies more than 256 rows or columns")));
+    }
+    let (columns, rows) = (columns as u16, rows as u16);
+    debug!("grid: calculated {rows}×{columns} layout from ispe dimensions");
+    Ok(GridConfig { rows, columns, output_width: grid.width, output_height: grid.height })
+}
+
+/// The layout [`DecodeConfig::allow_grid_fallback`] guesses: all tiles in
+/// one column, output size left to the decoder.
+fn fallback_grid_layout(tile_count: usize) -> GridConfig {
+    debug!("grid: using fallback {tile_count}×1 layout");
+    GridConfig {
+        rows: u16::try_from(tile_count).unwrap_or(u16::MAX),
+        columns: 1,
+        output_width: 0,
+        output_height: 0,
+    }
+}
+
+/// Parse a `grid` (ImageGrid) item payload.
+/// See ISO 23008-12:2017 § 6.6.2.3
+fn parse_image_grid(data: &[u8]) -> Result<GridConfig> {
+    let mut cursor = io::Cursor::new(data);
+
+    let version = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    if version != 0 {
+        return Err(at!(Error::Unsupported("grid version")));
+    }
+    // flags & 1 determines field size: 0 = 16-bit, 1 = 32-bit
+    let large_fields = cursor.read_u8().map_err(|e| at!(Error::from(e)))? & 1 != 0;
+    let rows_minus_one = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    let columns_minus_one = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    let (output_width, output_height) = if large_fields {
+        (be_u32(&mut cursor)?, be_u32(&mut cursor)?)
+    } else {
+        (u32::from(be_u16(&mut cursor)?), u32::from(be_u16(&mut cursor)?))
+    };
+    if output_width == 0 || output_height == 0 {
+        return Err(at!(Error::InvalidData("grid output dimensions cannot be zero")));
+    }
+
+    Ok(GridConfig {
+        rows: u16::from(rows_minus_one) + 1,
+        columns: u16::from(columns_minus_one) + 1,
+        output_width,
+        output_height,
+    })
+}
+
+/// Parse a `sato` (SampleTransform) item payload for `input_count` inputs.
+/// Returns `None` for versions other than 0.
+/// See AVIF § 4.2.3.3
+fn parse_sample_transform(data: &[u8], input_count: usize) -> Result<Option<(u8, alloc::vec::Vec<SampleTransformToken>)>> {
+    let mut cursor = io::Cursor::new(data);
+
+    // version (2 bits), reserved (4 bits), bit_depth (2 bits)
+    let byte = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    if byte >> 6 != 0 {
+        return Ok(None);
+    }
+    let bit_depth = 8 << (byte & 0x03);
+    let token_count = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+
+    let mut tokens = alloc::vec::Vec::new();
+    tokens.try_reserve_exact(usize::from(token_count)).map_err(|_| at!(Error::OutOfMemory))?;
+    // Values on the stack while evaluating
+    let mut depth = 0usize;
+    for _ in 0..token_count {
+        let token = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+        let (token, pops) = match token {
+            0 => {
+                let constant = match bit_depth {
+                    8 => i64::from(cursor.read_i8().map_err(|e| at!(Error::from(e)))?),
+                    16 => i64::from(cursor.read_i16::<byteorder::BigEndian>().map_err(|e| at!(Error::from(e)))?),
+                    32 => i64::from(be_i32(&mut cursor)?),
+                    _ => cursor.read_i64::<byteorder::BigEndian>().map_err(|e| at!(Error::from(e)))?,
+                };
+                (SampleTransformToken::Constant(constant), 0)
+            }
+            1..=32 => {
+                if usize::from(token) > input_count {
+                    return Err(at!(Error::InvalidData("sato input index exceeds its dimg references")));
+                }
+                (SampleTransformToken::Input(token - 1), 0)
+            }
+            64 => (SampleTransformToken::Negation, 1),
+            65 => (SampleTransformToken::Absolute, 1),
+            66 => (SampleTransformToken::Not, 1),
+            67 => (SampleTransformToken::BitScanReverse, 1),
+            128 => (SampleTransformToken::Sum, 2),
+            129 => (SampleTransformToken::Difference, 2),
+            130 => (SampleTransformToken::Product, 2),
+            131 => (SampleTransformToken::Quotient, 2),
+            132 => (SampleTransformToken::And, 2),
+            133 => (SampleTransformToken::Or, 2),
+            134 => (SampleTransformToken::Xor, 2),
+            135 => (SampleTransformToken::Pow, 2),
+            136 => (SampleTransformToken::Min, 2),
+            137 => (SampleTransformToken::Max, 2),
+            _ => return Err(at!(Error::InvalidData("unknown sato token"))),
+        };
+        if depth < pops {
+            return Err(at!(Error::InvalidData("sato operator lacks operands")));
+        }
+        depth = depth - pops + 1;
+        tokens.push(token);
+    }
+    if depth != 1 {
+        return Err(at!(Error::InvalidData("sato expression must leave one value")));
+    }
+
+    Ok(Some((bit_depth, tokens)))
+}
+
+/// Parse an `rgan` (region) item payload.
+/// See ISO 23008-12:2022 § 6.10.1.3
+fn parse_region_item(data: &[u8]) -> Result<(u32, u32, alloc::vec::Vec<Region>)> {
+    let mut cursor = io::Cursor::new(data);
+
+    let version = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    if version != 0 {
+        return Err(at!(Error::Unsupported("rgan version")));
+    }
+    // flags & 1 determines field size: 0 = 16-bit, 1 = 32-bit
+    let large_fields = cursor.read_u8().map_err(|e| at!(Error::from(e)))? & 1 != 0;
+    let unsigned = |cursor: &mut io::Cursor<&[u8]>| -> Result<u32> {
+        if large_fields { be_u32(cursor) } else { be_u16(cursor).map(u32::from) }
+    };
+    let signed = |cursor: &mut io::Cursor<&[u8]>| -> Result<i32> {
+        if large_fields { be_i32(cursor) } else { be_u16(cursor).map(|v| i32::from(v as i16)) }
+    };
+    let reference_width = unsigned(&mut cursor)?;
+    let reference_height = unsigned(&mut cursor)?;
+    let region_count = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+
+    let mut regions = alloc::vec::Vec::new();
+    regions.try_reserve_exact(usize::from(region_count)).map_err(|_| at!(Error::OutOfMemory))?;
+    for _ in 0..region_count {
+        let geometry_type = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+        let region = match geometry_type {
+            0 => Region::Point { x: signed(&mut cursor)?, y: signed(&mut cursor)? },
+            1 | 2 | 4 | 5 => {
+                let (x, y) = (signed(&mut cursor)?, signed(&mut cursor)?);
+                let (width, height) = (unsigned(&mut cursor)?, unsigned(&mut cursor)?);
+                match geometry_type {
+                    1 => Region::Rectangle { x, y, width, height },
+                    2 => Region::Ellipse { x, y, radius_x: width, radius_y: height },
+                    4 => Region::ReferencedMask { x, y, width, height },
+                    _ => {
+                        let mask_coding_method = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+                        if mask_coding_method != 0 {
+                            // A coded mask runs to the end of the item, so no region follows
+                            regions.push(Region::InlineMask { x, y, width, height });
+                            break;
+                        }
+                        // One bit per pixel, rows not padded
+                        let mask_bytes = (u64::from(width) * u64::from(height)).div_ceil(8);
+                        cursor.set_position(cursor.position().saturating_add(mask_bytes));
+                        Region::InlineMask { x, y, width, height }
+                    }
+                }
+            }
+            3 | 6 => {
+                let point_count = unsigned(&mut cursor)?;
+                let point_size = if large_fields { 8 } else { 4 };
+                let remaining = data.len().to_u64().saturating_sub(cursor.position());
+                if u64::from(point_count) * point_size > remaining {
+                    return Err(at!(Error::InvalidData("rgan point_count exceeds the item size")));
+                }
+                let mut points = alloc::vec::Vec::new();
+                points.try_reserve_exact(point_count as usize).map_err(|_| at!(Error::OutOfMemory))?;
+                for _ in 0..point_count {
+                    points.push((signed(&mut cursor)?, signed(&mut cursor)?));
+                }
+                if geometry_type == 3 { Region::Polygon { points } } else { Region::Polyline { points } }
+            }
+            _ => return Err(at!(Error::Unsupported("rgan geometry_type"))),
+        };
+        regions.push(region);
+    }
+
+    Ok((reference_width, reference_height, regions))
+}
+
+/// Parse an `iovl` (ImageOverlay) item payload for `input_count` inputs.
+/// See ISO 23008-12:2017 § 6.6.2.3.2
+fn parse_overlay(data: &[u8], input_count: usize) -> Result<OverlayConfig> {
+    let mut cursor = io::Cursor::new(data);
+
+    let version = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
+    if version != 0 {
+        return Err(at!(Error::Unsupported("iovl version")));
+    }
+    // flags & 1 determines field size: 0 = 16-bit, 1 = 32-bit
+    let large_fields = cursor.read_u8().map_err(|e| at!(Error::from(e)))? & 1 != 0;
+
+    let mut canvas_fill_value = [0u16; 4];
+    for value in canvas_fill_value.iter_mut() {
+        *value = be_u16(&mut cursor)?;
+    }
+    let (output_width, output_height) = if large_fields {
+        (be_u32(&mut cursor)?, be_u32(&mut cursor)?)
+    } else {
+        (u32::from(be_u16(&mut cursor)?), u32::from(be_u16(&mut cursor)?))
+    };
+    if output_width == 0 || output_height == 0 {
+        return Err(at!(Error::InvalidData("iovl output dimensions cannot be zero")));
+    }
+
+    // Each offset pair takes 4 or 8 bytes; reject counts the payload cannot hold
+    let bytes_per_input: u64 = if large_fields { 8 } else { 4 };
+    let remaining = data.len() as u64 - cursor.position();
+    if (input_count as u64) * bytes_per_input > remaining {
+        return Err(at!(Error::InvalidData("iovl payload too short for its dimg inputs")));
+    }
+    let mut offsets = alloc::vec::Vec::new();
+    offsets.try_reserve(input_count).map_err(|_| at!(Error::OutOfMemory))?;
+    for _ in 0..input_count {
+        let offset = if large_fields {
+            (be_i32(&mut cursor)?, be_i32(&mut cursor)?)
+        } else {
+            let horizontal = be_u16(&mut cursor)? as i16;
+            let vertical = be_u16(&mut cursor)? as i16;
+            (i32::from(horizontal), i32::from(vertical))
+        };
+        offsets.push(offset);
+    }
+
+    Ok(OverlayConfig {
+        canvas_fill_value,
+        output_width,
+        output_height,
+        offsets,
+    })
+}
+
 /// Parse a ToneMapImage (`tmap`) item payload into gain map metadata.
 ///
 /// See ISO 21496-1:2025 for the payload format.
@@ -4111,7 +9086,7 @@ const TMAP_FLAG_COMMON_DENOMINATOR: u8 = 0x08;
 const TMAP_FLAG_BACKWARD_DIRECTION: u8 = 0x04;
 
 fn parse_tone_map_image(data: &[u8]) -> Result<GainMapMetadata> {
-    let mut cursor = std::io::Cursor::new(data);
+    let mut cursor = io::Cursor::new(data);
 
     // version (u8) — must be 0
     let version = cursor.read_u8().map_err(|e| at!(Error::from(e)))?;
@@ -4177,152 +9152,881 @@ fn parse_tone_map_image(data: &[u8]) -> Result<GainMapMetadata> {
         alternate_hdr_headroom_n = be_u32(&mut cursor)?;
         alternate_hdr_headroom_d = common_d;
 
-        for ch in channels.iter_mut().take(channel_count) {
-            ch.gain_map_min_n = be_i32(&mut cursor)?;
-            ch.gain_map_min_d = common_d;
-            ch.gain_map_max_n = be_i32(&mut cursor)?;
-            ch.gain_map_max_d = common_d;
-            ch.gamma_n = be_u32(&mut cursor)?;
-            ch.gamma_d = common_d;
-            ch.base_offset_n = be_i32(&mut cursor)?;
-            ch.base_offset_d = common_d;
-            ch.alternate_offset_n = be_i32(&mut cursor)?;
-            ch.alternate_offset_d = common_d;
+        for ch in channels.iter_mut().take(channel_count) {
+            ch.gain_map_min_n = be_i32(&mut cursor)?;
+            ch.gain_map_min_d = common_d;
+            ch.gain_map_max_n = be_i32(&mut cursor)?;
+            ch.gain_map_max_d = common_d;
+            ch.gamma_n = be_u32(&mut cursor)?;
+            ch.gamma_d = common_d;
+            ch.base_offset_n = be_i32(&mut cursor)?;
+            ch.base_offset_d = common_d;
+            ch.alternate_offset_n = be_i32(&mut cursor)?;
+            ch.alternate_offset_d = common_d;
+        }
+    } else {
+        // Full layout — each fraction has its own denominator.
+        base_hdr_headroom_n = be_u32(&mut cursor)?;
+        base_hdr_headroom_d = be_u32(&mut cursor)?;
+        alternate_hdr_headroom_n = be_u32(&mut cursor)?;
+        alternate_hdr_headroom_d = be_u32(&mut cursor)?;
+
+        for ch in channels.iter_mut().take(channel_count) {
+            ch.gain_map_min_n = be_i32(&mut cursor)?;
+            ch.gain_map_min_d = be_u32(&mut cursor)?;
+            ch.gain_map_max_n = be_i32(&mut cursor)?;
+            ch.gain_map_max_d = be_u32(&mut cursor)?;
+            ch.gamma_n = be_u32(&mut cursor)?;
+            ch.gamma_d = be_u32(&mut cursor)?;
+            ch.base_offset_n = be_i32(&mut cursor)?;
+            ch.base_offset_d = be_u32(&mut cursor)?;
+            ch.alternate_offset_n = be_i32(&mut cursor)?;
+            ch.alternate_offset_d = be_u32(&mut cursor)?;
+        }
+    }
+
+    // Copy channel 0 to channels 1 and 2 if single-channel
+    if !is_multichannel {
+        channels[1] = channels[0];
+        channels[2] = channels[0];
+    }
+
+    // writer_version is parsed and validated (must be >= minimum_version)
+    // but not stored — we always emit 0 on serialize.
+    let _ = writer_version;
+
+    Ok(GainMapMetadata {
+        is_multichannel,
+        use_base_colour_space,
+        backward_direction,
+        base_hdr_headroom_n,
+        base_hdr_headroom_d,
+        alternate_hdr_headroom_n,
+        alternate_hdr_headroom_d,
+        channels,
+    })
+}
+
+/// Parse a metadata box in the context of an AVIF
+/// Currently requires the primary item to be an av01 item type and generates
+/// an error otherwise.
+/// See ISO 14496-12:2015 § 8.11.1
+fn read_avif_meta<T: Read + Offset>(
+    src: &mut BMFFBox<'_, T>,
+    options: &ParseContext,
+    select_alternative: bool,
+    stop: &dyn Stop,
+) -> Result<Option<AvifInternalMeta>> {
+    let version = read_fullbox_version_no_flags(src, options)?;
+
+    if version != 0 {
+        return Err(at!(Error::Unsupported("unsupported meta version")));
+    }
+
+    let mut primary_item_id = None;
+    let mut item_infos = None;
+    let mut iloc_items = None;
+    let mut item_references = TryVec::new();
+    let mut property_store = TryVec::new();
+    let mut properties = TryVec::new();
+    let mut idat = None;
+    let mut entity_groups = TryVec::new();
+    let mut uuid_boxes = TryVec::new();
+    let mut data_references = TryVec::new();
+    let mut protection_schemes = TryVec::new();
+
+    let mut iter = src.box_iter();
+    while let Some(mut b) = iter.next_box()? {
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::DataInformationBox => {
+                    data_references = read_dinf(b)?;
+                },
+                BoxType::ItemProtectionBox => {
+                    protection_schemes = read_ipro(b)?;
+                },
+                BoxType::ItemInfoBox => {
+                    if item_infos.is_some() {
+                        return Err(at!(Error::InvalidData("There should be zero or one iinf boxes per ISO 14496-12:2015 § 8.11.6.1")));
+                    }
+                    item_infos = Some(read_iinf(b, options)?);
+                },
+                BoxType::ItemLocationBox => {
+                    if iloc_items.is_some() {
+                        return Err(at!(Error::InvalidData("There should be zero or one iloc boxes per ISO 14496-12:2015 § 8.11.3.1")));
+                    }
+                    iloc_items = Some(read_iloc(b, options, stop)?);
+                },
+                BoxType::PrimaryItemBox => {
+                    if primary_item_id.is_some() {
+                        return Err(at!(Error::InvalidData("There should be zero or one iloc boxes per ISO 14496-12:2015 § 8.11.4.1")));
+                    }
+                    primary_item_id = Some(read_pitm(b, options)?);
+                },
+                BoxType::ImageReferenceBox => {
+                    item_references.append(&mut read_iref(b, options)?).map_err(|e| at!(Error::from(e)))?;
+                },
+                BoxType::ImagePropertiesBox => {
+                    (property_store, properties) = read_iprp(b, options)?;
+                },
+                BoxType::ItemDataBox => {
+                    if idat.is_some() {
+                        return Err(at!(Error::InvalidData("There should be zero or one idat boxes")));
+                    }
+                    idat = Some(b.read_into_try_vec().map_err(|e| at!(Error::from(e)))?);
+                },
+                BoxType::GroupsListBox => {
+                    entity_groups.append(&mut read_grpl(b)?).map_err(|e| at!(Error::from(e)))?;
+                },
+                BoxType::HandlerBox => {
+                    let hdlr = read_hdlr(b)?;
+                    if hdlr.handler_type != b"pict" {
+                        warn!("hdlr handler_type: {}", hdlr.handler_type);
+                        return Err(at!(Error::InvalidData("meta handler_type must be 'pict' for AVIF")));
+                    }
+                },
+                BoxType::UuidBox => {
+                    if let Some(uuid) = UuidBox::from_box(b, true) {
+                        uuid_boxes.push(uuid).map_err(|e| at!(Error::from(e)))?;
+                    }
+                    skip_box_content(b)?;
+                },
+                _ => skip_box_content(b)?,
+            }
+
+            check_parser_state(&b.head, &b.content)?;
+            Ok(())
+        })?;
+    }
+
+    // Some avis files carry a meta box that locates no still image; their
+    // first frame stands in for the primary item. Callers fail with
+    // ITEMLESS_META unless the file has a moov to fall back to.
+    if primary_item_id.is_none() && iloc_items.is_none() {
+        return Ok(None);
+    }
+
+    let item_infos = item_infos.ok_or_else(|| at!(Error::InvalidData("iinf missing")))?;
+    let mut protected_items = TryVec::new();
+    for item in item_infos.iter().filter(|item| item.protection_index != 0) {
+        let scheme = protection_schemes.get(usize::from(item.protection_index) - 1)
+            .ok_or_else(|| at!(Error::InvalidData("item_protection_index has no ipro entry")))?;
+        protected_items.push(ProtectedItem { item_id: item.item_id, scheme: *scheme }).map_err(|e| at!(Error::from(e)))?;
+    }
+
+    let primary_item_id = match primary_item_id {
+        Some(id) => id,
+        None => {
+            options.tolerate(
+                ParseWarningKind::MissingPrimaryItem, &BoxType::PrimaryItemBox.into(), src.start_offset(),
+                at!(Error::InvalidData("Required pitm box not present in meta box")),
+            )?;
+            guess_primary_item(&item_infos, &item_references)
+                .ok_or_else(|| at!(Error::InvalidData("Required pitm box not present in meta box")))?
+        }
+    };
+
+    if !item_infos.iter().any(|x| x.item_id == primary_item_id) {
+        return Err(at!(Error::InvalidData("primary_item_id not present in iinf box")));
+    }
+
+    let iloc_items = iloc_items.ok_or_else(|| at!(Error::InvalidData("iloc missing")))?;
+    check_data_references(&data_references, iloc_items.iter().map(|item| item.data_reference_index), options)?;
+
+    let mut meta = AvifInternalMeta {
+        property_store,
+        properties,
+        item_references,
+        primary_item_id,
+        iloc_items,
+        item_infos,
+        idat,
+        entity_groups,
+        uuid_boxes,
+        protected_items,
+        headerless_exif: false,
+    };
+    // A sato primary is an AVIF 1.2 extension: show its altr fallback
+    // and expose the expression through AvifParser::sample_transform()
+    let primary_is_sato = meta.item_infos.iter().any(|x| x.item_id == meta.primary_item_id && x.item_type == b"sato");
+    if select_alternative || primary_is_sato {
+        select_alternative_primary(&mut meta);
+    }
+    resolve_identity_primary(&mut meta)?;
+    check_protected_items(&meta)?;
+
+    if let Some(item_info) = meta.item_infos.iter().find(|x| x.item_id == meta.primary_item_id) {
+        // Allow "av01" (standard single-frame), "hvc1"/"hev1" with the heif feature, and the "grid"/"iovl" derived types
+        if !is_coded_image_item(&item_info.item_type) && item_info.item_type != b"grid" && item_info.item_type != b"iovl" {
+            warn!("primary_item_id type: {}", item_info.item_type);
+            return Err(at!(Error::InvalidData(UNSUPPORTED_PRIMARY_TYPE)));
+        }
+    }
+
+    Ok(Some(meta))
+}
+
+/// Bit depth of a `mini` image's pixel information.
+fn read_mini_bit_depth(bits: &mut BitReader<'_>) -> Result<u8> {
+    let float = bits.read_bool().map_err(|e| at!(Error::from(e)))?;
+    if float {
+        return Err(at!(Error::Unsupported("floating-point mini images")));
+    }
+    if bits.read_bool().map_err(|e| at!(Error::from(e)))? {
+        // bit_depth_minus9
+        Ok(9 + bits.read_u8(3).map_err(|e| at!(Error::from(e)))?)
+    } else {
+        Ok(8)
+    }
+}
+
+/// The gain map fields of a `mini` box's HDR section.
+struct MiniGainMap {
+    width: u32,
+    height: u32,
+    matrix_coefficients: u16,
+    full_range: bool,
+    chroma_subsampling: u8,
+    bit_depth: u8,
+    tmap_has_icc: bool,
+    tmap_color: ColorInformation,
+}
+
+/// Skip the chroma siting flags of a `mini` image with `chroma_subsampling`.
+fn skip_mini_chroma_position(bits: &mut BitReader<'_>, chroma_subsampling: u8) -> Result<()> {
+    // chroma_is_horizontally_centered, chroma_is_vertically_centered
+    let flags = match chroma_subsampling {
+        1 => 2,
+        2 => 1,
+        _ => 0,
+    };
+    bits.skip(flags).map_err(|e| at!(Error::from(e)))
+}
+
+/// The HDR properties of a `mini` image, or of its gain map's `tmap` item.
+#[derive(Default)]
+struct MiniHdrProperties {
+    clli: Option<ContentLightLevel>,
+    mdcv: Option<MasteringDisplayColourVolume>,
+    cclv: Option<ContentColourVolume>,
+}
+
+/// Read the HDR properties of a `mini` image; `amve`, `reve` and `ndwt`
+/// are skipped.
+fn read_mini_hdr_properties(bits: &mut BitReader<'_>) -> Result<MiniHdrProperties> {
+    let flag = |bits: &mut BitReader<'_>| bits.read_bool().map_err(|e| at!(Error::from(e)));
+    let u16_field = |bits: &mut BitReader<'_>| bits.read_u16(16).map_err(|e| at!(Error::from(e)));
+    let u32_field = |bits: &mut BitReader<'_>| bits.read_u32(32).map_err(|e| at!(Error::from(e)));
+    let i32_field = |bits: &mut BitReader<'_>| bits.read_i32(32).map_err(|e| at!(Error::from(e)));
+    let skip = |bits: &mut BitReader<'_>, count: u64| bits.skip(count).map_err(|e| at!(Error::from(e)));
+
+    let has_clli = flag(bits)?;
+    let has_mdcv = flag(bits)?;
+    let has_cclv = flag(bits)?;
+    let has_amve = flag(bits)?;
+    let has_reve = flag(bits)?;
+    let has_ndwt = flag(bits)?;
+
+    let mut hdr = MiniHdrProperties::default();
+    if has_clli {
+        hdr.clli = Some(ContentLightLevel {
+            max_content_light_level: u16_field(bits)?,
+            max_pic_average_light_level: u16_field(bits)?,
+        });
+    }
+    if has_mdcv {
+        let primaries = [
+            (u16_field(bits)?, u16_field(bits)?),
+            (u16_field(bits)?, u16_field(bits)?),
+            (u16_field(bits)?, u16_field(bits)?),
+        ];
+        let white_point = (u16_field(bits)?, u16_field(bits)?);
+        hdr.mdcv = Some(MasteringDisplayColourVolume {
+            primaries,
+            white_point,
+            max_luminance: u32_field(bits)?,
+            min_luminance: u32_field(bits)?,
+        });
+    }
+    if has_cclv {
+        // reserved, reserved, then the same presence flags as the cclv box
+        skip(bits, 2)?;
+        let primaries_present = flag(bits)?;
+        let min_lum_present = flag(bits)?;
+        let max_lum_present = flag(bits)?;
+        let avg_lum_present = flag(bits)?;
+        skip(bits, 2)?;
+        let primaries = if primaries_present {
+            Some([
+                (i32_field(bits)?, i32_field(bits)?),
+                (i32_field(bits)?, i32_field(bits)?),
+                (i32_field(bits)?, i32_field(bits)?),
+            ])
+        } else {
+            None
+        };
+        hdr.cclv = Some(ContentColourVolume {
+            primaries,
+            min_luminance: if min_lum_present { Some(u32_field(bits)?) } else { None },
+            max_luminance: if max_lum_present { Some(u32_field(bits)?) } else { None },
+            avg_luminance: if avg_lum_present { Some(u32_field(bits)?) } else { None },
+        });
+    }
+    // amve: ambient_illuminance, ambient_light_x, ambient_light_y
+    if has_amve {
+        skip(bits, 64)?;
+    }
+    // reve: surround_luminance, surround_light_x, surround_light_y
+    if has_reve {
+        skip(bits, 64)?;
+    }
+    // ndwt: diffuse_white_luminance
+    if has_ndwt {
+        skip(bits, 32)?;
+    }
+    Ok(hdr)
+}
+
+/// Read a `mini` (MinimizedImageBox) payload starting at file `offset` into
+/// the items a `meta` box would describe, and the byte range of its data
+/// chunks, which item extents point into like an `mdat`.
+///
+/// Item 1 is the primary image, 2 its alpha, 3 a `tmap` gain map item in an
+/// `altr` group with 1, 4 the gain map image, 5 Exif and 6 XMP.
+/// See ISO 23008-12:2024/Amd 2 § 12.2
+fn read_mini(data: &[u8], offset: u64) -> Result<(AvifInternalMeta, MdatBounds)> {
+    const ALPHA_URN: &[u8] = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0";
+
+    let mut bits = BitReader::new(data);
+    let flag = |bits: &mut BitReader<'_>| bits.read_bool().map_err(|e| at!(Error::from(e)));
+    let read = |bits: &mut BitReader<'_>, count: u8| bits.read_u32(count).map_err(|e| at!(Error::from(e)));
+
+    if read(&mut bits, 2)? != 0 {
+        return Err(at!(Error::Unsupported("mini version")));
+    }
+    let explicit_codec_types = flag(&mut bits)?;
+    if flag(&mut bits)? {
+        return Err(at!(Error::Unsupported("floating-point mini images")));
+    }
+    let full_range = flag(&mut bits)?;
+    let has_alpha = flag(&mut bits)?;
+    let explicit_cicp = flag(&mut bits)?;
+    let has_hdr = flag(&mut bits)?;
+    let has_icc = flag(&mut bits)?;
+    let has_exif = flag(&mut bits)?;
+    let has_xmp = flag(&mut bits)?;
+
+    // 0 = 4:0:0, 1 = 4:2:0, 2 = 4:2:2, 3 = 4:4:4
+    let chroma_subsampling = read(&mut bits, 2)? as u8;
+    let orientation = read(&mut bits, 3)? + 1;
+
+    let dimension_bits = if flag(&mut bits)? { 15 } else { 7 };
+    let width = read(&mut bits, dimension_bits)? + 1;
+    let height = read(&mut bits, dimension_bits)? + 1;
+    skip_mini_chroma_position(&mut bits, chroma_subsampling)?;
+    let bit_depth = if flag(&mut bits)? { 9 + read(&mut bits, 3)? as u8 } else { 8 };
+    let premultiplied = has_alpha && flag(&mut bits)?;
+
+    let (color_primaries, transfer_characteristics, matrix_coefficients) = if explicit_cicp {
+        let primaries = read(&mut bits, 8)? as u16;
+        let transfer = read(&mut bits, 8)? as u16;
+        let matrix = if chroma_subsampling != 0 { read(&mut bits, 8)? as u16 } else { 2 };
+        (primaries, transfer, matrix)
+    } else {
+        // sRGB unless an ICC profile describes the colours; BT.601 for YUV
+        let (primaries, transfer) = if has_icc { (2, 2) } else { (1, 13) };
+        (primaries, transfer, if chroma_subsampling != 0 { 6 } else { 2 })
+    };
+
+    if explicit_codec_types {
+        let infe_type = read(&mut bits, 32)?.to_be_bytes();
+        let codec_config_type = read(&mut bits, 32)?.to_be_bytes();
+        if &infe_type != b"av01" || &codec_config_type != b"av1C" {
+            return Err(at!(Error::Unsupported("mini codec other than av01")));
+        }
+    }
+
+    let mut hdr = MiniHdrProperties::default();
+    let mut gain_map = None;
+    if has_hdr {
+        if flag(&mut bits)? {
+            let width = read(&mut bits, dimension_bits)? + 1;
+            let height = read(&mut bits, dimension_bits)? + 1;
+            let matrix_coefficients = read(&mut bits, 8)? as u16;
+            let full_range = flag(&mut bits)?;
+            let chroma_subsampling = read(&mut bits, 2)? as u8;
+            skip_mini_chroma_position(&mut bits, chroma_subsampling)?;
+            let bit_depth = read_mini_bit_depth(&mut bits)?;
+            let tmap_has_icc = flag(&mut bits)?;
+            let tmap_color = if flag(&mut bits)? {
+                ColorInformation::Nclx {
+                    color_primaries: read(&mut bits, 8)? as u16,
+                    transfer_characteristics: read(&mut bits, 8)? as u16,
+                    matrix_coefficients: read(&mut bits, 8)? as u16,
+                    full_range: flag(&mut bits)?,
+                }
+            } else {
+                // The alternate rendition defaults to BT.2100 PQ
+                ColorInformation::Nclx {
+                    color_primaries: 9,
+                    transfer_characteristics: 16,
+                    matrix_coefficients: 9,
+                    full_range: true,
+                }
+            };
+            gain_map = Some(MiniGainMap {
+                width, height, matrix_coefficients, full_range, chroma_subsampling, bit_depth, tmap_has_icc, tmap_color,
+            });
+        }
+        hdr = read_mini_hdr_properties(&mut bits)?;
+    }
+    let tmap_hdr = match &gain_map {
+        Some(_) => read_mini_hdr_properties(&mut bits)?,
+        None => MiniHdrProperties::default(),
+    };
+
+    // Chunk sizes
+    let few_metadata_bytes = (has_icc || has_exif || has_xmp || gain_map.is_some()) && flag(&mut bits)?;
+    let few_codec_config_bytes = flag(&mut bits)?;
+    let few_item_data_bytes = flag(&mut bits)?;
+    let metadata_bits = if few_metadata_bytes { 10 } else { 20 };
+    let codec_config_bits = if few_codec_config_bytes { 3 } else { 12 };
+    let item_data_bits = if few_item_data_bytes { 15 } else { 28 };
+
+    let icc_size = if has_icc { read(&mut bits, metadata_bits)? + 1 } else { 0 };
+    let tmap_icc_size = match &gain_map {
+        Some(gmap) if gmap.tmap_has_icc => read(&mut bits, metadata_bits)? + 1,
+        _ => 0,
+    };
+    let gain_map_metadata_size = if gain_map.is_some() { read(&mut bits, metadata_bits)? } else { 0 };
+    let gain_map_data_size = if gain_map.is_some() { read(&mut bits, item_data_bits)? } else { 0 };
+    let gain_map_config_size = if gain_map_data_size > 0 { read(&mut bits, codec_config_bits)? } else { 0 };
+    let main_config_size = read(&mut bits, codec_config_bits)?;
+    let main_data_size = read(&mut bits, item_data_bits)? + 1;
+    let alpha_data_size = if has_alpha { read(&mut bits, item_data_bits)? } else { 0 };
+    let alpha_config_size = if alpha_data_size > 0 { read(&mut bits, codec_config_bits)? } else { 0 };
+    let metadata_compressed = (has_exif || has_xmp) && flag(&mut bits)?;
+    let exif_size = if has_exif { read(&mut bits, metadata_bits)? + 1 } else { 0 };
+    let xmp_size = if has_xmp { read(&mut bits, metadata_bits)? + 1 } else { 0 };
+
+    // The chunks follow, byte aligned, in this order
+    let header_len = bits.position().div_ceil(8);
+    let mut chunks = io::Cursor::new(data.get(usize::try_from(header_len).map_err(|e| at!(Error::from(e)))?..).unwrap_or_default());
+    let mut next_chunk = |size: u32| -> Result<Range<u64>> {
+        let start = offset + header_len + chunks.position();
+        let end = start + u64::from(size);
+        if chunks.position() + u64::from(size) > chunks.get_ref().len().to_u64() {
+            return Err(at!(Error::InvalidData("mini chunk sizes exceed the box")));
         }
-    } else {
-        // Full layout — each fraction has its own denominator.
-        base_hdr_headroom_n = be_u32(&mut cursor)?;
-        base_hdr_headroom_d = be_u32(&mut cursor)?;
-        alternate_hdr_headroom_n = be_u32(&mut cursor)?;
-        alternate_hdr_headroom_d = be_u32(&mut cursor)?;
+        chunks.set_position(chunks.position() + u64::from(size));
+        Ok(start..end)
+    };
+    // Checked against the box by `next_chunk`
+    let chunk_bytes = |range: &Range<u64>| &data[(range.start - offset) as usize..(range.end - offset) as usize];
+    let alpha_config = next_chunk(alpha_config_size)?;
+    let gain_map_config = next_chunk(gain_map_config_size)?;
+    let main_config = next_chunk(main_config_size)?;
+    let icc = next_chunk(icc_size)?;
+    let tmap_icc = next_chunk(tmap_icc_size)?;
+    let gain_map_metadata = next_chunk(gain_map_metadata_size)?;
+    let alpha_data = next_chunk(alpha_data_size)?;
+    let gain_map_data = next_chunk(gain_map_data_size)?;
+    let main_data = next_chunk(main_data_size)?;
+    let exif = next_chunk(exif_size)?;
+    let xmp = next_chunk(xmp_size)?;
+    let data_bounds = MdatBounds { offset: offset + header_len, length: xmp.end - (offset + header_len) };
+
+    let main_av1c = parse_av1c(chunk_bytes(&main_config))?;
+
+    let mut meta = AvifInternalMeta {
+        property_store: TryVec::new(),
+        properties: TryVec::new(),
+        item_references: TryVec::new(),
+        primary_item_id: 1,
+        iloc_items: TryVec::new(),
+        item_infos: TryVec::new(),
+        idat: None,
+        entity_groups: TryVec::new(),
+        uuid_boxes: TryVec::new(),
+        protected_items: TryVec::new(),
+        headerless_exif: true,
+    };
+    let add_item = |meta: &mut AvifInternalMeta, item_id: u32, item_type: &[u8; 4], content_type: &[u8], range: Range<u64>| -> Result<()> {
+        let mut content = TryVec::new();
+        content.extend_from_slice(content_type).map_err(|e| at!(Error::from(e)))?;
+        meta.item_infos.push(ItemInfoEntry { item_id, protection_index: 0, item_type: (*item_type).into(), content_type: content })
+            .map_err(|e| at!(Error::from(e)))?;
+        let mut extents = TryVec::new();
+        extents.push(ItemLocationBoxExtent { extent_index: None, extent_range: ExtentRange::WithLength(range) })
+            .map_err(|e| at!(Error::from(e)))?;
+        meta.iloc_items.push(ItemLocationBoxItem { item_id, construction_method: ConstructionMethod::File, data_reference_index: 0, extents })
+            .map_err(|e| at!(Error::from(e)))
+    };
+    let add_property = |meta: &mut AvifInternalMeta, item_id: u32, fourcc: &[u8; 4], essential: bool, property: ItemProperty| -> Result<()> {
+        let property_index = meta.property_store.len();
+        meta.property_store.push(property).map_err(|e| at!(Error::from(e)))?;
+        meta.properties.push(AssociatedProperty { item_id, property_index, fourcc: (*fourcc).into(), essential })
+            .map_err(|e| at!(Error::from(e)))
+    };
+    let add_mini_hdr_properties = |meta: &mut AvifInternalMeta, item_id: u32, hdr: MiniHdrProperties| -> Result<()> {
+        if let Some(clli) = hdr.clli {
+            add_property(meta, item_id, b"clli", false, ItemProperty::ContentLightLevel(clli))?;
+        }
+        if let Some(mdcv) = hdr.mdcv {
+            add_property(meta, item_id, b"mdcv", false, ItemProperty::MasteringDisplayColourVolume(mdcv))?;
+        }
+        if let Some(cclv) = hdr.cclv {
+            add_property(meta, item_id, b"cclv", false, ItemProperty::ContentColourVolume(cclv))?;
+        }
+        Ok(())
+    };
+    let add_reference = |meta: &mut AvifInternalMeta, item_type: &[u8; 4], from_item_id: u32, to_item_id: u32, reference_index: u16| -> Result<()> {
+        meta.item_references.push(SingleItemTypeReferenceBox { item_type: (*item_type).into(), from_item_id, to_item_id, reference_index })
+            .map_err(|e| at!(Error::from(e)))
+    };
+    let channels = |chroma_subsampling: u8, bit_depth: u8| {
+        let count = if chroma_subsampling == 0 { 1 } else { 3 };
+        PixelInformation { bits_per_channel: vec![bit_depth; count], channels: alloc::vec::Vec::new() }
+    };
 
-        for ch in channels.iter_mut().take(channel_count) {
-            ch.gain_map_min_n = be_i32(&mut cursor)?;
-            ch.gain_map_min_d = be_u32(&mut cursor)?;
-            ch.gain_map_max_n = be_i32(&mut cursor)?;
-            ch.gain_map_max_d = be_u32(&mut cursor)?;
-            ch.gamma_n = be_u32(&mut cursor)?;
-            ch.gamma_d = be_u32(&mut cursor)?;
-            ch.base_offset_n = be_i32(&mut cursor)?;
-            ch.base_offset_d = be_u32(&mut cursor)?;
-            ch.alternate_offset_n = be_i32(&mut cursor)?;
-            ch.alternate_offset_d = be_u32(&mut cursor)?;
+    // Primary image
+    add_item(&mut meta, 1, b"av01", &[], main_data)?;
+    add_property(&mut meta, 1, b"ispe", false, ItemProperty::ImageSpatialExtents(ImageSpatialExtents { width, height }))?;
+    add_property(&mut meta, 1, b"pixi", false, ItemProperty::PixelInformation(channels(chroma_subsampling, bit_depth)))?;
+    add_property(&mut meta, 1, b"av1C", true, ItemProperty::AV1Config(main_av1c.clone()))?;
+    add_property(&mut meta, 1, b"colr", false, ItemProperty::ColorInformation(ColorInformation::Nclx {
+        color_primaries, transfer_characteristics, matrix_coefficients, full_range,
+    }))?;
+    if has_icc {
+        let profile = ColorInformation::IccProfile(chunk_bytes(&icc).to_vec());
+        add_property(&mut meta, 1, b"colr", false, ItemProperty::ColorInformation(profile))?;
+    }
+    add_mini_hdr_properties(&mut meta, 1, hdr)?;
+    // EXIF orientation as irot (counter-clockwise) followed by imir
+    let (angle, axis) = match orientation {
+        2 => (0, Some(0)),
+        3 => (180, None),
+        4 => (0, Some(1)),
+        5 => (90, Some(1)),
+        6 => (270, None),
+        7 => (90, Some(0)),
+        8 => (90, None),
+        _ => (0, None),
+    };
+    if angle != 0 {
+        add_property(&mut meta, 1, b"irot", true, ItemProperty::Rotation(ImageRotation { angle }))?;
+    }
+    if let Some(axis) = axis {
+        add_property(&mut meta, 1, b"imir", true, ItemProperty::Mirror(ImageMirror { axis }))?;
+    }
+
+    // Alpha auxiliary image, sharing the primary image's codec configuration unless it has one
+    if alpha_data_size > 0 {
+        let alpha_av1c = if alpha_config_size > 0 { parse_av1c(chunk_bytes(&alpha_config))? } else { main_av1c.clone() };
+        let mut aux_data = TryVec::new();
+        aux_data.extend_from_slice(ALPHA_URN).map_err(|e| at!(Error::from(e)))?;
+        add_item(&mut meta, 2, b"av01", &[], alpha_data)?;
+        add_property(&mut meta, 2, b"ispe", false, ItemProperty::ImageSpatialExtents(ImageSpatialExtents { width, height }))?;
+        add_property(&mut meta, 2, b"pixi", false, ItemProperty::PixelInformation(channels(0, bit_depth)))?;
+        add_property(&mut meta, 2, b"av1C", true, ItemProperty::AV1Config(alpha_av1c))?;
+        add_property(&mut meta, 2, b"auxC", true, ItemProperty::AuxiliaryType(AuxiliaryTypeProperty { aux_data }))?;
+        add_reference(&mut meta, b"auxl", 2, 1, 0)?;
+        if premultiplied {
+            add_reference(&mut meta, b"prem", 1, 2, 0)?;
         }
     }
 
-    // Copy channel 0 to channels 1 and 2 if single-channel
-    if !is_multichannel {
-        channels[1] = channels[0];
-        channels[2] = channels[0];
+    // Gain map: a tmap item over the primary and gain map images, an alternative to the primary
+    if let Some(gmap) = gain_map
+        && gain_map_data_size > 0 {
+            let gmap_av1c = if gain_map_config_size > 0 { parse_av1c(chunk_bytes(&gain_map_config))? } else { main_av1c.clone() };
+            add_item(&mut meta, 3, b"tmap", &[], gain_map_metadata)?;
+            add_property(&mut meta, 3, b"ispe", false, ItemProperty::ImageSpatialExtents(ImageSpatialExtents { width, height }))?;
+            add_property(&mut meta, 3, b"colr", false, ItemProperty::ColorInformation(gmap.tmap_color))?;
+            if tmap_icc_size > 0 {
+                let profile = ColorInformation::IccProfile(chunk_bytes(&tmap_icc).to_vec());
+                add_property(&mut meta, 3, b"colr", false, ItemProperty::ColorInformation(profile))?;
+            }
+            add_mini_hdr_properties(&mut meta, 3, tmap_hdr)?;
+            add_reference(&mut meta, b"dimg", 3, 1, 0)?;
+            add_reference(&mut meta, b"dimg", 3, 4, 1)?;
+
+            add_item(&mut meta, 4, b"av01", &[], gain_map_data)?;
+            let gmap_ispe = ImageSpatialExtents { width: gmap.width, height: gmap.height };
+            add_property(&mut meta, 4, b"ispe", false, ItemProperty::ImageSpatialExtents(gmap_ispe))?;
+            add_property(&mut meta, 4, b"pixi", false, ItemProperty::PixelInformation(channels(gmap.chroma_subsampling, gmap.bit_depth)))?;
+            add_property(&mut meta, 4, b"av1C", true, ItemProperty::AV1Config(gmap_av1c))?;
+            add_property(&mut meta, 4, b"colr", false, ItemProperty::ColorInformation(ColorInformation::Nclx {
+                color_primaries: 2, transfer_characteristics: 2, matrix_coefficients: gmap.matrix_coefficients, full_range: gmap.full_range,
+            }))?;
+
+            let mut entity_ids = TryVec::new();
+            entity_ids.push(3).map_err(|e| at!(Error::from(e)))?;
+            entity_ids.push(1).map_err(|e| at!(Error::from(e)))?;
+            meta.entity_groups.push(EntityGroup { group_type: (*b"altr").into(), group_id: 7, entity_ids })
+                .map_err(|e| at!(Error::from(e)))?;
+        }
+
+    // Metadata items; compressed metadata is left out
+    if metadata_compressed {
+        debug!("mini Exif/XMP is compressed; ignored");
+    } else {
+        if has_exif {
+            add_item(&mut meta, 5, b"Exif", &[], exif)?;
+            add_reference(&mut meta, b"cdsc", 5, 1, 0)?;
+        }
+        if has_xmp {
+            add_item(&mut meta, 6, b"mime", b"application/rdf+xml", xmp)?;
+            add_reference(&mut meta, b"cdsc", 6, 1, 0)?;
+        }
     }
 
-    // writer_version is parsed and validated (must be >= minimum_version)
-    // but not stored — we always emit 0 on serialize.
-    let _ = writer_version;
+    Ok((meta, data_bounds))
+}
 
-    Ok(GainMapMetadata {
-        is_multichannel,
-        use_base_colour_space,
-        backward_direction,
-        base_hdr_headroom_n,
-        base_hdr_headroom_d,
-        alternate_hdr_headroom_n,
-        alternate_hdr_headroom_d,
-        channels,
-    })
+/// The item a file without `pitm` most likely meant as primary: the first
+/// `av01`, `grid`, `iovl` or `iden` item that is neither an input of a derived
+/// image nor an auxiliary image, thumbnail or description of another item,
+/// else the file's only item.
+fn guess_primary_item(item_infos: &[ItemInfoEntry], item_references: &[SingleItemTypeReferenceBox]) -> Option<u32> {
+    let is_dependent = |id: u32| {
+        item_references.iter().any(|iref| {
+            (iref.item_type == b"dimg" && iref.to_item_id == id)
+                || (iref.from_item_id == id && [b"auxl", b"thmb", b"cdsc"].iter().any(|t| iref.item_type == *t))
+        })
+    };
+    item_infos
+        .iter()
+        .filter(|x| is_coded_image_item(&x.item_type) || [b"grid", b"iovl", b"iden"].iter().any(|t| x.item_type == *t))
+        .map(|x| x.item_id)
+        .find(|&id| !is_dependent(id))
+        .or(match item_infos {
+            [only] => Some(only.item_id),
+            _ => None,
+        })
 }
 
-/// Parse a metadata box in the context of an AVIF
-/// Currently requires the primary item to be an av01 item type and generates
-/// an error otherwise.
-/// See ISO 14496-12:2015 § 8.11.1
-fn read_avif_meta<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<AvifInternalMeta> {
-    let version = read_fullbox_version_no_flags(src, options)?;
+/// Replace the primary item with the first supported item of its `altr` group.
+///
+/// Alternatives are listed in the file's order of preference; entities that are not image
+/// items this parser handles (other codecs, tracks) are skipped. A primary item outside any
+/// `altr` group is left alone.
+/// See ISO 23008-12:2017 § 6.8.1 and ISO 14496-12:2015 § 8.18.3
+fn select_alternative_primary(meta: &mut AvifInternalMeta) {
+    let is_supported = |id: u32| {
+        meta.item_infos
+            .iter()
+            .any(|x| {
+                x.item_id == id
+                    && (is_coded_image_item(&x.item_type) || [b"grid", b"iovl", b"iden"].iter().any(|t| x.item_type == *t))
+            })
+    };
+    let Some(group) = meta
+        .entity_groups
+        .iter()
+        .find(|g| g.group_type == b"altr" && g.entity_ids.contains(&meta.primary_item_id))
+    else {
+        return;
+    };
+    if let Some(&selected) = group.entity_ids.iter().find(|&&id| is_supported(id))
+        && selected != meta.primary_item_id {
+            debug!("altr group {} selects item {selected} over primary {}", group.group_id, meta.primary_item_id);
+            meta.primary_item_id = selected;
+        }
+}
 
-    if version != 0 {
-        return Err(at!(Error::Unsupported("unsupported meta version")));
+/// Fail if the primary image, its alpha, or any item they are derived from
+/// (grid tiles, overlay inputs) is protected. Other protected items, such as
+/// metadata, are left out of the parse and listed in
+/// [`AvifParser::protected_items`].
+fn check_protected_items(meta: &AvifInternalMeta) -> Result<()> {
+    let mut pending: TryVec<u32> = TryVec::new();
+    let mut seen: TryVec<u32> = TryVec::new();
+    pending.push(meta.primary_item_id).map_err(|e| at!(Error::from(e)))?;
+    if let Some(alpha) = alpha_item_id(meta) {
+        pending.push(alpha).map_err(|e| at!(Error::from(e)))?;
+    }
+    while let Some(item_id) = pending.pop() {
+        if seen.contains(&item_id) {
+            continue;
+        }
+        seen.push(item_id).map_err(|e| at!(Error::from(e)))?;
+        if let Some(item) = meta.protected_items.iter().find(|item| item.item_id == item_id) {
+            return Err(at!(Error::ProtectedItem { item_id, scheme: item.scheme }));
+        }
+        for r in meta.item_references.iter().filter(|r| r.from_item_id == item_id && r.item_type == b"dimg") {
+            pending.push(r.to_item_id).map_err(|e| at!(Error::from(e)))?;
+        }
     }
+    Ok(())
+}
 
-    let mut primary_item_id = None;
-    let mut item_infos = None;
-    let mut iloc_items = None;
-    let mut item_references = TryVec::new();
-    let mut properties = TryVec::new();
-    let mut idat = None;
-    let mut entity_groups = TryVec::new();
+/// The error for a `meta` box without `pitm` or `iloc` in a file with no
+/// `moov` whose first frame could stand in for the primary item.
+const ITEMLESS_META: &str = "meta box locates no items and there is no moov";
 
-    let mut iter = src.box_iter();
-    while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::ItemInfoBox => {
-                if item_infos.is_some() {
-                    return Err(at!(Error::InvalidData("There should be zero or one iinf boxes per ISO 14496-12:2015 § 8.11.6.1")));
-                }
-                item_infos = Some(read_iinf(&mut b, options)?);
-            },
-            BoxType::ItemLocationBox => {
-                if iloc_items.is_some() {
-                    return Err(at!(Error::InvalidData("There should be zero or one iloc boxes per ISO 14496-12:2015 § 8.11.3.1")));
-                }
-                iloc_items = Some(read_iloc(&mut b, options)?);
-            },
-            BoxType::PrimaryItemBox => {
-                if primary_item_id.is_some() {
-                    return Err(at!(Error::InvalidData("There should be zero or one iloc boxes per ISO 14496-12:2015 § 8.11.4.1")));
-                }
-                primary_item_id = Some(read_pitm(&mut b, options)?);
-            },
-            BoxType::ImageReferenceBox => {
-                item_references.append(&mut read_iref(&mut b, options)?).map_err(|e| at!(Error::from(e)))?;
-            },
-            BoxType::ImagePropertiesBox => {
-                properties = read_iprp(&mut b, options)?;
-            },
-            BoxType::ItemDataBox => {
-                if idat.is_some() {
-                    return Err(at!(Error::InvalidData("There should be zero or one idat boxes")));
-                }
-                idat = Some(b.read_into_try_vec().map_err(|e| at!(Error::from(e)))?);
-            },
-            BoxType::GroupsListBox => {
-                entity_groups.append(&mut read_grpl(&mut b)?).map_err(|e| at!(Error::from(e)))?;
-            },
-            BoxType::HandlerBox => {
-                let hdlr = read_hdlr(&mut b)?;
-                if hdlr.handler_type != b"pict" {
-                    warn!("hdlr handler_type: {}", hdlr.handler_type);
-                    return Err(at!(Error::InvalidData("meta handler_type must be 'pict' for AVIF")));
-                }
-            },
-            _ => skip_box_content(&mut b)?,
+/// Make an `iden` (identity derived image) primary item transparent.
+///
+/// Follows the single `dimg` reference of each `iden` item down to the underlying image item,
+/// which becomes the primary item. Properties of the `iden` items (typically transformative
+/// ones such as `clap`/`irot`/`imir`) are re-associated with that item ahead of its own, so
+/// they take precedence, and references to or from the `iden` items are redirected to it.
+/// See ISO 23008-12:2017 § 6.6.2.1
+fn resolve_identity_primary(meta: &mut AvifInternalMeta) -> Result<()> {
+    let mut chain: TryVec<u32> = TryVec::new();
+    let mut item_id = meta.primary_item_id;
+    while meta.item_infos.iter().any(|x| x.item_id == item_id && x.item_type == b"iden") {
+        if chain.len() >= meta.item_infos.len() {
+            return Err(at!(Error::InvalidData("cyclic iden references")));
         }
+        chain.push(item_id).map_err(|e| at!(Error::from(e)))?;
 
-        check_parser_state(&b.head, &b.content)?;
+        let mut inputs = meta
+            .item_references
+            .iter()
+            .filter(|r| r.from_item_id == item_id && r.item_type == b"dimg");
+        item_id = match (inputs.next(), inputs.next()) {
+            (Some(input), None) => input.to_item_id,
+            _ => return Err(at!(Error::InvalidData("iden item must have exactly one dimg reference"))),
+        };
+    }
+    if chain.is_empty() {
+        return Ok(());
     }
+    debug!("iden primary {} resolves to item {item_id}", meta.primary_item_id);
 
-    let primary_item_id = primary_item_id.ok_or_else(|| at!(Error::InvalidData("Required pitm box not present in meta box")))?;
+    let mut derived: TryVec<(usize, AssociatedProperty)> = TryVec::new();
+    let mut rest = TryVec::new();
+    for mut prop in core::mem::take(&mut meta.properties) {
+        if let Some(depth) = chain.iter().position(|&id| id == prop.item_id) {
+            prop.item_id = item_id;
+            derived.push((depth, prop)).map_err(|e| at!(Error::from(e)))?;
+        } else {
+            rest.push(prop).map_err(|e| at!(Error::from(e)))?;
+        }
+    }
+    // Outermost derivation first
+    derived.sort_by_key(|&(depth, _)| depth);
+    for (_, prop) in derived {
+        meta.properties.push(prop).map_err(|e| at!(Error::from(e)))?;
+    }
+    meta.properties.append(&mut rest).map_err(|e| at!(Error::from(e)))?;
 
-    let item_infos = item_infos.ok_or_else(|| at!(Error::InvalidData("iinf missing")))?;
+    for mut r in core::mem::take(&mut meta.item_references) {
+        // The chain's own dimg links would become self-references once redirected
+        if r.item_type == b"dimg" && chain.contains(&r.from_item_id) {
+            continue;
+        }
+        if chain.contains(&r.from_item_id) {
+            r.from_item_id = item_id;
+        }
+        if chain.contains(&r.to_item_id) {
+            r.to_item_id = item_id;
+        }
+        meta.item_references.push(r).map_err(|e| at!(Error::from(e)))?;
+    }
+    meta.primary_item_id = item_id;
+    Ok(())
+}
 
-    if let Some(item_info) = item_infos.iter().find(|x| x.item_id == primary_item_id) {
-        // Allow both "av01" (standard single-frame) and "grid" (tiled) types
-        if item_info.item_type != b"av01" && item_info.item_type != b"grid" {
-            warn!("primary_item_id type: {}", item_info.item_type);
-            return Err(at!(Error::InvalidData("primary_item_id type is not av01 or grid")));
+/// Read the scheme type of each `sinf` in an `ipro` box, if it has a `schm`.
+/// See ISO 14496-12:2015 § 8.11.5 and § 8.12
+fn read_ipro<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Option<[u8; 4]>>> {
+    let (_version, _flags) = read_fullbox_extra(src)?;
+    let _protection_count = be_u16(src)?;
+    let mut schemes = TryVec::new();
+    let mut iter = src.box_iter();
+    while let Some(mut sinf) = iter.next_box()? {
+        if sinf.head.name != BoxType::ProtectionSchemeInfoBox {
+            skip_box_remain(&mut sinf)?;
+            continue;
         }
-    } else {
-        return Err(at!(Error::InvalidData("primary_item_id not present in iinf box")));
+        let mut scheme = None;
+        let mut children = sinf.box_iter();
+        while let Some(mut b) = children.next_box()? {
+            if b.head.name == BoxType::SchemeTypeBox {
+                let (_version, _flags) = read_fullbox_extra(&mut b)?;
+                scheme = Some(be_u32(&mut b)?.to_be_bytes());
+            }
+            skip_box_remain(&mut b)?;
+        }
+        schemes.push(scheme).map_err(|e| at!(Error::from(e)))?;
     }
+    Ok(schemes)
+}
 
-    Ok(AvifInternalMeta {
-        properties,
-        item_references,
-        primary_item_id,
-        iloc_items: iloc_items.ok_or_else(|| at!(Error::InvalidData("iloc missing")))?,
-        item_infos,
-        idat,
-        entity_groups,
-    })
+/// Read the `dref` in a `dinf` box: one entry per data reference, `None`
+/// for the file itself.
+/// See ISO 14496-12:2015 § 8.7.1
+fn read_dinf<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Option<alloc::string::String>>> {
+    let mut entries = TryVec::new();
+    let mut iter = src.box_iter();
+    while let Some(mut b) = iter.next_box()? {
+        if b.head.name == BoxType::DataReferenceBox {
+            entries = read_dref(&mut b)?;
+        }
+        skip_box_remain(&mut b)?;
+    }
+    Ok(entries)
+}
+
+/// See ISO 14496-12:2015 § 8.7.2
+fn read_dref<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Option<alloc::string::String>>> {
+    let (_version, _flags) = read_fullbox_extra(src)?;
+    let entry_count = be_u32(src)?;
+    let mut entries = TryVec::new();
+    let mut iter = src.box_iter();
+    for _ in 0..entry_count {
+        let Some(mut b) = iter.next_box()? else {
+            break;
+        };
+        let (_version, flags) = read_fullbox_extra(&mut b)?;
+        // Flag 1: the data is in the same file as this box
+        let location = if flags & 1 != 0 {
+            None
+        } else {
+            let payload = b.read_into_try_vec().map_err(|e| at!(Error::from(e)))?;
+            let mut strings = payload.split(|&c| c == 0).map(|s| alloc::string::String::from_utf8_lossy(s).into_owned());
+            let first = strings.next().unwrap_or_default();
+            // A `urn ` names the resource, and may add where to find it
+            let location = match b.head.name {
+                BoxType::DataEntryUrnBox => strings.next().filter(|l| !l.is_empty()).unwrap_or(first),
+                _ => first,
+            };
+            Some(location)
+        };
+        skip_box_remain(&mut b)?;
+        entries.push(location).map_err(|e| at!(Error::from(e)))?;
+    }
+    Ok(entries)
+}
+
+/// Check `data_reference_index` values against `dref` entries: 0 and
+/// self-contained entries refer to this file, anything else is reported
+/// as [`Error::ExternalDataReference`]. Without any `dref` entries, an index
+/// is taken to mean this file too, except in pedantic mode.
+fn check_data_references(
+    entries: &[Option<alloc::string::String>],
+    indices: impl Iterator<Item = u16>,
+    options: &ParseContext,
+) -> Result<()> {
+    let mut urls: alloc::vec::Vec<alloc::string::String> = alloc::vec::Vec::new();
+    for index in indices.filter(|&index| index != 0) {
+        if entries.is_empty() {
+            options.pedantic(|| at!(Error::InvalidData("data_reference_index without a dref box")))?;
+            continue;
+        }
+        let entry = entries.get(usize::from(index) - 1)
+            .ok_or_else(|| at!(Error::InvalidData("data_reference_index has no dref entry")))?;
+        if let Some(url) = entry
+            && !urls.contains(url) {
+                urls.push(url.clone());
+            }
+    }
+    if !urls.is_empty() {
+        return Err(at!(Error::ExternalDataReference { urls }));
+    }
+    Ok(())
 }
 
 /// Parse a Handler Reference Box
@@ -4342,7 +10046,7 @@ fn read_hdlr<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<HandlerBox> {
 
 /// Parse a Primary Item Box
 /// See ISO 14496-12:2015 § 8.11.4
-fn read_pitm<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<u32> {
+fn read_pitm<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<u32> {
     let version = read_fullbox_version_no_flags(src, options)?;
 
     let item_id = match version {
@@ -4356,7 +10060,7 @@ fn read_pitm<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
 
 /// Parse an Item Information Box
 /// See ISO 14496-12:2015 § 8.11.6
-fn read_iinf<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<TryVec<ItemInfoEntry>> {
+fn read_iinf<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<TryVec<ItemInfoEntry>> {
     let version = read_fullbox_version_no_flags(src, options)?;
 
     match version {
@@ -4369,6 +10073,7 @@ fn read_iinf<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
     } else {
         be_u32(src)?.to_usize()
     };
+    options.check_item_count(entry_count.to_u64())?;
     // Cap pre-allocation: entry_count is untrusted, actual items come from box_iter
     let mut item_infos = TryVec::with_capacity(entry_count.min(4096)).map_err(|e| at!(Error::from(e)))?;
 
@@ -4377,6 +10082,7 @@ fn read_iinf<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
         if b.head.name != BoxType::ItemInfoEntry {
             return Err(at!(Error::InvalidData("iinf box should contain only infe boxes")));
         }
+        options.check_item_count(item_infos.len().to_u64() + 1)?;
 
         item_infos.push(read_infe(&mut b)?).map_err(|e| at!(Error::from(e)))?;
 
@@ -4400,22 +10106,39 @@ fn read_infe<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<ItemInfoEntry> {
         _ => return Err(at!(Error::Unsupported("unsupported version in 'infe' box"))),
     };
 
-    let item_protection_index = be_u16(src)?;
-
-    if item_protection_index != 0 {
-        return Err(at!(Error::Unsupported("protected items (infe.item_protection_index != 0) are not supported")));
-    }
+    // Checked against `ipro` by `read_avif_meta`
+    let protection_index = be_u16(src)?;
 
     let item_type = FourCC::from(be_u32(src)?);
     debug!("infe item_id {item_id} item_type: {item_type}");
 
+    // item_name is not of interest to us, but precedes content_type
+    let mut content_type = TryVec::new();
+    if item_type == b"mime" {
+        read_null_terminated(src, &mut TryVec::new())?;
+        read_null_terminated(src, &mut content_type)?;
+    }
+
     // There are some additional fields here, but they're not of interest to us
     skip_box_remain(src)?;
 
-    Ok(ItemInfoEntry { item_id, item_type })
+    Ok(ItemInfoEntry { item_id, protection_index, item_type, content_type })
+}
+
+/// Read a NUL-terminated string field into `dst`, excluding the terminator.
+/// A missing terminator at the end of the box is tolerated.
+fn read_null_terminated<T: Read>(src: &mut BMFFBox<'_, T>, dst: &mut TryVec<u8>) -> Result<()> {
+    while src.bytes_left() > 0 {
+        let byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+        if byte == 0 {
+            break;
+        }
+        dst.push(byte).map_err(|e| at!(Error::from(e)))?;
+    }
+    Ok(())
 }
 
-fn read_iref<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<TryVec<SingleItemTypeReferenceBox>> {
+fn read_iref<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<TryVec<SingleItemTypeReferenceBox>> {
     let mut item_references = TryVec::new();
     let version = read_fullbox_version_no_flags(src, options)?;
     if version > 1 {
@@ -4466,21 +10189,29 @@ const MUST_BE_ESSENTIAL: &[&[u8; 4]] = &[b"a1op", b"lsel", b"clap", b"irot", b"i
 /// See AVIF § 2.3.2.3.2 (a1lx).
 const MUST_NOT_BE_ESSENTIAL: &[&[u8; 4]] = &[b"a1lx"];
 
-fn read_iprp<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<TryVec<AssociatedProperty>> {
+/// Read the `ipco` properties into a store and the `ipma` associations that
+/// index it; a property shared by many items (a grid's tiles) is kept once.
+fn read_iprp<T: Read + Offset>(
+    src: &mut BMFFBox<'_, T>,
+    options: &ParseContext,
+) -> Result<(TryVec<ItemProperty>, TryVec<AssociatedProperty>)> {
     let mut iter = src.box_iter();
     let mut properties = TryVec::new();
     let mut associations = TryVec::new();
 
     while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::ItemPropertyContainerBox => {
-                properties = read_ipco(&mut b, options)?;
-            },
-            BoxType::ItemPropertyAssociationBox => {
-                associations = read_ipma(&mut b)?;
-            },
-            _ => return Err(at!(Error::InvalidData("unexpected ipco child"))),
-        }
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::ItemPropertyContainerBox => {
+                    properties = read_ipco(b, options)?;
+                },
+                BoxType::ItemPropertyAssociationBox => {
+                    associations = read_ipma(b)?;
+                },
+                _ => return Err(at!(Error::InvalidData("unexpected ipco child"))),
+            }
+            Ok(())
+        })?;
     }
 
     let mut associated = TryVec::new();
@@ -4499,6 +10230,7 @@ fn read_iprp<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
         };
 
         let Some(entry) = properties.get(index) else {
+            options.pedantic(|| at!(Error::InvalidData("ipma references a property missing from ipco")))?;
             continue;
         };
 
@@ -4509,24 +10241,28 @@ fn read_iprp<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
             // Validate essential flag for known property types
             if a.essential && MUST_NOT_BE_ESSENTIAL.contains(&fourcc_bytes) {
                 warn!("item {} has {} marked essential (spec forbids it)", a.item_id, entry.fourcc);
-                if !options.lenient {
-                    return Err(at!(Error::InvalidData(
-                        "property must not be marked essential",
-                    )));
-                }
+                options.tolerate(
+                    ParseWarningKind::PropertyWronglyEssential, &entry.fourcc, entry.offset,
+                    at!(Error::InvalidData("property must not be marked essential")),
+                )?;
+            }
+            // AVIF § 2.2.1: av1C "Essential: Yes", widely ignored by encoders
+            if !a.essential && fourcc_bytes == b"av1C" {
+                options.pedantic(|| at!(Error::InvalidData("av1C must be marked essential")))?;
             }
             if !a.essential && MUST_BE_ESSENTIAL.contains(&fourcc_bytes) {
                 warn!("item {} has {} not marked essential (spec requires it)", a.item_id, entry.fourcc);
-                if !options.lenient {
-                    return Err(at!(Error::InvalidData(
-                        "property must be marked essential",
-                    )));
-                }
+                options.tolerate(
+                    ParseWarningKind::PropertyNotEssential, &entry.fourcc, entry.offset,
+                    at!(Error::InvalidData("property must be marked essential")),
+                )?;
             }
 
             associated.push(AssociatedProperty {
                 item_id: a.item_id,
-                property: entry.property.try_clone().map_err(|e| at!(Error::from(e)))?,
+                property_index: index,
+                fourcc: entry.fourcc.clone(),
+                essential: a.essential,
             }).map_err(|e| at!(Error::from(e)))?;
         } else if a.essential {
             // Unknown property marked essential — this item cannot be correctly processed
@@ -4534,24 +10270,37 @@ fn read_iprp<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
                 "item {} has unsupported property {} marked essential; item will be unusable",
                 a.item_id, entry.fourcc
             );
-            if !options.lenient {
-                return Err(at!(Error::Unsupported(
-                    "unsupported property marked as essential",
-                )));
-            }
+            options.tolerate(
+                ParseWarningKind::UnsupportedEssentialProperty, &entry.fourcc, entry.offset,
+                at!(Error::Unsupported("unsupported property marked as essential")),
+            )?;
+            // Permissive: keep the association so callers can see the item is unusable
+            associated.push(AssociatedProperty {
+                item_id: a.item_id,
+                property_index: index,
+                fourcc: entry.fourcc.clone(),
+                essential: true,
+            }).map_err(|e| at!(Error::from(e)))?;
         }
         // Unknown non-essential properties are silently skipped (they're optional)
     }
-    Ok(associated)
+
+    let mut store = TryVec::with_capacity(properties.len()).map_err(|e| at!(Error::from(e)))?;
+    for entry in properties {
+        store.push(entry.property).map_err(|e| at!(Error::from(e)))?;
+    }
+    Ok((store, associated))
 }
 
 #[derive(Debug, PartialEq)]
 pub(crate) enum ItemProperty {
-    Channels(ArrayVec<u8, 16>),
+    PixelInformation(PixelInformation),
     AuxiliaryType(AuxiliaryTypeProperty),
     ImageSpatialExtents(ImageSpatialExtents),
     ImageGrid(GridConfig),
     AV1Config(AV1Config),
+    #[cfg(feature = "heif")]
+    HevcConfig(HevcConfig),
     ColorInformation(ColorInformation),
     Rotation(ImageRotation),
     Mirror(ImageMirror),
@@ -4564,34 +10313,10 @@ pub(crate) enum ItemProperty {
     OperatingPointSelector(OperatingPointSelector),
     LayerSelector(LayerSelector),
     AV1LayeredImageIndexing(AV1LayeredImageIndexing),
+    UserDescription(UserDescription),
     Unsupported,
 }
 
-impl TryClone for ItemProperty {
-    fn try_clone(&self) -> Result<Self, TryReserveError> {
-        Ok(match self {
-            Self::Channels(val) => Self::Channels(val.clone()),
-            Self::AuxiliaryType(val) => Self::AuxiliaryType(val.try_clone()?),
-            Self::ImageSpatialExtents(val) => Self::ImageSpatialExtents(*val),
-            Self::ImageGrid(val) => Self::ImageGrid(val.clone()),
-            Self::AV1Config(val) => Self::AV1Config(val.clone()),
-            Self::ColorInformation(val) => Self::ColorInformation(val.clone()),
-            Self::Rotation(val) => Self::Rotation(*val),
-            Self::Mirror(val) => Self::Mirror(*val),
-            Self::CleanAperture(val) => Self::CleanAperture(*val),
-            Self::PixelAspectRatio(val) => Self::PixelAspectRatio(*val),
-            Self::ContentLightLevel(val) => Self::ContentLightLevel(*val),
-            Self::MasteringDisplayColourVolume(val) => Self::MasteringDisplayColourVolume(*val),
-            Self::ContentColourVolume(val) => Self::ContentColourVolume(*val),
-            Self::AmbientViewingEnvironment(val) => Self::AmbientViewingEnvironment(*val),
-            Self::OperatingPointSelector(val) => Self::OperatingPointSelector(*val),
-            Self::LayerSelector(val) => Self::LayerSelector(*val),
-            Self::AV1LayeredImageIndexing(val) => Self::AV1LayeredImageIndexing(*val),
-            Self::Unsupported => Self::Unsupported,
-        })
-    }
-}
-
 struct Association {
     item_id: u32,
     essential: bool,
@@ -4600,7 +10325,10 @@ struct Association {
 
 pub(crate) struct AssociatedProperty {
     pub item_id: u32,
-    pub property: ItemProperty,
+    /// Index into [`AvifInternalMeta::property_store`], the `ipco` order.
+    pub property_index: usize,
+    pub fourcc: FourCC,
+    pub essential: bool,
 }
 
 fn read_ipma<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Association>> {
@@ -4643,68 +10371,113 @@ fn read_ipma<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<Association>> {
 /// A parsed property with its box FourCC, for essential flag validation.
 struct IndexedProperty {
     fourcc: FourCC,
+    /// Absolute offset of the property box.
+    offset: u64,
     property: ItemProperty,
 }
 
-fn read_ipco<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<TryVec<IndexedProperty>> {
+fn read_ipco<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<TryVec<IndexedProperty>> {
     let mut properties = TryVec::new();
 
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        let fourcc: FourCC = b.head.name.into();
-        // Must push for every property to have correct index for them
-        let prop = match b.head.name {
-            BoxType::PixelInformationBox => ItemProperty::Channels(read_pixi(&mut b, options)?),
-            BoxType::AuxiliaryTypeProperty => ItemProperty::AuxiliaryType(read_auxc(&mut b, options)?),
-            BoxType::ImageSpatialExtentsBox => ItemProperty::ImageSpatialExtents(read_ispe(&mut b, options)?),
-            BoxType::ImageGridBox => ItemProperty::ImageGrid(read_grid(&mut b, options)?),
-            BoxType::AV1CodecConfigurationBox => ItemProperty::AV1Config(read_av1c(&mut b)?),
-            BoxType::ColorInformationBox => {
-                match read_colr(&mut b) {
-                    Ok(colr) => ItemProperty::ColorInformation(colr),
-                    Err(_) => ItemProperty::Unsupported,
-                }
-            },
-            BoxType::ImageRotationBox => ItemProperty::Rotation(read_irot(&mut b)?),
-            BoxType::ImageMirrorBox => ItemProperty::Mirror(read_imir(&mut b)?),
-            BoxType::CleanApertureBox => ItemProperty::CleanAperture(read_clap(&mut b)?),
-            BoxType::PixelAspectRatioBox => ItemProperty::PixelAspectRatio(read_pasp(&mut b)?),
-            BoxType::ContentLightLevelBox => ItemProperty::ContentLightLevel(read_clli(&mut b)?),
-            BoxType::MasteringDisplayColourVolumeBox => ItemProperty::MasteringDisplayColourVolume(read_mdcv(&mut b)?),
-            BoxType::ContentColourVolumeBox => ItemProperty::ContentColourVolume(read_cclv(&mut b)?),
-            BoxType::AmbientViewingEnvironmentBox => ItemProperty::AmbientViewingEnvironment(read_amve(&mut b)?),
-            BoxType::OperatingPointSelectorBox => ItemProperty::OperatingPointSelector(read_a1op(&mut b)?),
-            BoxType::LayerSelectorBox => ItemProperty::LayerSelector(read_lsel(&mut b)?),
-            BoxType::AV1LayeredImageIndexingBox => ItemProperty::AV1LayeredImageIndexing(read_a1lx(&mut b)?),
-            _ => {
-                skip_box_remain(&mut b)?;
-                ItemProperty::Unsupported
-            },
-        };
-        properties.push(IndexedProperty { fourcc, property: prop }).map_err(|e| at!(Error::from(e)))?;
+        in_box(&mut b, |b| {
+            let fourcc: FourCC = b.head.name.into();
+            let offset = b.start_offset();
+            // Must push for every property to have correct index for them
+            let prop = match b.head.name {
+                BoxType::PixelInformationBox => ItemProperty::PixelInformation(read_pixi(b, options)?),
+                BoxType::AuxiliaryTypeProperty => ItemProperty::AuxiliaryType(read_auxc(b, options)?),
+                BoxType::ImageSpatialExtentsBox => ItemProperty::ImageSpatialExtents(read_ispe(b, options)?),
+                BoxType::ImageGridBox => ItemProperty::ImageGrid(read_grid(b, options)?),
+                BoxType::AV1CodecConfigurationBox => ItemProperty::AV1Config(read_av1c(b)?),
+                #[cfg(feature = "heif")]
+                BoxType::HEVCConfigurationBox => ItemProperty::HevcConfig(read_hvcc(b)?),
+                BoxType::ColorInformationBox => {
+                    match read_colr(b) {
+                        Ok(colr) => ItemProperty::ColorInformation(colr),
+                        Err(_) => ItemProperty::Unsupported,
+                    }
+                },
+                BoxType::ImageRotationBox => ItemProperty::Rotation(read_irot(b)?),
+                BoxType::ImageMirrorBox => ItemProperty::Mirror(read_imir(b)?),
+                BoxType::CleanApertureBox => ItemProperty::CleanAperture(read_clap(b)?),
+                BoxType::PixelAspectRatioBox => ItemProperty::PixelAspectRatio(read_pasp(b)?),
+                BoxType::ContentLightLevelBox => ItemProperty::ContentLightLevel(read_clli(b)?),
+                BoxType::MasteringDisplayColourVolumeBox => ItemProperty::MasteringDisplayColourVolume(read_mdcv(b)?),
+                BoxType::ContentColourVolumeBox => ItemProperty::ContentColourVolume(read_cclv(b)?),
+                BoxType::AmbientViewingEnvironmentBox => ItemProperty::AmbientViewingEnvironment(read_amve(b)?),
+                BoxType::OperatingPointSelectorBox => ItemProperty::OperatingPointSelector(read_a1op(b)?),
+                BoxType::LayerSelectorBox => ItemProperty::LayerSelector(read_lsel(b)?),
+                BoxType::AV1LayeredImageIndexingBox => ItemProperty::AV1LayeredImageIndexing(read_a1lx(b)?),
+                BoxType::UserDescriptionBox => match read_udes(b, options)? {
+                    Some(udes) => ItemProperty::UserDescription(udes),
+                    None => ItemProperty::Unsupported,
+                },
+                _ => {
+                    skip_box_remain(b)?;
+                    ItemProperty::Unsupported
+                },
+            };
+            properties.push(IndexedProperty { fourcc, offset, property: prop }).map_err(|e| at!(Error::from(e)))?;
+            Ok(())
+        })?;
     }
     Ok(properties)
 }
 
-fn read_pixi<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<ArrayVec<u8, 16>> {
+fn read_pixi<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<PixelInformation> {
     let version = read_fullbox_version_no_flags(src, options)?;
-    if version != 0 {
+    if version > 1 {
         return Err(at!(Error::Unsupported("pixi version")));
     }
 
     let num_channels = usize::from(src.read_u8().map_err(|e| at!(Error::from(e)))?);
-    let mut channels = ArrayVec::new();
-    let clamped = num_channels.min(channels.capacity());
-    channels.extend((0..clamped).map(|_| 0));
-    src.read_exact(&mut channels).map_err(|_| at!(Error::InvalidData("invalid num_channels")))?;
+    let mut bits_per_channel = alloc::vec::Vec::new();
+    bits_per_channel.try_reserve_exact(num_channels).map_err(|_| at!(Error::OutOfMemory))?;
+    bits_per_channel.resize(num_channels, 0);
+    src.read_exact(&mut bits_per_channel).map_err(|_| at!(Error::InvalidData("invalid num_channels")))?;
+
+    let mut channels = alloc::vec::Vec::new();
+    if version == 1 {
+        channels.try_reserve_exact(num_channels).map_err(|_| at!(Error::OutOfMemory))?;
+        for _ in 0..num_channels {
+            // channel_idc (3), reserved (1), component_format (2),
+            // subsampling_flag (1), channel_label_flag (1)
+            let byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+            let (subsampling_type, subsampling_location) = if byte & 0x02 != 0 {
+                let subsampling = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+                (Some(subsampling >> 4), Some(subsampling & 0x0F))
+            } else {
+                (None, None)
+            };
+            let label = if byte & 0x01 != 0 {
+                let mut bytes = TryVec::new();
+                read_null_terminated(src, &mut bytes)?;
+                Some(alloc::string::String::from_utf8_lossy(&bytes).into_owned())
+            } else {
+                None
+            };
+            channels.push(ChannelInformation {
+                channel_idc: byte >> 5,
+                component_format: (byte >> 2) & 0x03,
+                subsampling_type,
+                subsampling_location,
+                label,
+            });
+        }
+    }
 
-    // In lenient mode, skip any extra bytes (e.g., extended_pixi.avif has 6 extra bytes)
-    if options.lenient && src.bytes_left() > 0 {
+    // In permissive mode, skip any extra bytes (e.g., extended_pixi.avif has 6 extra bytes)
+    if src.bytes_left() > 0 {
+        let fourcc: FourCC = src.head.name.into();
+        options.tolerate(ParseWarningKind::TrailingBytes, &fourcc, src.start_offset(),
+            at!(Error::InvalidData("unread box content or bad parser sync")))?;
         skip(src, src.bytes_left())?;
     }
 
     check_parser_state(&src.head, &src.content)?;
-    Ok(channels)
+    Ok(PixelInformation { bits_per_channel, channels })
 }
 
 #[derive(Debug, PartialEq)]
@@ -4725,15 +10498,7 @@ impl AuxiliaryTypeProperty {
     }
 }
 
-impl TryClone for AuxiliaryTypeProperty {
-    fn try_clone(&self) -> Result<Self, TryReserveError> {
-        Ok(Self {
-            aux_data: self.aux_data.try_clone()?,
-        })
-    }
-}
-
-fn read_auxc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<AuxiliaryTypeProperty> {
+fn read_auxc<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<AuxiliaryTypeProperty> {
     let version = read_fullbox_version_no_flags(src, options)?;
     if version != 0 {
         return Err(at!(Error::Unsupported("auxC version")));
@@ -4754,9 +10519,68 @@ fn is_depth_auxiliary_urn(urn: &[u8]) -> bool {
         || urn == b"urn:mpeg:hevc:2015:auxid:2"
 }
 
+/// Error for files [`is_supported_major_brand`] rejects, naming what the
+/// enabled features accept.
+const UNSUPPORTED_BRAND: &str = if cfg!(feature = "heif") {
+    "ftyp must be or list 'avif' or 'avis', be 'mif3' with minor version 'avif', or be 'heic', 'heix' or 'mif1'"
+} else {
+    "ftyp must be or list 'avif' or 'avis', or be 'mif3' with minor version 'avif'"
+};
+
+/// Error for a primary item that is neither a coded image nor a supported
+/// derived image.
+const UNSUPPORTED_PRIMARY_TYPE: &str = if cfg!(feature = "heif") {
+    "primary_item_id type is not av01, hvc1, hev1, grid or iovl"
+} else {
+    "primary_item_id type is not av01, grid or iovl"
+};
+
+/// Whether this parser reads files with this `ftyp`: an `avif` or `avis`
+/// major brand, or any major brand (typically `mif1` or `miaf`) with `avif`
+/// or `avis` among the compatible brands, as MIAF allows; `mif3` with `avif`
+/// in minor_version (where minimized files name it); and with the `heif`
+/// feature the `heic`, `heix` and `mif1` major brands.
+/// See ISO 23000-22:2019 § 10 and ISO 14496-12:2015 § 4.3.1
+fn is_supported_major_brand(ftyp: &FileTypeBox) -> bool {
+    let is_avif = |brand: &FourCC| *brand == b"avif" || *brand == b"avis";
+    let major = &ftyp.major_brand;
+    is_avif(major)
+        || ftyp.compatible_brands.iter().any(is_avif)
+        || (*major == b"mif3" && ftyp.minor_version.to_be_bytes() == *b"avif")
+        || (cfg!(feature = "heif") && [b"heic", b"heix", b"mif1"].iter().any(|brand| *major == *brand))
+}
+
+/// Whether `item_type` is a coded image item: `av01`, and with the `heif`
+/// feature `hvc1` and `hev1` (which may also carry parameter sets in-band).
+fn is_coded_image_item(item_type: &FourCC) -> bool {
+    *item_type == b"av01" || (cfg!(feature = "heif") && (*item_type == b"hvc1" || *item_type == b"hev1"))
+}
+
+/// HEIF requires image files to list `mif1` and image sequences `msf1`
+/// among their compatible brands. See ISO 23008-12:2017 § 10.2.1 and § 10.3.1
+fn check_structural_brand(ftyp: &FileTypeBox) -> Result<()> {
+    // Itself a structural brand, for files with a `mini` box
+    if ftyp.major_brand == b"mif3" {
+        return Ok(());
+    }
+    let required = if ftyp.major_brand == b"avis" || ftyp.major_brand == b"msf1" { b"msf1" } else { b"mif1" };
+    if ftyp.major_brand.value != *required && !ftyp.compatible_brands.iter().any(|brand| brand.value == *required) {
+        warn!("ftyp {} without {} compatible brand", ftyp.major_brand, FourCC::from(u32::from_be_bytes(*required)));
+        return Err(at!(Error::InvalidData("ftyp compatible_brands lacks the HEIF structural brand")));
+    }
+    Ok(())
+}
+
 /// Parse an AV1 Codec Configuration property box
 /// See AV1-ISOBMFF § 2.3
 fn read_av1c<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<AV1Config> {
+    let payload = src.read_into_try_vec().map_err(|e| at!(Error::from(e)))?;
+    parse_av1c(&payload)
+}
+
+/// Parse an `av1C` payload, also stored bare in a `mini` box.
+fn parse_av1c(data: &[u8]) -> Result<AV1Config> {
+    let mut src = io::Cursor::new(data);
     // av1C is NOT a FullBox — it has no version/flags
     let byte0 = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let marker = byte0 >> 7;
@@ -4793,8 +10617,7 @@ fn read_av1c<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<AV1Config> {
         8
     };
 
-    // Skip any configOBUs (remainder of box)
-    skip_box_remain(src)?;
+    let config_obus = data.get(4..).unwrap_or_default();
 
     Ok(AV1Config {
         profile,
@@ -4805,6 +10628,67 @@ fn read_av1c<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<AV1Config> {
         chroma_subsampling_x,
         chroma_subsampling_y,
         chroma_sample_position,
+        config_obus: config_obus.to_vec(),
+    })
+}
+
+/// Parse an HEVC Decoder Configuration Record property box
+/// See ISO 14496-15 § 8.3.3.1
+#[cfg(feature = "heif")]
+fn read_hvcc<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<HevcConfig> {
+    // hvcC is NOT a FullBox — it has no version/flags
+    let version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    if version != 1 {
+        return Err(at!(Error::Unsupported("hvcC configurationVersion must be 1")));
+    }
+    let byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    let profile_compatibility_flags = be_u32(src)?;
+    let constraint_indicator_flags = (u64::from(be_u32(src)?) << 16) | u64::from(be_u16(src)?);
+    let level_idc = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    // min_spatial_segmentation_idc (12 bits) and parallelismType (2 bits)
+    skip(src, 3)?;
+    let chroma_format = src.read_u8().map_err(|e| at!(Error::from(e)))? & 0x03;
+    let bit_depth_luma = (src.read_u8().map_err(|e| at!(Error::from(e)))? & 0x07) + 8;
+    let bit_depth_chroma = (src.read_u8().map_err(|e| at!(Error::from(e)))? & 0x07) + 8;
+    // avgFrameRate
+    skip(src, 2)?;
+    let length_size = (src.read_u8().map_err(|e| at!(Error::from(e)))? & 0x03) + 1;
+
+    let num_arrays = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    let mut nal_arrays = alloc::vec::Vec::new();
+    for _ in 0..num_arrays {
+        let array_byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+        let num_nalus = be_u16(src)?;
+        let mut units = alloc::vec::Vec::new();
+        for _ in 0..num_nalus {
+            let len = be_u16(src)?;
+            if u64::from(len) > src.bytes_left() {
+                return Err(at!(Error::InvalidData("hvcC NAL unit exceeds box size")));
+            }
+            let mut unit = alloc::vec::Vec::new();
+            unit.try_reserve_exact(usize::from(len)).map_err(|_| at!(Error::OutOfMemory))?;
+            unit.resize(usize::from(len), 0);
+            src.read_exact(&mut unit).map_err(|e| at!(Error::from(e)))?;
+            units.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+            units.push(unit);
+        }
+        nal_arrays.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+        nal_arrays.push(HevcNalArray { complete: array_byte & 0x80 != 0, nal_unit_type: array_byte & 0x3F, units });
+    }
+    skip_box_remain(src)?;
+
+    Ok(HevcConfig {
+        profile_space: byte >> 6,
+        tier: (byte >> 5) & 1,
+        profile_idc: byte & 0x1F,
+        profile_compatibility_flags,
+        constraint_indicator_flags,
+        level_idc,
+        chroma_format,
+        bit_depth_luma,
+        bit_depth_chroma,
+        length_size,
+        nal_arrays,
     })
 }
 
@@ -4841,6 +10725,27 @@ fn read_colr<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<ColorInformation> {
     }
 }
 
+/// Find the ICC profile among the primary item's `colr` properties.
+///
+/// Unlike `find_prop!(ColorInformation)`, this skips a preceding `nclx` box.
+fn primary_icc_profile(meta: &AvifInternalMeta) -> Option<alloc::vec::Vec<u8>> {
+    meta.properties.iter().find_map(|p| match meta.property(p) {
+        ItemProperty::ColorInformation(ColorInformation::IccProfile(icc)) if p.item_id == meta.primary_item_id => Some(icc.clone()),
+        _ => None,
+    })
+}
+
+fn primary_user_descriptions(meta: &AvifInternalMeta) -> Result<alloc::vec::Vec<UserDescription>> {
+    let mut descriptions = alloc::vec::Vec::new();
+    for p in meta.properties.iter().filter(|p| p.item_id == meta.primary_item_id) {
+        if let ItemProperty::UserDescription(udes) = meta.property(p) {
+            descriptions.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+            descriptions.push(udes.clone());
+        }
+    }
+    Ok(descriptions)
+}
+
 /// Parse an Image Rotation property box.
 /// See ISOBMFF § 12.1.4. NOT a FullBox.
 fn read_irot<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<ImageRotation> {
@@ -5012,7 +10917,7 @@ fn read_a1lx<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<AV1LayeredImageIndexin
 
 /// Parse an Image Spatial Extents property box
 /// See ISO/IEC 23008-12:2017 § 6.5.3
-fn read_ispe<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<ImageSpatialExtents> {
+fn read_ispe<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<ImageSpatialExtents> {
     let _version = read_fullbox_version_no_flags(src, options)?;
     // Version is always 0 for ispe
 
@@ -5027,6 +10932,29 @@ fn read_ispe<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
     Ok(ImageSpatialExtents { width, height })
 }
 
+/// Parse a User Description property box: four null-terminated UTF-8
+/// strings, invalid sequences replaced. See HEIF § 6.5.20.
+/// Returns `None` for versions other than 0, which are skipped.
+fn read_udes<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<Option<UserDescription>> {
+    let version = read_fullbox_version_no_flags(src, options)?;
+    if version != 0 {
+        warn!("skipping udes version {version}");
+        skip_box_remain(src)?;
+        return Ok(None);
+    }
+    let mut read_string = || -> Result<alloc::string::String> {
+        let mut bytes = TryVec::new();
+        read_null_terminated(src, &mut bytes)?;
+        Ok(alloc::string::String::from_utf8_lossy(&bytes).into_owned())
+    };
+    let lang = read_string()?;
+    let name = read_string()?;
+    let description = read_string()?;
+    let tags = read_string()?;
+    skip_box_remain(src)?;
+    Ok(Some(UserDescription { lang, name, description, tags }))
+}
+
 /// Parse a Movie Header box (mvhd)
 /// See ISO/IEC 14496-12:2015 § 8.2.2
 fn read_mvhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<MovieHeader> {
@@ -5050,7 +10978,7 @@ fn read_mvhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<MovieHeader> {
     // Skip rest of mvhd (rate, volume, matrix, etc.)
     skip_box_remain(src)?;
 
-    Ok(MovieHeader { _timescale: timescale, _duration: duration })
+    Ok(MovieHeader { timescale, _duration: duration })
 }
 
 /// Parse a Media Header box (mdhd)
@@ -5079,9 +11007,18 @@ fn read_mdhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<MediaHeader> {
     Ok(MediaHeader { timescale, _duration: duration })
 }
 
+/// Poll `stop` every 64k entries of a sample table. Tables are bounded by
+/// their box size, but that still allows millions of entries per box.
+fn check_table_stop(index: u32, stop: &dyn Stop) -> Result<()> {
+    if index.is_multiple_of(1 << 16) {
+        stop.check().map_err(|e| at!(Error::from(e)))?;
+    }
+    Ok(())
+}
+
 /// Parse Time To Sample box (stts)
 /// See ISO/IEC 14496-12:2015 § 8.6.1.2
-fn read_stts<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<TimeToSampleEntry>> {
+fn read_stts<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<TryVec<TimeToSampleEntry>> {
     let _version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let _flags = [src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?];
     let entry_count = be_u32(src)?;
@@ -5093,7 +11030,8 @@ fn read_stts<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<TimeToSampleEnt
     }
 
     let mut entries = TryVec::new();
-    for _ in 0..entry_count {
+    for index in 0..entry_count {
+        check_table_stop(index, stop)?;
         entries.push(TimeToSampleEntry {
             sample_count: be_u32(src)?,
             sample_delta: be_u32(src)?,
@@ -5103,9 +11041,58 @@ fn read_stts<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<TimeToSampleEnt
     Ok(entries)
 }
 
+/// Parse Sync Sample box (stss)
+/// See ISO/IEC 14496-12:2015 § 8.6.2
+fn read_stss<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<TryVec<u32>> {
+    let (_version, _flags) = read_fullbox_extra(src)?;
+    let entry_count = be_u32(src)?;
+    // Each entry: sample_number (4) = 4 bytes
+    if (entry_count as u64) * 4 > src.bytes_left() {
+        return Err(at!(Error::InvalidData(
+            "stss entry_count exceeds remaining box bytes",
+        )));
+    }
+
+    let mut entries = TryVec::new();
+    for index in 0..entry_count {
+        check_table_stop(index, stop)?;
+        entries.push(be_u32(src)?).map_err(|e| at!(Error::from(e)))?;
+    }
+    // The spec requires strictly increasing sample numbers; keep lookups correct regardless
+    entries.sort_unstable();
+
+    Ok(entries)
+}
+
+/// Parse Composition Time to Sample box (ctts)
+/// See ISO/IEC 14496-12:2015 § 8.6.1.3
+fn read_ctts<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<TryVec<CompositionOffsetEntry>> {
+    let (version, _flags) = read_fullbox_extra(src)?;
+    let entry_count = be_u32(src)?;
+    // Each entry: sample_count (4) + sample_offset (4) = 8 bytes
+    if (entry_count as u64) * 8 > src.bytes_left() {
+        return Err(at!(Error::InvalidData(
+            "ctts entry_count exceeds remaining box bytes",
+        )));
+    }
+
+    let mut entries = TryVec::new();
+    for index in 0..entry_count {
+        check_table_stop(index, stop)?;
+        let sample_count = be_u32(src)?;
+        let raw = be_u32(src)?;
+        // Version 0 offsets are unsigned, version 1 offsets signed
+        let sample_offset = if version == 0 { i64::from(raw) } else { i64::from(raw as i32) };
+        entries.push(CompositionOffsetEntry { sample_count, sample_offset })
+            .map_err(|e| at!(Error::from(e)))?;
+    }
+
+    Ok(entries)
+}
+
 /// Parse Sample To Chunk box (stsc)
 /// See ISO/IEC 14496-12:2015 § 8.7.4
-fn read_stsc<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<SampleToChunkEntry>> {
+fn read_stsc<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<TryVec<SampleToChunkEntry>> {
     let _version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let _flags = [src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?];
     let entry_count = be_u32(src)?;
@@ -5117,7 +11104,8 @@ fn read_stsc<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<SampleToChunkEn
     }
 
     let mut entries = TryVec::new();
-    for _ in 0..entry_count {
+    for index in 0..entry_count {
+        check_table_stop(index, stop)?;
         entries.push(SampleToChunkEntry {
             first_chunk: be_u32(src)?,
             samples_per_chunk: be_u32(src)?,
@@ -5130,7 +11118,7 @@ fn read_stsc<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<SampleToChunkEn
 
 /// Parse Sample Size box (stsz)
 /// See ISO/IEC 14496-12:2015 § 8.7.3
-fn read_stsz<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<SampleSizes> {
+fn read_stsz<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<SampleSizes> {
     let _version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let _flags = [src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?];
     let sample_size = be_u32(src)?;
@@ -5152,7 +11140,8 @@ fn read_stsz<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<SampleSizes> {
             )));
         }
         let mut sizes = TryVec::new();
-        for _ in 0..sample_count {
+        for index in 0..sample_count {
+            check_table_stop(index, stop)?;
             sizes.push(be_u32(src)?).map_err(|e| at!(Error::from(e)))?;
         }
         Ok(SampleSizes::Variable(sizes))
@@ -5167,9 +11156,53 @@ fn read_stsz<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<SampleSizes> {
     }
 }
 
+/// Parse Compact Sample Size box (stz2)
+/// See ISO/IEC 14496-12:2015 § 8.7.3.3
+fn read_stz2<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<SampleSizes> {
+    let (_version, _flags) = read_fullbox_extra(src)?;
+    // reserved (24 bits)
+    skip(src, 3)?;
+    let field_size = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    let sample_count = be_u32(src)?;
+    if !matches!(field_size, 4 | 8 | 16) {
+        return Err(at!(Error::InvalidData("stz2 field_size must be 4, 8 or 16")));
+    }
+    // Entries are packed, so the box size bounds the count
+    if (sample_count as u64 * field_size as u64).div_ceil(8) > src.bytes_left() {
+        return Err(at!(Error::InvalidData(
+            "stz2 sample_count exceeds remaining box bytes",
+        )));
+    }
+
+    let mut sizes = TryVec::new();
+    if field_size == 4 {
+        // Two sizes per byte, the first in the high nibble
+        for pair in 0..sample_count.div_ceil(2) {
+            check_table_stop(pair, stop)?;
+            let byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+            sizes.push(u32::from(byte >> 4)).map_err(|e| at!(Error::from(e)))?;
+            if pair * 2 + 1 < sample_count {
+                sizes.push(u32::from(byte & 0x0f)).map_err(|e| at!(Error::from(e)))?;
+            }
+        }
+    } else {
+        for index in 0..sample_count {
+            check_table_stop(index, stop)?;
+            let size = if field_size == 8 {
+                u32::from(src.read_u8().map_err(|e| at!(Error::from(e)))?)
+            } else {
+                u32::from(be_u16(src)?)
+            };
+            sizes.push(size).map_err(|e| at!(Error::from(e)))?;
+        }
+    }
+    skip_box_remain(src)?;
+    Ok(SampleSizes::Variable(sizes))
+}
+
 /// Parse Chunk Offset box (stco or co64)
 /// See ISO/IEC 14496-12:2015 § 8.7.5
-fn read_chunk_offsets<T: Read>(src: &mut BMFFBox<'_, T>, is_64bit: bool) -> Result<TryVec<u64>> {
+fn read_chunk_offsets<T: Read>(src: &mut BMFFBox<'_, T>, is_64bit: bool, stop: &dyn Stop) -> Result<TryVec<u64>> {
     let _version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let _flags = [src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?];
     let entry_count = be_u32(src)?;
@@ -5181,7 +11214,8 @@ fn read_chunk_offsets<T: Read>(src: &mut BMFFBox<'_, T>, is_64bit: bool) -> Resu
     }
 
     let mut offsets = TryVec::new();
-    for _ in 0..entry_count {
+    for index in 0..entry_count {
+        check_table_stop(index, stop)?;
         let offset = if is_64bit {
             be_u64(src)?
         } else {
@@ -5211,6 +11245,7 @@ fn read_stsd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TrackCodecConfig> {
         let Some(mut entry_box) = iter.next_box()? else {
             break;
         };
+        config.sample_entry.get_or_insert_with(|| FourCC::from(entry_box.head.name));
 
         // Check if this is an av01 VisualSampleEntry
         if entry_box.head.name != BoxType::AV1SampleEntry {
@@ -5227,17 +11262,39 @@ fn read_stsd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TrackCodecConfig> {
             skip_box_remain(&mut entry_box)?;
             continue;
         }
-        skip(&mut entry_box, VISUAL_SAMPLE_ENTRY_SIZE)?;
+        skip(&mut entry_box, 6)?;
+        config.data_reference_index = be_u16(&mut entry_box)?;
+        skip(&mut entry_box, VISUAL_SAMPLE_ENTRY_SIZE - 8)?;
 
-        // Parse sub-boxes within the VisualSampleEntry for av1C and colr
+        // Parse sub-boxes within the VisualSampleEntry for av1C, colr, pasp
+        // and the HDR boxes that still images carry as properties
         let mut sub_iter = entry_box.box_iter();
         while let Some(mut sub_box) = sub_iter.next_box()? {
             match sub_box.head.name {
                 BoxType::AV1CodecConfigurationBox => {
                     config.av1_config = Some(read_av1c(&mut sub_box)?);
                 }
+                BoxType::PixelAspectRatioBox => {
+                    config.pixel_aspect_ratio = Some(read_pasp(&mut sub_box)?);
+                }
+                BoxType::ContentLightLevelBox => {
+                    config.content_light_level = Some(read_clli(&mut sub_box)?);
+                }
+                BoxType::MasteringDisplayColourVolumeBox => {
+                    config.mastering_display = Some(read_mdcv(&mut sub_box)?);
+                }
+                BoxType::ContentColourVolumeBox => {
+                    config.content_colour_volume = Some(read_cclv(&mut sub_box)?);
+                }
+                BoxType::AmbientViewingEnvironmentBox => {
+                    config.ambient_viewing = Some(read_amve(&mut sub_box)?);
+                }
                 BoxType::ColorInformationBox => {
                     if let Ok(colr) = read_colr(&mut sub_box) {
+                        // An entry may carry one nclx and one ICC colr; keep both reachable
+                        if let ColorInformation::IccProfile(icc) = &colr {
+                            config.icc_profile = Some(icc.clone());
+                        }
                         config.color_info = Some(colr);
                     } else {
                         skip_box_remain(&mut sub_box)?;
@@ -5260,52 +11317,82 @@ fn read_stsd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TrackCodecConfig> {
 
 /// Parse Sample Table box (stbl)
 /// See ISO/IEC 14496-12:2015 § 8.5
-fn read_stbl<T: Read>(
+fn read_stbl<T: Read + Offset>(
     src: &mut BMFFBox<'_, T>,
+    ctx: &ParseContext,
     stop: &dyn Stop,
 ) -> Result<(SampleTable, TrackCodecConfig)> {
     let mut time_to_sample = TryVec::new();
     let mut sample_to_chunk = TryVec::new();
     let mut sample_sizes = SampleSizes::Variable(TryVec::new());
     let mut chunk_offsets = TryVec::new();
+    let mut sync_samples = None;
+    let mut composition_offsets = TryVec::new();
     let mut codec_config = TrackCodecConfig::default();
 
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::SampleDescriptionBox => {
-                codec_config = read_stsd(&mut b)?;
-            }
-            BoxType::TimeToSampleBox => {
-                time_to_sample = read_stts(&mut b)?;
-            }
-            BoxType::SampleToChunkBox => {
-                sample_to_chunk = read_stsc(&mut b)?;
-            }
-            BoxType::SampleSizeBox => {
-                sample_sizes = read_stsz(&mut b)?;
-            }
-            BoxType::ChunkOffsetBox => {
-                chunk_offsets = read_chunk_offsets(&mut b, false)?;
-            }
-            BoxType::ChunkLargeOffsetBox => {
-                chunk_offsets = read_chunk_offsets(&mut b, true)?;
-            }
-            _ => {
-                skip_box_remain(&mut b)?;
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::SampleDescriptionBox => {
+                    codec_config = read_stsd(b)?;
+                }
+                BoxType::TimeToSampleBox => {
+                    time_to_sample = read_stts(b, stop)?;
+                }
+                BoxType::SampleToChunkBox => {
+                    sample_to_chunk = read_stsc(b, stop)?;
+                }
+                BoxType::SampleSizeBox => {
+                    sample_sizes = read_stsz(b, stop)?;
+                }
+                BoxType::CompactSampleSizeBox => {
+                    sample_sizes = read_stz2(b, stop)?;
+                }
+                BoxType::ChunkOffsetBox => {
+                    chunk_offsets = read_chunk_offsets(b, false, stop)?;
+                }
+                BoxType::ChunkLargeOffsetBox => {
+                    chunk_offsets = read_chunk_offsets(b, true, stop)?;
+                }
+                BoxType::SyncSampleBox => {
+                    sync_samples = Some(read_stss(b, stop)?);
+                }
+                BoxType::CompositionOffsetBox => {
+                    composition_offsets = read_ctts(b, stop)?;
+                }
+                _ => {
+                    skip_box_remain(b)?;
+                }
             }
-        }
+            Ok(())
+        })?;
     }
 
+    // The per-sample tables below are sized by the sample count, so bound it first
+    ctx.check_sample_count(sample_sizes.len())?;
+
     // Precompute per-sample byte offsets from sample_to_chunk + chunk_offsets + sample_sizes.
     // This flattens the ISOBMFF indirection into a simple array for O(1) frame lookup.
     let sample_offsets =
         precompute_sample_offsets(&sample_to_chunk, &chunk_offsets, &sample_sizes, stop)?;
+    // Likewise for timing, which would otherwise walk the stts and ctts runs per frame
+    let (sample_times, media_duration) = precompute_sample_times(
+        &time_to_sample, &composition_offsets, sync_samples.as_ref(), sample_sizes.len(), stop,
+    )?;
+    let presentation_order = if composition_offsets.is_empty() {
+        TryVec::new()
+    } else {
+        precompute_presentation_order(&sample_times)?
+    };
 
     Ok((SampleTable {
-        time_to_sample,
         sample_sizes,
         sample_offsets,
+        sync_samples,
+        presentation_order,
+        sample_times,
+        media_duration,
     }, codec_config))
 }
 
@@ -5362,9 +11449,62 @@ fn precompute_sample_offsets(
     Ok(sample_offsets)
 }
 
+/// Flatten time_to_sample + composition offsets + sync samples into per-sample
+/// timing for `sample_count` samples, and return it with the total of the
+/// `stts` deltas.
+fn precompute_sample_times(
+    time_to_sample: &TryVec<TimeToSampleEntry>,
+    composition_offsets: &TryVec<CompositionOffsetEntry>,
+    sync_samples: Option<&TryVec<u32>>,
+    sample_count: usize,
+    stop: &dyn Stop,
+) -> Result<(TryVec<SampleTime>, u64)> {
+    let mut deltas = time_to_sample.iter()
+        .flat_map(|e| core::iter::repeat_n(e.sample_delta, e.sample_count as usize));
+    let mut offsets = composition_offsets.iter()
+        .flat_map(|e| core::iter::repeat_n(e.sample_offset, e.sample_count as usize));
+    let mut next_sync = 0;
+    let mut decode_time = 0u64;
+    let mut times = TryVec::new();
+    for index in 0..sample_count {
+        // Same cancellation cadence as precompute_sample_offsets
+        if index.is_multiple_of(1 << 16) {
+            stop.check().map_err(|e| at!(Error::from(e)))?;
+        }
+        let duration = deltas.next().unwrap_or(0);
+        let offset = offsets.next().unwrap_or(0);
+        // Sync sample numbers are sorted and 1-based
+        let is_sync = sync_samples.is_none_or(|sync| {
+            let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+            while sync.get(next_sync).is_some_and(|&n| n < number) {
+                next_sync += 1;
+            }
+            sync.get(next_sync) == Some(&number)
+        });
+        times.push(SampleTime {
+            presentation_time: apply_composition_offset(decode_time, offset),
+            duration,
+            is_sync,
+        }).map_err(|e| at!(Error::from(e)))?;
+        decode_time = decode_time.saturating_add(u64::from(duration));
+    }
+    Ok((times, decode_time))
+}
+
+/// Sample indices sorted by presentation time, ties in decode order.
+fn precompute_presentation_order(sample_times: &TryVec<SampleTime>) -> Result<TryVec<u32>> {
+    let mut order = TryVec::with_capacity(sample_times.len()).map_err(|e| at!(Error::from(e)))?;
+    for index in 0..sample_times.len() {
+        let index = u32::try_from(index).map_err(|e| at!(Error::from(e)))?;
+        order.push(index).map_err(|e| at!(Error::from(e)))?;
+    }
+    order.sort_by_key(|&i| (sample_times.get(i as usize).map_or(0, |t| t.presentation_time), i));
+    Ok(order)
+}
+
 /// Parse Track Header box (tkhd)
 /// See ISO/IEC 14496-12:2015 § 8.3.2
-fn read_tkhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<u32> {
+fn read_tkhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TrackHeader> {
     let version = src.read_u8().map_err(|e| at!(Error::from(e)))?;
     let _flags = [src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?, src.read_u8().map_err(|e| at!(Error::from(e)))?];
 
@@ -5384,9 +11524,16 @@ fn read_tkhd<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<u32> {
         track_id
     };
 
-    // Skip rest (reserved, layer, alternate_group, volume, matrix, width, height)
+    // reserved[2], layer, alternate_group, volume, reserved and matrix[9]
+    // precede the 16.16 fixed-point width and height
+    let (width, height) = if src.bytes_left() >= 60 {
+        skip(src, 52)?;
+        (be_u32(src)? >> 16, be_u32(src)? >> 16)
+    } else {
+        (0, 0)
+    };
     skip_box_remain(src)?;
-    Ok(track_id)
+    Ok(TrackHeader { track_id, width, height })
 }
 
 /// Parse Track Reference box (tref)
@@ -5416,96 +11563,156 @@ fn read_tref<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<TryVec<TrackReference>
 /// Parse Edit List box (elst) to extract loop count from flags.
 /// See ISO/IEC 14496-12:2015 § 8.6.6
 ///
-/// Returns the loop count: flags bit 0 set = infinite looping (0), otherwise 1.
-fn read_elst<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<u32> {
+/// Returns the loop count (flags bit 0 set = infinite looping (0), otherwise
+/// 1) and the first edit that presents media.
+fn read_elst<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<(u32, Option<EditListEntry>)> {
     let (version, flags) = read_fullbox_extra(src)?;
 
     let entry_count = be_u32(src)?;
-    // Skip all entries
     let entry_size: u64 = if version == 1 { 20 } else { 12 };
-    skip(src, (entry_count as u64).checked_mul(entry_size)
-        .ok_or_else(|| at!(Error::InvalidData("edit list entry count overflow")))?)?;
+    if (entry_count as u64).checked_mul(entry_size)
+        .ok_or_else(|| at!(Error::InvalidData("edit list entry count overflow")))? > src.bytes_left()
+    {
+        return Err(at!(Error::InvalidData("elst entry_count exceeds remaining box bytes")));
+    }
+
+    // Keep the first edit that presents media; empty edits (media_time -1) only delay it
+    let mut edit = None;
+    for _ in 0..entry_count {
+        let (segment_duration, media_time) = if version == 1 {
+            (be_u64(src)?, be_u64(src)? as i64)
+        } else {
+            let segment_duration = be_u32(src)?;
+            let media_time = be_u32(src)? as i32;
+            // 32-bit all ones is the version 0 spelling of an unbounded edit
+            let segment_duration = if segment_duration == u32::MAX { u64::MAX } else { u64::from(segment_duration) };
+            (segment_duration, i64::from(media_time))
+        };
+        // media_rate_integer and media_rate_fraction
+        skip(src, 4)?;
+        if edit.is_none() && media_time >= 0 {
+            edit = Some(EditListEntry { segment_duration, media_time: media_time as u64 });
+        }
+    }
     skip_box_remain(src)?;
 
     // Bit 0 of flags: repeat (1 = infinite loop → loop_count=0, 0 = play once → loop_count=1)
-    if flags & 1 != 0 {
-        Ok(0) // infinite
+    let loop_count = if flags & 1 != 0 {
+        0 // infinite
     } else {
-        Ok(1) // play once
-    }
+        1 // play once
+    };
+    Ok((loop_count, edit))
 }
 
 /// Parse animation from moov box.
 /// Returns all parsed tracks.
-fn read_moov<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<TryVec<ParsedTrack>> {
+fn read_moov<T: Read + Offset>(src: &mut BMFFBox<'_, T>, ctx: &ParseContext, stop: &dyn Stop) -> Result<TryVec<ParsedTrack>> {
     let mut tracks = TryVec::new();
+    let mut movie_timescale = 0;
 
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::MovieHeaderBox => {
-                let _mvhd = read_mvhd(&mut b)?;
-            }
-            BoxType::TrackBox => {
-                if let Some(track) = read_trak(&mut b, stop)? {
-                    tracks.push(track).map_err(|e| at!(Error::from(e)))?;
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::MovieHeaderBox => {
+                    movie_timescale = read_mvhd(b)?.timescale;
+                }
+                BoxType::TrackBox => {
+                    if let Some(track) = read_trak(b, ctx, stop)? {
+                        tracks.push(track).map_err(|e| at!(Error::from(e)))?;
+                    }
+                }
+                _ => {
+                    skip_box_remain(b)?;
                 }
             }
-            _ => {
-                skip_box_remain(&mut b)?;
-            }
+            Ok(())
+        })?;
+    }
+
+    // mvhd may follow the tracks, so edits are applied once everything is read
+    for track in tracks.iter_mut() {
+        if let Some(edit) = track.edit {
+            track.duration = edited_duration(track.duration, edit, movie_timescale, track.media_timescale);
         }
     }
 
     Ok(tracks)
 }
 
+/// Length of the media presented by `edit`, in media timescale units.
+///
+/// `media_duration` is the sum of the sample durations. The edit starts
+/// `media_time` into the media and lasts `segment_duration` movie ticks; a
+/// segment longer than the rest of the media (e.g. one spanning every
+/// repetition of a loop) or an unbounded one presents the media to its end.
+fn edited_duration(media_duration: u64, edit: EditListEntry, movie_timescale: u32, media_timescale: u32) -> u64 {
+    let remaining = media_duration.saturating_sub(edit.media_time);
+    if edit.segment_duration == 0 || edit.segment_duration == u64::MAX || movie_timescale == 0 {
+        return remaining;
+    }
+    let segment = u128::from(edit.segment_duration) * u128::from(media_timescale) / u128::from(movie_timescale);
+    u64::try_from(segment).map_or(remaining, |segment| segment.min(remaining))
+}
+
 /// Parse track box (trak).
 /// Returns a ParsedTrack if this track has a valid sample table.
-fn read_trak<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<Option<ParsedTrack>> {
-    let mut track_id = 0u32;
+fn read_trak<T: Read + Offset>(src: &mut BMFFBox<'_, T>, ctx: &ParseContext, stop: &dyn Stop) -> Result<Option<ParsedTrack>> {
+    let mut header = TrackHeader { track_id: 0, width: 0, height: 0 };
     let mut references = TryVec::new();
     let mut loop_count = 1u32; // default: play once
+    let mut edit = None;
     let mut mdia_result: Option<(FourCC, u32, SampleTable, TrackCodecConfig)> = None;
 
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::TrackHeaderBox => {
-                track_id = read_tkhd(&mut b)?;
-            }
-            BoxType::TrackReferenceBox => {
-                references = read_tref(&mut b)?;
-            }
-            BoxType::EditBox => {
-                // Parse edts to find elst
-                let mut edts_iter = b.box_iter();
-                while let Some(mut eb) = edts_iter.next_box()? {
-                    if eb.head.name == BoxType::EditListBox {
-                        loop_count = read_elst(&mut eb)?;
-                    } else {
-                        skip_box_remain(&mut eb)?;
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::TrackHeaderBox => {
+                    header = read_tkhd(b)?;
+                }
+                BoxType::TrackReferenceBox => {
+                    references = read_tref(b)?;
+                }
+                BoxType::EditBox => {
+                    // Parse edts to find elst
+                    let mut edts_iter = b.box_iter();
+                    while let Some(mut eb) = edts_iter.next_box()? {
+                        if eb.head.name == BoxType::EditListBox {
+                            (loop_count, edit) = read_elst(&mut eb)?;
+                        } else {
+                            skip_box_remain(&mut eb)?;
+                        }
                     }
                 }
+                BoxType::MediaBox => {
+                    mdia_result = read_mdia(b, ctx, stop)?;
+                }
+                _ => {
+                    skip_box_remain(b)?;
+                }
             }
-            BoxType::MediaBox => {
-                mdia_result = read_mdia(&mut b, stop)?;
-            }
-            _ => {
-                skip_box_remain(&mut b)?;
-            }
-        }
+            Ok(())
+        })?;
     }
 
     if let Some((handler_type, media_timescale, sample_table, codec_config)) = mdia_result {
+        let duration = sample_table.media_duration;
+        let kind = TrackKind::classify(&handler_type, &references);
         Ok(Some(ParsedTrack {
-            track_id,
+            track_id: header.track_id,
             handler_type,
+            kind,
             media_timescale,
             sample_table,
             references,
             loop_count,
+            edit,
+            duration,
             codec_config,
+            width: header.width,
+            height: header.height,
         }))
     } else {
         Ok(None)
@@ -5514,8 +11721,9 @@ fn read_trak<T: Read>(src: &mut BMFFBox<'_, T>, stop: &dyn Stop) -> Result<Optio
 
 /// Parse media box (mdia).
 /// Returns (handler_type, media_timescale, sample_table, codec_config) if valid.
-fn read_mdia<T: Read>(
+fn read_mdia<T: Read + Offset>(
     src: &mut BMFFBox<'_, T>,
+    ctx: &ParseContext,
     stop: &dyn Stop,
 ) -> Result<Option<(FourCC, u32, SampleTable, TrackCodecConfig)>> {
     let mut media_timescale = 1000; // default
@@ -5524,22 +11732,25 @@ fn read_mdia<T: Read>(
 
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        match b.head.name {
-            BoxType::MediaHeaderBox => {
-                let mdhd = read_mdhd(&mut b)?;
-                media_timescale = mdhd.timescale;
-            }
-            BoxType::HandlerBox => {
-                let hdlr = read_hdlr(&mut b)?;
-                handler_type = hdlr.handler_type;
-            }
-            BoxType::MediaInformationBox => {
-                stbl_result = read_minf(&mut b, stop)?;
-            }
-            _ => {
-                skip_box_remain(&mut b)?;
+        in_box(&mut b, |b| {
+            match b.head.name {
+                BoxType::MediaHeaderBox => {
+                    let mdhd = read_mdhd(b)?;
+                    media_timescale = mdhd.timescale;
+                }
+                BoxType::HandlerBox => {
+                    let hdlr = read_hdlr(b)?;
+                    handler_type = hdlr.handler_type;
+                }
+                BoxType::MediaInformationBox => {
+                    stbl_result = read_minf(b, ctx, stop)?;
+                }
+                _ => {
+                    skip_box_remain(b)?;
+                }
             }
-        }
+            Ok(())
+        })?;
     }
 
     if let Some((stbl, codec_config)) = stbl_result {
@@ -5551,27 +11762,38 @@ fn read_mdia<T: Read>(
 
 /// Associate parsed tracks into color + optional alpha animation data.
 ///
-/// - Color track: first with handler `pict` (fallback: first track with a sample table)
-/// - Alpha track: handler `auxv` with `tref/auxl` referencing color's track_id
+/// - Color track: the one with `track_id` if given, else the first
+///   [`TrackKind::Picture`], then [`TrackKind::Video`] (fallback: first
+///   track that is not audio)
+/// - Alpha track: [`TrackKind::Auxiliary`] with `tref/auxl` referencing color's track_id
 /// - Audio tracks (handler `soun`) are skipped
-fn associate_tracks(tracks: TryVec<ParsedTrack>) -> Result<ParsedAnimationData> {
-    // Find color track: first with handler_type == "pict"
-    let color_idx = tracks
-        .iter()
-        .position(|t| t.handler_type == b"pict")
-        .or_else(|| {
-            // Fallback: first track that isn't audio
-            tracks.iter().position(|t| t.handler_type != b"soun")
-        })
-        .ok_or_else(|| at!(Error::InvalidData("no color track found in moov")))?;
+/// - The remaining tracks are kept for [`AvifParser::track_frames`]
+fn associate_tracks(tracks: TryVec<ParsedTrack>, track_id: Option<u32>) -> Result<ParsedAnimationData> {
+    let color_idx = if let Some(id) = track_id {
+        tracks.iter().position(|t| t.track_id == id)
+            .ok_or_else(|| at!(Error::InvalidData("no track with the configured track_id")))?
+    } else {
+        // Find color track: first image sequence, then first video; an
+        // alpha `pict` track listed before its color track is auxiliary
+        tracks
+            .iter()
+            .position(|t| t.kind == TrackKind::Picture)
+            .or_else(|| tracks.iter().position(|t| t.kind == TrackKind::Video))
+            .or_else(|| {
+                // Fallback: first track that isn't audio
+                tracks.iter().position(|t| t.kind != TrackKind::Audio)
+            })
+            .ok_or_else(|| at!(Error::InvalidData("no color track found in moov")))?
+    };
 
     let color_track = tracks.get(color_idx)
         .ok_or_else(|| at!(Error::InvalidData("color track index out of bounds")))?;
     let color_track_id = color_track.track_id;
 
     // Find alpha track: handler_type == "auxv" or "pict" with tref/auxl referencing color track
-    let alpha_idx = tracks.iter().position(|t| {
-        matches!(&t.handler_type.value, b"auxv" | b"pict")
+    let alpha_idx = tracks.iter().enumerate().position(|(i, t)| {
+        i != color_idx
+            && t.kind == TrackKind::Auxiliary
             && t.references.iter().any(|r| {
                 r.reference_type == b"auxl"
                     && r.track_ids.iter().any(|&id| id == color_track_id)
@@ -5593,9 +11815,11 @@ fn associate_tracks(tracks: TryVec<ParsedTrack>) -> Result<ParsedAnimationData>
         }
     }
 
+    let infos = tracks.iter().map(|t| TrackInfo { selected: t.track_id == color_track_id, ..t.info() }).collect();
+
     // Destructure — we need to consume the vec
     // Convert to a std vec so we can remove by index
-    let mut tracks_vec: std::vec::Vec<ParsedTrack> = tracks.into_iter().collect();
+    let mut tracks_vec: alloc::vec::Vec<ParsedTrack> = tracks.into_iter().collect();
 
     // Remove alpha first if it has a higher index to avoid shifting
     let (color_track, alpha_track) = if let Some(ai) = alpha_idx {
@@ -5613,35 +11837,46 @@ fn associate_tracks(tracks: TryVec<ParsedTrack>) -> Result<ParsedAnimationData>
         (color, None)
     };
 
-    let (alpha_timescale, alpha_sample_table) = match alpha_track {
-        Some(t) => (Some(t.media_timescale), Some(t.sample_table)),
-        None => (None, None),
+    let (alpha_track_id, alpha_timescale, alpha_sample_table) = match alpha_track {
+        Some(t) => (Some(t.track_id), Some(t.media_timescale), Some(t.sample_table)),
+        None => (None, None, None),
     };
 
     Ok(ParsedAnimationData {
+        color_track_id,
+        alpha_track_id,
+        tracks: infos,
+        other_tracks: tracks_vec,
         color_timescale: color_track.media_timescale,
         color_codec_config: color_track.codec_config,
         color_sample_table: color_track.sample_table,
         alpha_timescale,
         alpha_sample_table,
         loop_count: color_track.loop_count,
+        color_duration: color_track.duration,
     })
 }
 
 /// Parse media information box (minf)
-fn read_minf<T: Read>(
+fn read_minf<T: Read + Offset>(
     src: &mut BMFFBox<'_, T>,
+    ctx: &ParseContext,
     stop: &dyn Stop,
 ) -> Result<Option<(SampleTable, TrackCodecConfig)>> {
+    let mut data_references = TryVec::new();
+    let mut stbl = None;
     let mut iter = src.box_iter();
     while let Some(mut b) = iter.next_box()? {
-        if b.head.name == BoxType::SampleTableBox {
-            return Ok(Some(read_stbl(&mut b, stop)?));
-        } else {
-            skip_box_remain(&mut b)?;
+        match b.head.name {
+            BoxType::SampleTableBox => stbl = Some(in_box(&mut b, |b| read_stbl(b, ctx, stop))?),
+            BoxType::DataInformationBox => data_references = in_box(&mut b, read_dinf)?,
+            _ => skip_box_remain(&mut b)?,
         }
     }
-    Ok(None)
+    if let Some((_, config)) = &stbl {
+        check_data_references(&data_references, core::iter::once(config.data_reference_index), ctx)?;
+    }
+    Ok(stbl)
 }
 
 /// Extract animation frames using sample table
@@ -5654,26 +11889,17 @@ fn extract_animation_frames(
 ) -> Result<TryVec<AnimationFrame>> {
     let mut frames = TryVec::new();
 
-    // Calculate frame durations from time-to-sample
-    let mut frame_durations = TryVec::new();
-    for entry in &sample_table.time_to_sample {
-        for _ in 0..entry.sample_count {
-            let duration_ms = if media_timescale > 0 {
-                ((entry.sample_delta as u64) * 1000) / (media_timescale as u64)
-            } else {
-                0
-            };
-            frame_durations.push(u32::try_from(duration_ms).unwrap_or(u32::MAX)).map_err(|e| at!(Error::from(e)))?;
-        }
-    }
-
     // Extract each frame using precomputed sample offsets
     for i in 0..sample_table.sample_sizes.len() {
         let sample_offset = *sample_table.sample_offsets.get(i)
             .ok_or_else(|| at!(Error::InvalidData("sample offset index out of bounds")))?;
         let sample_size = sample_table.sample_sizes.get(i)
             .ok_or_else(|| at!(Error::InvalidData("sample size index out of bounds")))?;
-        let duration_ms = frame_durations.get(i).copied().unwrap_or(0);
+        let duration_ms = match sample_table.sample_delta(i) {
+            Some(delta) if media_timescale > 0 => u64::from(delta) * 1000 / u64::from(media_timescale),
+            _ => 0,
+        };
+        let duration_ms = u32::try_from(duration_ms).unwrap_or(u32::MAX);
 
         let mut frame_data = TryVec::new();
         let mut found = false;
@@ -5710,15 +11936,16 @@ fn extract_animation_frames(
 
 /// Parse an ImageGrid property box
 /// See ISO/IEC 23008-12:2017 § 6.6.2.3
-fn read_grid<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<GridConfig> {
+fn read_grid<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext) -> Result<GridConfig> {
     let version = read_fullbox_version_no_flags(src, options)?;
     if version > 0 {
         return Err(at!(Error::Unsupported("grid version > 0")));
     }
 
     let flags_byte = src.read_u8().map_err(|e| at!(Error::from(e)))?;
-    let rows = src.read_u8().map_err(|e| at!(Error::from(e)))?;
-    let columns = src.read_u8().map_err(|e| at!(Error::from(e)))?;
+    // Stored minus one, so a byte covers 1-256
+    let rows = u16::from(src.read_u8().map_err(|e| at!(Error::from(e)))?) + 1;
+    let columns = u16::from(src.read_u8().map_err(|e| at!(Error::from(e)))?) + 1;
 
     // flags & 1 determines field size: 0 = 16-bit, 1 = 32-bit
     let (output_width, output_height) = if flags_byte & 1 == 0 {
@@ -5739,7 +11966,7 @@ fn read_grid<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
 
 /// Parse an item location box inside a meta box
 /// See ISO 14496-12:2015 § 8.11.3
-fn read_iloc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Result<TryVec<ItemLocationBoxItem>> {
+fn read_iloc<T: Read + Offset>(src: &mut BMFFBox<'_, T>, options: &ParseContext, stop: &dyn Stop) -> Result<TryVec<ItemLocationBoxItem>> {
     let version: IlocVersion = read_fullbox_version_no_flags(src, options)?.try_into()?;
 
     let iloc = src.read_into_try_vec().map_err(|e| at!(Error::from(e)))?;
@@ -5761,11 +11988,17 @@ fn read_iloc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
         IlocVersion::Zero | IlocVersion::One => iloc.read_u32(16).map_err(|e| at!(Error::from(e)))?,
         IlocVersion::Two => iloc.read_u32(32).map_err(|e| at!(Error::from(e)))?,
     };
+    options.check_item_count(u64::from(item_count))?;
 
     // Cap pre-allocation: item_count is untrusted, actual data is bounded by bitstream
     let mut items = TryVec::with_capacity(item_count.to_usize().min(4096)).map_err(|e| at!(Error::from(e)))?;
 
-    for _ in 0..item_count {
+    for index in 0..item_count {
+        // Each item may carry up to 65535 extents, so poll more often than
+        // the sample tables do
+        if index.is_multiple_of(1 << 12) {
+            stop.check().map_err(|e| at!(Error::from(e)))?;
+        }
         let item_id = match version {
             IlocVersion::Zero | IlocVersion::One => iloc.read_u32(16).map_err(|e| at!(Error::from(e)))?,
             IlocVersion::Two => iloc.read_u32(32).map_err(|e| at!(Error::from(e)))?,
@@ -5783,30 +12016,30 @@ fn read_iloc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
                 match iloc.read_u16(4).map_err(|e| at!(Error::from(e)))? {
                     0 => ConstructionMethod::File,
                     1 => ConstructionMethod::Idat,
-                    2 => return Err(at!(Error::Unsupported("construction_method 'item_offset' is not supported"))),
+                    2 => ConstructionMethod::Item,
                     _ => return Err(at!(Error::InvalidData("construction_method is taken from the set 0, 1 or 2 per ISO 14496-12:2015 § 8.11.3.3"))),
                 }
             },
         };
 
+        // Checked against `dref` by `read_avif_meta`
         let data_reference_index = iloc.read_u16(16).map_err(|e| at!(Error::from(e)))?;
 
-        if data_reference_index != 0 {
-            return Err(at!(Error::Unsupported("external file references (iloc.data_reference_index != 0) are not supported")));
-        }
-
         let base_offset = iloc.read_u64(base_offset_size.to_bits()).map_err(|e| at!(Error::from(e)))?;
         let extent_count = iloc.read_u16(16).map_err(|e| at!(Error::from(e)))?;
 
         if extent_count < 1 {
             return Err(at!(Error::InvalidData("extent_count must have a value 1 or greater per ISO 14496-12:2015 § 8.11.3.3")));
         }
+        if let Some(limit) = options.max_extents_per_item
+            && u32::from(extent_count) > limit {
+                return Err(at!(Error::ResourceLimitExceeded("extent count limit exceeded")));
+            }
 
         let mut extents = TryVec::with_capacity(extent_count.to_usize()).map_err(|e| at!(Error::from(e)))?;
 
         for _ in 0..extent_count {
-            // Parsed but currently ignored, see `ItemLocationBoxExtent`
-            let _extent_index = match &index_size {
+            let extent_index = match &index_size {
                 None | Some(IlocFieldSize::Zero) => None,
                 Some(index_size) => Some(iloc.read_u64(index_size.to_bits()).map_err(|e| at!(Error::from(e)))?),
             };
@@ -5832,10 +12065,10 @@ fn read_iloc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
                 ExtentRange::WithLength(Range { start, end })
             };
 
-            extents.push(ItemLocationBoxExtent { extent_range }).map_err(|e| at!(Error::from(e)))?;
+            extents.push(ItemLocationBoxExtent { extent_index, extent_range }).map_err(|e| at!(Error::from(e)))?;
         }
 
-        items.push(ItemLocationBoxItem { item_id, construction_method, extents }).map_err(|e| at!(Error::from(e)))?;
+        items.push(ItemLocationBoxItem { item_id, construction_method, data_reference_index, extents }).map_err(|e| at!(Error::from(e)))?;
     }
 
     if iloc.remaining() == 0 {
@@ -5845,6 +12078,174 @@ fn read_iloc<T: Read>(src: &mut BMFFBox<'_, T>, options: &ParseOptions) -> Resul
     }
 }
 
+/// Rewrite items stored with construction_method 2 ("item_offset") so their extents point
+/// straight at file or `idat` bytes. Later stages then only ever see `File`/`Idat` items.
+/// Each item is flattened once, sources before the items built from them, so shared
+/// sources are not re-resolved per reference.
+/// See ISO 14496-12:2015 § 8.11.3.1
+fn flatten_item_constructions(meta: &mut AvifInternalMeta, tracker: &ResourceTracker<'_>) -> Result<()> {
+    if !meta.iloc_items.iter().any(|item| item.construction_method == ConstructionMethod::Item) {
+        return Ok(());
+    }
+    let mut cache = alloc::vec::Vec::new();
+    cache.try_reserve(meta.iloc_items.len()).map_err(|_| at!(Error::OutOfMemory))?;
+    cache.resize_with(meta.iloc_items.len(), || FlattenSlot::Pending);
+
+    let mut total_extents = 0u64;
+    for index in 0..meta.iloc_items.len() {
+        if meta.iloc_items[index].construction_method == ConstructionMethod::Item {
+            flatten_item(meta, index, &mut cache, tracker)?;
+            if let FlattenSlot::Done(flat) = &cache[index] {
+                total_extents = total_extents.saturating_add(flat.extents.len() as u64);
+                tracker.validate_flattened_extent_count(total_extents)?;
+            }
+        }
+    }
+    for (item, slot) in meta.iloc_items.iter_mut().zip(cache) {
+        if item.construction_method != ConstructionMethod::Item {
+            continue;
+        }
+        if let FlattenSlot::Done(flat) = slot {
+            item.construction_method = flat.construction_method;
+            item.extents = flat.extents;
+        }
+    }
+    Ok(())
+}
+
+/// An item's extents resolved to `File`/`Idat` ranges, with the length of the
+/// longest `item_offset` chain below it.
+struct FlattenedItem {
+    construction_method: ConstructionMethod,
+    extents: TryVec<ItemLocationBoxExtent>,
+    depth: u32,
+}
+
+/// Per-item state while flattening; `Visiting` marks items on the current
+/// resolution path, so reaching one again means a reference cycle.
+enum FlattenSlot {
+    Pending,
+    Visiting,
+    Done(FlattenedItem),
+}
+
+/// Resolve the item at `index` in `meta.iloc_items` to the `File`/`Idat` ranges backing
+/// it, flattening its `iloc`-referenced sources first and caching every result.
+fn flatten_item(
+    meta: &AvifInternalMeta,
+    index: usize,
+    cache: &mut [FlattenSlot],
+    tracker: &ResourceTracker<'_>,
+) -> Result<()> {
+    match cache[index] {
+        FlattenSlot::Done(_) => return Ok(()),
+        FlattenSlot::Visiting => return Err(at!(Error::InvalidData("cyclic item_offset references"))),
+        FlattenSlot::Pending => {},
+    }
+    let item = &meta.iloc_items[index];
+    let mut out = TryVec::new();
+    if item.construction_method != ConstructionMethod::Item {
+        for extent in item.extents.iter() {
+            out.push(extent.clone()).map_err(|e| at!(Error::from(e)))?;
+        }
+        cache[index] = FlattenSlot::Done(FlattenedItem { construction_method: item.construction_method, extents: out, depth: 0 });
+        return Ok(());
+    }
+    cache[index] = FlattenSlot::Visiting;
+
+    let mut construction_method = None;
+    let mut depth = 1u32;
+    let mut total_len = 0u64;
+    for extent in item.extents.iter() {
+        // extent_index is 1-based; with index_size 0 the single referenced item is implied
+        let extent_index = extent.extent_index.unwrap_or(1);
+        let source_id = meta
+            .item_references
+            .iter()
+            .filter(|r| r.from_item_id == item.item_id && r.item_type == b"iloc")
+            .find(|r| u64::from(r.reference_index) + 1 == extent_index)
+            .map(|r| r.to_item_id)
+            .ok_or_else(|| at!(Error::InvalidData("item_offset extent_index has no matching iloc reference")))?;
+        let source_index = meta
+            .iloc_items
+            .iter()
+            .position(|l| l.item_id == source_id)
+            .ok_or_else(|| at!(Error::InvalidData("item_offset source item not found in iloc")))?;
+        flatten_item(meta, source_index, cache, tracker)?;
+        let FlattenSlot::Done(source) = &cache[source_index] else {
+            return Err(at!(Error::InvalidData("cyclic item_offset references")));
+        };
+        depth = depth.max(source.depth.saturating_add(1));
+        tracker.validate_item_construction_depth(depth)?;
+        match construction_method {
+            None => construction_method = Some(source.construction_method),
+            Some(method) if method != source.construction_method => {
+                return Err(at!(Error::Unsupported("item_offset sources mixing file and idat storage are not supported")));
+            },
+            Some(_) => {},
+        }
+
+        // Select [offset, offset + length) from the concatenated source extents
+        let (mut skip, mut remaining) = match &extent.extent_range {
+            ExtentRange::WithLength(r) => (r.start, Some(r.end - r.start)),
+            ExtentRange::ToEnd(r) => (r.start, None),
+        };
+        for source_extent in source.extents.iter() {
+            if remaining == Some(0) {
+                break;
+            }
+            let extent_range = match &source_extent.extent_range {
+                ExtentRange::WithLength(r) => {
+                    let len = r.end - r.start;
+                    if skip >= len {
+                        skip -= len;
+                        continue;
+                    }
+                    let start = r.start + skip;
+                    let take = remaining.map_or(len - skip, |rem| rem.min(len - skip));
+                    skip = 0;
+                    if let Some(rem) = remaining.as_mut() {
+                        *rem -= take;
+                    }
+                    total_len = total_len.saturating_add(take);
+                    ExtentRange::WithLength(start..start + take)
+                },
+                ExtentRange::ToEnd(r) => {
+                    // An open-ended source extent runs to the end of its container
+                    let start = r.start
+                        .checked_add(skip)
+                        .ok_or_else(|| at!(Error::InvalidData("offset calculation overflow")))?;
+                    skip = 0;
+                    match remaining.take() {
+                        Some(rem) => {
+                            total_len = total_len.saturating_add(rem);
+                            remaining = Some(0);
+                            let end = start
+                                .checked_add(rem)
+                                .ok_or_else(|| at!(Error::InvalidData("end calculation overflow")))?;
+                            ExtentRange::WithLength(start..end)
+                        },
+                        None => ExtentRange::ToEnd(start..),
+                    }
+                },
+            };
+            if out.len() >= usize::from(u16::MAX) {
+                return Err(at!(Error::InvalidData("item_offset construction yields too many extents")));
+            }
+            out.push(ItemLocationBoxExtent { extent_index: None, extent_range }).map_err(|e| at!(Error::from(e)))?;
+        }
+        if skip > 0 || remaining.is_some_and(|rem| rem > 0) {
+            return Err(at!(Error::InvalidData("item_offset extent exceeds source item data")));
+        }
+        tracker.validate_constructed_item_size(total_len)?;
+    }
+
+    let construction_method = construction_method
+        .ok_or_else(|| at!(Error::InvalidData("item_offset item has no extents")))?;
+    cache[index] = FlattenSlot::Done(FlattenedItem { construction_method, extents: out, depth });
+    Ok(())
+}
+
 /// Parse an ftyp box.
 /// See ISO 14496-12:2015 § 4.3
 fn read_ftyp<T: Read>(src: &mut BMFFBox<'_, T>) -> Result<FileTypeBox> {
@@ -5880,8 +12281,16 @@ fn check_parser_state<T>(header: &BoxHeader, left: &Take<T>) -> Result<()> {
 
 /// Skip a number of bytes that we don't care to parse.
 fn skip<T: Read>(src: &mut T, bytes: u64) -> Result<()> {
-    std::io::copy(&mut src.take(bytes), &mut std::io::sink()).map_err(|e| at!(Error::from(e)))?;
-    Ok(())
+    let mut src = src.take(bytes);
+    let mut scratch = [0; 8 * 1024];
+    loop {
+        match src.read(&mut scratch) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {},
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(at!(Error::from(e))),
+        }
+    }
 }
 
 fn be_u16<T: ReadBytesExt>(src: &mut T) -> Result<u16> {
@@ -5913,7 +12322,7 @@ mod sample_offset_overflow_tests {
     fn skip_box_content_accepts_size_zero_extends_to_eof() {
         // 'free' box: size=16, 8 payload bytes; then 'mdat' with
         // size32=0 (extends to EOF) and 5 payload bytes.
-        let mut bytes = std::vec::Vec::new();
+        let mut bytes = alloc::vec::Vec::new();
         bytes.extend_from_slice(&16u32.to_be_bytes());
         bytes.extend_from_slice(b"free");
         bytes.extend_from_slice(&[0u8; 8]);
@@ -5921,7 +12330,7 @@ mod sample_offset_overflow_tests {
         bytes.extend_from_slice(b"mdat");
         bytes.extend_from_slice(&[1, 2, 3, 4, 5]);
 
-        let mut cursor = std::io::Cursor::new(bytes.as_slice());
+        let mut cursor = io::Cursor::new(bytes.as_slice());
         let total = bytes.len() as u64;
         let mut iter = super::BoxIter::with_max_remaining(&mut cursor, total);
 
@@ -5930,6 +12339,7 @@ mod sample_offset_overflow_tests {
         drop(free);
 
         let mut mdat = iter.next_box().expect("iter").expect("mdat box");
+        assert_eq!(mdat.head.size, 13, "bounded by the input length");
         assert_eq!(mdat.bytes_left(), 5, "clamped to the real remainder");
         super::skip_box_content(&mut mdat)
             .expect("size=0 (extends-to-EOF) box must skip cleanly");
@@ -5982,9 +12392,12 @@ mod sample_offset_overflow_tests {
         let mut sample_sizes = TryVec::new();
         sample_sizes.push(10u32).unwrap(); // MAX-5 + 10 wraps
         let sample_table = SampleTable {
-            time_to_sample: TryVec::new(),
             sample_sizes: SampleSizes::Variable(sample_sizes),
             sample_offsets,
+            sync_samples: None,
+            presentation_order: TryVec::new(),
+            sample_times: TryVec::new(),
+            media_duration: 0,
         };
         let mut mdats: [MediaDataBox; 0] = [];
         let result = extract_animation_frames(&sample_table, 1, &mut mdats[..]);
@@ -5995,6 +12408,53 @@ mod sample_offset_overflow_tests {
         }
     }
 
+    /// Sample timing folds stts runs, signed ctts offsets and stss into one
+    /// entry per sample; samples past the stts table get duration 0.
+    #[test]
+    fn sample_times_flatten_stts_ctts_and_stss() {
+        let mut stts = TryVec::new();
+        stts.push(TimeToSampleEntry { sample_count: 3, sample_delta: 10 }).unwrap();
+        let mut ctts = TryVec::new();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: 0 }).unwrap();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: 20 }).unwrap();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: -10 }).unwrap();
+        let mut stss = TryVec::new();
+        stss.push(1u32).unwrap();
+        stss.push(4u32).unwrap();
+
+        let (times, total) = precompute_sample_times(&stts, &ctts, Some(&stss), 4, &Unstoppable).unwrap();
+        let times: alloc::vec::Vec<_> = times.iter().map(|t| (t.presentation_time, t.duration, t.is_sync)).collect();
+        assert_eq!(times, [(0, 10, true), (30, 10, false), (10, 10, false), (30, 0, true)]);
+        assert_eq!(total, 30);
+    }
+
+    /// With `ctts`, the sample shown at a time is found through the
+    /// presentation-order index rather than a scan.
+    #[test]
+    fn sample_at_follows_presentation_order() {
+        let mut stts = TryVec::new();
+        stts.push(TimeToSampleEntry { sample_count: 3, sample_delta: 10 }).unwrap();
+        let mut ctts = TryVec::new();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: 10 }).unwrap();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: 20 }).unwrap();
+        ctts.push(CompositionOffsetEntry { sample_count: 1, sample_offset: -10 }).unwrap();
+        let (sample_times, media_duration) = precompute_sample_times(&stts, &ctts, None, 3, &Unstoppable).unwrap();
+        let table = SampleTable {
+            sample_sizes: SampleSizes::Constant { size: 1, count: 3 },
+            sample_offsets: TryVec::new(),
+            sync_samples: None,
+            presentation_order: precompute_presentation_order(&sample_times).unwrap(),
+            sample_times,
+            media_duration,
+        };
+        // Presentation times are 10, 30 and 10
+        assert_eq!(&table.presentation_order[..], &[0, 2, 1]);
+        assert_eq!(table.sample_at(5), 0);
+        assert_eq!(table.sample_at(10), 2);
+        assert_eq!(table.sample_at(29), 2);
+        assert_eq!(table.sample_at(30), 1);
+    }
+
     /// Sanity: a non-malicious table still computes offsets correctly.
     #[test]
     fn benign_offsets_compute_correctly() {
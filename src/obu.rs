@@ -1,11 +1,14 @@
 #![allow(unused)]
 #![allow(bad_style)]
 
-use crate::{ChromaSubsampling, Error, Result};
+use crate::{
+    AV1HdrMetadata, AV1Timecode, ChromaSubsampling, ContentLightLevel, Error, ItutT35, MasteringDisplayColourVolume,
+    Result,
+};
 use whereat::at;
 
 use bitreader::BitReader;
-use std::num::{NonZeroU8, NonZeroU32};
+use core::num::{NonZeroU8, NonZeroU32};
 
 #[derive(Debug, Clone)]
 struct Header {
@@ -39,6 +42,367 @@ fn get_byte(data: &mut &[u8]) -> Result<u8> {
     Ok(b)
 }
 
+/// The type of an [`Obu`], from its `obu_type` field (AV1 § 6.2.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ObuType {
+    /// `OBU_SEQUENCE_HEADER` (1)
+    SequenceHeader,
+    /// `OBU_TEMPORAL_DELIMITER` (2)
+    TemporalDelimiter,
+    /// `OBU_FRAME_HEADER` (3)
+    FrameHeader,
+    /// `OBU_TILE_GROUP` (4)
+    TileGroup,
+    /// `OBU_METADATA` (5)
+    Metadata,
+    /// `OBU_FRAME` (6): a frame header followed by its tile group
+    Frame,
+    /// `OBU_REDUNDANT_FRAME_HEADER` (7)
+    RedundantFrameHeader,
+    /// `OBU_TILE_LIST` (8)
+    TileList,
+    /// `OBU_PADDING` (15)
+    Padding,
+    /// A reserved type; decoders ignore these.
+    Reserved(u8),
+}
+
+impl ObuType {
+    fn from_bits(obu_type: u8) -> Self {
+        match obu_type {
+            1 => Self::SequenceHeader,
+            2 => Self::TemporalDelimiter,
+            3 => Self::FrameHeader,
+            4 => Self::TileGroup,
+            5 => Self::Metadata,
+            6 => Self::Frame,
+            7 => Self::RedundantFrameHeader,
+            8 => Self::TileList,
+            15 => Self::Padding,
+            other => Self::Reserved(other),
+        }
+    }
+}
+
+/// One open bitstream unit, borrowed from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Obu<'a> {
+    /// What the OBU contains.
+    pub obu_type: ObuType,
+    /// `temporal_id` from the extension header, 0 without one.
+    pub temporal_id: u8,
+    /// `spatial_id` from the extension header, 0 without one.
+    pub spatial_id: u8,
+    /// The OBU payload, after the header and size field.
+    pub payload: &'a [u8],
+    /// The whole OBU as stored: header, optional size field and payload.
+    pub bytes: &'a [u8],
+}
+
+/// Iterator over the OBUs of an AV1 bitstream.
+///
+/// [`new`](Self::new) reads the low-overhead format used in AVIF and
+/// ISOBMFF samples (AV1 § 5.2); [`annex_b`](Self::annex_b) reads the
+/// length-delimited format of AV1 Annex B. A malformed OBU yields one error
+/// and ends the iteration.
+///
+/// ```
+/// use zenavif_parse::{ObuIter, ObuType};
+///
+/// # fn run(temporal_unit: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+/// // Drop temporal delimiters and padding before handing the payload on
+/// let mut filtered = Vec::new();
+/// for obu in ObuIter::new(temporal_unit) {
+///     let obu = obu?;
+///     if !matches!(obu.obu_type, ObuType::TemporalDelimiter | ObuType::Padding) {
+///         filtered.extend_from_slice(obu.bytes);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ObuIter<'a> {
+    data: &'a [u8],
+    annex_b: bool,
+    temporal_unit: &'a [u8],
+    frame_unit: &'a [u8],
+    failed: bool,
+}
+
+impl<'a> ObuIter<'a> {
+    /// Iterate over a low-overhead bitstream, such as an AVIF item or sample.
+    /// An OBU without a size field extends to the end of `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, annex_b: false, temporal_unit: &[], frame_unit: &[], failed: false }
+    }
+
+    /// Iterate over an Annex B bitstream: temporal units, frame units and
+    /// OBUs each prefixed with their `leb128` length.
+    pub fn annex_b(data: &'a [u8]) -> Self {
+        Self { data, annex_b: true, temporal_unit: &[], frame_unit: &[], failed: false }
+    }
+
+    fn next_obu(&mut self) -> Result<Option<Obu<'a>>> {
+        if !self.annex_b {
+            if self.data.is_empty() {
+                return Ok(None);
+            }
+            return read_obu(&mut self.data).map(Some);
+        }
+
+        // temporal_unit(temporal_unit_size) > frame_unit(frame_unit_size) > obu_length, per AV1 § B.2
+        loop {
+            if !self.frame_unit.is_empty() {
+                let obu_length = read_leb128(&mut self.frame_unit)?;
+                let mut data = take(&mut self.frame_unit, obu_length)?;
+                let obu = read_obu(&mut data)?;
+                if !data.is_empty() {
+                    return Err(at!(Error::InvalidData("OBU size does not match obu_length")));
+                }
+                return Ok(Some(obu));
+            }
+            if !self.temporal_unit.is_empty() {
+                let frame_unit_size = read_leb128(&mut self.temporal_unit)?;
+                self.frame_unit = take(&mut self.temporal_unit, frame_unit_size)?;
+                continue;
+            }
+            if self.data.is_empty() {
+                return Ok(None);
+            }
+            let temporal_unit_size = read_leb128(&mut self.data)?;
+            self.temporal_unit = take(&mut self.data, temporal_unit_size)?;
+        }
+    }
+}
+
+impl<'a> Iterator for ObuIter<'a> {
+    type Item = Result<Obu<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.next_obu();
+        self.failed = next.is_err();
+        next.transpose()
+    }
+}
+
+impl core::iter::FusedIterator for ObuIter<'_> {}
+
+/// Read one OBU (AV1 § 5.3.1), which must fit in `data`.
+fn read_obu<'a>(data: &mut &'a [u8]) -> Result<Obu<'a>> {
+    let start = *data;
+    let b = get_byte(data)?;
+    if 0 != b & 0b1000_0000 {
+        return Err(at!(Error::InvalidData("not obu")));
+    }
+    let obu_type = ObuType::from_bits((b >> 3) & 0x0F);
+    let (temporal_id, spatial_id) = if 0 != (b & 0b100) {
+        let ext = get_byte(data)?;
+        (ext >> 5, (ext >> 3) & 0b11)
+    } else {
+        (0, 0)
+    };
+    let payload = if 0 != (b & 0b010) {
+        let obu_size = read_leb128(data)?;
+        take(data, obu_size)?
+    } else {
+        core::mem::take(data)
+    };
+    let bytes = &start[..start.len() - data.len()];
+    Ok(Obu { obu_type, temporal_id, spatial_id, payload, bytes })
+}
+
+/// Read an unsigned LEB128 value of at most 64 bits (AV1 § 4.10.5).
+fn read_leb128(data: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let byte = get_byte(data).map_err(|_| at!(Error::InvalidData("leb")))?;
+        let bits = u64::from(byte & 0x7F);
+        // The tenth byte holds only the 64th bit
+        if i == 9 && bits > 1 {
+            return Err(at!(Error::InvalidData("leb")));
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(at!(Error::InvalidData("leb")))
+}
+
+/// Split `len` bytes off the front of `data`.
+fn take<'a>(data: &mut &'a [u8], len: u64) -> Result<&'a [u8]> {
+    let len = usize::try_from(len).map_err(|_| at!(Error::UnexpectedEOF))?;
+    if len > data.len() {
+        return Err(at!(Error::UnexpectedEOF));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+/// Re-frame `data` as low-overhead OBUs, each with a size field, if it is a
+/// well-formed, non-empty Annex B bitstream; `None` otherwise.
+pub(crate) fn annex_b_to_low_overhead(data: &[u8]) -> Result<Option<alloc::vec::Vec<u8>>> {
+    let mut out = alloc::vec::Vec::new();
+    for obu in ObuIter::annex_b(data) {
+        let Ok(obu) = obu else {
+            return Ok(None);
+        };
+        let header_len = 1 + usize::from(0 != obu.bytes[0] & 0b100);
+        out.try_reserve(header_len + 10 + obu.payload.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        out.push(obu.bytes[0] | 0b010);
+        out.extend_from_slice(&obu.bytes[1..header_len]);
+        let mut size = obu.payload.len();
+        loop {
+            let byte = (size & 0x7F) as u8;
+            size >>= 7;
+            if size == 0 {
+                out.push(byte);
+                break;
+            }
+            out.push(byte | 0x80);
+        }
+        out.extend_from_slice(obu.payload);
+    }
+    Ok(if out.is_empty() { None } else { Some(out) })
+}
+
+const METADATA_TYPE_HDR_CLL: u64 = 1;
+const METADATA_TYPE_HDR_MDCV: u64 = 2;
+const METADATA_TYPE_ITUT_T35: u64 = 4;
+const METADATA_TYPE_TIMECODE: u64 = 5;
+
+/// Metadata OBUs found in a bitstream.
+#[derive(Debug, Default)]
+pub(crate) struct MetadataObus {
+    pub hdr: AV1HdrMetadata,
+    pub timecode: Option<AV1Timecode>,
+}
+
+/// Decode the HDR and timecode metadata OBUs of `data` (AV1 § 5.8), best
+/// effort: a malformed OBU is skipped and scanning stops at the first
+/// framing error. The first OBU of each type wins, except T.35 payloads,
+/// which are all kept.
+pub(crate) fn parse_metadata_obus(data: &[u8]) -> MetadataObus {
+    let mut found = MetadataObus::default();
+    for obu in ObuIter::new(data).map_while(|obu| obu.ok()) {
+        if obu.obu_type != ObuType::Metadata {
+            continue;
+        }
+        let mut payload = obu.payload;
+        let Ok(metadata_type) = read_leb128(&mut payload) else {
+            continue;
+        };
+        let parsed = match metadata_type {
+            METADATA_TYPE_HDR_CLL if found.hdr.content_light_level.is_none() => {
+                read_hdr_cll(payload).map(|cll| found.hdr.content_light_level = Some(cll))
+            }
+            METADATA_TYPE_HDR_MDCV if found.hdr.mastering_display.is_none() => {
+                read_hdr_mdcv(payload).map(|mdcv| found.hdr.mastering_display = Some(mdcv))
+            }
+            METADATA_TYPE_ITUT_T35 => read_itut_t35(payload).and_then(|t35| {
+                found.hdr.itut_t35.try_reserve(1).map_err(|_| at!(Error::OutOfMemory))?;
+                found.hdr.itut_t35.push(t35);
+                Ok(())
+            }),
+            METADATA_TYPE_TIMECODE if found.timecode.is_none() => {
+                read_timecode(payload).map(|timecode| found.timecode = Some(timecode))
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = parsed {
+            log::warn!("skipping malformed metadata OBU of type {metadata_type}: {e:?}");
+        }
+    }
+    found
+}
+
+/// `metadata_hdr_cll()`: MaxCLL and MaxFALL in cd/m².
+fn read_hdr_cll(data: &[u8]) -> Result<ContentLightLevel> {
+    let mut b = BitReader::new(data);
+    Ok(ContentLightLevel {
+        max_content_light_level: b.read_u16(16).map_err(|e| at!(Error::from(e)))?,
+        max_pic_average_light_level: b.read_u16(16).map_err(|e| at!(Error::from(e)))?,
+    })
+}
+
+/// `metadata_hdr_mdcv()`, converted from AV1's fixed-point fields to the
+/// units and green/blue/red primary order of the `mdcv` box.
+fn read_hdr_mdcv(data: &[u8]) -> Result<MasteringDisplayColourVolume> {
+    let mut b = BitReader::new(data);
+    // 0.16 fixed point to 0.00002 units
+    let mut chromaticity = || -> Result<u16> {
+        let v = u32::from(b.read_u16(16).map_err(|e| at!(Error::from(e)))?);
+        Ok(((v * 50_000 + 32_768) / 65_536) as u16)
+    };
+    let mut xy = || -> Result<(u16, u16)> { Ok((chromaticity()?, chromaticity()?)) };
+    // AV1 lists red, green, blue
+    let (red, green, blue) = (xy()?, xy()?, xy()?);
+    let white_point = xy()?;
+    // 24.8 and 18.14 fixed point to 0.0001 cd/m²
+    let max = u64::from(b.read_u32(32).map_err(|e| at!(Error::from(e)))?);
+    let min = u64::from(b.read_u32(32).map_err(|e| at!(Error::from(e)))?);
+    Ok(MasteringDisplayColourVolume {
+        primaries: [green, blue, red],
+        white_point,
+        max_luminance: u32::try_from((max * 10_000 + 128) / 256).unwrap_or(u32::MAX),
+        min_luminance: u32::try_from((min * 10_000 + 8_192) / 16_384).unwrap_or(u32::MAX),
+    })
+}
+
+/// `metadata_itut_t35()`: country code and the payload bytes before the OBU's trailing bits.
+fn read_itut_t35(mut data: &[u8]) -> Result<ItutT35> {
+    let country_code = get_byte(&mut data)?;
+    let country_code_extension = if country_code == 0xFF { Some(get_byte(&mut data)?) } else { None };
+    let end = data.iter().rposition(|&b| b != 0).ok_or_else(|| at!(Error::InvalidData("T.35 metadata without trailing bits")))?;
+    if data[end] != 0x80 {
+        return Err(at!(Error::InvalidData("T.35 metadata without trailing bits")));
+    }
+    let mut payload = alloc::vec::Vec::new();
+    payload.try_reserve_exact(end).map_err(|_| at!(Error::OutOfMemory))?;
+    payload.extend_from_slice(&data[..end]);
+    Ok(ItutT35 { country_code, country_code_extension, payload })
+}
+
+/// `metadata_timecode()`
+fn read_timecode(data: &[u8]) -> Result<AV1Timecode> {
+    let mut b = BitReader::new(data);
+    let counting_type = b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
+    let full_timestamp_flag = b.read_bool().map_err(|e| at!(Error::from(e)))?;
+    let discontinuity = b.read_bool().map_err(|e| at!(Error::from(e)))?;
+    let dropped_frames = b.read_bool().map_err(|e| at!(Error::from(e)))?;
+    let n_frames = b.read_u16(9).map_err(|e| at!(Error::from(e)))?;
+    // With full_timestamp_flag all three are present; otherwise each has its own flag
+    let mut field = |bits: u8| -> Result<Option<u8>> {
+        if full_timestamp_flag || b.read_bool().map_err(|e| at!(Error::from(e)))? {
+            return Ok(Some(b.read_u8(bits).map_err(|e| at!(Error::from(e)))?));
+        }
+        Ok(None)
+    };
+    let (seconds, minutes, hours) = if full_timestamp_flag {
+        (field(6)?, field(6)?, field(5)?)
+    } else {
+        // Minutes are only signaled after seconds, hours only after minutes
+        let seconds = field(6)?;
+        let minutes = if seconds.is_some() { field(6)? } else { None };
+        let hours = if minutes.is_some() { field(5)? } else { None };
+        (seconds, minutes, hours)
+    };
+    let time_offset_length = b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
+    let time_offset = if time_offset_length > 0 {
+        Some(b.read_u32(time_offset_length).map_err(|e| at!(Error::from(e)))?)
+    } else {
+        None
+    };
+    Ok(AV1Timecode { counting_type, discontinuity, dropped_frames, n_frames, seconds, minutes, hours, time_offset })
+}
+
 const INTRA_FRAME: usize = 0;
 const LAST_FRAME: usize = 1;
 const LAST2_FRAME: usize = 2;
@@ -53,6 +417,20 @@ pub(crate) fn parse_obu(mut data: &[u8]) -> Result<SequenceHeaderObu> {
     Ok(seq)
 }
 
+/// Parse the first sequence header in `data`, which only needs to extend
+/// that far: the rest of the temporal unit may be cut off.
+pub(crate) fn parse_leading_sequence_header(mut data: &[u8]) -> Result<SequenceHeaderObu> {
+    while !data.is_empty() {
+        let h = obu_header(&mut data)?;
+        let remaining_data = data.get(..h.obu_size).ok_or_else(|| at!(Error::UnexpectedEOF))?;
+        if h.is_sequence_header() {
+            return SequenceHeaderObu::read(remaining_data);
+        }
+        data = &data[h.obu_size..];
+    }
+    Err(at!(Error::UnexpectedEOF))
+}
+
 /// Parse OBUs to extract both the sequence header and (optionally) frame quantization info.
 ///
 /// Scans OBUs looking for a sequence header first, then attempts to parse the
@@ -99,7 +477,7 @@ impl SequenceHeaderObu {
         let reduced_still_picture_header = b.read_bool().map_err(|e| at!(Error::from(e)))?;
 
         let decoder_model_info_present_flag = false;
-        read_operating_points(
+        let (seq_level_idx, seq_tier) = read_operating_points(
             &mut b,
             reduced_still_picture_header,
             decoder_model_info_present_flag,
@@ -123,6 +501,8 @@ impl SequenceHeaderObu {
         Ok(Self {
             color,
             seq_profile,
+            seq_level_idx,
+            seq_tier,
             still_picture,
             reduced_still_picture_header,
             max_frame_width: frame_dims.max_width,
@@ -152,17 +532,18 @@ impl SequenceHeaderObu {
     }
 }
 
-/// Per-operating-point fields read for their side effect on the bit position.
-/// Errors propagate exactly as the inline code did (timing info / decoder
-/// model trigger `Unsupported`).
+/// Per-operating-point fields, returning `seq_level_idx` and `seq_tier` of
+/// operating point 0; the rest are read for their side effect on the bit
+/// position. Errors propagate exactly as the inline code did (timing info /
+/// decoder model trigger `Unsupported`).
 fn read_operating_points(
     b: &mut BitReader,
     reduced_still_picture_header: bool,
     decoder_model_info_present_flag: bool,
-) -> Result<()> {
+) -> Result<(u8, u8)> {
     if reduced_still_picture_header {
-        let _seq_level_idx = b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
-        return Ok(());
+        let seq_level_idx = b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
+        return Ok((seq_level_idx, 0));
     }
 
     let timing_info_present_flag = b.read_bool().map_err(|e| at!(Error::from(e)))?;
@@ -172,10 +553,12 @@ fn read_operating_points(
     let initial_display_delay_present_flag = b.read_bool().map_err(|e| at!(Error::from(e)))?;
     let operating_points_cnt = 1 + b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
 
+    let mut first = None;
     for _ in 0..operating_points_cnt {
         let _operating_point_idc = b.read_u16(12).map_err(|e| at!(Error::from(e)))?;
         let seq_level_idx = b.read_u8(5).map_err(|e| at!(Error::from(e)))?;
-        let _seq_tier = if seq_level_idx > 7 { b.read_bool().map_err(|e| at!(Error::from(e)))? } else { false };
+        let seq_tier = if seq_level_idx > 7 { b.read_u8(1).map_err(|e| at!(Error::from(e)))? } else { 0 };
+        first.get_or_insert((seq_level_idx, seq_tier));
         if decoder_model_info_present_flag {
             b.read_bool().map_err(|e| at!(Error::from(e)))?;
             return Err(at!(Error::Unsupported("decoder_model_info_present_flag")));
@@ -187,7 +570,7 @@ fn read_operating_points(
             }
         }
     }
-    Ok(())
+    Ok(first.unwrap_or_default())
 }
 
 struct MaxFrameDims {
@@ -290,6 +673,10 @@ pub(crate) struct SequenceHeaderObu {
     pub color: ColorConfig,
 
     pub seq_profile: u8,
+    /// `seq_level_idx` of operating point 0.
+    pub seq_level_idx: u8,
+    /// `seq_tier` of operating point 0.
+    pub seq_tier: u8,
     pub still_picture: bool,
     pub reduced_still_picture_header: bool,
 
@@ -747,8 +1134,7 @@ fn obu_header(data: &mut &[u8]) -> Result<Header> {
     }
 
     let obu_size = if obu_has_size_field {
-        leb128::read::unsigned(data)
-            .map_err(|_| at!(Error::InvalidData("leb")))?
+        read_leb128(data)?
             .try_into()
             .map_err(|_| at!(Error::UnexpectedEOF))?
     } else {
@@ -0,0 +1,305 @@
+//! Push-based incremental parsing for progressive downloads.
+
+use crate::boxes::BoxType;
+use crate::{
+    AvifParser, BoxHeader, ConstructionMethod, DecodeConfig, Error, ExtentRange, ImageSpatialExtents, ItemProperty,
+    ParsedStructure, Result, ToU64, Unstoppable, is_padding_box, property_for, read_box_header,
+};
+use whereat::at;
+
+/// Progress reported by [`AvifPushParser::push`].
+pub enum ParseProgress {
+    /// More input is needed. The value is a lower bound on the number of
+    /// additional bytes required before the next step can complete (1 when
+    /// the amount is not yet known).
+    NeedMoreData(usize),
+    /// All structural boxes (`ftyp`, `meta`, and `moov` for sequences) have
+    /// arrived; [`AvifPushParser::major_brand`] and
+    /// [`AvifPushParser::spatial_extents`] are now available. Item or frame
+    /// data is still missing.
+    MetadataReady,
+    /// Everything the file's items and frames reference has arrived.
+    Complete(alloc::boxed::Box<AvifParser<'static>>),
+}
+
+impl core::fmt::Debug for ParseProgress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NeedMoreData(bytes) => f.debug_tuple("NeedMoreData").field(bytes).finish(),
+            Self::MetadataReady => f.write_str("MetadataReady"),
+            // The parser itself has no Debug representation
+            Self::Complete(_) => f.write_str("Complete(..)"),
+        }
+    }
+}
+
+/// Incremental AVIF parser fed with chunks as they arrive.
+///
+/// Chunks are buffered; top-level boxes are scanned as they complete, so the
+/// brand and primary image dimensions are known as soon as the `meta` box
+/// has been received, typically long before the `mdat` payload.
+///
+/// ```
+/// use zenavif_parse::{AvifPushParser, ParseProgress};
+///
+/// # fn run(chunks: &[&[u8]]) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut push = AvifPushParser::new();
+/// for chunk in chunks {
+///     match push.push(chunk)? {
+///         ParseProgress::NeedMoreData(_) => {}
+///         ParseProgress::MetadataReady => println!("size: {:?}", push.spatial_extents()),
+///         ParseProgress::Complete(parser) => {
+///             let _primary = parser.primary_data()?;
+///             break;
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AvifPushParser {
+    config: DecodeConfig,
+    buf: alloc::vec::Vec<u8>,
+    /// File offset of the next top-level box header to scan.
+    next_box: u64,
+    major_brand: Option<[u8; 4]>,
+    seen_meta: bool,
+    seen_moov: bool,
+    /// Set once the structural boxes have been parsed.
+    metadata: Option<PushMetadata>,
+    complete: bool,
+}
+
+struct PushMetadata {
+    spatial_extents: Option<ImageSpatialExtents>,
+    /// File length needed for all referenced data, if it could be determined.
+    required_len: Option<u64>,
+}
+
+impl Default for AvifPushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AvifPushParser {
+    /// Create a push parser with default resource limits.
+    pub fn new() -> Self {
+        Self::with_config(DecodeConfig::default())
+    }
+
+    /// Create a push parser with resource limits.
+    ///
+    /// `config.max_input_bytes` and `config.peak_memory_limit` cap the total
+    /// buffered input.
+    pub fn with_config(config: DecodeConfig) -> Self {
+        Self {
+            config,
+            buf: alloc::vec::Vec::new(),
+            next_box: 0,
+            major_brand: None,
+            seen_meta: false,
+            seen_moov: false,
+            metadata: None,
+            complete: false,
+        }
+    }
+
+    /// The `ftyp` major brand, once the `ftyp` box has arrived.
+    pub fn major_brand(&self) -> Option<[u8; 4]> {
+        self.major_brand
+    }
+
+    /// Whether all structural boxes have arrived and been parsed.
+    pub fn is_metadata_ready(&self) -> bool {
+        self.metadata.is_some()
+    }
+
+    /// The primary item's `ispe` dimensions, once metadata is ready.
+    pub fn spatial_extents(&self) -> Option<&ImageSpatialExtents> {
+        self.metadata.as_ref().and_then(|m| m.spatial_extents.as_ref())
+    }
+
+    /// Number of bytes buffered so far.
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Feed the next chunk of the file.
+    ///
+    /// Returns [`ParseProgress::MetadataReady`] once, when the structural
+    /// boxes complete, unless the referenced data has also fully arrived, in
+    /// which case [`ParseProgress::Complete`] is returned directly. Pushing
+    /// after completion is an error.
+    pub fn push(&mut self, data: &[u8]) -> Result<ParseProgress> {
+        if self.complete {
+            return Err(at!(Error::InvalidData("push parser already complete")));
+        }
+        let total = self.buf.len().to_u64().saturating_add(data.len().to_u64());
+        self.config.check_input_len(total)?;
+        self.buf.try_reserve(data.len()).map_err(|_| at!(Error::OutOfMemory))?;
+        self.buf.extend_from_slice(data);
+
+        if self.metadata.is_none() {
+            if let Some(needed) = self.scan_boxes()? {
+                return Ok(ParseProgress::NeedMoreData(needed));
+            }
+            let metadata = self.parse_metadata()?;
+            let ready = metadata.required_len.is_some_and(|len| len <= self.buf.len().to_u64());
+            self.metadata = Some(metadata);
+            if !ready {
+                return Ok(ParseProgress::MetadataReady);
+            }
+        }
+
+        match self.metadata.as_ref().and_then(|m| m.required_len) {
+            Some(len) if len > self.buf.len().to_u64() => {
+                Ok(ParseProgress::NeedMoreData(usize::try_from(len - self.buf.len().to_u64()).unwrap_or(usize::MAX)))
+            }
+            Some(_) => {
+                self.complete = true;
+                let buf = core::mem::take(&mut self.buf);
+                let parser = AvifParser::from_owned_with_config(buf, &self.config, &Unstoppable)?;
+                Ok(ParseProgress::Complete(alloc::boxed::Box::new(parser)))
+            }
+            None => Ok(ParseProgress::NeedMoreData(1)),
+        }
+    }
+
+    /// Parse whatever has been buffered, at the end of the stream.
+    ///
+    /// Use this when the input ends without [`ParseProgress::Complete`], e.g.
+    /// for files whose last box has size 0 ("extends to end of file"). Input
+    /// that stops inside a box is only accepted with
+    /// [`DecodeConfig::allow_truncated`].
+    pub fn finish(self) -> Result<AvifParser<'static>> {
+        if self.complete {
+            return Err(at!(Error::InvalidData("push parser already complete")));
+        }
+        AvifParser::from_owned_with_config(self.buf, &self.config, &Unstoppable)
+    }
+
+    /// Scan complete top-level boxes until the structural ones are in.
+    ///
+    /// Returns `Some(bytes)` while more input is needed, `None` once metadata
+    /// can be parsed from `buf[..next_box]`.
+    fn scan_boxes(&mut self) -> Result<Option<usize>> {
+        loop {
+            if self.is_structure_complete() {
+                return Ok(None);
+            }
+            let start = usize::try_from(self.next_box).map_err(|e| at!(Error::from(e)))?;
+            let available = &self.buf[start.min(self.buf.len())..];
+            let head = match peek_box_header(available)? {
+                Ok(head) => head,
+                Err(needed) => return Ok(Some(needed)),
+            };
+            // Padding boxes before `ftyp` are left for `parse_raw` to tolerate or reject
+            let is_first = self.major_brand.is_none();
+            if is_first && head.name != BoxType::FileTypeBox && !is_padding_box(head.name) {
+                return Err(at!(Error::InvalidData("'ftyp' box must occur first")));
+            }
+            // A sequence without `meta` is complete once its media data starts
+            if head.name == BoxType::MediaDataBox && self.is_sequence() && self.seen_moov {
+                return Ok(None);
+            }
+            if head.size == u64::MAX {
+                // size=0 extends to the end of the file, which is unknown until `finish()`
+                return Ok(Some(1));
+            }
+
+            let structural = matches!(head.name, BoxType::FileTypeBox | BoxType::MetadataBox | BoxType::MovieBox);
+            if structural {
+                if let Some(limit) = self.config.peak_memory_limit
+                    && head.size > limit {
+                        return Err(at!(Error::ResourceLimitExceeded("peak memory limit exceeded")));
+                    }
+                let have = available.len().to_u64();
+                if have < head.size {
+                    return Ok(Some(usize::try_from(head.size - have).unwrap_or(usize::MAX)));
+                }
+            }
+
+            match head.name {
+                BoxType::FileTypeBox if is_first => {
+                    let brand_at = usize::try_from(head.offset).map_err(|e| at!(Error::from(e)))?;
+                    let brand = available.get(brand_at..brand_at + 4).ok_or_else(|| at!(Error::InvalidData("ftyp too short")))?;
+                    self.major_brand = Some([brand[0], brand[1], brand[2], brand[3]]);
+                }
+                BoxType::MetadataBox => self.seen_meta = true,
+                BoxType::MovieBox => self.seen_moov = true,
+                _ => {}
+            }
+            self.next_box = self.next_box.checked_add(head.size)
+                .ok_or_else(|| at!(Error::InvalidData("box size overflow")))?;
+        }
+    }
+
+    fn is_sequence(&self) -> bool {
+        self.major_brand == Some(*b"avis")
+    }
+
+    fn is_structure_complete(&self) -> bool {
+        self.seen_meta && (!self.is_sequence() || self.seen_moov)
+    }
+
+    /// Parse the structural prefix and work out how much of the file it references.
+    fn parse_metadata(&self) -> Result<PushMetadata> {
+        let prefix_len = usize::try_from(self.next_box).map_err(|e| at!(Error::from(e)))?;
+        let parsed = AvifParser::parse_raw(&self.buf[..prefix_len], &self.config, &Unstoppable)?;
+
+        let spatial_extents = parsed.meta.as_ref().and_then(|meta| {
+            property_for(meta, meta.primary_item_id, |p| match p {
+                ItemProperty::ImageSpatialExtents(e) => Some(*e),
+                _ => None,
+            })
+        });
+        Ok(PushMetadata { spatial_extents, required_len: required_len(&parsed, self.next_box) })
+    }
+}
+
+/// Read a top-level box header from the start of `data`.
+///
+/// Returns `Ok(Err(bytes))` when the header itself is incomplete.
+fn peek_box_header(data: &[u8]) -> Result<core::result::Result<BoxHeader, usize>> {
+    // Large-size boxes carry a 64-bit size after the type
+    let header_len = if data.get(..4) == Some(&[0, 0, 0, 1]) { 16 } else { 8 };
+    if data.len() < header_len {
+        return Ok(Err(header_len - data.len()));
+    }
+    read_box_header(&mut &data[..header_len]).map(Ok)
+}
+
+/// The file length needed to resolve every file-stored item extent and track sample.
+///
+/// `None` when an extent runs to the end of an `mdat` that has not been seen yet.
+fn required_len(parsed: &ParsedStructure, scanned_len: u64) -> Option<u64> {
+    let mut required = scanned_len;
+    if let Some(meta) = &parsed.meta {
+        let file_extents = meta
+            .iloc_items
+            .iter()
+            .filter(|item| item.construction_method == ConstructionMethod::File)
+            .flat_map(|item| item.extents.iter());
+        for extent in file_extents {
+            let end = match &extent.extent_range {
+                ExtentRange::WithLength(range) => range.end,
+                ExtentRange::ToEnd(range) => {
+                    let mdat = parsed.mdat_bounds.iter().find(|m| range.start >= m.offset && range.start < m.offset + m.length)?;
+                    mdat.offset + mdat.length
+                }
+            };
+            required = required.max(end);
+        }
+    }
+    if let Some(anim) = &parsed.animation_data {
+        let tables = core::iter::once(&anim.color_sample_table).chain(anim.alpha_sample_table.as_ref());
+        for table in tables {
+            for (index, &offset) in table.sample_offsets.iter().enumerate() {
+                let size = table.sample_sizes.get(index).unwrap_or(0);
+                required = required.max(offset.saturating_add(u64::from(size)));
+            }
+        }
+    }
+    Some(required)
+}
@@ -0,0 +1,908 @@
+//! Lossless rewriting of existing files: metadata and loop count.
+
+use crate::boxes::FourCC;
+use crate::writer::{bmff_box, full_box};
+use crate::{
+    AvifInternalMeta, AvifParser, CleanAperture, ConstructionMethod, DecodeConfig, Error, ExtentRange, ImageMirror,
+    ImageRotation, MdatBounds, Result, ToU64, Unstoppable, read_box_header,
+};
+use crate::io::Write;
+use whereat::at;
+
+/// What to do with one kind of metadata in [`MetadataRewriter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetadataEdit<'a> {
+    /// Leave it as it is.
+    #[default]
+    Keep,
+    /// Remove it.
+    Strip,
+    /// Remove any existing copy and store this one instead.
+    ///
+    /// Exif data starts at the TIFF header, as returned by
+    /// [`AvifParser::exif_data`]; XMP is the raw packet; ICC is the profile.
+    Replace(&'a [u8]),
+}
+
+/// Rewrites a still AVIF file with its Exif, XMP or ICC metadata removed or
+/// replaced, or its rotation, mirror or crop changed, leaving the coded
+/// images untouched.
+///
+/// Transforms (`clap`, `irot`, `imir`) are set on the primary item and its
+/// `auxl` auxiliary images, marked essential, and ordered after the
+/// descriptive properties as HEIF requires.
+///
+/// The output has the input's `ftyp`, a rebuilt `meta` and a single `mdat`
+/// holding only the data of the items that are kept, so stripped metadata
+/// does not survive anywhere in the file. Stripped items stored in `idat` are
+/// zeroed. Image sequences (files with a `moov` box) are not supported.
+///
+/// ```
+/// use zenavif_parse::{MetadataEdit, MetadataRewriter};
+///
+/// # fn run(input: &[u8], srgb_icc: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut output = Vec::new();
+/// MetadataRewriter::new(input)
+///     .exif(MetadataEdit::Strip)
+///     .xmp(MetadataEdit::Strip)
+///     .icc(MetadataEdit::Replace(srgb_icc))
+///     .write_to(&mut output)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetadataRewriter<'a> {
+    data: &'a [u8],
+    exif: MetadataEdit<'a>,
+    xmp: MetadataEdit<'a>,
+    icc: MetadataEdit<'a>,
+    /// `None` keeps the property; `Some(None)` removes it.
+    rotation: Option<Option<ImageRotation>>,
+    mirror: Option<Option<ImageMirror>>,
+    clean_aperture: Option<Option<CleanAperture>>,
+}
+
+/// A complete box within a parent's payload.
+struct RawBox<'a> {
+    name: [u8; 4],
+    bytes: &'a [u8],
+    content: &'a [u8],
+}
+
+/// An item's location in the rewritten file.
+struct NewItem<'a> {
+    id: u32,
+    construction_method: ConstructionMethod,
+    extents: alloc::vec::Vec<NewExtent<'a>>,
+}
+
+/// `(extent_index, offset, length, source)`; `source` holds the bytes copied
+/// into the new `mdat` for file-stored extents.
+type NewExtent<'a> = (Option<u64>, u64, u64, Option<&'a [u8]>);
+
+/// A metadata item the rewrite adds: `(item_id, item_type, content_type, payload)`.
+type AddedItem<'a> = (u32, &'a [u8; 4], Option<&'a [u8]>, &'a [u8]);
+
+/// `(essential, property_index)` for each property associated with an item.
+type Associations = alloc::vec::Vec<(bool, usize)>;
+
+impl<'a> MetadataRewriter<'a> {
+    /// Prepare to rewrite the AVIF file in `data`; nothing changes until an edit is set.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            exif: MetadataEdit::Keep,
+            xmp: MetadataEdit::Keep,
+            icc: MetadataEdit::Keep,
+            rotation: None,
+            mirror: None,
+            clean_aperture: None,
+        }
+    }
+
+    /// Edit the Exif item (`cdsc`-linked `Exif`).
+    pub fn exif(mut self, edit: MetadataEdit<'a>) -> Self {
+        self.exif = edit;
+        self
+    }
+
+    /// Edit the XMP item (`cdsc`-linked `mime` item of type `application/rdf+xml`).
+    pub fn xmp(mut self, edit: MetadataEdit<'a>) -> Self {
+        self.xmp = edit;
+        self
+    }
+
+    /// Edit the ICC profile (`colr` property of type `prof` or `rICC`).
+    pub fn icc(mut self, edit: MetadataEdit<'a>) -> Self {
+        self.icc = edit;
+        self
+    }
+
+    /// Set the rotation (`irot`), or remove it with `None`.
+    ///
+    /// The angle must be 0, 90, 180 or 270 degrees counter-clockwise.
+    pub fn rotation(mut self, rotation: Option<ImageRotation>) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Set the mirror axis (`imir`), or remove it with `None`.
+    pub fn mirror(mut self, mirror: Option<ImageMirror>) -> Self {
+        self.mirror = Some(mirror);
+        self
+    }
+
+    /// Set the crop rectangle (`clap`), or remove it with `None`.
+    pub fn clean_aperture(mut self, clean_aperture: Option<CleanAperture>) -> Self {
+        self.clean_aperture = Some(clean_aperture);
+        self
+    }
+
+    /// Write the rewritten file.
+    pub fn write_to<W: Write>(&self, mut w: W) -> Result<()> {
+        let parsed = AvifParser::parse_raw(self.data, &DecodeConfig::default(), &Unstoppable)?;
+        if parsed.animation_data.is_some() {
+            return Err(at!(Error::Unsupported("metadata rewriting of image sequences is not supported")));
+        }
+        let meta = parsed.meta.as_ref().ok_or_else(|| at!(Error::InvalidData("missing meta")))?;
+
+        let top = child_boxes(self.data)?;
+        let ftyp = top.first().filter(|b| &b.name == b"ftyp")
+            .ok_or_else(|| at!(Error::InvalidData("'ftyp' box must occur first")))?;
+        let meta_box = top.iter().find(|b| &b.name == b"meta").ok_or_else(|| at!(Error::InvalidData("missing meta")))?;
+        // Other top-level boxes are kept, except media data (rebuilt) and padding
+        let kept_top: alloc::vec::Vec<&RawBox<'_>> = top.iter()
+            .filter(|b| !matches!(&b.name, b"ftyp" | b"meta" | b"mdat" | b"free" | b"skip"))
+            .collect();
+
+        let mut removed = alloc::vec::Vec::new();
+        for info in meta.item_infos.iter() {
+            let is_exif = info.item_type == b"Exif" && self.exif != MetadataEdit::Keep;
+            let is_xmp = info.is_xmp() && self.xmp != MetadataEdit::Keep;
+            if is_exif || is_xmp {
+                removed.push(info.item_id);
+            }
+        }
+
+        // New metadata items are linked to the file's primary item (before `iden` resolution)
+        let meta_children = child_boxes(meta_box.content.get(4..).unwrap_or_default())?;
+        let pitm = meta_children.iter().find(|b| &b.name == b"pitm")
+            .ok_or_else(|| at!(Error::InvalidData("Required pitm box not present in meta box")))?;
+        let primary_id = read_sized_id(pitm.content, 4, pitm.content.first() == Some(&0))?;
+
+        let exif_payload = match self.exif {
+            MetadataEdit::Replace(tiff) => {
+                // Exif items start with the offset to the TIFF header (ISO 23008-12 Annex A.2.1)
+                let mut payload = alloc::vec::Vec::with_capacity(4 + tiff.len());
+                payload.extend_from_slice(&0u32.to_be_bytes());
+                payload.extend_from_slice(tiff);
+                Some(payload)
+            }
+            _ => None,
+        };
+        let mut next_id = meta.item_infos.iter().map(|i| i.item_id)
+            .chain(meta.iloc_items.iter().map(|i| i.item_id))
+            .max()
+            .unwrap_or(0);
+        let mut added: alloc::vec::Vec<AddedItem<'_>> = alloc::vec::Vec::new();
+        if let Some(payload) = &exif_payload {
+            next_id = next_id.checked_add(1).ok_or_else(|| at!(Error::Unsupported("item ID space exhausted")))?;
+            added.push((next_id, b"Exif", None, &payload[..]));
+        }
+        if let MetadataEdit::Replace(xmp) = self.xmp {
+            next_id = next_id.checked_add(1).ok_or_else(|| at!(Error::Unsupported("item ID space exhausted")))?;
+            added.push((next_id, b"mime", Some(&b"application/rdf+xml"[..]), xmp));
+        }
+
+        let mut items = self.kept_items(meta, &parsed.mdat_bounds, &removed)?;
+        for &(id, _, _, payload) in &added {
+            let length = payload.len().to_u64();
+            items.push(NewItem { id, construction_method: ConstructionMethod::File, extents: vec![(None, 0, length, Some(payload))] });
+        }
+
+        // Lay out the new `mdat` and point file extents into it
+        let mdat_len: u64 = items.iter().flat_map(|i| &i.extents).filter_map(|e| e.3).map(|s| s.len().to_u64()).sum();
+        let mdat_header_len = if mdat_len + 8 > u64::from(u32::MAX) { 16 } else { 8 };
+        let rest_len: u64 = kept_top.iter().map(|b| b.bytes.len().to_u64()).sum();
+
+        let build_meta = |mdat_data_start: u64, items: &mut [NewItem<'_>]| -> Result<alloc::vec::Vec<u8>> {
+            let mut pos = mdat_data_start;
+            for extent in items.iter_mut().flat_map(|i| &mut i.extents) {
+                if let Some(source) = extent.3 {
+                    extent.1 = pos;
+                    pos += source.len().to_u64();
+                }
+            }
+            self.rebuild_meta(meta_box.content, meta, &removed, primary_id, &added, items)
+        };
+        // The iloc field widths depend on the offsets, so settle the layout first
+        let mut meta_len = build_meta(0, &mut items)?.len().to_u64();
+        let new_meta = loop {
+            let start = ftyp.bytes.len().to_u64() + meta_len + rest_len + mdat_header_len;
+            let new_meta = build_meta(start, &mut items)?;
+            if new_meta.len().to_u64() == meta_len {
+                break new_meta;
+            }
+            meta_len = new_meta.len().to_u64();
+        };
+
+        let io = |e: crate::io::Error| at!(Error::from(e));
+        w.write_all(ftyp.bytes).map_err(io)?;
+        w.write_all(&new_meta).map_err(io)?;
+        for b in kept_top {
+            w.write_all(b.bytes).map_err(io)?;
+        }
+        if mdat_header_len == 16 {
+            w.write_all(&1u32.to_be_bytes()).map_err(io)?;
+            w.write_all(b"mdat").map_err(io)?;
+            w.write_all(&(mdat_len + 16).to_be_bytes()).map_err(io)?;
+        } else {
+            w.write_all(&((mdat_len + 8) as u32).to_be_bytes()).map_err(io)?;
+            w.write_all(b"mdat").map_err(io)?;
+        }
+        for source in items.iter().flat_map(|i| &i.extents).filter_map(|e| e.3) {
+            w.write_all(source).map_err(io)?;
+        }
+        Ok(())
+    }
+
+    /// Locations of the items that survive, with file extents still to be placed.
+    fn kept_items(&self, meta: &AvifInternalMeta, mdat_bounds: &[MdatBounds], removed: &[u32]) -> Result<alloc::vec::Vec<NewItem<'a>>> {
+        let mut items = alloc::vec::Vec::new();
+        for item in meta.iloc_items.iter().filter(|i| !removed.contains(&i.item_id)) {
+            let mut extents = alloc::vec::Vec::new();
+            for extent in item.extents.iter() {
+                let (start, end) = match &extent.extent_range {
+                    ExtentRange::WithLength(r) => (r.start, Some(r.end)),
+                    ExtentRange::ToEnd(r) => (r.start, None),
+                };
+                if item.construction_method == ConstructionMethod::File {
+                    // "To the end" means the end of the enclosing mdat
+                    let end = end.or_else(|| {
+                        mdat_bounds.iter().find(|m| start >= m.offset && start < m.offset + m.length).map(|m| m.offset + m.length)
+                    }).unwrap_or(self.data.len().to_u64());
+                    let source = usize::try_from(start).ok().zip(usize::try_from(end).ok())
+                        .and_then(|(start, end)| self.data.get(start..end))
+                        .ok_or_else(|| at!(Error::InvalidData("iloc extent outside the file")))?;
+                    extents.push((extent.extent_index, 0, end - start, Some(source)));
+                } else {
+                    // Offsets into `idat` or other items don't move; length 0 still means "to the end"
+                    extents.push((extent.extent_index, start, end.map_or(0, |end| end - start), None));
+                }
+            }
+            items.push(NewItem { id: item.item_id, construction_method: item.construction_method, extents });
+        }
+        Ok(items)
+    }
+
+    fn rebuild_meta(
+        &self,
+        meta_content: &[u8],
+        meta: &AvifInternalMeta,
+        removed: &[u32],
+        primary_id: u32,
+        added: &[AddedItem<'_>],
+        items: &[NewItem<'_>],
+    ) -> Result<alloc::vec::Vec<u8>> {
+        let (version_flags, children) = meta_content.split_at_checked(4)
+            .ok_or_else(|| at!(Error::InvalidData("meta too short")))?;
+        let mut out = version_flags.to_vec();
+        let mut wrote_iref = false;
+        for child in child_boxes(children)? {
+            match &child.name {
+                b"iloc" => out.extend_from_slice(&iloc(items)),
+                b"iinf" => out.extend_from_slice(&iinf(&child, removed, added)?),
+                b"iref" => {
+                    out.extend_from_slice(&iref(Some(&child), removed, primary_id, added)?);
+                    wrote_iref = true;
+                }
+                b"iprp" => out.extend_from_slice(&self.iprp(&child, meta, removed, primary_id)?),
+                b"idat" => {
+                    // Zero stripped items so their bytes don't survive
+                    let mut idat = child.bytes.to_vec();
+                    let header_len = child.bytes.len() - child.content.len();
+                    for item in meta.iloc_items.iter().filter(|i| removed.contains(&i.item_id)) {
+                        if item.construction_method != ConstructionMethod::Idat {
+                            continue;
+                        }
+                        for extent in item.extents.iter() {
+                            let start = usize::try_from(extent.extent_range.start()).unwrap_or(usize::MAX).saturating_add(header_len);
+                            let end = match &extent.extent_range {
+                                ExtentRange::WithLength(r) => usize::try_from(r.end).unwrap_or(usize::MAX).saturating_add(header_len),
+                                ExtentRange::ToEnd(_) => idat.len(),
+                            };
+                            let end = end.min(idat.len());
+                            if let Some(bytes) = idat.get_mut(start..end) {
+                                bytes.fill(0);
+                            }
+                        }
+                    }
+                    out.extend_from_slice(&idat);
+                }
+                _ => out.extend_from_slice(child.bytes),
+            }
+        }
+        if !wrote_iref && !added.is_empty() {
+            out.extend_from_slice(&iref(None, removed, primary_id, added)?);
+        }
+        Ok(bmff_box(b"meta", &out))
+    }
+
+    fn iprp(&self, iprp: &RawBox<'_>, meta: &AvifInternalMeta, removed: &[u32], primary_id: u32) -> Result<alloc::vec::Vec<u8>> {
+        let children = child_boxes(iprp.content)?;
+        let ipco = children.iter().find(|b| &b.name == b"ipco")
+            .ok_or_else(|| at!(Error::InvalidData("iprp without ipco")))?;
+        let properties = child_boxes(ipco.content)?;
+        let is_icc = |p: &RawBox<'_>| &p.name == b"colr" && matches!(p.content.get(..4), Some(b"prof" | b"rICC"));
+
+        // Stripped or replaced ICC profiles are dropped from `ipco`, so the 1-based
+        // property indices after them shift down; `renumber` maps old to new
+        let mut kinds: alloc::vec::Vec<[u8; 4]> = alloc::vec::Vec::new();
+        let mut renumber = vec![None; properties.len() + 1];
+        let mut new_ipco = alloc::vec::Vec::new();
+        for (i, property) in properties.iter().enumerate() {
+            if self.icc != MetadataEdit::Keep && is_icc(property) {
+                continue;
+            }
+            new_ipco.extend_from_slice(property.bytes);
+            kinds.push(property.name);
+            renumber[i + 1] = Some(kinds.len());
+        }
+        let mut add_property = |name: [u8; 4], payload: &[u8]| {
+            new_ipco.extend_from_slice(&bmff_box(&name, payload));
+            kinds.push(name);
+            kinds.len()
+        };
+
+        let new_icc = match self.icc {
+            MetadataEdit::Replace(icc) => {
+                let mut colr = b"prof".to_vec();
+                colr.extend_from_slice(icc);
+                Some(add_property(*b"colr", &colr))
+            }
+            _ => None,
+        };
+        let edits = [
+            (*b"clap", self.clean_aperture.map(|c| c.as_ref().map(clap).transpose()).transpose()?),
+            (*b"irot", self.rotation.map(|r| r.as_ref().map(irot).transpose()).transpose()?),
+            (*b"imir", self.mirror.map(|m| m.as_ref().map(imir).transpose()).transpose()?),
+        ];
+        // Each edited transform type, with the index of its replacement if any
+        let mut transforms = alloc::vec::Vec::new();
+        for (name, edit) in edits {
+            if let Some(payload) = edit {
+                transforms.push((name, payload.map(|payload| add_property(name, &payload))));
+            }
+        }
+
+        // Transforms apply to the primary image and its auxiliary images (alpha, depth)
+        let targets: alloc::vec::Vec<u32> = core::iter::once(primary_id)
+            .chain(meta.item_references.iter()
+                .filter(|r| r.item_type == b"auxl" && r.to_item_id == primary_id)
+                .map(|r| r.from_item_id))
+            .collect();
+        let kind = |index: usize| kinds.get(index.wrapping_sub(1));
+        let edit = |item_id: u32, associations: &mut Associations| {
+            *associations = associations.iter()
+                .filter_map(|&(essential, index)| renumber.get(index).copied().flatten().map(|index| (essential, index)))
+                .collect();
+            if item_id == primary_id && let Some(index) = new_icc {
+                associations.push((false, index));
+            }
+            if targets.contains(&item_id) {
+                for &(name, new_index) in &transforms {
+                    associations.retain(|&(_, index)| kind(index) != Some(&name));
+                    if let Some(index) = new_index {
+                        associations.push((true, index));
+                    }
+                }
+            }
+            // Descriptive properties come first, then the transforms in the order they apply
+            associations.sort_by_key(|&(_, index)| match kind(index) {
+                Some(b"clap") => 1,
+                Some(b"irot") => 2,
+                Some(b"imir") => 3,
+                _ => 0,
+            });
+        };
+
+        let mut out = bmff_box(b"ipco", &new_ipco);
+        for child in &children {
+            match &child.name {
+                b"ipco" => {}
+                b"ipma" => out.extend_from_slice(&ipma(child, removed, primary_id, &edit)?),
+                _ => out.extend_from_slice(child.bytes),
+            }
+        }
+        Ok(bmff_box(b"iprp", &out))
+    }
+}
+
+fn irot(rotation: &ImageRotation) -> Result<alloc::vec::Vec<u8>> {
+    let angle = match rotation.angle {
+        0 => 0,
+        90 => 1,
+        180 => 2,
+        270 => 3,
+        _ => return Err(at!(Error::InvalidData("irot angle must be 0, 90, 180 or 270"))),
+    };
+    Ok(vec![angle])
+}
+
+fn imir(mirror: &ImageMirror) -> Result<alloc::vec::Vec<u8>> {
+    if mirror.axis > 1 {
+        return Err(at!(Error::InvalidData("imir axis must be 0 or 1")));
+    }
+    Ok(vec![mirror.axis])
+}
+
+fn clap(clap: &CleanAperture) -> Result<alloc::vec::Vec<u8>> {
+    if clap.width_d == 0 || clap.height_d == 0 || clap.horiz_off_d == 0 || clap.vert_off_d == 0 {
+        return Err(at!(Error::InvalidData("clap denominator cannot be zero")));
+    }
+    let mut payload = alloc::vec::Vec::with_capacity(32);
+    for field in [clap.width_n, clap.width_d, clap.height_n, clap.height_d] {
+        payload.extend_from_slice(&field.to_be_bytes());
+    }
+    payload.extend_from_slice(&clap.horiz_off_n.to_be_bytes());
+    payload.extend_from_slice(&clap.horiz_off_d.to_be_bytes());
+    payload.extend_from_slice(&clap.vert_off_n.to_be_bytes());
+    payload.extend_from_slice(&clap.vert_off_d.to_be_bytes());
+    Ok(payload)
+}
+
+/// Set how often an image sequence plays, without touching its media data.
+///
+/// `loop_count` follows [`AnimationInfo::loop_count`](crate::AnimationInfo::loop_count):
+/// 0 loops forever and 1 plays once; other counts cannot be expressed with
+/// the `elst` repeat flag and are rejected. The flag is patched in the `elst`
+/// of each visual track (`pict`, `vide` or `auxv` handler), and tracks
+/// without one get an `edts`/`elst` covering the track duration. When `moov`
+/// grows, the chunk offsets (`stco`/`co64`) and `iloc` file offsets that
+/// point past it are shifted to match.
+///
+/// ```
+/// # fn run(gif_conversion: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+/// let looping = zenavif_parse::set_loop_count(gif_conversion, 0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn set_loop_count(input: &[u8], loop_count: u32) -> Result<alloc::vec::Vec<u8>> {
+    let repeat = match loop_count {
+        0 => true,
+        1 => false,
+        _ => return Err(at!(Error::Unsupported("only loop counts of 0 (infinite) and 1 (play once) can be stored"))),
+    };
+    let parsed = AvifParser::parse_raw(input, &DecodeConfig::default(), &Unstoppable)?;
+    if parsed.animation_data.is_none() {
+        return Err(at!(Error::Unsupported("not an image sequence")));
+    }
+
+    let top = child_boxes(input)?;
+    let mut moov_end = 0;
+    let mut moov = None;
+    for b in &top {
+        moov_end += b.bytes.len().to_u64();
+        if &b.name == b"moov" {
+            moov = Some(b);
+            break;
+        }
+    }
+    let moov = moov.ok_or_else(|| at!(Error::Unsupported("not an image sequence")))?;
+
+    // Boxes added to `moov` move everything stored after it
+    let unshifted = rebuild_moov(moov, repeat, &|offset| Ok(offset))?;
+    let delta = unshifted.len() as i64 - moov.bytes.len() as i64;
+    let shift = |offset: u64| -> Result<u64> {
+        if offset < moov_end {
+            return Ok(offset);
+        }
+        offset.checked_add_signed(delta).ok_or_else(|| at!(Error::InvalidData("offset overflow")))
+    };
+    let new_moov = if delta == 0 { unshifted } else { rebuild_moov(moov, repeat, &shift)? };
+
+    let mut out = alloc::vec::Vec::new();
+    out.try_reserve(input.len() + new_moov.len()).map_err(|_| at!(Error::OutOfMemory))?;
+    for b in &top {
+        match &b.name {
+            b"moov" => out.extend_from_slice(&new_moov),
+            b"meta" if delta != 0 => out.extend_from_slice(&shift_meta(b, &shift)?),
+            _ => out.extend_from_slice(b.bytes),
+        }
+    }
+    Ok(out)
+}
+
+fn rebuild_moov(moov: &RawBox<'_>, repeat: bool, shift: &dyn Fn(u64) -> Result<u64>) -> Result<alloc::vec::Vec<u8>> {
+    let mut out = alloc::vec::Vec::new();
+    for child in child_boxes(moov.content)? {
+        if &child.name == b"trak" {
+            out.extend_from_slice(&rebuild_trak(&child, repeat, shift)?);
+        } else {
+            out.extend_from_slice(child.bytes);
+        }
+    }
+    Ok(bmff_box(b"moov", &out))
+}
+
+fn rebuild_trak(trak: &RawBox<'_>, repeat: bool, shift: &dyn Fn(u64) -> Result<u64>) -> Result<alloc::vec::Vec<u8>> {
+    let children = child_boxes(trak.content)?;
+    let handler = match children.iter().find(|b| &b.name == b"mdia") {
+        Some(mdia) => child_boxes(mdia.content)?.into_iter().find(|b| &b.name == b"hdlr").and_then(|h| h.content.get(8..12)),
+        None => None,
+    };
+    let visual = matches!(handler, Some(b"pict" | b"vide" | b"auxv"));
+    let has_edts = children.iter().any(|b| &b.name == b"edts");
+
+    let mut out = alloc::vec::Vec::new();
+    for child in &children {
+        match &child.name {
+            b"edts" if visual => out.extend_from_slice(&edts(Some(child), repeat, 0)?),
+            b"mdia" => {
+                if visual && !has_edts {
+                    let tkhd = children.iter().find(|b| &b.name == b"tkhd")
+                        .ok_or_else(|| at!(Error::InvalidData("trak without tkhd")))?;
+                    // tkhd duration, in the movie timescale like elst segment_duration
+                    let duration = if tkhd.content.first() == Some(&1) {
+                        read_uint(tkhd.content, 28, 8)?
+                    } else {
+                        read_uint(tkhd.content, 20, 4)?
+                    };
+                    out.extend_from_slice(&edts(None, repeat, duration)?);
+                }
+                out.extend_from_slice(&shift_chunk_offsets(child, shift)?);
+            }
+            _ => out.extend_from_slice(child.bytes),
+        }
+    }
+    Ok(bmff_box(b"trak", &out))
+}
+
+/// Set the repeat flag of `existing`'s `elst`, or create one edit of `duration`.
+fn edts(existing: Option<&RawBox<'_>>, repeat: bool, duration: u64) -> Result<alloc::vec::Vec<u8>> {
+    let mut out = alloc::vec::Vec::new();
+    let mut has_elst = false;
+    for child in existing.map(|b| child_boxes(b.content)).transpose()?.unwrap_or_default() {
+        if &child.name == b"elst" {
+            // flags bit 0 is `repeat` (ISO 14496-12 § 8.6.6)
+            let flags_at = child.bytes.len() - child.content.len() + 3;
+            let mut elst = child.bytes.to_vec();
+            let flags = elst.get_mut(flags_at).ok_or_else(|| at!(Error::InvalidData("elst too short")))?;
+            *flags = (*flags & !1) | u8::from(repeat);
+            out.extend_from_slice(&elst);
+            has_elst = true;
+        } else {
+            out.extend_from_slice(child.bytes);
+        }
+    }
+    if !has_elst {
+        let mut entry = 1u32.to_be_bytes().to_vec(); // entry_count
+        let version = if duration > u64::from(u32::MAX) {
+            entry.extend_from_slice(&duration.to_be_bytes());
+            entry.extend_from_slice(&0i64.to_be_bytes()); // media_time
+            1
+        } else {
+            entry.extend_from_slice(&(duration as u32).to_be_bytes());
+            entry.extend_from_slice(&0i32.to_be_bytes());
+            0
+        };
+        entry.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // media_rate 1.0
+        out.extend_from_slice(&full_box(b"elst", version, u32::from(repeat), &entry));
+    }
+    Ok(bmff_box(b"edts", &out))
+}
+
+/// Copy a box, passing the chunk offsets (`stco`/`co64`) within it through `shift`.
+fn shift_chunk_offsets(b: &RawBox<'_>, shift: &dyn Fn(u64) -> Result<u64>) -> Result<alloc::vec::Vec<u8>> {
+    match &b.name {
+        b"mdia" | b"minf" | b"stbl" => {
+            let mut out = alloc::vec::Vec::new();
+            for child in child_boxes(b.content)? {
+                out.extend_from_slice(&shift_chunk_offsets(&child, shift)?);
+            }
+            Ok(bmff_box(&b.name, &out))
+        }
+        b"stco" | b"co64" => {
+            let size = if &b.name == b"co64" { 8 } else { 4 };
+            let count = read_uint(b.content, 4, 4)?;
+            let mut content = b.content.to_vec();
+            for i in 0..count as usize {
+                let at = 8 + i * size;
+                write_uint(&mut content, at, size, shift(read_uint(b.content, at, size)?)?)?;
+            }
+            Ok(bmff_box(&b.name, &content))
+        }
+        _ => Ok(b.bytes.to_vec()),
+    }
+}
+
+/// Copy `meta`, passing the file offsets in its `iloc` through `shift`.
+fn shift_meta(meta: &RawBox<'_>, shift: &dyn Fn(u64) -> Result<u64>) -> Result<alloc::vec::Vec<u8>> {
+    let (version_flags, children) = meta.content.split_at_checked(4)
+        .ok_or_else(|| at!(Error::InvalidData("meta too short")))?;
+    let mut out = version_flags.to_vec();
+    for child in child_boxes(children)? {
+        if &child.name == b"iloc" {
+            out.extend_from_slice(&shift_iloc(&child, shift)?);
+        } else {
+            out.extend_from_slice(child.bytes);
+        }
+    }
+    Ok(bmff_box(b"meta", &out))
+}
+
+/// Copy `iloc`, passing the start of each file-stored extent through `shift`.
+fn shift_iloc(iloc: &RawBox<'_>, shift: &dyn Fn(u64) -> Result<u64>) -> Result<alloc::vec::Vec<u8>> {
+    let source = iloc.content;
+    let mut content = source.to_vec();
+    let version = read_uint(source, 0, 1)?;
+    let sizes = read_uint(source, 4, 2)? as usize;
+    let (offset_size, length_size, base_offset_size) = (sizes >> 12, sizes >> 8 & 0xF, sizes >> 4 & 0xF);
+    let index_size = if version == 0 { 0 } else { sizes & 0xF };
+    let id_size = if version == 2 { 4 } else { 2 };
+
+    let mut pos = 6;
+    let count = read_uint(source, pos, id_size)?;
+    pos += id_size;
+    for _ in 0..count {
+        pos += id_size;
+        let construction_method = if version == 0 {
+            0
+        } else {
+            pos += 2;
+            read_uint(source, pos - 2, 2)? & 0xF
+        };
+        pos += 2; // data_reference_index
+        let base_offset_at = pos;
+        let base_offset = read_uint(source, pos, base_offset_size)?;
+        pos += base_offset_size;
+        let extent_count = read_uint(source, pos, 2)?;
+        pos += 2;
+        let in_file = construction_method == 0;
+        if in_file && offset_size == 0 {
+            // Every extent starts at base_offset
+            write_uint(&mut content, base_offset_at, base_offset_size, shift(base_offset)?)?;
+        }
+        for _ in 0..extent_count {
+            pos += index_size;
+            if in_file && offset_size > 0 {
+                let start = base_offset.checked_add(read_uint(source, pos, offset_size)?)
+                    .ok_or_else(|| at!(Error::InvalidData("offset calculation overflow")))?;
+                let offset = shift(start)?.checked_sub(base_offset)
+                    .ok_or_else(|| at!(Error::InvalidData("offset calculation overflow")))?;
+                write_uint(&mut content, pos, offset_size, offset)?;
+            }
+            pos += offset_size + length_size;
+        }
+    }
+    Ok(bmff_box(b"iloc", &content))
+}
+
+/// Split `data` into its complete boxes.
+fn child_boxes(data: &[u8]) -> Result<alloc::vec::Vec<RawBox<'_>>> {
+    let mut boxes = alloc::vec::Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let mut reader = rest;
+        let head = read_box_header(&mut reader)?;
+        let size = if head.size == u64::MAX { rest.len() } else { usize::try_from(head.size).map_err(|e| at!(Error::from(e)))? };
+        let header_len = usize::try_from(head.offset).map_err(|e| at!(Error::from(e)))?;
+        let bytes = rest.get(..size).ok_or_else(|| at!(Error::InvalidData("box extends past its parent")))?;
+        let content = bytes.get(header_len..).ok_or_else(|| at!(Error::InvalidData("box too short")))?;
+        boxes.push(RawBox { name: FourCC::from(head.name).value, bytes, content });
+        rest = &rest[size..];
+    }
+    Ok(boxes)
+}
+
+/// Read a big-endian unsigned field of `len` bytes (at most 8) at `at`.
+fn read_uint(data: &[u8], at: usize, len: usize) -> Result<u64> {
+    let bytes = at.checked_add(len).filter(|_| len <= 8).and_then(|end| data.get(at..end))
+        .ok_or_else(|| at!(Error::InvalidData("box too short")))?;
+    Ok(bytes.iter().fold(0, |value, &b| value << 8 | u64::from(b)))
+}
+
+/// Overwrite a big-endian unsigned field of `len` bytes (at most 8) at `at`.
+fn write_uint(buf: &mut [u8], at: usize, len: usize, value: u64) -> Result<()> {
+    if len > 8 || (len < 8 && value >> (len * 8) != 0) {
+        return Err(at!(Error::Unsupported("shifted offset no longer fits its field")));
+    }
+    let field = at.checked_add(len).and_then(|end| buf.get_mut(at..end))
+        .ok_or_else(|| at!(Error::InvalidData("box too short")))?;
+    field.copy_from_slice(&value.to_be_bytes()[8 - len..]);
+    Ok(())
+}
+
+/// Read a 16- or 32-bit ID at `at`.
+fn read_sized_id(data: &[u8], at: usize, short: bool) -> Result<u32> {
+    read_uint(data, at, if short { 2 } else { 4 }).map(|id| id as u32)
+}
+
+fn push_sized_id(out: &mut alloc::vec::Vec<u8>, id: u32, short: bool) {
+    if short {
+        out.extend_from_slice(&(id as u16).to_be_bytes());
+    } else {
+        out.extend_from_slice(&id.to_be_bytes());
+    }
+}
+
+/// Serialize `iloc` (version 1, or 2 for 32-bit item IDs) with no base offsets.
+fn iloc(items: &[NewItem<'_>]) -> alloc::vec::Vec<u8> {
+    let short_ids = items.iter().all(|i| i.id <= u32::from(u16::MAX));
+    let fits = |v: u64| v <= u64::from(u32::MAX);
+    let extents = || items.iter().flat_map(|i| &i.extents);
+    let offset_size: u8 = if extents().all(|e| fits(e.1)) { 4 } else { 8 };
+    let length_size: u8 = if extents().all(|e| fits(e.2)) { 4 } else { 8 };
+    let index_size: u8 = if extents().any(|e| e.0.is_some()) { 4 } else { 0 };
+    let put = |out: &mut alloc::vec::Vec<u8>, v: u64, size: u8| match size {
+        4 => out.extend_from_slice(&(v as u32).to_be_bytes()),
+        8 => out.extend_from_slice(&v.to_be_bytes()),
+        _ => {}
+    };
+
+    let mut payload = vec![offset_size << 4 | length_size, index_size];
+    push_sized_id(&mut payload, items.len() as u32, short_ids);
+    for item in items {
+        push_sized_id(&mut payload, item.id, short_ids);
+        let method: u16 = match item.construction_method {
+            ConstructionMethod::File => 0,
+            ConstructionMethod::Idat => 1,
+            ConstructionMethod::Item => 2,
+        };
+        payload.extend_from_slice(&method.to_be_bytes());
+        payload.extend_from_slice(&0u16.to_be_bytes()); // data_reference_index
+        payload.extend_from_slice(&(item.extents.len() as u16).to_be_bytes());
+        for &(index, offset, length, _) in &item.extents {
+            put(&mut payload, index.unwrap_or(1), index_size);
+            put(&mut payload, offset, offset_size);
+            put(&mut payload, length, length_size);
+        }
+    }
+    full_box(b"iloc", if short_ids { 1 } else { 2 }, 0, &payload)
+}
+
+/// Drop the `infe` entries of removed items and append entries for added ones.
+fn iinf(iinf: &RawBox<'_>, removed: &[u32], added: &[AddedItem<'_>]) -> Result<alloc::vec::Vec<u8>> {
+    let version = *iinf.content.first().ok_or_else(|| at!(Error::InvalidData("iinf too short")))?;
+    let count_len = if version == 0 { 2 } else { 4 };
+    let entries = iinf.content.get(4 + count_len..).ok_or_else(|| at!(Error::InvalidData("iinf too short")))?;
+
+    let mut kept = alloc::vec::Vec::new();
+    let mut count = 0u32;
+    for infe in child_boxes(entries)? {
+        let infe_version = infe.content.first().copied().unwrap_or(0);
+        let id = read_sized_id(infe.content, 4, infe_version < 3)?;
+        if !removed.contains(&id) {
+            kept.extend_from_slice(infe.bytes);
+            count += 1;
+        }
+    }
+    for &(id, item_type, content_type, _) in added {
+        let mut infe = alloc::vec::Vec::new();
+        let short = id <= u32::from(u16::MAX);
+        push_sized_id(&mut infe, id, short);
+        infe.extend_from_slice(&0u16.to_be_bytes()); // item_protection_index
+        infe.extend_from_slice(item_type);
+        infe.push(0); // item_name
+        if let Some(content_type) = content_type {
+            infe.extend_from_slice(content_type);
+            infe.push(0);
+        }
+        kept.extend_from_slice(&full_box(b"infe", if short { 2 } else { 3 }, 0, &infe));
+        count += 1;
+    }
+
+    let mut payload = alloc::vec::Vec::new();
+    let version = if count > u32::from(u16::MAX) { 1 } else { version };
+    push_sized_id(&mut payload, count, version == 0);
+    payload.extend_from_slice(&kept);
+    Ok(full_box(b"iinf", version, 0, &payload))
+}
+
+/// Drop references from or to removed items and add `cdsc` links for added ones.
+fn iref(
+    iref: Option<&RawBox<'_>>,
+    removed: &[u32],
+    primary_id: u32,
+    added: &[AddedItem<'_>],
+) -> Result<alloc::vec::Vec<u8>> {
+    let mut references: alloc::vec::Vec<([u8; 4], u32, alloc::vec::Vec<u32>)> = alloc::vec::Vec::new();
+    if let Some(iref) = iref {
+        let short = iref.content.first() == Some(&0);
+        let id_len = if short { 2 } else { 4 };
+        for reference in child_boxes(iref.content.get(4..).unwrap_or_default())? {
+            let from = read_sized_id(reference.content, 0, short)?;
+            let count = read_sized_id(reference.content, id_len, true)?;
+            let mut to = alloc::vec::Vec::new();
+            for i in 0..count as usize {
+                to.push(read_sized_id(reference.content, id_len + 2 + i * id_len, short)?);
+            }
+            to.retain(|id| !removed.contains(id));
+            if !removed.contains(&from) && !to.is_empty() {
+                references.push((reference.name, from, to));
+            }
+        }
+    }
+    for &(id, ..) in added {
+        references.push((*b"cdsc", id, vec![primary_id]));
+    }
+
+    let short = references.iter().all(|(_, from, to)| {
+        *from <= u32::from(u16::MAX) && to.iter().all(|id| *id <= u32::from(u16::MAX))
+    });
+    let mut payload = alloc::vec::Vec::new();
+    for (name, from, to) in &references {
+        let mut entry = alloc::vec::Vec::new();
+        push_sized_id(&mut entry, *from, short);
+        entry.extend_from_slice(&(to.len() as u16).to_be_bytes());
+        for id in to {
+            push_sized_id(&mut entry, *id, short);
+        }
+        payload.extend_from_slice(&bmff_box(name, &entry));
+    }
+    Ok(full_box(b"iref", if short { 0 } else { 1 }, 0, &payload))
+}
+
+/// Drop the entries of removed items and pass each item's `(essential, property_index)`
+/// associations through `edit`, adding an entry for `primary_id` if it has none.
+fn ipma(
+    ipma: &RawBox<'_>,
+    removed: &[u32],
+    primary_id: u32,
+    edit: &dyn Fn(u32, &mut Associations),
+) -> Result<alloc::vec::Vec<u8>> {
+    let content = ipma.content;
+    let version = *content.first().ok_or_else(|| at!(Error::InvalidData("ipma too short")))?;
+    let wide_index = content.get(3).is_some_and(|flags| flags & 1 != 0);
+    let short_ids = version == 0;
+    let count = read_sized_id(content, 4, false)?;
+
+    let mut entries: alloc::vec::Vec<(u32, Associations)> = alloc::vec::Vec::new();
+    let mut pos = 8;
+    for _ in 0..count {
+        let item_id = read_sized_id(content, pos, short_ids)?;
+        pos += if short_ids { 2 } else { 4 };
+        let associations = *content.get(pos).ok_or_else(|| at!(Error::InvalidData("ipma too short")))?;
+        pos += 1;
+        let mut properties = alloc::vec::Vec::new();
+        for _ in 0..associations {
+            let value = if wide_index {
+                read_sized_id(content, pos, true)?
+            } else {
+                u32::from(*content.get(pos).ok_or_else(|| at!(Error::InvalidData("ipma too short")))?)
+            };
+            pos += if wide_index { 2 } else { 1 };
+            properties.push(if wide_index {
+                (value & 0x8000 != 0, (value & 0x7FFF) as usize)
+            } else {
+                (value & 0x80 != 0, (value & 0x7F) as usize)
+            });
+        }
+        if !removed.contains(&item_id) {
+            entries.push((item_id, properties));
+        }
+    }
+    if !entries.iter().any(|(id, _)| *id == primary_id) {
+        entries.push((primary_id, alloc::vec::Vec::new()));
+    }
+    for (item_id, properties) in &mut entries {
+        edit(*item_id, properties);
+    }
+    entries.retain(|(_, properties)| !properties.is_empty());
+
+    let wide_index = wide_index || entries.iter().flat_map(|(_, p)| p).any(|&(_, index)| index > 0x7F);
+    let short_ids = short_ids && entries.iter().all(|(id, _)| *id <= u32::from(u16::MAX));
+    let mut payload = (entries.len() as u32).to_be_bytes().to_vec();
+    for (item_id, properties) in &entries {
+        push_sized_id(&mut payload, *item_id, short_ids);
+        payload.push(properties.len() as u8);
+        for &(essential, index) in properties {
+            if wide_index {
+                payload.extend_from_slice(&(index as u16 | if essential { 0x8000 } else { 0 }).to_be_bytes());
+            } else {
+                payload.push(index as u8 | if essential { 0x80 } else { 0 });
+            }
+        }
+    }
+    Ok(full_box(b"ipma", if short_ids { 0 } else { 1 }, u32::from(wide_index), &payload))
+}
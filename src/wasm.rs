@@ -0,0 +1,126 @@
+//! JavaScript bindings (`wasm-bindgen` feature).
+//!
+//! ```js
+//! import { parse } from "zenavif-parse";
+//!
+//! const avif = parse(new Uint8Array(await file.arrayBuffer()));
+//! console.log(avif.width, avif.height, avif.frameCount);
+//! const av1 = avif.primaryData(); // Uint8Array, ready for a WebCodecs VideoDecoder
+//! ```
+//!
+//! Byte accessors return `Uint8Array` views into the parsed file where the
+//! data is stored contiguously, and copies where it has to be assembled
+//! (e.g. split extents, or `primaryData()` with its configOBUs prefix). A
+//! view is only valid until the next call into the module, which may grow
+//! wasm memory, and while the `JsAvifInfo` is alive; call `.slice()` to keep
+//! the bytes longer.
+
+// The glue `#[wasm_bindgen]` generates is unsafe code
+#![allow(unsafe_code)]
+
+use crate::{AvifParser, Error};
+use js_sys::Uint8Array;
+use std::borrow::Cow;
+use wasm_bindgen::prelude::*;
+use whereat::At;
+
+/// A parsed AVIF file.
+#[wasm_bindgen]
+pub struct JsAvifInfo {
+    parser: AvifParser<'static>,
+}
+
+/// Parse an AVIF file. The bytes are copied into wasm memory.
+#[wasm_bindgen]
+pub fn parse(bytes: &[u8]) -> Result<JsAvifInfo, JsError> {
+    let parser = AvifParser::from_owned(bytes.to_vec()).map_err(js_error)?;
+    Ok(JsAvifInfo { parser })
+}
+
+#[wasm_bindgen]
+impl JsAvifInfo {
+    /// Width from the primary item's `ispe`, 0 if absent.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.parser.spatial_extents().map_or(0, |e| e.width)
+    }
+
+    /// Height from the primary item's `ispe`, 0 if absent.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.parser.spatial_extents().map_or(0, |e| e.height)
+    }
+
+    /// Bit depth from `av1C`: 8, 10 or 12.
+    #[wasm_bindgen(getter, js_name = bitDepth)]
+    pub fn bit_depth(&self) -> Option<u8> {
+        self.parser.av1_config().map(|c| c.bit_depth)
+    }
+
+    /// Whether there is an alpha image or alpha track.
+    #[wasm_bindgen(getter, js_name = hasAlpha)]
+    pub fn has_alpha(&self) -> bool {
+        self.parser.has_alpha_item() || self.parser.animation_info().is_some_and(|a| a.has_alpha)
+    }
+
+    /// Number of animation frames; 0 for still images.
+    #[wasm_bindgen(getter, js_name = frameCount)]
+    pub fn frame_count(&self) -> usize {
+        self.parser.animation_info().map_or(0, |a| a.frame_count)
+    }
+
+    /// Animation loop count; 0 loops forever.
+    #[wasm_bindgen(getter, js_name = loopCount)]
+    pub fn loop_count(&self) -> u32 {
+        self.parser.animation_info().map_or(0, |a| a.loop_count)
+    }
+
+    /// The primary image's AV1 payload, prefixed with its `av1C` configOBUs.
+    #[wasm_bindgen(js_name = primaryData)]
+    pub fn primary_data(&self) -> Result<Uint8Array, JsError> {
+        self.parser.primary_decoder_payload().map(bytes).map_err(js_error)
+    }
+
+    /// The alpha image's AV1 payload, if any.
+    #[wasm_bindgen(js_name = alphaData)]
+    pub fn alpha_data(&self) -> Result<Option<Uint8Array>, JsError> {
+        self.parser.alpha_data().transpose().map(|data| data.map(bytes)).map_err(js_error)
+    }
+
+    /// AV1 payload of animation frame `index`.
+    #[wasm_bindgen(js_name = frameData)]
+    pub fn frame_data(&self, index: usize) -> Result<Uint8Array, JsError> {
+        self.parser.frame(index).map(|frame| bytes(frame.data)).map_err(js_error)
+    }
+
+    /// Display duration of animation frame `index` in milliseconds.
+    #[wasm_bindgen(js_name = frameDurationMs)]
+    pub fn frame_duration_ms(&self, index: usize) -> Result<u32, JsError> {
+        self.parser.frame(index).map(|frame| frame.duration_ms).map_err(js_error)
+    }
+
+    /// The ICC profile, if any.
+    #[wasm_bindgen(js_name = iccProfile)]
+    pub fn icc_profile(&self) -> Option<Uint8Array> {
+        self.parser.icc_profile().map(bytes)
+    }
+
+    /// The EXIF payload starting at the TIFF header, if any.
+    pub fn exif(&self) -> Result<Option<Uint8Array>, JsError> {
+        self.parser.exif_data().transpose().map(|data| data.map(bytes)).map_err(js_error)
+    }
+}
+
+fn bytes(data: Cow<'_, [u8]>) -> Uint8Array {
+    match data {
+        // SAFETY: the slice borrows the input buffer owned by the parser,
+        // which lives as long as the `JsAvifInfo`. The module docs tell
+        // callers the view is invalidated when wasm memory grows.
+        Cow::Borrowed(data) => unsafe { Uint8Array::view(data) },
+        Cow::Owned(data) => Uint8Array::from(&data[..]),
+    }
+}
+
+fn js_error(err: At<Error>) -> JsError {
+    JsError::new(&err.error().to_string())
+}
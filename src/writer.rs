@@ -0,0 +1,256 @@
+//! Still-image AVIF muxing.
+
+use crate::{AV1Config, Error, Result, ToU64};
+use crate::io::Write;
+use whereat::at;
+
+const PRIMARY_ID: u16 = 1;
+const ALPHA_ID: u16 = 2;
+const ALPHA_URN: &[u8] = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0";
+
+/// Builds a still AVIF file from already-encoded AV1 payloads.
+///
+/// Emits `ftyp`, a `meta` box (`hdlr`, `pitm`, `iloc`, `iinf`, `iref`,
+/// `iprp`) and a single `mdat` holding the payloads. Each payload must be a
+/// complete AV1 temporal unit including its sequence header, as produced by
+/// an encoder for a single still frame.
+///
+/// ```
+/// use zenavif_parse::{AV1Config, AvifBuilder};
+///
+/// # fn run(color: &[u8], config: AV1Config) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut file = Vec::new();
+/// AvifBuilder::new()
+///     .primary_av1(color, config)
+///     .ispe(640, 480)
+///     .write_to(&mut file)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AvifBuilder<'a> {
+    primary: Option<(&'a [u8], AV1Config)>,
+    alpha: Option<(&'a [u8], AV1Config)>,
+    size: Option<(u32, u32)>,
+    icc: Option<&'a [u8]>,
+}
+
+impl<'a> AvifBuilder<'a> {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the color image: an AV1 temporal unit and its codec configuration.
+    pub fn primary_av1(mut self, payload: &'a [u8], av1c: AV1Config) -> Self {
+        self.primary = Some((payload, av1c));
+        self
+    }
+
+    /// Add an alpha plane, stored as an `auxl` auxiliary image of the primary.
+    pub fn alpha(mut self, payload: &'a [u8], av1c: AV1Config) -> Self {
+        self.alpha = Some((payload, av1c));
+        self
+    }
+
+    /// Set the image dimensions (`ispe`), shared by the color and alpha images.
+    pub fn ispe(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Attach an ICC profile to the color image (`colr` of type `prof`).
+    pub fn icc(mut self, profile: &'a [u8]) -> Self {
+        self.icc = Some(profile);
+        self
+    }
+
+    /// Write the complete file.
+    ///
+    /// Fails if no primary image or dimensions were set, or if the payloads
+    /// do not fit the 32-bit offsets this writer uses.
+    pub fn write_to<W: Write>(&self, mut w: W) -> Result<()> {
+        let (primary, _) = self.primary.as_ref().ok_or_else(|| at!(Error::InvalidData("AvifBuilder: no primary image")))?;
+        if self.size.is_none() {
+            return Err(at!(Error::InvalidData("AvifBuilder: image dimensions (ispe) are required")));
+        }
+
+        let ftyp = self.ftyp();
+        // Offsets are fixed-width, so the meta size does not depend on them
+        let meta_len = self.meta(0)?.len();
+        let mdat_len = primary.len() + self.alpha.as_ref().map_or(0, |(alpha, _)| alpha.len());
+        let mdat_size = u32::try_from(8 + mdat_len).map_err(|_| at!(Error::Unsupported("AvifBuilder: payload too large")))?;
+        let mdat_data_start = u32::try_from(ftyp.len() + meta_len + 8)
+            .map_err(|_| at!(Error::Unsupported("AvifBuilder: metadata too large")))?;
+        mdat_data_start.checked_add(mdat_size).ok_or_else(|| at!(Error::Unsupported("AvifBuilder: payload too large")))?;
+
+        let io = |e: crate::io::Error| at!(Error::from(e));
+        w.write_all(&ftyp).map_err(io)?;
+        w.write_all(&self.meta(mdat_data_start)?).map_err(io)?;
+        w.write_all(&mdat_size.to_be_bytes()).map_err(io)?;
+        w.write_all(b"mdat").map_err(io)?;
+        w.write_all(primary).map_err(io)?;
+        if let Some((alpha, _)) = &self.alpha {
+            w.write_all(alpha).map_err(io)?;
+        }
+        Ok(())
+    }
+
+    fn ftyp(&self) -> alloc::vec::Vec<u8> {
+        let mut payload = b"avif".to_vec();
+        payload.extend_from_slice(&0u32.to_be_bytes());
+        payload.extend_from_slice(b"avifmif1miaf");
+        bmff_box(b"ftyp", &payload)
+    }
+
+    /// Serialize `meta` for a file whose `mdat` payload starts at `mdat_data_start`.
+    fn meta(&self, mdat_data_start: u32) -> Result<alloc::vec::Vec<u8>> {
+        let Some((primary, primary_config)) = &self.primary else {
+            return Err(at!(Error::InvalidData("AvifBuilder: no primary image")));
+        };
+        let (width, height) = self.size.unwrap_or_default();
+        let primary_len = payload_len(primary)?;
+
+        let mut hdlr = vec![0; 4];
+        hdlr.extend_from_slice(b"pict");
+        hdlr.extend_from_slice(&[0; 13]);
+
+        // iloc v0: 4-byte offsets and lengths, no base_offset
+        let mut items = vec![(PRIMARY_ID, mdat_data_start, primary_len)];
+        if let Some((alpha, _)) = &self.alpha {
+            items.push((ALPHA_ID, mdat_data_start.wrapping_add(primary_len), payload_len(alpha)?));
+        }
+        let mut iloc = vec![0x44, 0x00];
+        iloc.extend_from_slice(&(items.len() as u16).to_be_bytes());
+        for &(id, offset, length) in &items {
+            iloc.extend_from_slice(&id.to_be_bytes());
+            iloc.extend_from_slice(&0u16.to_be_bytes()); // data_reference_index
+            iloc.extend_from_slice(&1u16.to_be_bytes()); // extent_count
+            iloc.extend_from_slice(&offset.to_be_bytes());
+            iloc.extend_from_slice(&length.to_be_bytes());
+        }
+
+        let mut iinf = (items.len() as u16).to_be_bytes().to_vec();
+        for &(id, ..) in &items {
+            let mut infe = id.to_be_bytes().to_vec();
+            infe.extend_from_slice(&0u16.to_be_bytes()); // item_protection_index
+            infe.extend_from_slice(b"av01");
+            infe.push(0); // item_name
+            iinf.extend_from_slice(&full_box(b"infe", 2, 0, &infe));
+        }
+
+        // Properties are numbered from 1 in ipco order; the high bit marks them essential
+        let mut properties: alloc::vec::Vec<alloc::vec::Vec<u8>> = alloc::vec::Vec::new();
+        let mut property = |property: alloc::vec::Vec<u8>, essential: bool| {
+            properties.push(property);
+            properties.len() as u8 | if essential { 0x80 } else { 0 }
+        };
+
+        let mut primary_props = vec![
+            property(ispe(width, height), false),
+            property(av1c(primary_config), true),
+            property(pixi(primary_config), false),
+        ];
+        if let Some(icc) = self.icc {
+            let mut colr = b"prof".to_vec();
+            colr.extend_from_slice(icc);
+            primary_props.push(property(bmff_box(b"colr", &colr), false));
+        }
+        let mut associations = vec![(PRIMARY_ID, primary_props)];
+        if let Some((_, alpha_config)) = &self.alpha {
+            let alpha_props = vec![
+                property(ispe(width, height), false),
+                property(av1c(alpha_config), true),
+                property(pixi(alpha_config), false),
+                property(full_box(b"auxC", 0, 0, ALPHA_URN), false),
+            ];
+            associations.push((ALPHA_ID, alpha_props));
+        }
+        let ipco = properties.concat();
+
+        let mut ipma = (associations.len() as u32).to_be_bytes().to_vec();
+        for (id, props) in &associations {
+            ipma.extend_from_slice(&id.to_be_bytes());
+            ipma.push(props.len() as u8);
+            ipma.extend_from_slice(props);
+        }
+        let mut iprp = bmff_box(b"ipco", &ipco);
+        iprp.extend_from_slice(&full_box(b"ipma", 0, 0, &ipma));
+
+        let mut meta = full_box(b"hdlr", 0, 0, &hdlr);
+        meta.extend_from_slice(&full_box(b"pitm", 0, 0, &PRIMARY_ID.to_be_bytes()));
+        meta.extend_from_slice(&full_box(b"iloc", 0, 0, &iloc));
+        meta.extend_from_slice(&full_box(b"iinf", 0, 0, &iinf));
+        if self.alpha.is_some() {
+            let mut auxl = ALPHA_ID.to_be_bytes().to_vec();
+            auxl.extend_from_slice(&1u16.to_be_bytes());
+            auxl.extend_from_slice(&PRIMARY_ID.to_be_bytes());
+            meta.extend_from_slice(&full_box(b"iref", 0, 0, &bmff_box(b"auxl", &auxl)));
+        }
+        meta.extend_from_slice(&bmff_box(b"iprp", &iprp));
+        Ok(full_box(b"meta", 0, 0, &meta))
+    }
+}
+
+fn payload_len(payload: &[u8]) -> Result<u32> {
+    u32::try_from(payload.len()).map_err(|_| at!(Error::Unsupported("AvifBuilder: payload too large")))
+}
+
+/// Serialize a box, switching to a 64-bit `largesize` header when the
+/// payload does not fit a 32-bit size.
+pub(crate) fn bmff_box(name: &[u8; 4], payload: &[u8]) -> alloc::vec::Vec<u8> {
+    let mut buf = alloc::vec::Vec::with_capacity(16 + payload.len());
+    match u32::try_from(payload.len()).ok().and_then(|len| len.checked_add(8)) {
+        Some(size) => {
+            buf.extend_from_slice(&size.to_be_bytes());
+            buf.extend_from_slice(name);
+        }
+        None => {
+            buf.extend_from_slice(&1u32.to_be_bytes());
+            buf.extend_from_slice(name);
+            buf.extend_from_slice(&(payload.len().to_u64() + 16).to_be_bytes());
+        }
+    }
+    buf.extend_from_slice(payload);
+    buf
+}
+
+pub(crate) fn full_box(name: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> alloc::vec::Vec<u8> {
+    let mut body = vec![version];
+    body.extend_from_slice(&flags.to_be_bytes()[1..]);
+    body.extend_from_slice(payload);
+    bmff_box(name, &body)
+}
+
+fn ispe(width: u32, height: u32) -> alloc::vec::Vec<u8> {
+    let mut payload = width.to_be_bytes().to_vec();
+    payload.extend_from_slice(&height.to_be_bytes());
+    full_box(b"ispe", 0, 0, &payload)
+}
+
+/// `av1C`, followed by the configuration's configOBUs (AV1-ISOBMFF § 2.3.3).
+fn av1c(config: &AV1Config) -> alloc::vec::Vec<u8> {
+    let high_bitdepth = u8::from(config.bit_depth > 8);
+    let twelve_bit = u8::from(config.bit_depth == 12);
+    let mut payload = vec![
+        0x81,
+        (config.profile << 5) | (config.level & 0x1F),
+        (config.tier << 7)
+            | (high_bitdepth << 6)
+            | (twelve_bit << 5)
+            | (u8::from(config.monochrome) << 4)
+            | ((config.chroma_subsampling_x & 1) << 3)
+            | ((config.chroma_subsampling_y & 1) << 2)
+            | (config.chroma_sample_position & 3),
+        0,
+    ];
+    payload.extend_from_slice(&config.config_obus);
+    bmff_box(b"av1C", &payload)
+}
+
+fn pixi(config: &AV1Config) -> alloc::vec::Vec<u8> {
+    let channels: u8 = if config.monochrome { 1 } else { 3 };
+    let mut payload = vec![channels];
+    payload.extend((0..channels).map(|_| config.bit_depth));
+    full_box(b"pixi", 0, 0, &payload)
+}
@@ -130,7 +130,7 @@ fn test_dir(dir: &str) {
         }
         log::debug!("parsing {:?}", path.display());
         let input = &mut File::open(path).expect("bad file");
-        match zenavif_parse::read_avif(input).map_err(|e| e.decompose().0) {
+        match zenavif_parse::read_avif(input).map_err(|e| e.decompose().0.into_root()) {
             Ok(avif) => {
                 if avif.grid_config.is_none() {
                     avif.primary_item_metadata().unwrap();
@@ -220,6 +220,19 @@ fn parser_from_bytes_primary() {
     assert!(parser.animation_info().is_none());
 }
 
+/// kodim-extents.avif with a copy of its second extent appended to the mdat
+/// and the `iloc` pointed at it, so the two extents are no longer adjacent.
+fn kodim_split_extents() -> Vec<u8> {
+    let mut bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let second = bytes[0x955..0x11e7].to_vec();
+    let second_offset = bytes.len() as u32;
+    bytes[76..80].copy_from_slice(&second_offset.to_be_bytes()); // second extent_offset
+    bytes.extend_from_slice(&second);
+    let mdat_size = (bytes.len() - 0xbc) as u32;
+    bytes[0xbc..0xc0].copy_from_slice(&mdat_size.to_be_bytes());
+    bytes
+}
+
 #[test]
 fn parser_from_bytes_multi_extent() {
     let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
@@ -228,8 +241,15 @@ fn parser_from_bytes_multi_extent() {
     let primary = parser.primary_data().expect("primary_data failed");
     assert_eq!(primary.len(), 4387);
 
-    // Multi-extent -> Cow::Owned
-    assert!(matches!(primary, Cow::Owned(_)), "Expected Cow::Owned for multi-extent");
+    // Two extents that follow each other in the file -> still Cow::Borrowed
+    assert!(matches!(primary, Cow::Borrowed(_)), "Expected Cow::Borrowed for adjacent extents");
+
+    // Separated extents -> Cow::Owned, same bytes
+    let split = kodim_split_extents();
+    let split_parser = zenavif_parse::AvifParser::from_bytes(&split).expect("from_bytes failed");
+    let split_primary = split_parser.primary_data().expect("primary_data failed");
+    assert!(matches!(split_primary, Cow::Owned(_)), "Expected Cow::Owned for separated extents");
+    assert_eq!(split_primary, primary);
 }
 
 #[test]
@@ -242,6 +262,73 @@ fn parser_from_owned_primary() {
     assert_eq!(&primary[0..4], &[0x12, 0x00, 0x0a, 0x0a]);
 }
 
+#[test]
+fn parser_clones_share_the_parsed_file() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<zenavif_parse::AvifParser<'static>>();
+
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_owned(bytes).expect("from_owned failed");
+    let clone = parser.clone();
+    let (a, b) = (parser.frame(0).expect("frame"), clone.frame(0).expect("frame"));
+    assert_eq!(a.data.as_ptr(), b.data.as_ptr(), "clones borrow the same buffer");
+
+    let workers: Vec<_> = (0..parser.animation_info().expect("animated").frame_count)
+        .map(|i| {
+            let parser = parser.clone();
+            std::thread::spawn(move || parser.frame(i).expect("frame").data.len())
+        })
+        .collect();
+    let sizes: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    assert_eq!(sizes, [38, 60, 47, 35, 39]);
+}
+
+#[test]
+fn into_primary_reuses_the_owned_buffer() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let expected = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").primary_data().expect("primary").into_owned();
+
+    let input = bytes.clone();
+    let ptr = input.as_ptr();
+    let primary = zenavif_parse::AvifParser::from_owned(input).expect("from_owned failed").into_primary().expect("primary");
+    assert_eq!(primary, expected);
+    assert_eq!(primary.as_ptr(), ptr, "the input buffer is handed back");
+
+    // Separated extents are moved together too
+    let primary = zenavif_parse::AvifParser::from_owned(kodim_split_extents()).expect("parse").into_primary().expect("primary");
+    assert_eq!(primary, expected);
+
+    // A live clone or borrowed input means copying
+    let parser = zenavif_parse::AvifParser::from_owned(bytes.clone()).expect("from_owned failed");
+    let clone = parser.clone();
+    assert_eq!(parser.into_primary().expect("primary"), expected);
+    assert_eq!(clone.primary_data().expect("primary"), expected.as_slice());
+    assert_eq!(zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").into_primary().expect("primary"), expected);
+}
+
+#[test]
+fn into_frames_reuses_the_owned_buffer() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let expected: Vec<Vec<u8>> = parser.frames().map(|f| f.expect("frame").data.into_owned()).collect();
+
+    let input = bytes.clone();
+    let ptr = input.as_ptr();
+    let frames = zenavif_parse::AvifParser::from_owned(input).expect("from_owned failed").into_frames().expect("frames");
+    assert_eq!(frames.len(), 5);
+    assert!(frames.iter().eq(expected.iter().map(Vec::as_slice)));
+    assert!(frames.get(5).is_none());
+    let (data, ends) = frames.into_parts();
+    assert_eq!(data.as_ptr(), ptr, "the input buffer is handed back");
+    assert_eq!(ends, [38, 98, 145, 180, 219]);
+
+    let copied = parser.into_frames().expect("frames");
+    assert!(copied.iter().eq(expected.iter().map(Vec::as_slice)));
+
+    let still = std::fs::read(IMAGE_AVIF).expect("read file");
+    assert!(zenavif_parse::AvifParser::from_owned(still).expect("parse").into_frames().expect("frames").is_empty());
+}
+
 #[test]
 fn parser_from_reader_primary() {
     let parser = zenavif_parse::AvifParser::from_reader(
@@ -392,6 +479,31 @@ fn parser_grid_via_reader() {
     }
 }
 
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_tiles_and_frames_match_sequential() {
+    let bytes = std::fs::read(IMAGE_GRID_5X4).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("from_bytes failed");
+    let tiles = parser.all_tiles_parallel().expect("tiles");
+    assert_eq!(tiles.len(), 20);
+    for (i, tile) in tiles.iter().enumerate() {
+        assert_eq!(*tile, parser.tile_data(i).expect("tile_data failed"), "tile {i}");
+    }
+    assert!(parser.all_frames_parallel().expect("still image").is_empty());
+
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes)).expect("parse");
+    let frames = parser.all_frames_parallel().expect("frames");
+    assert_eq!(frames.len(), 5);
+    for (i, frame) in frames.iter().enumerate() {
+        let expected = parser.frame(i).expect("frame");
+        assert_eq!(frame.data, expected.data, "frame {i}");
+        assert_eq!(frame.alpha_data, expected.alpha_data, "frame {i}");
+        assert_eq!(frame.timestamp, expected.timestamp, "frame {i}");
+    }
+    assert!(parser.all_tiles_parallel().expect("no grid").is_empty());
+}
+
 #[test]
 fn parser_animation_frames() {
     let bytes = std::fs::read(ANIMATED_AVIF).expect("read file");
@@ -475,6 +587,84 @@ fn parser_metadata() {
     assert!(parser.alpha_metadata().is_none());
 }
 
+#[test]
+fn metadata_sequence_coding_tools() {
+    // Temporal delimiter and a 64×48 reduced still-picture sequence header
+    let plain = [0x12, 0x00, 0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x00, 0x08];
+    let meta = zenavif_parse::AV1Metadata::parse_av1_bitstream(&plain).expect("parse");
+    assert!(!meta.film_grain_params_present);
+    assert!(!meta.enable_superres && !meta.enable_cdef && !meta.enable_restoration);
+
+    // Same header with enable_cdef, enable_restoration and film_grain_params_present set
+    let grain = [0x12, 0x00, 0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x30, 0x18];
+    let meta = zenavif_parse::AV1Metadata::parse_av1_bitstream(&grain).expect("parse");
+    assert!(meta.film_grain_params_present);
+    assert!(!meta.enable_superres);
+    assert!(meta.enable_cdef && meta.enable_restoration);
+    assert_eq!((meta.max_frame_width.get(), meta.max_frame_height.get()), (64, 48));
+}
+
+#[test]
+fn metadata_from_annex_b_bitstream() {
+    let seq_payload = [0x18, 0x15, 0x7F, 0xBC, 0x02, 0x12, 0x20, 0x13, 0x08];
+    let low_overhead = [&[0x12, 0x00, 0x0A, 0x09][..], &seq_payload].concat();
+    // One temporal unit holding one frame unit: a temporal delimiter and the
+    // sequence header, neither with a size field
+    let annex_b = [&[14, 13, 1, 0x10, 10, 0x08][..], &seq_payload].concat();
+
+    let expected = zenavif_parse::AV1Metadata::parse_av1_bitstream(&low_overhead).expect("low overhead");
+    let meta = zenavif_parse::AV1Metadata::parse_av1_bitstream(&annex_b).expect("annex b");
+    assert_eq!((meta.max_frame_width.get(), meta.max_frame_height.get()), (64, 48));
+    assert_eq!(meta.color_info(), expected.color_info());
+    assert_eq!(meta.bit_depth, expected.bit_depth);
+
+    // Neither framing
+    assert!(zenavif_parse::AV1Metadata::parse_av1_bitstream(&annex_b[..annex_b.len() - 1]).is_err());
+}
+
+#[test]
+fn metadata_sequence_header_cicp() {
+    // No color description: everything unspecified, limited range
+    let plain = [0x12, 0x00, 0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x00, 0x08];
+    let meta = zenavif_parse::AV1Metadata::parse_av1_bitstream(&plain).expect("parse");
+    assert_eq!(
+        (meta.color_primaries, meta.transfer_characteristics, meta.matrix_coefficients, meta.full_range),
+        (2, 2, 2, false)
+    );
+
+    // BT.2020 primaries, PQ, BT.2020 NCL matrix, full range
+    let pq = [0x12, 0x00, 0x0A, 0x09, 0x18, 0x15, 0x7F, 0xBC, 0x02, 0x12, 0x20, 0x13, 0x08];
+    let meta = zenavif_parse::AV1Metadata::parse_av1_bitstream(&pq).expect("parse");
+    assert_eq!(
+        meta.color_info(),
+        zenavif_parse::ColorInformation::Nclx {
+            color_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            full_range: true,
+        }
+    );
+    assert_eq!((meta.max_frame_width.get(), meta.max_frame_height.get()), (64, 48));
+}
+
+#[cfg(feature = "dav1d")]
+#[test]
+fn dav1d_decodes_primary() {
+    use zenavif_parse::dav1d;
+
+    let bytes = std::fs::read(IMAGE_AVIF).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("from_bytes failed");
+    let mut decoder = dav1d::Decoder::with_settings(&dav1d::settings(&parser)).expect("decoder");
+    dav1d::send_primary(&mut decoder, &parser).expect("send");
+    let picture = decoder.get_picture().expect("picture");
+
+    let extents = parser.spatial_extents().expect("ispe");
+    assert_eq!((picture.width(), picture.height()), (extents.width, extents.height));
+    let hints = dav1d::OutputHints::new(&parser);
+    assert_eq!(Some(picture.bit_depth() as u8), hints.bit_depth);
+    assert!(!hints.has_alpha);
+}
+
 #[test]
 fn parser_spatial_extents_come_from_ispe() {
     let bytes = std::fs::read("tests/ispe-1x1.avif").expect("read file");
@@ -777,6 +967,86 @@ fn parser_brands_avis() {
     assert!(compat.iter().any(|b| b == b"MA1B"), "should have MA1B brand");
 }
 
+#[test]
+fn parser_accepts_avif_as_compatible_brand() {
+    let mut bytes = build_avif(&[TestItem::new(1, b"av01", b"primary").prop(ispe(4, 4))], 1, &[]);
+    // ftyp major brand; the compatible brands are avif, mif1, miaf
+    bytes[8..12].copy_from_slice(b"mif1");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.major_brand(), b"mif1");
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"primary");
+
+    // Without avif or avis anywhere, the file is not an AVIF
+    bytes[16..20].copy_from_slice(b"isom");
+    bytes[8..12].copy_from_slice(b"miaf");
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn profile_compliance_checks_advertised_brands() {
+    let with_av1c = |profile, level| {
+        let mut bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(av1c(profile, level))], 1, &[]);
+        // The compatible brands are avif, mif1, miaf
+        bytes[24..28].copy_from_slice(b"MA1B");
+        bytes
+    };
+
+    let bytes = with_av1c(0, 13);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.profile_compliance().expect("check"), vec![]);
+
+    let bytes = with_av1c(1, 16);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let violations: Vec<_> = parser
+        .profile_compliance()
+        .expect("check")
+        .iter()
+        .map(|v| (v.brand, v.image, v.field, v.value, v.limit))
+        .collect();
+    assert_eq!(violations, vec![(*b"MA1B", "primary", "seq_profile", 1, 0), (*b"MA1B", "primary", "seq_level_idx_0", 16, 13)]);
+
+    // Brands the file does not advertise are not checked
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(av1c(1, 16))], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.profile_compliance().expect("check"), vec![]);
+}
+
+#[test]
+fn profile_compliance_checks_tiles_and_alpha() {
+    let advertise_baseline = |mut bytes: Vec<u8>| {
+        bytes[24..28].copy_from_slice(b"MA1B");
+        bytes
+    };
+    let check = |bytes: &[u8]| -> Vec<(&'static str, &'static str, u32)> {
+        let parser = zenavif_parse::AvifParser::from_bytes(bytes).expect("parse");
+        parser.profile_compliance().expect("check").iter().map(|v| (v.image, v.field, v.value)).collect()
+    };
+
+    let grid = advertise_baseline(build_avif(
+        &[
+            TestItem::new(1, b"grid", &[0, 0, 0, 1, 0, 8, 0, 4]).prop(ispe(16, 4)),
+            TestItem::new(2, b"av01", b"left").prop(ispe(8, 4)).prop(av1c(0, 13)),
+            TestItem::new(3, b"av01", b"right").prop(ispe(8, 4)).prop(av1c(0, 14)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2, 3])],
+    ));
+    assert_eq!(check(&grid), vec![("grid tile", "seq_level_idx_0", 14)]);
+
+    let alpha = advertise_baseline(build_avif(
+        &[
+            TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(av1c(0, 13)),
+            TestItem::new(2, b"av01", b"alpha")
+                .prop(ispe(4, 4))
+                .prop(av1c(1, 13))
+                .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0")),
+        ],
+        1,
+        &[(b"auxl", 2, &[1])],
+    ));
+    assert_eq!(check(&alpha), vec![("alpha", "seq_profile", 1)]);
+}
+
 #[cfg(feature = "eager")]
 #[test]
 fn eager_brands() {
@@ -1029,8 +1299,8 @@ fn test_dir_all_paths(dir: &str) {
         // Map each `At<Error>` to its inner `&Error` so the existing
         // `Error::Unsupported(..)` patterns match the location-wrapped results.
         match (
-            eager_result.as_ref().map_err(|e| e.error()),
-            parser_result.as_ref().map_err(|e| e.error()),
+            eager_result.as_ref().map_err(|e| e.error().root()),
+            parser_result.as_ref().map_err(|e| e.error().root()),
         ) {
             (Ok(avif), Ok(parser)) => {
                 if avif.grid_config.is_none() {
@@ -1222,7 +1492,7 @@ fn decode_config_default_has_sane_limits() {
     assert_eq!(config.total_megapixels_limit, Some(512));
     assert_eq!(config.max_animation_frames, Some(10_000));
     assert_eq!(config.max_grid_tiles, Some(1_000));
-    assert!(!config.lenient);
+    assert_eq!(config.strictness, zenavif_parse::Strictness::Standard);
 }
 
 #[test]
@@ -1232,7 +1502,7 @@ fn decode_config_unlimited() {
     assert_eq!(config.total_megapixels_limit, None);
     assert_eq!(config.max_animation_frames, None);
     assert_eq!(config.max_grid_tiles, None);
-    assert!(!config.lenient);
+    assert_eq!(config.strictness, zenavif_parse::Strictness::Standard);
 }
 
 #[test]
@@ -1242,13 +1512,13 @@ fn decode_config_builder_methods() {
         .with_total_megapixels_limit(7)
         .with_max_animation_frames(3)
         .with_max_grid_tiles(5)
-        .lenient(true);
+        .with_strictness(zenavif_parse::Strictness::Pedantic);
 
     assert_eq!(config.peak_memory_limit, Some(42));
     assert_eq!(config.total_megapixels_limit, Some(7));
     assert_eq!(config.max_animation_frames, Some(3));
     assert_eq!(config.max_grid_tiles, Some(5));
-    assert!(config.lenient);
+    assert_eq!(config.strictness, zenavif_parse::Strictness::Pedantic);
 }
 
 // Parser-specific resource limit tests
@@ -1344,6 +1614,75 @@ fn parser_cancellation_via_reader() {
     }
 }
 
+#[test]
+fn large_iloc_is_checked_for_cancellation() {
+    struct Counting(std::sync::atomic::AtomicU32);
+    impl zenavif_parse::Stop for Counting {
+        fn check(&self) -> std::result::Result<(), zenavif_parse::StopReason> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        }
+    }
+    let checks = |item_count: u16| {
+        let mut items = vec![TestItem::new(1, b"av01", b"primary").prop(ispe(4, 4))];
+        items.extend((2..=item_count).map(|id| TestItem::new(id, b"mime", b"x")));
+        let bytes = build_avif(&items, 1, &[]);
+        let stop = Counting(std::sync::atomic::AtomicU32::new(0));
+        zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &zenavif_parse::DecodeConfig::default(), &stop)
+            .expect("parse");
+        stop.0.into_inner()
+    };
+    // Polled every 4096 iloc items: twice more for 9000 items than for one
+    assert_eq!(checks(9000), checks(1) + 2);
+}
+
+#[test]
+fn reader_is_read_in_chunks_with_progress_and_a_size_limit() {
+    /// An endless stream of zeroes, cancelled after a few chunks.
+    struct Endless;
+    impl std::io::Read for Endless {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            buf.fill(0);
+            Ok(buf.len())
+        }
+    }
+    struct AfterChecks(std::sync::atomic::AtomicU32);
+    impl zenavif_parse::Stop for AfterChecks {
+        fn check(&self) -> std::result::Result<(), zenavif_parse::StopReason> {
+            let checks = self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            if checks > 3 { Err(zenavif_parse::StopReason::Cancelled) } else { Ok(()) }
+        }
+    }
+
+    let mut reported = Vec::new();
+    let result = zenavif_parse::AvifParser::from_reader_with_progress(
+        &mut Endless,
+        &zenavif_parse::DecodeConfig::unlimited(),
+        &AfterChecks(std::sync::atomic::AtomicU32::new(0)),
+        |read| reported.push(read),
+    );
+    assert!(matches!(result.map_err(|e| e.decompose().0), Err(zenavif_parse::Error::Stopped(_))));
+    assert_eq!(reported.len(), 3);
+    assert!(reported.windows(2).all(|w| w[0] < w[1]));
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_input_bytes(100_000);
+    let result = zenavif_parse::AvifParser::from_reader_with_config(&mut Endless, &config, &zenavif_parse::Unstoppable);
+    assert!(matches!(
+        result.map_err(|e| e.decompose().0),
+        Err(zenavif_parse::Error::ResourceLimitExceeded("input exceeds max_input_bytes"))
+    ));
+
+    // Files within the limit are unaffected
+    let bytes = std::fs::read(IMAGE_AVIF).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default().with_max_input_bytes(bytes.len() as u64);
+    let mut last = 0;
+    let parser = zenavif_parse::AvifParser::from_reader_with_progress(
+        &mut &bytes[..], &config, &zenavif_parse::Unstoppable, |read| last = read,
+    ).expect("parse");
+    assert_eq!(last, bytes.len() as u64);
+    assert!(!parser.primary_data().expect("primary").is_empty());
+}
+
 // ============================================================================
 // Multi-track animation tests
 // ============================================================================
@@ -1479,6 +1818,92 @@ fn anim_loop_count_audio_file() {
     assert_eq!(info.loop_count, 1, "Audio file color track should play once");
 }
 
+/// Every frame's color and alpha payload, for comparing rewritten files.
+fn frame_payloads(parser: &zenavif_parse::AvifParser<'_>) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+    parser.frames()
+        .map(|frame| {
+            let frame = frame.expect("frame");
+            (frame.data.to_vec(), frame.alpha_data.map(|alpha| alpha.to_vec()))
+        })
+        .collect()
+}
+
+#[test]
+fn set_loop_count_patches_existing_elst() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let source = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let once = zenavif_parse::set_loop_count(&bytes, 1).expect("set loop count");
+    // Only the repeat flags change
+    assert_eq!(once.len(), bytes.len());
+    let parser = zenavif_parse::AvifParser::from_bytes(&once).expect("parse rewritten");
+    assert_eq!(parser.animation_info().expect("animation").loop_count, 1);
+    assert_eq!(frame_payloads(&parser), frame_payloads(&source));
+
+    let forever = zenavif_parse::set_loop_count(&once, 0).expect("set loop count");
+    assert_eq!(forever, bytes);
+}
+
+#[test]
+fn set_loop_count_adds_edts_and_shifts_offsets() {
+    let mut bytes = std::fs::read(ANIM_8BPC).expect("read file");
+    // Turn the track's edts into padding so a new one has to be inserted
+    let edts = bytes.windows(4).position(|w| w == b"edts").expect("edts");
+    bytes[edts..edts + 4].copy_from_slice(b"free");
+    let source = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(source.animation_info().expect("animation").loop_count, 1);
+
+    let looping = zenavif_parse::set_loop_count(&bytes, 0).expect("set loop count");
+    assert!(looping.len() > bytes.len());
+    let parser = zenavif_parse::AvifParser::from_bytes(&looping).expect("parse rewritten");
+    assert_eq!(parser.animation_info().expect("animation").loop_count, 0);
+    assert_eq!(frame_payloads(&parser), frame_payloads(&source));
+    assert_eq!(parser.primary_data().expect("primary"), source.primary_data().expect("primary"));
+}
+
+#[test]
+fn set_loop_count_rejects_unsupported_input() {
+    let anim = std::fs::read(ANIM_8BPC).expect("read file");
+    assert!(zenavif_parse::set_loop_count(&anim, 3).is_err());
+    let still = std::fs::read(IMAGE_AVIF).expect("read file");
+    let err = zenavif_parse::set_loop_count(&still, 0).expect_err("still images have no tracks");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::Unsupported(_)));
+}
+
+#[test]
+fn parser_animation_without_still_image() {
+    let source_bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let source = zenavif_parse::AvifParser::from_bytes(&source_bytes).expect("parse");
+    let mut bytes = source_bytes.clone();
+    // Keep the meta box and its hdlr, but drop every item from it
+    for name in [b"pitm", b"iloc", b"iinf"] {
+        let at = bytes.windows(4).position(|w| w == name).expect("box");
+        bytes[at..at + 4].copy_from_slice(b"free");
+    }
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let first = source.frame(0).expect("frame 0");
+    assert_eq!(parser.primary_data().expect("primary"), first.data);
+    let alpha = parser.alpha_data().expect("alpha").expect("alpha data");
+    assert_eq!(Some(alpha), first.alpha_data);
+    assert_eq!(frame_payloads(&parser), frame_payloads(&source));
+
+    let mut out = Vec::new();
+    parser.primary_data_into(&mut out).expect("primary");
+    assert_eq!(out, &*first.data);
+
+    // Both the in-place and the copying path fall back to frame 0
+    let shared = zenavif_parse::AvifParser::from_owned(bytes.clone()).expect("parse");
+    let clone = shared.clone();
+    assert_eq!(shared.into_primary().expect("copied"), &*first.data);
+    assert_eq!(clone.into_primary().expect("in place"), &*first.data);
+
+    // Without a moov there is no frame to fall back to
+    let moov_at = bytes.windows(4).position(|w| w == b"moov").expect("moov");
+    bytes[moov_at..moov_at + 4].copy_from_slice(b"free");
+    let err = zenavif_parse::AvifParser::from_bytes(&bytes).err().expect("item-less meta without moov");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData("meta box locates no items and there is no moov")));
+}
+
 // -- Zero-copy verification --
 
 #[test]
@@ -1780,6 +2205,16 @@ fn parser_gain_map_basic() {
     assert!(parser.gain_map_color_info().is_some(), "tmap colr property should be present");
 }
 
+#[test]
+fn parser_gain_map_item_properties() {
+    let bytes = std::fs::read("tests/gainmap/seine_sdr_gainmap_srgb.avif").expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("from_bytes failed");
+
+    let extents = parser.gain_map_spatial_extents().expect("gain map ispe");
+    assert_eq!((extents.width, extents.height), (400, 300));
+    assert!(parser.gain_map_av1_config().is_some(), "gain map av1C should be present");
+}
+
 #[test]
 fn parser_gain_map_grid() {
     let bytes = std::fs::read("tests/gainmap/color_grid_gainmap_different_grid.avif").expect("read file");
@@ -2461,3 +2896,3503 @@ fn parser_default_path_allows_within_total_megapixels_limit() {
         &bytes, &config, &zenavif_parse::Unstoppable,
     ).expect("parser should accept grid within total_megapixels_limit");
 }
+
+// ============================================================================
+// Exif / XMP metadata item tests
+// ============================================================================
+
+/// The `Exif` item's 4-byte `exif_tiff_header_offset` prefix is stripped.
+#[test]
+fn parser_exif_data() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let exif = parser
+        .exif_data()
+        .expect("exif_data() should be Some")
+        .expect("exif should resolve");
+    assert_eq!(&exif[..4], b"II*\0", "EXIF should start at the TIFF header");
+    assert_eq!(exif.len(), 1126);
+    assert!(matches!(exif, Cow::Borrowed(_)), "single-extent EXIF should be zero-copy");
+}
+
+#[test]
+fn parser_no_exif_on_normal_image() {
+    let bytes = std::fs::read(IMAGE_AVIF).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert!(parser.exif_data().is_none());
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_exif_matches_parser() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    #[allow(deprecated)]
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+
+    let exif = avif.exif.as_ref().expect("eager exif");
+    assert_eq!(&exif[..], &parser.exif_data().unwrap().unwrap()[..]);
+}
+
+/// XMP is read from the `mime` item whose content type is `application/rdf+xml`.
+#[test]
+fn parser_xmp_data() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let xmp = parser
+        .xmp_data()
+        .expect("xmp_data() should be Some")
+        .expect("xmp should resolve");
+    assert!(xmp.starts_with(b"<?xpacket begin="));
+    assert!(xmp.ends_with(b"<?xpacket end=\"w\"?>"));
+}
+
+#[test]
+fn parser_no_xmp_on_normal_image() {
+    let bytes = std::fs::read(IMAGE_AVIF).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert!(parser.xmp_data().is_none());
+}
+
+/// XMP can also be a `uuid` box, as some writers store it.
+#[test]
+fn parser_uuid_boxes_and_xmp_in_uuid() {
+    let uuid_box = |uuid: &[u8; 16], payload: &[u8]| bmff_box(b"uuid", &[&uuid[..], payload].concat());
+    let vendor = [7; 16];
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))];
+    let mut bytes = build_avif_with_meta_boxes(&items, 1, &[], &[uuid_box(&vendor, b"vendor")]);
+    let xmp = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>";
+    let xmp_at = bytes.len() as u64 + 24;
+    bytes.extend_from_slice(&uuid_box(&zenavif_parse::UuidBox::XMP, xmp));
+
+    let parsers = [
+        zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse"),
+        zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes.clone())).expect("parse"),
+    ];
+    for parser in parsers {
+        let boxes = parser.uuid_boxes();
+        assert_eq!(boxes.len(), 2);
+        assert_eq!((boxes[0].uuid, boxes[0].in_meta), (vendor, true));
+        assert_eq!(&bytes[boxes[0].payload.start as usize..boxes[0].payload.end as usize], b"vendor");
+        assert_eq!(
+            (boxes[1].uuid, boxes[1].in_meta, boxes[1].payload.clone()),
+            (zenavif_parse::UuidBox::XMP, false, xmp_at..xmp_at + xmp.len() as u64),
+        );
+        assert_eq!(parser.xmp_data().expect("xmp").expect("resolve").as_ref(), xmp);
+        assert!(parser.summary().has_xmp);
+    }
+}
+
+/// Path, uuid and the start of the payload of one unknown box.
+type CollectedBox = (Vec<[u8; 4]>, Option<[u8; 16]>, Vec<u8>);
+
+/// Collects what [`zenavif_parse::BoxHandler`] is given.
+#[derive(Default)]
+struct CollectBoxes(std::sync::Mutex<Vec<CollectedBox>>);
+
+impl zenavif_parse::BoxHandler for CollectBoxes {
+    fn unknown_box(&self, path: &[[u8; 4]], uuid: Option<&[u8; 16]>, payload: &mut dyn std::io::Read) -> std::io::Result<()> {
+        // Read part of the payload; the parser skips the rest
+        let mut data = vec![0; 2];
+        payload.read_exact(&mut data)?;
+        self.0.lock().unwrap().push((path.to_vec(), uuid.copied(), data));
+        Ok(())
+    }
+}
+
+#[test]
+fn box_handler_receives_unknown_boxes() {
+    let vendor = [7; 16];
+    let uuid_box = bmff_box(b"uuid", &[&vendor[..], b"vendor"].concat());
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(bmff_box(b"xopt", b"option"))];
+    let mut bytes = build_avif_with_meta_boxes(&items, 1, &[], &[uuid_box]);
+    bytes.extend_from_slice(&bmff_box(b"zzzz", b"trailer"));
+
+    let expected = [
+        (vec![*b"meta", *b"iprp", *b"ipco", *b"xopt"], None, b"op".to_vec()),
+        (vec![*b"meta", *b"uuid"], Some(vendor), b"ve".to_vec()),
+        (vec![*b"zzzz"], None, b"tr".to_vec()),
+    ];
+    for seekable in [false, true] {
+        let handler = std::sync::Arc::new(CollectBoxes::default());
+        let config = zenavif_parse::DecodeConfig { box_handler: Some(handler.clone()), ..Default::default() };
+        if seekable {
+            zenavif_parse::AvifParser::from_seekable_with_config(std::io::Cursor::new(bytes.clone()), &config, &zenavif_parse::Unstoppable)
+                .expect("parse");
+        } else {
+            zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).expect("parse");
+        }
+        assert_eq!(*handler.0.lock().unwrap(), expected);
+    }
+}
+
+#[test]
+fn preserve_layout_records_every_box() {
+    let uuid_box = bmff_box(b"uuid", &[&[7; 16][..], b"vendor"].concat());
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))];
+    let mut bytes = build_avif_with_meta_boxes(&items, 1, &[], &[uuid_box]);
+    let trailer_at = bytes.len() as u64;
+    bytes.extend_from_slice(&bmff_box(b"zzzz", b"trailer"));
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert!(parser.layout().is_none());
+
+    let config = zenavif_parse::DecodeConfig::default().preserve_layout(true);
+    let layout = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("parse")
+        .layout()
+        .expect("layout")
+        .clone();
+    let seekable = zenavif_parse::AvifParser::from_seekable_with_config(std::io::Cursor::new(bytes.clone()), &config, &zenavif_parse::Unstoppable)
+        .expect("parse");
+    assert_eq!(seekable.layout(), Some(&layout));
+    assert_eq!(layout.file_size, bytes.len() as u64);
+
+    // Top-level boxes cover the file; children lie within the box before them at the level above
+    let top: Vec<_> = layout.boxes.iter().filter(|b| b.depth == 1).collect();
+    let types: Vec<_> = top.iter().map(|b| &b.box_type).collect();
+    assert_eq!(types, [b"ftyp", b"meta", b"mdat", b"zzzz"]);
+    assert_eq!(top[0].range.start, 0);
+    assert!(top.windows(2).all(|pair| pair[0].range.end == pair[1].range.start));
+    assert_eq!(top[3].range, trailer_at..bytes.len() as u64);
+    assert!(!top[3].known);
+    for (i, b) in layout.boxes.iter().enumerate().filter(|(_, b)| b.depth > 1) {
+        let parent = layout.boxes[..i].iter().rev().find(|p| p.depth == b.depth - 1).expect("parent");
+        assert!(parent.range.start + parent.header_size <= b.range.start && b.range.end <= parent.range.end);
+    }
+    for b in &layout.boxes {
+        let size = u32::from_be_bytes(bytes[b.range.start as usize..][..4].try_into().unwrap());
+        assert_eq!((size as u64, &bytes[b.range.start as usize + 4..][..4]), (b.range.end - b.range.start, &b.box_type[..]));
+    }
+
+    let uuid = layout.boxes.iter().find(|b| b.box_type == *b"uuid").expect("uuid box");
+    assert_eq!((uuid.depth, uuid.uuid, uuid.header_size, uuid.known), (2, Some([7; 16]), 24, false));
+    assert!(layout.boxes.iter().any(|b| b.box_type == *b"ispe" && b.depth == 4 && b.known));
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_xmp_matches_parser() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    #[allow(deprecated)]
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+
+    let xmp = avif.xmp_data().expect("eager xmp");
+    assert_eq!(xmp, &parser.xmp_data().unwrap().unwrap()[..]);
+}
+
+// ============================================================================
+// Synthetic file builder
+// ============================================================================
+
+/// Serialize a plain ISOBMFF box.
+fn bmff_box(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(8 + payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(name);
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// Serialize a FullBox (version + 24-bit flags header).
+fn full_box(name: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+    let mut body = vec![version];
+    body.extend_from_slice(&flags.to_be_bytes()[1..]);
+    body.extend_from_slice(payload);
+    bmff_box(name, &body)
+}
+
+/// An item for [`build_avif`]: its data is stored in `mdat` as a single extent.
+struct TestItem {
+    id: u16,
+    item_type: &'static [u8; 4],
+    content_type: Option<&'static str>,
+    data: Vec<u8>,
+    /// Complete property boxes, associated in order (essential for transformative ones).
+    props: Vec<Vec<u8>>,
+    /// `(extent_index, extent_offset, extent_length)` for construction_method 2;
+    /// when non-empty, `data` is ignored.
+    item_offset_extents: Vec<(u32, u32, u32)>,
+}
+
+impl TestItem {
+    fn new(id: u16, item_type: &'static [u8; 4], data: &[u8]) -> Self {
+        Self { id, item_type, content_type: None, data: data.to_vec(), props: Vec::new(), item_offset_extents: Vec::new() }
+    }
+
+    /// An item whose data is built from other items (`iloc` construction_method 2).
+    fn constructed(id: u16, item_type: &'static [u8; 4], extents: &[(u32, u32, u32)]) -> Self {
+        Self { item_offset_extents: extents.to_vec(), ..Self::new(id, item_type, &[]) }
+    }
+
+    fn prop(mut self, property: Vec<u8>) -> Self {
+        self.props.push(property);
+        self
+    }
+}
+
+fn ispe(width: u32, height: u32) -> Vec<u8> {
+    let mut payload = width.to_be_bytes().to_vec();
+    payload.extend_from_slice(&height.to_be_bytes());
+    full_box(b"ispe", 0, 0, &payload)
+}
+
+/// Build a still AVIF: `ftyp` + `meta` (hdlr/pitm/iloc/iinf/iref/iprp) + `mdat`.
+///
+/// `irefs` entries are `(reference_type, from_item_id, to_item_ids)`.
+fn build_avif(items: &[TestItem], primary: u16, irefs: &[(&[u8; 4], u16, &[u16])]) -> Vec<u8> {
+    build_avif_with_meta_boxes(items, primary, irefs, &[])
+}
+
+/// `ftyp` with major brand `avif` and compatible brands `avif`, `mif1`, `miaf`.
+fn avif_ftyp() -> Vec<u8> {
+    let mut ftyp = b"avif".to_vec();
+    ftyp.extend_from_slice(&0u32.to_be_bytes());
+    ftyp.extend_from_slice(b"avifmif1miaf");
+    bmff_box(b"ftyp", &ftyp)
+}
+
+/// The `meta` box of [`build_avif_with_meta_boxes`], with each item's data
+/// at the file offset in `item_offsets` (ignored for `item_offset_extents`).
+fn avif_meta(
+    items: &[TestItem],
+    primary: u16,
+    irefs: &[(&[u8; 4], u16, &[u16])],
+    extra: &[Vec<u8>],
+    item_offsets: &[u32],
+) -> Vec<u8> {
+    let mut hdlr = vec![0; 4];
+    hdlr.extend_from_slice(b"pict");
+    hdlr.extend_from_slice(&[0; 13]);
+
+    // iloc v1: 4-byte offsets/lengths, no base_offset, 4-byte extent_index
+    let mut iloc = vec![0x44, 0x04];
+    iloc.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    for (item, offset) in items.iter().zip(item_offsets) {
+        iloc.extend_from_slice(&item.id.to_be_bytes());
+        if item.item_offset_extents.is_empty() {
+            iloc.extend_from_slice(&0u16.to_be_bytes()); // construction_method 0
+            iloc.extend_from_slice(&0u16.to_be_bytes()); // data_reference_index
+            iloc.extend_from_slice(&1u16.to_be_bytes()); // extent_count
+            iloc.extend_from_slice(&0u32.to_be_bytes());
+            iloc.extend_from_slice(&offset.to_be_bytes());
+            iloc.extend_from_slice(&(item.data.len() as u32).to_be_bytes());
+        } else {
+            iloc.extend_from_slice(&2u16.to_be_bytes()); // construction_method 2
+            iloc.extend_from_slice(&0u16.to_be_bytes());
+            iloc.extend_from_slice(&(item.item_offset_extents.len() as u16).to_be_bytes());
+            for &(index, extent_offset, extent_length) in &item.item_offset_extents {
+                iloc.extend_from_slice(&index.to_be_bytes());
+                iloc.extend_from_slice(&extent_offset.to_be_bytes());
+                iloc.extend_from_slice(&extent_length.to_be_bytes());
+            }
+        }
+    }
+
+    let mut iinf = (items.len() as u16).to_be_bytes().to_vec();
+    for item in items {
+        let mut infe = item.id.to_be_bytes().to_vec();
+        infe.extend_from_slice(&0u16.to_be_bytes());
+        infe.extend_from_slice(item.item_type);
+        infe.push(0); // item_name
+        if let Some(content_type) = item.content_type {
+            infe.extend_from_slice(content_type.as_bytes());
+            infe.push(0);
+        }
+        iinf.extend_from_slice(&full_box(b"infe", 2, 0, &infe));
+    }
+
+    let mut iref = Vec::new();
+    for &(reference_type, from, tos) in irefs {
+        let mut entry = from.to_be_bytes().to_vec();
+        entry.extend_from_slice(&(tos.len() as u16).to_be_bytes());
+        for to in tos {
+            entry.extend_from_slice(&to.to_be_bytes());
+        }
+        iref.extend_from_slice(&bmff_box(reference_type, &entry));
+    }
+
+    let mut ipco = Vec::new();
+    let mut ipma = (items.len() as u32).to_be_bytes().to_vec();
+    // More than 127 properties need the 15-bit index form (flags = 1)
+    let wide = items.iter().map(|item| item.props.len()).sum::<usize>() > 127;
+    let mut property_index = 0u16;
+    for item in items {
+        ipma.extend_from_slice(&item.id.to_be_bytes());
+        ipma.push(item.props.len() as u8);
+        for prop in &item.props {
+            ipco.extend_from_slice(prop);
+            property_index += 1;
+            // Transformative properties must be marked essential; `xess` is
+            // an unknown property that tests mark essential too
+            let essential = [b"clap", b"irot", b"imir", b"xess"].contains(&&[prop[4], prop[5], prop[6], prop[7]]);
+            if wide {
+                ipma.extend_from_slice(&(property_index | if essential { 0x8000 } else { 0 }).to_be_bytes());
+            } else {
+                ipma.push(property_index as u8 | if essential { 0x80 } else { 0 });
+            }
+        }
+    }
+    let mut iprp = bmff_box(b"ipco", &ipco);
+    iprp.extend_from_slice(&full_box(b"ipma", 0, u32::from(wide), &ipma));
+
+    let mut meta = full_box(b"hdlr", 0, 0, &hdlr);
+    // Item ID 0 is reserved, so `primary: 0` leaves out pitm
+    if primary != 0 {
+        meta.extend_from_slice(&full_box(b"pitm", 0, 0, &primary.to_be_bytes()));
+    }
+    meta.extend_from_slice(&full_box(b"iloc", 1, 0, &iloc));
+    meta.extend_from_slice(&full_box(b"iinf", 0, 0, &iinf));
+    if !irefs.is_empty() {
+        meta.extend_from_slice(&full_box(b"iref", 0, 0, &iref));
+    }
+    meta.extend_from_slice(&bmff_box(b"iprp", &iprp));
+    for b in extra {
+        meta.extend_from_slice(b);
+    }
+    full_box(b"meta", 0, 0, &meta)
+}
+
+/// Like [`build_avif`], with `extra` complete boxes appended to `meta` after `iprp`.
+fn build_avif_with_meta_boxes(
+    items: &[TestItem],
+    primary: u16,
+    irefs: &[(&[u8; 4], u16, &[u16])],
+    extra: &[Vec<u8>],
+) -> Vec<u8> {
+    let ftyp = avif_ftyp();
+
+    // Offsets are fixed-width, so the meta size does not depend on them.
+    let meta_len = avif_meta(items, primary, irefs, extra, &vec![0; items.len()]).len();
+    let mut offset = (ftyp.len() + meta_len + 8) as u32;
+    let mut item_offsets = Vec::new();
+    let mut mdat = Vec::new();
+    for item in items {
+        item_offsets.push(offset);
+        if item.item_offset_extents.is_empty() {
+            mdat.extend_from_slice(&item.data);
+            offset += item.data.len() as u32;
+        }
+    }
+
+    let mut file = ftyp;
+    file.extend_from_slice(&avif_meta(items, primary, irefs, extra, &item_offsets));
+    file.extend_from_slice(&bmff_box(b"mdat", &mdat));
+    file
+}
+
+/// 8-bit 4:2:0 `av1C` for the given profile and level.
+fn av1c(profile: u8, level: u8) -> Vec<u8> {
+    bmff_box(b"av1C", &[0x81, (profile << 5) | level, 0x0c, 0x00])
+}
+
+#[test]
+fn synthetic_builder_roundtrips_primary_data() {
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"primary").prop(ispe(4, 4))], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"primary");
+    assert_eq!(parser.spatial_extents().map(|e| (e.width, e.height)), Some((4, 4)));
+}
+
+#[test]
+fn verify_codec_config_reports_mismatches() {
+    // Temporal delimiter and a 64×48 profile 0, level 0, 8-bit 4:2:0 sequence header
+    let bitstream = [0x12, 0x00, 0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x00, 0x08];
+
+    let item = TestItem::new(1, b"av01", &bitstream).prop(ispe(64, 48)).prop(av1c(0, 0));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.verify_codec_config().expect("verify"), []);
+
+    let item = TestItem::new(1, b"av01", &bitstream).prop(ispe(64, 48)).prop(av1c(1, 8));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let mismatches = parser.verify_codec_config().expect("verify");
+    let fields: Vec<_> = mismatches.iter().map(|m| (m.field, m.av1c, m.sequence_header)).collect();
+    assert_eq!(fields, [("seq_profile", 1, 0), ("seq_level_idx_0", 8, 0)]);
+
+    // Nothing to check against
+    let item = TestItem::new(1, b"av01", &bitstream).prop(ispe(64, 48));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert!(parser.verify_codec_config().is_err());
+}
+
+#[test]
+fn primary_decoder_payload_prepends_config_obus() {
+    let seq_header = [0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x00, 0x08];
+    let mut av1c_with_obus = av1c(0, 0);
+    av1c_with_obus.extend_from_slice(&seq_header);
+    av1c_with_obus[3] += seq_header.len() as u8;
+
+    let item = TestItem::new(1, b"av01", b"frame").prop(ispe(64, 48)).prop(av1c_with_obus);
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.av1_config().expect("av1C").config_obus, seq_header);
+    let payload = parser.primary_decoder_payload().expect("payload");
+    assert_eq!(&payload[..], [&seq_header[..], b"frame"].concat());
+
+    let item = TestItem::new(1, b"av01", b"frame").prop(ispe(64, 48)).prop(av1c(0, 0));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert!(parser.av1_config().expect("av1C").config_obus.is_empty());
+    let payload = parser.primary_decoder_payload().expect("payload");
+    assert!(matches!(payload, std::borrow::Cow::Borrowed(b"frame")));
+}
+
+// ============================================================================
+// ICC profile tests
+// ============================================================================
+
+fn colr_nclx(color_primaries: u16, transfer_characteristics: u16, matrix_coefficients: u16) -> Vec<u8> {
+    let mut payload = b"nclx".to_vec();
+    payload.extend_from_slice(&color_primaries.to_be_bytes());
+    payload.extend_from_slice(&transfer_characteristics.to_be_bytes());
+    payload.extend_from_slice(&matrix_coefficients.to_be_bytes());
+    payload.push(0x80);
+    bmff_box(b"colr", &payload)
+}
+
+fn colr_icc(colour_type: &[u8; 4], icc: &[u8]) -> Vec<u8> {
+    let mut payload = colour_type.to_vec();
+    payload.extend_from_slice(icc);
+    bmff_box(b"colr", &payload)
+}
+
+#[test]
+fn known_color_space_from_nclx_or_sequence_header() {
+    let space = |props: Vec<Vec<u8>>, data: &[u8]| {
+        let item = props.into_iter().fold(TestItem::new(1, b"av01", data).prop(ispe(64, 48)), TestItem::prop);
+        zenavif_parse::AvifParser::from_bytes(&build_avif(&[item], 1, &[])).expect("parse").known_color_space()
+    };
+    use zenavif_parse::KnownColorSpace;
+    assert_eq!(space(vec![colr_nclx(1, 13, 6)], b"av1"), Some(KnownColorSpace::Srgb));
+    assert_eq!(space(vec![colr_nclx(12, 13, 6)], b"av1"), Some(KnownColorSpace::DisplayP3));
+    assert_eq!(space(vec![colr_nclx(9, 18, 9)], b"av1"), Some(KnownColorSpace::Bt2020Hlg));
+    assert_eq!(space(vec![colr_nclx(2, 2, 2)], b"av1"), None);
+    assert_eq!(space(vec![colr_icc(b"prof", b"fake icc")], b"av1"), None);
+
+    // Without colr, the sequence header's CICP (BT.2020, PQ) applies
+    let pq = [0x12, 0x00, 0x0A, 0x09, 0x18, 0x15, 0x7F, 0xBC, 0x02, 0x12, 0x20, 0x13, 0x08];
+    assert_eq!(space(vec![], &pq), Some(KnownColorSpace::Bt2020Pq));
+    assert_eq!(space(vec![], b"not av1"), None);
+}
+
+#[test]
+fn hdr_kind_classifies_transfer_gain_map_and_hdr10_metadata() {
+    use zenavif_parse::HdrKind;
+    let kind = |props: Vec<Vec<u8>>| {
+        let item = props.into_iter().fold(TestItem::new(1, b"av01", b"av1").prop(ispe(4, 4)), TestItem::prop);
+        zenavif_parse::AvifParser::from_bytes(&build_avif(&[item], 1, &[])).expect("parse").hdr_kind()
+    };
+    assert_eq!(kind(vec![colr_nclx(9, 16, 9)]), HdrKind::Pq);
+    assert_eq!(kind(vec![colr_nclx(9, 18, 9)]), HdrKind::Hlg);
+    assert_eq!(kind(vec![colr_nclx(1, 13, 6)]), HdrKind::Sdr);
+
+    // No transfer known: clli on a 10-bit image is HDR10, on an 8-bit one it is not enough
+    let clli = bmff_box(b"clli", &[0x03, 0xE8, 0x01, 0x90]);
+    let av1c_10bit = bmff_box(b"av1C", &[0x81, 0x08, 0x4c, 0x00]);
+    assert_eq!(kind(vec![av1c_10bit.clone(), clli.clone()]), HdrKind::Pq);
+    assert_eq!(kind(vec![av1c(0, 8), clli]), HdrKind::Sdr);
+    assert_eq!(kind(vec![av1c_10bit]), HdrKind::Sdr);
+
+    let bytes = std::fs::read("tests/gainmap/seine_sdr_gainmap_srgb.avif").expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.hdr_kind(), HdrKind::GainMap);
+}
+
+#[test]
+fn bits_per_channel_from_pixi() {
+    let color = TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(full_box(b"pixi", 0, 0, &[3, 10, 10, 10]));
+    let alpha = TestItem::new(2, b"av01", b"alpha")
+        .prop(ispe(4, 4))
+        .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0"))
+        .prop(full_box(b"pixi", 0, 0, &[1, 10]));
+    let bytes = build_avif(&[color, alpha], 1, &[(b"auxl", 2, &[1])]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.bits_per_channel(), Some(&[10, 10, 10][..]));
+    assert_eq!(parser.alpha_bits_per_channel(), Some(&[10][..]));
+
+    let item = TestItem::new(1, b"av01", b"color").prop(ispe(4, 4));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!((parser.bits_per_channel(), parser.alpha_bits_per_channel()), (None, None));
+}
+
+#[test]
+fn pixel_information_version_1() {
+    // Y: unsigned, no extras; Cb: 4:2:0 at location 1; Cr: labeled
+    let payload = [3, 10, 10, 10, 0x00, 0x22, 0x11, 0x41, b'C', b'r', 0];
+    let color = TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(full_box(b"pixi", 1, 0, &payload));
+    let bytes = build_avif(&[color], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let pixi = parser.pixel_information().expect("pixi");
+    assert_eq!(pixi.bits_per_channel, vec![10, 10, 10]);
+    assert_eq!(parser.bits_per_channel(), Some(&[10, 10, 10][..]));
+    let channels: Vec<_> = pixi
+        .channels
+        .iter()
+        .map(|c| (c.channel_idc, c.component_format, c.subsampling_type, c.subsampling_location, c.label.as_deref()))
+        .collect();
+    assert_eq!(channels, vec![(0, 0, None, None, None), (1, 0, Some(1), Some(1), None), (2, 0, None, None, Some("Cr"))]);
+}
+
+#[test]
+fn auxiliary_items_report_urn_and_subtype() {
+    let color = TestItem::new(1, b"av01", b"color").prop(ispe(4, 4));
+    let alpha = TestItem::new(2, b"av01", b"alpha")
+        .prop(ispe(4, 4))
+        .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0"));
+    let gain_map = TestItem::new(3, b"av01", b"gain")
+        .prop(ispe(2, 2))
+        .prop(full_box(b"auxC", 0, 0, b"urn:com:apple:photo:2020:aux:hdrgainmap\0\x01\x02"));
+    let bytes = build_avif(&[color, alpha, gain_map], 1, &[(b"auxl", 2, &[1]), (b"auxl", 3, &[1])]);
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let aux: Vec<_> = parser.auxiliary_items().iter()
+        .map(|a| (a.item_id, a.aux_type.as_str(), a.aux_subtype.as_slice(), a.auxiliary_for.as_slice()))
+        .collect();
+    assert_eq!(
+        aux,
+        [
+            (2, "urn:mpeg:mpegB:cicp:systems:auxiliary:alpha", &[][..], &[1][..]),
+            (3, "urn:com:apple:photo:2020:aux:hdrgainmap", &[1, 2][..], &[1][..]),
+        ]
+    );
+    assert!(parser.alpha_data().is_some());
+}
+
+#[test]
+fn parser_references_graph() {
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"av01", b"thumb").prop(ispe(2, 2)),
+        TestItem::new(3, b"Exif", b"\0\0\0\0exif"),
+    ];
+    let bytes = build_avif(&items, 1, &[(b"thmb", 2, &[1]), (b"cdsc", 3, &[1, 2]), (b"xref", 1, &[3])]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let edges: Vec<_> = parser.references().iter().map(|r| (&r.reference_type, r.from_item_id, r.to_item_id, r.index)).collect();
+    assert_eq!(edges, [(b"thmb", 2, 1, 0), (b"cdsc", 3, 1, 0), (b"cdsc", 3, 2, 1), (b"xref", 1, 3, 0)]);
+
+    let bytes = build_avif(&items[..1], 1, &[]);
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).unwrap().references().is_empty());
+}
+
+#[test]
+fn alpha_must_match_the_color_item() {
+    let file = |alpha_ispe: (u32, u32), alpha_av1c: Vec<u8>| {
+        let color = TestItem::new(1, b"av01", b"color").prop(ispe(8, 8)).prop(av1c(0, 8));
+        let alpha = TestItem::new(2, b"av01", b"alpha")
+            .prop(ispe(alpha_ispe.0, alpha_ispe.1))
+            .prop(alpha_av1c)
+            .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0"));
+        build_avif(&[color, alpha], 1, &[(b"auxl", 2, &[1])])
+    };
+    let permissive = zenavif_parse::DecodeConfig::default().with_strictness(zenavif_parse::Strictness::Permissive);
+    let warned = |bytes: &[u8]| {
+        let parser = zenavif_parse::AvifParser::from_bytes_with_config(bytes, &permissive, &zenavif_parse::Unstoppable)
+            .expect("permissive parse");
+        parser.warnings().iter()
+            .filter(|w| w.kind == zenavif_parse::ParseWarningKind::AlphaMismatch)
+            .map(|w| w.fourcc)
+            .collect::<Vec<_>>()
+    };
+
+    let consistent = file((8, 8), av1c(0, 8));
+    assert!(zenavif_parse::AvifParser::from_bytes(&consistent).is_ok());
+    assert!(warned(&consistent).is_empty());
+
+    let smaller = file((4, 4), av1c(0, 8));
+    let err = zenavif_parse::AvifParser::from_bytes(&smaller).err().expect("size mismatch");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData("alpha item size differs from the color item")));
+    assert_eq!(warned(&smaller), [*b"ispe"]);
+
+    // 10-bit alpha on an 8-bit color item
+    let deeper = file((8, 8), bmff_box(b"av1C", &[0x81, 0x08, 0x4c, 0x00]));
+    assert!(zenavif_parse::AvifParser::from_bytes(&deeper).is_err());
+    assert_eq!(warned(&deeper), [*b"av1C"]);
+}
+
+#[test]
+fn parser_icc_profile_prof() {
+    let item = TestItem::new(1, b"av01", b"av1").prop(ispe(4, 4)).prop(colr_icc(b"prof", b"fake icc"));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(parser.icc_profile().as_deref(), Some(&b"fake icc"[..]));
+    assert_eq!(
+        parser.color_info(),
+        Some(&zenavif_parse::ColorInformation::IccProfile(b"fake icc".to_vec()))
+    );
+}
+
+#[test]
+fn parser_icc_profile_alongside_nclx() {
+    let item = TestItem::new(1, b"av01", b"av1")
+        .prop(ispe(4, 4))
+        .prop(colr_nclx(9, 16, 9))
+        .prop(colr_icc(b"rICC", b"restricted icc"));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(parser.icc_profile().as_deref(), Some(&b"restricted icc"[..]));
+    assert!(matches!(
+        parser.color_info(),
+        Some(zenavif_parse::ColorInformation::Nclx { color_primaries: 9, .. })
+    ));
+}
+
+#[test]
+fn parser_no_icc_profile_with_nclx_only() {
+    let item = TestItem::new(1, b"av01", b"av1").prop(ispe(4, 4)).prop(colr_nclx(1, 13, 6));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert!(parser.icc_profile().is_none());
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_icc_profile_alongside_nclx() {
+    let item = TestItem::new(1, b"av01", b"av1")
+        .prop(ispe(4, 4))
+        .prop(colr_nclx(9, 16, 9))
+        .prop(colr_icc(b"prof", b"fake icc"));
+    let bytes = build_avif(&[item], 1, &[]);
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+
+    assert_eq!(avif.icc_profile.as_deref(), Some(&b"fake icc"[..]));
+}
+
+// ============================================================================
+// Thumbnail tests
+// ============================================================================
+
+#[test]
+fn parser_thumbnail_via_thmb() {
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"av01", b"full size").prop(ispe(640, 480)).prop(av1c(0, 8)),
+            TestItem::new(2, b"av01", b"thumb").prop(ispe(160, 120)).prop(av1c(0, 1)),
+        ],
+        1,
+        &[(b"thmb", 2, &[1])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let thumb = parser.thumbnail_data().expect("thumbnail").expect("resolve");
+    assert_eq!(&thumb[..], b"thumb");
+    let extents = parser.thumbnail_spatial_extents().expect("thumbnail ispe");
+    assert_eq!((extents.width, extents.height), (160, 120));
+    assert_eq!(parser.thumbnail_av1_config().map(|c| c.level), Some(1));
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"full size");
+}
+
+#[test]
+fn parser_drops_thumbnail_without_iloc() {
+    let mut bytes = build_avif(
+        &[
+            TestItem::new(1, b"av01", b"full size").prop(ispe(640, 480)),
+            TestItem::new(2, b"av01", b"thumb").prop(ispe(160, 120)),
+        ],
+        1,
+        &[(b"thmb", 2, &[1])],
+    );
+    // Point the thumbnail's iloc entry at an item that does not exist
+    let entry_at = iloc_extent_at(&bytes, 2) - 12;
+    bytes[entry_at + 1] = 9;
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"full size");
+    assert!(parser.thumbnail_data().is_none());
+    let kinds: Vec<_> = parser.warnings().iter().map(|w| (w.kind, w.fourcc)).collect();
+    assert_eq!(kinds, [(zenavif_parse::ParseWarningKind::MalformedThumbnail, *b"thmb")]);
+}
+
+#[test]
+fn parser_no_thumbnail_without_thmb() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert!(parser.thumbnail_data().is_none());
+    assert!(parser.thumbnail_metadata().is_none());
+    assert!(parser.thumbnail_spatial_extents().is_none());
+}
+
+// ============================================================================
+// iloc construction_method 2 (item_offset) tests
+// ============================================================================
+
+/// Primary item 1 is stitched together from slices of items 2 and 3.
+fn item_offset_avif() -> Vec<u8> {
+    build_avif(
+        &[
+            TestItem::constructed(1, b"av01", &[(1, 2, 3), (2, 0, 0)]).prop(ispe(4, 4)),
+            TestItem::new(2, b"av01", b"xxabcxx"),
+            TestItem::new(3, b"av01", b"defg"),
+        ],
+        1,
+        &[(b"iloc", 1, &[2, 3])],
+    )
+}
+
+#[test]
+fn parser_item_offset_construction() {
+    let bytes = item_offset_avif();
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"abcdefg");
+}
+
+#[test]
+fn parser_item_offset_nested() {
+    let bytes = build_avif(
+        &[
+            TestItem::constructed(1, b"av01", &[(1, 1, 2)]).prop(ispe(4, 4)),
+            TestItem::constructed(2, b"av01", &[(1, 1, 0)]),
+            TestItem::new(3, b"av01", b"wxyz"),
+        ],
+        1,
+        &[(b"iloc", 1, &[2]), (b"iloc", 2, &[3])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"yz");
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_item_construction_depth(1);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("depth limit should reject nested construction");
+    assert!(matches!(err.error(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+/// Each item repeats the previous one twice, doubling the flattened extents per level.
+#[test]
+fn parser_item_offset_extent_blowup() {
+    let mut items = vec![TestItem::new(1, b"av01", b"ab")];
+    for id in 2..=40u16 {
+        let item = TestItem::constructed(id, b"av01", &[(1, 0, 0), (1, 0, 0)]);
+        items.push(if id == 40 { item.prop(ispe(4, 4)) } else { item });
+    }
+    let sources: Vec<[u16; 1]> = (1..40u16).map(|id| [id]).collect();
+    let irefs: Vec<(&[u8; 4], u16, &[u16])> = sources.iter().map(|s| (b"iloc", s[0] + 1, &s[..])).collect();
+    let bytes = build_avif(&items, 40, &irefs);
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_item_construction_depth(64);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("extent cap should reject the construction");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+/// `meta/iprp/ipco/ispe` is four levels deep.
+#[test]
+fn parser_max_box_depth() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default().with_max_box_depth(4);
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("four levels are allowed");
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_box_depth(3);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("depth limit should reject ipco children");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+
+    let anim = std::fs::read(ANIM_8BPC).expect("read file");
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&anim, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("depth limit should reject the sample table");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+#[test]
+fn parser_max_total_boxes() {
+    let padding: Vec<_> = (0..1000).map(|_| bmff_box(b"free", &[])).collect();
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))];
+    let bytes = build_avif_with_meta_boxes(&items, 1, &[], &padding);
+    let boxes = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").stats().boxes;
+    assert!(boxes > 1000);
+
+    for (limit, allowed) in [(boxes, true), (boxes - 1, false)] {
+        let config = zenavif_parse::DecodeConfig::default().with_max_total_boxes(limit);
+        let results = [
+            zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).map(|_| ()),
+            zenavif_parse::AvifParser::from_seekable_with_config(
+                std::io::Cursor::new(bytes.clone()), &config, &zenavif_parse::Unstoppable,
+            ).map(|_| ()),
+        ];
+        for result in results {
+            match result {
+                Ok(()) => assert!(allowed, "{limit} boxes should be rejected"),
+                Err(err) => {
+                    assert!(!allowed, "{limit} boxes should be allowed: {err:?}");
+                    assert!(matches!(
+                        err.error().root(),
+                        zenavif_parse::Error::ResourceLimitExceeded("box count exceeds max_total_boxes"),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// `ftyp`, an `mdat` holding the first item, `meta`, then one `mdat` per
+/// remaining item, the way progressive writers put data before the index.
+fn build_avif_meta_between_mdats(items: &[TestItem], primary: u16, irefs: &[(&[u8; 4], u16, &[u16])]) -> Vec<u8> {
+    let ftyp = avif_ftyp();
+    let meta_len = avif_meta(items, primary, irefs, &[], &vec![0; items.len()]).len();
+    let mut offset = (ftyp.len() + 8) as u32;
+    let mut item_offsets = vec![offset];
+    offset += (items[0].data.len() + meta_len) as u32;
+    for item in &items[1..] {
+        item_offsets.push(offset + 8);
+        offset += 8 + item.data.len() as u32;
+    }
+
+    let mut file = ftyp;
+    file.extend_from_slice(&bmff_box(b"mdat", &items[0].data));
+    file.extend_from_slice(&avif_meta(items, primary, irefs, &[], &item_offsets));
+    for item in &items[1..] {
+        file.extend_from_slice(&bmff_box(b"mdat", &item.data));
+    }
+    file
+}
+
+#[test]
+fn parser_reads_meta_between_mdats() {
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+        TestItem::new(3, b"av01", b"thumb").prop(ispe(2, 2)),
+    ];
+    let irefs: &[(&[u8; 4], u16, &[u16])] = &[(b"cdsc", 2, &[1]), (b"thmb", 3, &[1])];
+    let bytes = build_avif_meta_between_mdats(&items, 1, irefs);
+    assert_eq!(&bytes[32..36], b"mdat");
+
+    let validating = zenavif_parse::DecodeConfig::default().validate_extents(true);
+    let in_memory = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &validating, &zenavif_parse::Unstoppable)
+        .expect("parse");
+    let seekable = zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes.clone())).expect("parse seekable");
+    for parser in [&in_memory, &seekable] {
+        assert_eq!(&*parser.primary_data().expect("primary"), b"color");
+        assert_eq!(&*parser.exif().expect("exif").expect("exif data"), b"II*\0");
+        assert_eq!(&*parser.thumbnail_data().expect("thumbnail").expect("thumbnail data"), b"thumb");
+    }
+
+    #[cfg(feature = "eager")]
+    {
+        let context = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+        assert_eq!(&context.primary_item[..], b"color");
+    }
+
+    // A prefix ending in the first mdat has not reached meta yet
+    let err = zenavif_parse::AvifInfo::peek(&bytes[..38]).expect_err("meta comes later");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::DataNotAvailable));
+}
+
+#[test]
+fn parser_max_item_count() {
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+        TestItem::new(3, b"av01", b"thumb"),
+    ];
+    let bytes = build_avif(&items, 1, &[(b"cdsc", 2, &[1])]);
+    let config = zenavif_parse::DecodeConfig::default().with_max_item_count(3);
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("three items are allowed");
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_item_count(2);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("item count limit should reject the file");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+/// The primary item of kodim-extents.avif is stored in two extents.
+#[test]
+fn parser_max_extents_per_item() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default().with_max_extents_per_item(2);
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("two extents are allowed");
+
+    let config = zenavif_parse::DecodeConfig::default().with_max_extents_per_item(1);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("extent limit should reject the primary item");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+/// A temporal delimiter and a reduced still-picture sequence header for a
+/// 65535×65535 frame, the largest AV1 can signal.
+const HUGE_SEQUENCE_HEADER: &[u8] = &[0x12, 0x00, 0x0a, 0x08, 0x18, 0x3f, 0xff, 0xff, 0xbf, 0xff, 0x80, 0x00];
+
+#[test]
+fn parser_megapixel_limit_single_image() {
+    let config = zenavif_parse::DecodeConfig::default();
+    let color = TestItem::new(1, b"av01", b"color").prop(ispe(65535, 65535));
+    let bytes = build_avif(&[color], 1, &[]);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("ispe exceeds the megapixel limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded("total megapixels limit exceeded")));
+
+    let color = TestItem::new(1, b"av01", b"color").prop(ispe(4, 4));
+    let alpha = TestItem::new(2, b"av01", b"alpha")
+        .prop(ispe(65535, 65535))
+        .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0"));
+    let bytes = build_avif(&[color, alpha], 1, &[(b"auxl", 2, &[1])]);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("alpha ispe exceeds the megapixel limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+
+    // An understated ispe does not hide the frame size from the bitstream
+    let color = TestItem::new(1, b"av01", HUGE_SEQUENCE_HEADER).prop(ispe(4, 4));
+    let bytes = build_avif(&[color], 1, &[]);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("sequence header exceeds the megapixel limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+    let err = zenavif_parse::AvifParser::from_seekable_with_config(
+        std::io::Cursor::new(bytes.clone()),
+        &config,
+        &zenavif_parse::Unstoppable,
+    )
+    .err()
+    .expect("sequence header exceeds the megapixel limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+
+    let config = zenavif_parse::DecodeConfig::unlimited();
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("no limit without total_megapixels_limit");
+}
+
+#[test]
+fn obu_iter_low_overhead_and_annex_b() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let primary = parser.primary_data().expect("primary");
+
+    let obus: Vec<_> = zenavif_parse::ObuIter::new(&primary).collect::<Result<_, _>>().expect("well-formed OBUs");
+    assert_eq!(obus[0].obu_type, zenavif_parse::ObuType::TemporalDelimiter);
+    assert_eq!(obus[1].obu_type, zenavif_parse::ObuType::SequenceHeader);
+    assert_eq!(obus.iter().flat_map(|obu| obu.bytes).copied().collect::<Vec<_>>(), primary.as_ref());
+
+    // The same temporal unit as a single Annex B frame unit
+    let leb128 = |mut n: usize, out: &mut Vec<u8>| loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    };
+    let mut frame_unit = Vec::new();
+    for obu in &obus {
+        leb128(obu.bytes.len(), &mut frame_unit);
+        frame_unit.extend_from_slice(obu.bytes);
+    }
+    let mut temporal_unit = Vec::new();
+    leb128(frame_unit.len(), &mut temporal_unit);
+    temporal_unit.extend_from_slice(&frame_unit);
+    let mut annex_b = Vec::new();
+    leb128(temporal_unit.len(), &mut annex_b);
+    annex_b.extend_from_slice(&temporal_unit);
+    let from_annex_b: Vec<_> = zenavif_parse::ObuIter::annex_b(&annex_b).collect::<Result<_, _>>().expect("Annex B");
+    assert_eq!(from_annex_b, obus);
+}
+
+#[test]
+fn obu_iter_extension_header_and_errors() {
+    // OBU_FRAME with temporal_id 2, spatial_id 1, then a padding OBU without a size field
+    let data = [0x36, 0x48, 0x01, 0xAA, 0x78, 0x00, 0x00];
+    let obus: Vec<_> = zenavif_parse::ObuIter::new(&data).collect::<Result<_, _>>().expect("valid");
+    assert_eq!(obus.len(), 2);
+    assert_eq!(obus[0].obu_type, zenavif_parse::ObuType::Frame);
+    assert_eq!((obus[0].temporal_id, obus[0].spatial_id), (2, 1));
+    assert_eq!(obus[0].payload, &[0xAA]);
+    assert_eq!(obus[1].obu_type, zenavif_parse::ObuType::Padding);
+    assert_eq!(obus[1].payload, &[0x00, 0x00]);
+
+    // A size field past the end yields one error, then nothing
+    let mut iter = zenavif_parse::ObuIter::new(&HUGE_SEQUENCE_HEADER[..6]);
+    assert_eq!(iter.next().unwrap().unwrap().obu_type, zenavif_parse::ObuType::TemporalDelimiter);
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+/// A metadata OBU (with size field) of `metadata_type`, ending in trailing bits.
+fn metadata_obu(metadata_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut obu = vec![0x2A, body.len() as u8 + 2, metadata_type];
+    obu.extend_from_slice(body);
+    obu.push(0x80);
+    obu
+}
+
+#[test]
+fn bitstream_hdr_metadata_obus() {
+    // Temporal delimiter and a 64×48 reduced still-picture sequence header
+    let mut bitstream = vec![0x12, 0x00, 0x0A, 0x06, 0x18, 0x15, 0x7F, 0xBC, 0x00, 0x08];
+    bitstream.extend(metadata_obu(1, &[0x03, 0xE8, 0x01, 0x90]));
+    // BT.2020 primaries (R, G, B) and D65 in 0.16 fixed point, 1000 and 0.005 cd/m²
+    let mut mdcv = Vec::new();
+    for v in [46399u16, 19137, 11141, 52232, 8585, 3015, 20493, 21561] {
+        mdcv.extend_from_slice(&v.to_be_bytes());
+    }
+    mdcv.extend_from_slice(&(1000u32 << 8).to_be_bytes());
+    mdcv.extend_from_slice(&82u32.to_be_bytes());
+    bitstream.extend(metadata_obu(2, &mdcv));
+    bitstream.extend(metadata_obu(4, &[0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04]));
+
+    let bytes = build_avif(&[TestItem::new(1, b"av01", &bitstream).prop(ispe(64, 48))], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert!(parser.content_light_level().is_none());
+    let hdr = parser.bitstream_hdr_metadata().expect("metadata");
+
+    let cll = hdr.content_light_level.expect("cll");
+    assert_eq!((cll.max_content_light_level, cll.max_pic_average_light_level), (1000, 400));
+    let mdcv = hdr.mastering_display.expect("mdcv");
+    assert_eq!(mdcv.primaries, [(8500, 39850), (6550, 2300), (35400, 14600)]);
+    assert_eq!(mdcv.white_point, (15635, 16450));
+    assert_eq!((mdcv.max_luminance, mdcv.min_luminance), (10_000_000, 50));
+    assert_eq!(hdr.itut_t35.len(), 1);
+    assert_eq!(hdr.itut_t35[0].country_code, 0xB5);
+    assert_eq!(hdr.itut_t35[0].country_code_extension, None);
+    assert_eq!(hdr.itut_t35[0].payload, [0x00, 0x3C, 0x00, 0x01, 0x04]);
+
+    let metadata = parser.primary_metadata().expect("sequence header");
+    assert_eq!(metadata.max_frame_width.get(), 64);
+    assert_eq!(metadata.hdr, hdr);
+    assert!(metadata.timecode.is_none());
+}
+
+#[test]
+fn parse_stats_count_what_was_parsed() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let stats = parser.stats();
+    // ftyp, meta (pitm, iloc, iinf (infe), iprp (ipco (ispe, av1C), ipma)), mdat
+    assert_eq!(stats.boxes, 12);
+    assert_eq!(stats.items, 1);
+    assert_eq!(stats.properties, 2);
+    assert_eq!((stats.mdat_count, stats.mdat_bytes), (1, 4387));
+    assert_eq!(stats.extents, 2);
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    assert!(stats.parse_duration.is_some());
+
+    let seekable = zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes.clone())).expect("parse");
+    let counts = |s: zenavif_parse::ParseStats| (s.boxes, s.items, s.properties, s.mdat_count, s.mdat_bytes, s.extents);
+    assert_eq!(counts(seekable.stats()), counts(stats));
+    // Items read on demand are accounted for
+    assert!(seekable.stats().peak_memory >= 4387);
+
+    // The owned input is accounted for
+    let owned = zenavif_parse::AvifParser::from_owned(bytes.clone()).expect("parse");
+    assert!(owned.stats().peak_memory >= bytes.len() as u64);
+
+    let anim = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let stats = zenavif_parse::AvifParser::from_bytes(&anim).expect("parse").stats();
+    assert_eq!(stats.mdat_count, 1);
+    assert!(stats.boxes > 12 && stats.items > 0);
+}
+
+/// Once separated, the two extents of kodim-extents.avif (about 4.4 KB) are
+/// concatenated on access.
+#[test]
+fn parser_peak_memory_limit() {
+    let bytes = kodim_split_extents();
+    let config = zenavif_parse::DecodeConfig::default().with_peak_memory_limit(5_000);
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("the concatenated primary item fits");
+
+    // The owned input counts too
+    let err = zenavif_parse::AvifParser::from_owned_with_config(bytes.clone(), &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("input plus concatenated item exceed the limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded("peak memory limit exceeded")));
+
+    let config = zenavif_parse::DecodeConfig::default().with_peak_memory_limit(4_000);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("the concatenated primary item exceeds the limit");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded("peak memory limit exceeded")));
+
+    // Adjacent extents are borrowed, so nothing is allocated for them
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("the borrowed primary item costs nothing");
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[test]
+fn wasm_rejects_time_budget() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    zenavif_parse::AvifParser::from_bytes(&bytes).expect("no budget, no clock needed");
+
+    let config = zenavif_parse::DecodeConfig::default().with_time_budget(std::time::Duration::from_secs(60));
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("there is no clock to enforce the budget with");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::Unsupported(_)));
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[test]
+fn parser_time_budget() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default().with_time_budget(std::time::Duration::from_secs(60));
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("parsing fits in a generous budget");
+
+    let config = zenavif_parse::DecodeConfig::default().with_time_budget(std::time::Duration::ZERO);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("an exhausted budget should stop parsing");
+    assert!(matches!(
+        err.error().root(),
+        zenavif_parse::Error::ResourceLimitExceeded("time budget exceeded")
+    ));
+
+    let err = zenavif_parse::AvifParser::from_seekable_with_config(
+        std::io::Cursor::new(bytes.clone()),
+        &config,
+        &zenavif_parse::Unstoppable,
+    )
+    .err()
+    .expect("an exhausted budget should stop seekable parsing");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+#[test]
+fn parser_item_offset_cycle_rejected() {
+    let bytes = build_avif(
+        &[
+            TestItem::constructed(1, b"av01", &[(1, 0, 1)]).prop(ispe(4, 4)),
+            TestItem::constructed(2, b"av01", &[(1, 0, 1)]),
+        ],
+        1,
+        &[(b"iloc", 1, &[2]), (b"iloc", 2, &[1])],
+    );
+    let config = zenavif_parse::DecodeConfig::unlimited();
+    assert!(zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).is_err());
+}
+
+#[test]
+fn parser_item_offset_out_of_range_rejected() {
+    let bytes = build_avif(
+        &[TestItem::constructed(1, b"av01", &[(1, 2, 10)]).prop(ispe(4, 4)), TestItem::new(2, b"av01", b"abc")],
+        1,
+        &[(b"iloc", 1, &[2])],
+    );
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_item_offset_construction() {
+    let bytes = item_offset_avif();
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+
+    assert_eq!(&avif.primary_item[..], b"abcdefg");
+}
+
+// ============================================================================
+// Overlay (iovl) tests
+// ============================================================================
+
+fn overlay_avif() -> Vec<u8> {
+    let mut iovl = vec![0, 0]; // version, flags (16-bit fields)
+    for fill in [0xffffu16, 0x8000, 0, 0xffff] {
+        iovl.extend_from_slice(&fill.to_be_bytes());
+    }
+    iovl.extend_from_slice(&64u16.to_be_bytes());
+    iovl.extend_from_slice(&48u16.to_be_bytes());
+    for offset in [0i16, 0, 32, -8] {
+        iovl.extend_from_slice(&offset.to_be_bytes());
+    }
+    build_avif(
+        &[
+            TestItem::new(1, b"iovl", &iovl).prop(ispe(64, 48)),
+            TestItem::new(2, b"av01", b"background").prop(ispe(64, 48)),
+            TestItem::new(3, b"av01", b"sticker").prop(ispe(16, 16)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2, 3])],
+    )
+}
+
+#[test]
+fn parser_overlay() {
+    let bytes = overlay_avif();
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let overlay = parser.overlay_config().expect("overlay config");
+    assert_eq!(overlay.canvas_fill_value, [0xffff, 0x8000, 0, 0xffff]);
+    assert_eq!((overlay.output_width, overlay.output_height), (64, 48));
+    assert_eq!(overlay.offsets, vec![(0, 0), (32, -8)]);
+
+    assert_eq!(parser.overlay_input_count(), 2);
+    assert_eq!(&parser.overlay_input_data(0).expect("input 0")[..], b"background");
+    assert_eq!(&parser.overlay_input_data(1).expect("input 1")[..], b"sticker");
+    assert!(parser.overlay_input_data(2).is_err());
+    assert!(parser.grid_config().is_none());
+}
+
+#[test]
+fn parser_overlay_truncated_payload_rejected() {
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"iovl", &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 0, 16]),
+            TestItem::new(2, b"av01", b"input"),
+        ],
+        1,
+        &[(b"dimg", 1, &[2])],
+    );
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn parser_no_overlay_on_single_image() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert!(parser.overlay_config().is_none());
+    assert_eq!(parser.overlay_input_count(), 0);
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_overlay_matches_parser() {
+    let bytes = overlay_avif();
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(avif.overlay_config.as_ref(), parser.overlay_config());
+    assert_eq!(avif.overlay_inputs.len(), 2);
+    assert_eq!(avif.overlay_inputs[1].as_slice(), b"sticker");
+}
+
+// ============================================================================
+// Identity derived image (iden) tests
+// ============================================================================
+
+fn irot(quarter_turns: u8) -> Vec<u8> {
+    bmff_box(b"irot", &[quarter_turns & 0x03])
+}
+
+fn iden_avif() -> Vec<u8> {
+    build_avif(
+        &[
+            TestItem::new(1, b"iden", b"").prop(ispe(8, 4)).prop(irot(1)),
+            TestItem::new(2, b"av01", b"coded").prop(ispe(8, 4)).prop(av1c(0, 8)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2])],
+    )
+}
+
+fn clap(width: u32, height: u32, horiz_off: (i32, u32), vert_off: (i32, u32)) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for value in [width, 1, height, 1] {
+        payload.extend_from_slice(&value.to_be_bytes());
+    }
+    payload.extend_from_slice(&horiz_off.0.to_be_bytes());
+    payload.extend_from_slice(&horiz_off.1.to_be_bytes());
+    payload.extend_from_slice(&vert_off.0.to_be_bytes());
+    payload.extend_from_slice(&vert_off.1.to_be_bytes());
+    bmff_box(b"clap", &payload)
+}
+
+#[test]
+fn dimensions_and_display_dimensions() {
+    let dims = |props: Vec<Vec<u8>>| {
+        let item = props.into_iter().fold(TestItem::new(1, b"av01", b"coded"), TestItem::prop);
+        let bytes = build_avif(&[item], 1, &[]);
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        let size = |e: Option<zenavif_parse::ImageSpatialExtents>| e.map(|e| (e.width, e.height));
+        (size(parser.dimensions()), size(parser.display_dimensions()))
+    };
+
+    assert_eq!(dims(vec![ispe(10, 8)]), (Some((10, 8)), Some((10, 8))));
+    // Cropped to 6×4 around the centre, then turned a quarter
+    assert_eq!(dims(vec![ispe(10, 8), clap(6, 4, (0, 1), (0, 1)), irot(1)]), (Some((10, 8)), Some((4, 6))));
+    assert_eq!(dims(vec![ispe(10, 8), irot(2)]), (Some((10, 8)), Some((10, 8))));
+    // An odd crop centred on an even image starts half a pixel in
+    assert_eq!(dims(vec![ispe(10, 8), clap(5, 4, (0, 1), (0, 1))]).1, None);
+    assert_eq!(dims(vec![ispe(10, 8), clap(5, 4, (1, 2), (0, 1))]).1, Some((5, 4)));
+    assert_eq!(dims(vec![]), (None, None));
+}
+
+#[test]
+fn display_transform_orders_clap_irot_imir() {
+    // Properties listed out of order still apply as crop, rotate, flip
+    let item = TestItem::new(1, b"av01", b"coded")
+        .prop(ispe(10, 8))
+        .prop(bmff_box(b"imir", &[1]))
+        .prop(irot(3))
+        .prop(clap(6, 4, (1, 1), (0, 1)));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let transform = parser.display_transform().expect("transform");
+    assert_eq!(transform.crop, zenavif_parse::Rect { x: 3, y: 2, width: 6, height: 4 });
+    assert_eq!(transform.rotation, 270);
+    assert_eq!(transform.flip, Some(zenavif_parse::Axis::Horizontal));
+    assert_eq!((transform.width, transform.height), (4, 6));
+
+    // Without transforms: the whole image, as stored
+    let item = TestItem::new(1, b"av01", b"coded").prop(ispe(10, 8));
+    let bytes = build_avif(&[item], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let transform = parser.display_transform().expect("transform");
+    assert_eq!(transform.crop, zenavif_parse::Rect { x: 0, y: 0, width: 10, height: 8 });
+    assert_eq!((transform.rotation, transform.flip), (0, None));
+    assert_eq!((transform.width, transform.height), (10, 8));
+}
+
+#[test]
+fn parser_iden_primary_resolves_to_coded_item() {
+    let bytes = iden_avif();
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary data")[..], b"coded");
+    assert_eq!(parser.rotation().map(|r| r.angle), Some(90));
+    assert!(parser.grid_config().is_none());
+}
+
+#[test]
+fn parser_iden_chain_resolves() {
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"iden", b"").prop(irot(2)),
+            TestItem::new(2, b"iden", b"").prop(irot(1)),
+            TestItem::new(3, b"av01", b"coded").prop(ispe(8, 4)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2]), (b"dimg", 2, &[3])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary data")[..], b"coded");
+    // The outermost derivation takes precedence
+    assert_eq!(parser.rotation().map(|r| r.angle), Some(180));
+}
+
+#[test]
+fn parser_iden_cycle_rejected() {
+    let bytes = build_avif(
+        &[TestItem::new(1, b"iden", b""), TestItem::new(2, b"iden", b"")],
+        1,
+        &[(b"dimg", 1, &[2]), (b"dimg", 2, &[1])],
+    );
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn parser_iden_without_input_rejected() {
+    let bytes = build_avif(&[TestItem::new(1, b"iden", b"").prop(ispe(8, 4))], 1, &[]);
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+}
+
+#[cfg(feature = "eager")]
+#[test]
+fn eager_iden_primary() {
+    let bytes = iden_avif();
+    let avif = zenavif_parse::read_avif(&mut std::io::Cursor::new(&bytes)).expect("read_avif");
+
+    assert_eq!(&avif.primary_item[..], b"coded");
+}
+
+// ============================================================================
+// Alternative group (grpl/altr) tests
+// ============================================================================
+
+/// A `grpl` box holding one `altr` group.
+fn grpl_altr(group_id: u32, entity_ids: &[u32]) -> Vec<u8> {
+    let mut payload = group_id.to_be_bytes().to_vec();
+    payload.extend_from_slice(&(entity_ids.len() as u32).to_be_bytes());
+    for id in entity_ids {
+        payload.extend_from_slice(&id.to_be_bytes());
+    }
+    bmff_box(b"grpl", &full_box(b"altr", 0, 0, &payload))
+}
+
+fn alternatives_avif() -> Vec<u8> {
+    build_avif_with_meta_boxes(
+        &[
+            TestItem::new(1, b"av01", b"sdr").prop(ispe(8, 8)),
+            TestItem::new(2, b"j2k1", b"jpeg2000").prop(ispe(8, 8)),
+            TestItem::new(3, b"av01", b"hdr").prop(ispe(8, 8)),
+        ],
+        1,
+        &[],
+        &[grpl_altr(7, &[2, 3, 1])],
+    )
+}
+
+#[test]
+fn parser_alternatives_listed() {
+    let bytes = alternatives_avif();
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let groups = parser.alternatives();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].group_id, 7);
+    assert_eq!(groups[0].entity_ids, vec![2, 3, 1]);
+    // Without the knob, pitm stays the primary item
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"sdr");
+}
+
+#[test]
+fn parser_select_alternative_skips_unsupported() {
+    let bytes = alternatives_avif();
+    let config = zenavif_parse::DecodeConfig::default().with_select_alternative(true);
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"hdr");
+}
+
+#[test]
+fn parser_select_alternative_ignores_groups_without_primary() {
+    let bytes = build_avif_with_meta_boxes(
+        &[TestItem::new(1, b"av01", b"primary"), TestItem::new(2, b"av01", b"other"), TestItem::new(3, b"av01", b"third")],
+        1,
+        &[],
+        &[grpl_altr(1, &[3, 2])],
+    );
+    let config = zenavif_parse::DecodeConfig::default().with_select_alternative(true);
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"primary");
+}
+
+#[test]
+fn parser_gain_map_alternatives() {
+    let bytes = std::fs::read("tests/gainmap/seine_sdr_gainmap_srgb.avif").expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let groups = parser.alternatives();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].entity_ids, vec![2, 1]);
+
+    // The tmap alternative is not a supported primary, so selection keeps the base image
+    let config = zenavif_parse::DecodeConfig::default().with_select_alternative(true);
+    let selected = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).expect("parse");
+    assert_eq!(selected.primary_data().expect("primary"), parser.primary_data().expect("primary"));
+}
+
+#[test]
+fn parser_sample_transform_falls_back_to_alternative() {
+    // 16-bit: input 0 * 256 + input 1
+    let sato = [0x01, 5, 1, 0, 0x01, 0x00, 130, 2, 128];
+    let bytes = build_avif_with_meta_boxes(
+        &[
+            TestItem::new(1, b"sato", &sato),
+            TestItem::new(2, b"av01", b"msb").prop(ispe(8, 8)),
+            TestItem::new(3, b"av01", b"lsb").prop(ispe(8, 8)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2, 3])],
+        &[grpl_altr(1, &[1, 2])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"msb");
+    let transform = parser.sample_transform().expect("sato");
+    assert_eq!(transform.item_id, 1);
+    assert_eq!(transform.bit_depth, 16);
+    assert_eq!(transform.input_item_ids, vec![2, 3]);
+    use zenavif_parse::SampleTransformToken as T;
+    assert_eq!(transform.tokens, vec![T::Input(0), T::Constant(256), T::Product, T::Input(1), T::Sum]);
+    assert_eq!(&parser.sample_transform_input_data(1).expect("input")[..], b"lsb");
+}
+
+#[test]
+fn parser_drops_malformed_sample_transform() {
+    // Token 200 is not defined, and a lone operator has no operands
+    for sato in [&[0x01, 1, 200][..], &[0x01, 1, 128][..], &[0x01, 3, 1][..]] {
+        let bytes = build_avif_with_meta_boxes(
+            &[
+                TestItem::new(1, b"sato", sato),
+                TestItem::new(2, b"av01", b"msb").prop(ispe(8, 8)),
+                TestItem::new(3, b"av01", b"lsb").prop(ispe(8, 8)),
+            ],
+            1,
+            &[(b"dimg", 1, &[2, 3])],
+            &[grpl_altr(1, &[1, 2])],
+        );
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+        assert!(parser.sample_transform().is_none());
+        assert_eq!(&parser.primary_data().expect("primary")[..], b"msb");
+        let kinds: Vec<_> = parser.warnings().iter().map(|w| (w.kind, w.fourcc)).collect();
+        assert_eq!(kinds, [(zenavif_parse::ParseWarningKind::MalformedSampleTransform, *b"sato")]);
+    }
+}
+
+// ============================================================================
+// Seekable reader tests
+// ============================================================================
+
+/// A `Read + Seek` wrapper that counts the bytes actually read.
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(n as u64, std::sync::atomic::Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: std::io::Seek> std::io::Seek for CountingReader<R> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn seekable_matches_from_bytes() {
+    for path in [IMAGE_AVIF_EXTENTS, ANIM_8BPC_ALPHA] {
+        let bytes = std::fs::read(path).expect("read file");
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        let file = std::fs::File::open(path).expect("open file");
+        let seekable = zenavif_parse::AvifParser::from_seekable(file).expect("parse seekable");
+
+        assert_eq!(seekable.primary_data().expect("primary"), parser.primary_data().expect("primary"), "{path}");
+        assert_eq!(seekable.exif().map(|e| e.expect("exif")), parser.exif().map(|e| e.expect("exif")), "{path}");
+        assert_eq!(seekable.animation_info().map(|a| a.frame_count), parser.animation_info().map(|a| a.frame_count));
+        if let Some(info) = parser.animation_info() {
+            for i in 0..info.frame_count {
+                let a = parser.frame(i).expect("frame");
+                let b = seekable.frame(i).expect("frame");
+                assert_eq!(a.data, b.data);
+                assert_eq!(a.alpha_data, b.alpha_data);
+                assert_eq!(a.duration_ms, b.duration_ms);
+            }
+        }
+    }
+}
+
+#[test]
+fn seekable_skips_mdat_during_parse() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let bytes_read = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let reader = CountingReader { inner: std::io::Cursor::new(bytes.clone()), bytes_read: bytes_read.clone() };
+    let parser = zenavif_parse::AvifParser::from_seekable(reader).expect("parse seekable");
+
+    let after_parse = bytes_read.load(std::sync::atomic::Ordering::Relaxed);
+    assert!(after_parse < bytes.len() as u64 / 2, "parse read {after_parse} of {} bytes", bytes.len());
+
+    let primary = parser.primary_data().expect("primary");
+    assert_eq!(bytes_read.load(std::sync::atomic::Ordering::Relaxed), after_parse + primary.len() as u64);
+}
+
+#[test]
+fn seekable_rejects_missing_ftyp() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let ftyp_len = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    let truncated = bytes[ftyp_len..].to_vec();
+    assert!(zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(truncated)).is_err());
+}
+
+#[test]
+fn seekable_truncated_item_data_errors_on_access() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let truncated = bytes[..bytes.len() - 16].to_vec();
+    if let Ok(parser) = zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(truncated)) {
+        assert!(parser.primary_data().is_err());
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn from_file_matches_from_bytes() {
+    for path in [IMAGE_AVIF_EXTENTS, ANIM_8BPC_ALPHA, "tests/gainmap/seine_sdr_gainmap_srgb.avif"] {
+        let bytes = std::fs::read(path).expect("read file");
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        let mapped = zenavif_parse::AvifParser::from_file(path).expect("parse mapped file");
+
+        assert_eq!(mapped.primary_data().expect("primary"), parser.primary_data().expect("primary"), "{path}");
+        assert_eq!(mapped.spatial_extents(), parser.spatial_extents(), "{path}");
+        assert_eq!(mapped.animation_info().map(|a| a.frame_count), parser.animation_info().map(|a| a.frame_count));
+        assert_eq!(mapped.gain_map().is_some(), parser.gain_map().is_some(), "{path}");
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn from_file_missing_path_errors() {
+    assert!(zenavif_parse::AvifParser::from_file("tests/does-not-exist.avif").is_err());
+}
+
+// ============================================================================
+// Shared (bytes::Bytes) input tests
+// ============================================================================
+
+#[cfg(feature = "bytes")]
+#[test]
+fn shared_bytes_are_slices_of_the_input() {
+    let input = bytes::Bytes::from(std::fs::read(ANIM_8BPC_ALPHA).expect("read file"));
+    let within_input = |data: &bytes::Bytes| {
+        let range = input.as_ptr_range();
+        range.start <= data.as_ptr() && data.as_ptr_range().end <= range.end
+    };
+    let parser = zenavif_parse::AvifParser::from_bytes_shared(input.clone()).expect("parse");
+
+    let mut frames = Vec::new();
+    for i in 0..parser.animation_info().expect("animated").frame_count {
+        let frame = parser.frame(i).expect("frame");
+        let data = parser.frame_bytes(i).expect("frame bytes");
+        let alpha = parser.alpha_frame_bytes(i).expect("alpha bytes").expect("alpha track");
+        assert_eq!(data, frame.data.as_ref());
+        assert_eq!(Some(&alpha[..]), frame.alpha_data.as_deref());
+        assert!(within_input(&data) && within_input(&alpha), "frame {i} was copied");
+        frames.push(data);
+    }
+    assert!(parser.frame_bytes(frames.len()).is_err());
+
+    // The payloads outlive the parser and can move to another thread
+    drop(parser);
+    let total = std::thread::spawn(move || frames.iter().map(|f| f.len()).sum::<usize>()).join().unwrap();
+    assert_eq!(total, 38 + 60 + 47 + 35 + 39);
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn shared_bytes_copy_only_separated_extents() {
+    let input = bytes::Bytes::from(std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file"));
+    let parser = zenavif_parse::AvifParser::from_bytes_shared(input.clone()).expect("parse");
+    let primary = parser.primary_bytes().expect("primary");
+    assert_eq!(primary, parser.primary_data().expect("primary").as_ref());
+    assert!(input.as_ptr_range().contains(&primary.as_ptr()), "adjacent extents are sliced");
+    assert!(parser.alpha_bytes().is_none());
+    assert!(parser.tile_bytes(0).is_err());
+
+    let split = bytes::Bytes::from(kodim_split_extents());
+    let parser = zenavif_parse::AvifParser::from_bytes_shared(split.clone()).expect("parse");
+    let joined = parser.primary_bytes().expect("primary");
+    assert_eq!(joined, primary);
+    assert!(!split.as_ptr_range().contains(&joined.as_ptr()), "separated extents are joined");
+
+    // Other constructors hand out copies
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.primary_bytes().expect("primary"), primary);
+}
+
+// ============================================================================
+// Async reader tests
+// ============================================================================
+
+/// Drive a future to completion on the current thread.
+#[cfg(feature = "async")]
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    let mut fut = std::pin::pin!(fut);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_reader_matches_from_bytes() {
+    for path in [IMAGE_AVIF_EXTENTS, ANIM_8BPC_ALPHA] {
+        let bytes = std::fs::read(path).expect("read file");
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        let from_async = block_on(zenavif_parse::AvifParser::from_async_reader(bytes.as_slice())).expect("parse async");
+
+        assert_eq!(from_async.primary_data().expect("primary"), parser.primary_data().expect("primary"), "{path}");
+        assert_eq!(from_async.animation_info().map(|a| a.frame_count), parser.animation_info().map(|a| a.frame_count));
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_reader_respects_peak_memory_limit() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default().with_peak_memory_limit(100);
+    let result = block_on(zenavif_parse::AvifParser::from_async_reader_with_config(
+        bytes.as_slice(),
+        &config,
+        &zenavif_parse::Unstoppable,
+    ));
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_reader_cancellation() {
+    struct ImmediatelyCancelled;
+    impl zenavif_parse::Stop for ImmediatelyCancelled {
+        fn check(&self) -> std::result::Result<(), zenavif_parse::StopReason> {
+            Err(zenavif_parse::StopReason::Cancelled)
+        }
+    }
+
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let config = zenavif_parse::DecodeConfig::default();
+    let result = block_on(zenavif_parse::AvifParser::from_async_reader_with_config(bytes.as_slice(), &config, &ImmediatelyCancelled));
+
+    match result.map_err(|e| e.decompose().0) {
+        Err(zenavif_parse::Error::Stopped(reason)) => assert_eq!(reason, zenavif_parse::StopReason::Cancelled),
+        Ok(_) => panic!("Expected cancellation"),
+        Err(e) => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+// ============================================================================
+// Push parser tests
+// ============================================================================
+
+/// Push `bytes` in `chunk`-sized pieces; returns the parser plus whether
+/// `MetadataReady` was reported before completion.
+fn push_in_chunks(bytes: &[u8], chunk: usize) -> (zenavif_parse::AvifParser<'static>, bool) {
+    let mut push = zenavif_parse::AvifPushParser::new();
+    let mut saw_metadata = false;
+    for piece in bytes.chunks(chunk) {
+        match push.push(piece).expect("push") {
+            zenavif_parse::ParseProgress::NeedMoreData(needed) => assert!(needed > 0),
+            zenavif_parse::ParseProgress::MetadataReady => {
+                assert!(!saw_metadata, "MetadataReady reported twice");
+                assert!(push.is_metadata_ready());
+                saw_metadata = true;
+            }
+            zenavif_parse::ParseProgress::Complete(parser) => return (*parser, saw_metadata),
+        }
+    }
+    panic!("push parser never completed");
+}
+
+#[test]
+fn push_parser_reports_metadata_before_data() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let reference = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    for chunk in [1, 7, 64, 1000] {
+        let (parser, saw_metadata) = push_in_chunks(&bytes, chunk);
+        assert!(saw_metadata, "chunk size {chunk}");
+        assert_eq!(parser.primary_data().expect("primary"), reference.primary_data().expect("primary"));
+    }
+}
+
+#[test]
+fn push_parser_exposes_brand_and_dimensions_early() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let reference = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let mut push = zenavif_parse::AvifPushParser::new();
+
+    // ftyp alone gives the brand but not the metadata
+    let ftyp_len = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    assert!(matches!(push.push(&bytes[..ftyp_len]).expect("push"), zenavif_parse::ParseProgress::NeedMoreData(_)));
+    assert_eq!(push.major_brand(), Some(*b"avif"));
+    assert!(push.spatial_extents().is_none());
+
+    // Everything up to the end of meta (mdat header excluded)
+    let meta_len = u32::from_be_bytes(bytes[ftyp_len..ftyp_len + 4].try_into().unwrap()) as usize;
+    let progress = push.push(&bytes[ftyp_len..ftyp_len + meta_len]).expect("push");
+    assert!(matches!(progress, zenavif_parse::ParseProgress::MetadataReady));
+    assert_eq!(push.spatial_extents(), reference.spatial_extents());
+
+    let progress = push.push(&bytes[ftyp_len + meta_len..]).expect("push");
+    assert!(matches!(progress, zenavif_parse::ParseProgress::Complete(_)));
+    assert!(push.push(&[]).is_err(), "push after completion");
+}
+
+#[test]
+fn push_parser_whole_file_completes_immediately() {
+    for path in [IMAGE_AVIF_EXTENTS, ANIM_8BPC_ALPHA] {
+        let bytes = std::fs::read(path).expect("read file");
+        let mut push = zenavif_parse::AvifPushParser::new();
+        let progress = push.push(&bytes).expect("push");
+        let zenavif_parse::ParseProgress::Complete(parser) = progress else {
+            panic!("{path}: expected completion");
+        };
+        let reference = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        assert_eq!(parser.animation_info().map(|a| a.frame_count), reference.animation_info().map(|a| a.frame_count));
+    }
+}
+
+#[test]
+fn push_parser_animation_in_chunks() {
+    let bytes = std::fs::read(ANIM_8BPC).expect("read file");
+    let reference = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let (parser, _) = push_in_chunks(&bytes, 100);
+
+    let frame_count = reference.animation_info().expect("animated").frame_count;
+    for i in 0..frame_count {
+        assert_eq!(parser.frame(i).expect("frame").data, reference.frame(i).expect("frame").data);
+    }
+}
+
+#[test]
+fn push_parser_rejects_missing_ftyp() {
+    let mut push = zenavif_parse::AvifPushParser::new();
+    let mut bytes = 16u32.to_be_bytes().to_vec();
+    bytes.extend_from_slice(b"mdat");
+    bytes.extend_from_slice(&[0; 8]);
+    assert!(push.push(&bytes).is_err());
+
+    // Padding before `ftyp` is left for `finish()` to reject
+    let mut push = zenavif_parse::AvifPushParser::new();
+    bytes[4..8].copy_from_slice(b"free");
+    push.push(&bytes).expect("padding is deferred");
+    assert!(push.finish().is_err());
+}
+
+#[test]
+fn push_parser_finish_on_truncated_input() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let mut push = zenavif_parse::AvifPushParser::new();
+    push.push(&bytes[..bytes.len() / 2]).expect("push");
+    // The structure is intact, but item data is missing
+    if let Ok(parser) = push.finish() {
+        assert!(parser.primary_data().is_err());
+    }
+}
+
+// ============================================================================
+// AvifInfo::peek tests
+// ============================================================================
+
+#[test]
+fn peek_still_image() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let info = zenavif_parse::AvifInfo::peek(&bytes).expect("peek");
+
+    let ispe = parser.spatial_extents().expect("ispe");
+    assert_eq!((info.width, info.height), (ispe.width, ispe.height));
+    assert_eq!(info.major_brand, *b"avif");
+    assert_eq!(info.bit_depth, parser.av1_config().map(|c| c.bit_depth));
+    assert!(!info.has_alpha);
+    assert!(!info.is_animated);
+    assert_eq!(info.frame_count, 0);
+}
+
+#[test]
+fn peek_animation_with_alpha() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let info = zenavif_parse::AvifInfo::peek(&bytes).expect("peek");
+
+    let anim = parser.animation_info().expect("animated");
+    assert!(info.is_animated);
+    assert!(info.has_alpha);
+    assert_eq!(info.frame_count, anim.frame_count);
+    assert_eq!(info.bit_depth, Some(8));
+}
+
+#[test]
+fn peek_without_mdat() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let full = zenavif_parse::AvifInfo::peek(&bytes).expect("peek");
+
+    // Cut inside mdat, and right after meta (no mdat at all)
+    let ftyp_len = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    let meta_len = u32::from_be_bytes(bytes[ftyp_len..ftyp_len + 4].try_into().unwrap()) as usize;
+    for cut in [ftyp_len + meta_len + 100, ftyp_len + meta_len] {
+        assert_eq!(zenavif_parse::AvifInfo::peek(&bytes[..cut]).expect("peek prefix"), full, "cut at {cut}");
+    }
+}
+
+#[test]
+fn peek_truncated_meta_errors() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let ftyp_len = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    assert!(zenavif_parse::AvifInfo::peek(&bytes[..ftyp_len + 20]).is_err());
+    assert!(zenavif_parse::AvifInfo::peek(&bytes[..ftyp_len]).is_err());
+}
+
+// ============================================================================
+// Byte range tests
+// ============================================================================
+
+/// Concatenate `ranges` of `bytes`.
+fn gather(bytes: &[u8], ranges: &[std::ops::Range<u64>]) -> Vec<u8> {
+    ranges.iter().flat_map(|r| bytes[r.start as usize..r.end as usize].iter().copied()).collect()
+}
+
+#[test]
+fn primary_byte_ranges_cover_primary_data() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let ranges = parser.primary_byte_ranges().expect("ranges");
+    assert_eq!(ranges.len(), 2, "kodim-extents stores the primary item in two extents");
+    assert_eq!(gather(&bytes, &ranges), parser.primary_data().expect("primary").as_ref());
+}
+
+#[test]
+fn frame_byte_ranges_cover_frames() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let info = parser.animation_info().expect("animated");
+    for i in 0..info.frame_count {
+        let frame = parser.frame(i).expect("frame");
+        let ranges = parser.frame_byte_ranges(i).expect("ranges");
+        assert_eq!(ranges.len(), 1 + frame.alpha_data.is_some() as usize);
+        assert_eq!(gather(&bytes, &ranges[..1]), frame.data.as_ref());
+        if let Some(alpha) = &frame.alpha_data {
+            assert_eq!(gather(&bytes, &ranges[1..]), alpha.as_ref());
+        }
+    }
+    assert!(parser.frame_byte_ranges(info.frame_count).is_err());
+}
+
+#[test]
+fn frames_from_and_frames_range() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let all = frame_payloads(&parser);
+    let payloads = |frames: zenavif_parse::FrameIterator<'_>| -> Vec<Vec<u8>> {
+        frames.map(|frame| frame.expect("frame").data.to_vec()).collect()
+    };
+    let color: Vec<Vec<u8>> = all.iter().map(|(data, _)| data.clone()).collect();
+
+    let from = parser.frames_from(2);
+    assert_eq!(from.len(), all.len() - 2);
+    assert_eq!(payloads(from), color[2..]);
+    let range = parser.frames_range(1..3);
+    assert_eq!(range.len(), 2);
+    assert_eq!(payloads(range), color[1..3]);
+
+    assert_eq!(parser.frames_from(all.len()).len(), 0);
+    assert_eq!(parser.frames_range(3..100).len(), all.len() - 3);
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = parser.frames_range(4..2);
+    assert_eq!(reversed.len(), 0);
+}
+
+#[test]
+fn frame_location_matches_frame_data() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let info = parser.animation_info().expect("animated");
+    for i in 0..info.frame_count {
+        let frame = parser.frame(i).expect("frame");
+        let (offset, size) = parser.frame_location(i).expect("location");
+        assert_eq!(&bytes[offset as usize..][..size as usize], frame.data.as_ref());
+        let (offset, size) = parser.alpha_frame_location(i).expect("alpha location").expect("alpha track");
+        assert_eq!(&bytes[offset as usize..][..size as usize], frame.alpha_data.expect("alpha").as_ref());
+    }
+    assert!(parser.frame_location(info.frame_count).is_err());
+    assert!(parser.alpha_frame_location(info.frame_count).is_err());
+    assert!(parser.frame_byte_ranges(info.frame_count).is_err());
+
+    let still = std::fs::read(IMAGE_AVIF).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&still).expect("parse");
+    assert!(parser.frame_location(0).is_err());
+    assert!(parser.alpha_frame_location(0).is_err());
+}
+
+#[test]
+fn mdat_ranges_and_idat() {
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+    ];
+    let bytes = build_avif_meta_between_mdats(&items, 1, &[(b"cdsc", 2, &[1])]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let ranges = parser.mdat_ranges();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(gather(&bytes, &ranges[..1]), b"color");
+    assert_eq!(gather(&bytes, &ranges[1..]), b"\0\0\0\0II*\0");
+    assert_eq!(parser.idat(), None);
+
+    let idat = bmff_box(b"idat", b"item data");
+    let bytes = build_avif_with_meta_boxes(&items, 1, &[], &[idat]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.idat(), Some(&b"item data"[..]));
+}
+
+#[test]
+fn keyframes_from_stss() {
+    // The color track's stss lists only sample 1
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.keyframes(), [0]);
+    let keyframes: Vec<bool> = parser.frames().map(|f| f.expect("frame").is_keyframe).collect();
+    assert_eq!(keyframes, [true, false, false, false, false]);
+    assert!(parser.summary().frames[0].is_keyframe && !parser.summary().frames[1].is_keyframe);
+
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    assert!(parser.keyframes().is_empty());
+}
+
+#[test]
+fn frame_timestamps_in_timescale_units() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let info = parser.animation_info().expect("animated");
+    assert_eq!(info.timescale, 30);
+    let frame = parser.frame(4).expect("frame");
+    assert_eq!((frame.timestamp, frame.duration, frame.timescale), (20, 5, 30));
+    // 20/30 s is 666.67 ms; the ms fields truncate, Duration keeps nanoseconds
+    assert_eq!(frame.timestamp_ms, 666);
+    assert_eq!(frame.presentation_time(), std::time::Duration::from_nanos(666_666_666));
+    assert_eq!(frame.display_duration(), std::time::Duration::from_nanos(166_666_666));
+
+    let summary = parser.summary();
+    assert_eq!((summary.frames[4].timestamp, summary.frames[4].duration), (20, 5));
+    assert_eq!(info.ticks_to_duration(30), std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn animation_total_duration_and_fps() {
+    let mut bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let info = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").animation_info().expect("animated");
+    // Five 5-tick samples at timescale 30
+    assert_eq!(info.duration, 25);
+    assert_eq!(info.total_duration(), std::time::Duration::from_nanos(833_333_333));
+    assert_eq!(info.fps_estimate(), Some(6.0));
+
+    // The color track's elst (version 1) segment_duration, in mvhd ticks (also 30/s)
+    let segment_duration = 814 + 16;
+    bytes[segment_duration..segment_duration + 8].copy_from_slice(&15u64.to_be_bytes());
+    let info = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").animation_info().expect("animated");
+    assert_eq!(info.duration, 15, "a shorter edit trims the loop");
+    assert_eq!(info.fps_estimate().map(f64::round), Some(10.0));
+
+    // An unbounded edit presents the whole media
+    bytes[segment_duration..segment_duration + 8].copy_from_slice(&u64::MAX.to_be_bytes());
+    let info = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse").animation_info().expect("animated");
+    assert_eq!(info.duration, 25);
+}
+
+#[test]
+fn frame_index_at_time() {
+    use std::time::Duration;
+
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    // Frames start every 1/6 s and only frame 0 is a keyframe
+    let at = |ms| {
+        let position = parser.frame_index_at(Duration::from_millis(ms)).expect("animated");
+        (position.index, position.keyframe_index)
+    };
+    assert_eq!(at(0), (0, 0));
+    assert_eq!(at(166), (0, 0));
+    assert_eq!(at(167), (1, 0));
+    assert_eq!(at(400), (2, 0));
+    assert_eq!(at(60_000).0, 4, "past the end clamps to the last frame");
+    let frame = parser.frame_at_time(Duration::from_millis(400)).expect("frame");
+    assert_eq!(frame.data, parser.frame(2).unwrap().data);
+
+    // With ctts offsets 0, +5, -5, 0, 0 the presentation order is 0, 2, 1, 3, 4
+    let mut ctts = Vec::new();
+    ctts.extend_from_slice(&4u32.to_be_bytes());
+    for (count, offset) in [(1u32, 0i32), (1, 5), (1, -5), (2, 0)] {
+        ctts.extend_from_slice(&count.to_be_bytes());
+        ctts.extend_from_slice(&offset.to_be_bytes());
+    }
+    let bytes = anim_with_color_stbl_child(&full_box(b"ctts", 1, 0, &ctts));
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let order: Vec<usize> = [0, 200, 400, 550, 700].iter()
+        .map(|&ms| parser.frame_index_at(Duration::from_millis(ms)).expect("animated").index)
+        .collect();
+    assert_eq!(order, [0, 2, 1, 3, 4]);
+
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    assert_eq!(parser.frame_index_at(Duration::ZERO), None);
+    assert!(parser.frame_at_time(Duration::ZERO).is_err());
+}
+
+#[test]
+fn tracks_enumerated_and_selectable() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let tracks = parser.tracks();
+    let summary: Vec<_> = tracks.iter()
+        .map(|t| (t.track_id, t.handler_type.as_str(), t.width, t.height, t.codec.as_deref(), t.frame_count, t.selected))
+        .collect();
+    assert_eq!(summary, [
+        (1, "pict", 150, 150, Some("av01"), 5, true),
+        (2, "auxv", 150, 150, Some("av01"), 5, false),
+    ]);
+    assert_eq!(parser.summary().tracks, tracks);
+    let alpha = parser.track_frames(2, 3).expect("alpha track frame");
+    assert_eq!(Some(alpha.data), parser.frame(3).unwrap().alpha_data);
+    assert!(parser.track_frames(1, 3).unwrap().alpha_data.is_some());
+    assert!(parser.track_frames(3, 0).is_err());
+
+    // Selecting the alpha track reads its samples as the frames
+    let config = zenavif_parse::DecodeConfig::default().with_track(2);
+    let selected = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("parse");
+    assert!(selected.tracks()[1].selected && !selected.tracks()[0].selected);
+    let frame = selected.frame(3).expect("frame");
+    assert_eq!(frame.data, parser.frame(3).unwrap().alpha_data.unwrap());
+    assert!(frame.alpha_data.is_none());
+    assert_eq!(selected.track_frames(1, 3).unwrap().data, parser.frame(3).unwrap().data);
+
+    let config = zenavif_parse::DecodeConfig::default().with_track(7);
+    assert!(zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable).is_err());
+    assert!(zenavif_parse::AvifParser::from_bytes(&summary_test_file()).unwrap().tracks().is_empty());
+}
+
+#[test]
+fn tracks_classified_by_handler_and_tref() {
+    use zenavif_parse::TrackKind;
+
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let kinds: Vec<_> = parser.tracks().iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, [TrackKind::Picture, TrackKind::Auxiliary]);
+
+    // Move the alpha track (2) before the color track and give it a `pict`
+    // handler: its auxl reference still marks it auxiliary
+    let (color, alpha) = (694..1474, 1474..2132);
+    let mut swapped = [&bytes[..color.start], &bytes[alpha.clone()], &bytes[color.clone()], &bytes[alpha.end..]].concat();
+    let alpha_handler = 1702 + 16 - alpha.start + color.start;
+    assert_eq!(&swapped[alpha_handler..alpha_handler + 4], b"auxv");
+    swapped[alpha_handler..alpha_handler + 4].copy_from_slice(b"pict");
+    let reordered = zenavif_parse::AvifParser::from_bytes(&swapped).expect("parse");
+    let tracks: Vec<_> = reordered.tracks().iter().map(|t| (t.track_id, t.kind, t.selected)).collect();
+    assert_eq!(tracks, [(2, TrackKind::Auxiliary, false), (1, TrackKind::Picture, true)]);
+    assert_eq!(reordered.frame(0).unwrap().data, parser.frame(0).unwrap().data);
+    assert_eq!(reordered.frame(0).unwrap().alpha_data, parser.frame(0).unwrap().alpha_data);
+}
+
+#[test]
+fn track_codec_config_from_stsd() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let color = &parser.tracks()[0];
+    let av1c = color.av1_config.as_ref().expect("av1C in the av01 sample entry");
+    assert_eq!((av1c.profile, av1c.bit_depth, av1c.chroma_subsampling_x, av1c.chroma_subsampling_y), (0, 8, 1, 1));
+    assert_eq!(
+        color.color_info,
+        Some(zenavif_parse::ColorInformation::Nclx {
+            color_primaries: 1,
+            transfer_characteristics: 13,
+            matrix_coefficients: 6,
+            full_range: true,
+        })
+    );
+    assert_eq!(color.pixel_aspect_ratio, None);
+    // The alpha sample entry has an av1C but no colr
+    let alpha = &parser.tracks()[1];
+    assert!(alpha.av1_config.is_some() && alpha.color_info.is_none());
+}
+
+#[test]
+fn ambient_viewing_environment_from_track_and_cclv() {
+    let mut bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert!(parser.ambient_viewing_environment().is_none() && parser.content_colour_volume().is_none());
+
+    // Replace the 19-byte colr of the primary item with a cclv (min and max
+    // luminance present) and that of the color track's av01 entry with an amve
+    let (item_colr, track_colr) = (435, 1307);
+    assert_eq!((&bytes[item_colr + 4..item_colr + 8], &bytes[track_colr + 4..track_colr + 8]), (&b"colr"[..], &b"colr"[..]));
+    let cclv = [&b"cclv"[..], &[0x18], &5u32.to_be_bytes(), &10_000_000u32.to_be_bytes(), &[0; 2]].concat();
+    bytes[item_colr + 4..item_colr + 19].copy_from_slice(&cclv);
+    let amve = [&b"amve"[..], &3_140_000u32.to_be_bytes(), &15_635u16.to_be_bytes(), &16_450u16.to_be_bytes(), &[0; 3]].concat();
+    bytes[track_colr + 4..track_colr + 19].copy_from_slice(&amve);
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let amve = parser.ambient_viewing_environment().expect("amve from the sample entry");
+    assert_eq!((amve.ambient_illuminance, amve.ambient_light_x, amve.ambient_light_y), (3_140_000, 15_635, 16_450));
+    let cclv = parser.content_colour_volume().expect("cclv");
+    assert_eq!((cclv.primaries, cclv.min_luminance, cclv.max_luminance, cclv.avg_luminance), (None, Some(5), Some(10_000_000), None));
+    let summary = parser.summary();
+    assert_eq!(summary.ambient_viewing_environment.as_ref(), Some(amve));
+    assert_eq!(summary.content_colour_volume.as_ref(), Some(cclv));
+}
+
+#[test]
+fn compact_sample_sizes_from_stz2() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    // The color track's 40-byte stsz lists sizes 38, 60, 47, 35, 39
+    let stsz = 1394..1434;
+    assert_eq!(&bytes[stsz.start + 4..stsz.start + 8], b"stsz");
+    let with_stz2 = |field_size: u8, entries: &[u8]| {
+        let mut payload = vec![0, 0, 0, field_size];
+        payload.extend_from_slice(&5u32.to_be_bytes());
+        payload.extend_from_slice(entries);
+        let mut replacement = full_box(b"stz2", 0, 0, &payload);
+        // Pad with a free box so no offsets move
+        replacement.extend(bmff_box(b"free", &vec![0; stsz.len() - replacement.len() - 8]));
+        [&bytes[..stsz.start], &replacement[..], &bytes[stsz.end..]].concat()
+    };
+
+    for file in [
+        with_stz2(8, &[38, 60, 47, 35, 39]),
+        with_stz2(16, &[0, 38, 0, 60, 0, 47, 0, 35, 0, 39]),
+    ] {
+        let compact = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+        assert_eq!(compact.animation_info().map(|info| info.frame_count), Some(5));
+        for i in 0..5 {
+            assert_eq!(compact.frame(i).unwrap().data, parser.frame(i).unwrap().data);
+        }
+    }
+    // Nibble-packed sizes parse too, though these are not the real ones
+    let file = with_stz2(4, &[0x12, 0x34, 0x50]);
+    let sizes: Vec<u32> = zenavif_parse::AvifParser::from_bytes(&file).expect("parse")
+        .summary().frames.iter().map(|f| f.size).collect();
+    assert_eq!(sizes, [1, 2, 3, 4, 5]);
+    assert!(zenavif_parse::AvifParser::from_bytes(&with_stz2(5, &[0; 4])).is_err());
+}
+
+#[test]
+fn data_into_appends_to_caller_buffer() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let seekable = zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes.clone())).expect("parse");
+    for p in [&parser, &seekable] {
+        let mut buf = Vec::new();
+        for i in 0..5 {
+            buf.clear();
+            p.frame_into(i, &mut buf).expect("frame");
+            assert_eq!(buf, &parser.frame(i).unwrap().data[..]);
+            buf.clear();
+            assert!(p.alpha_frame_into(i, &mut buf).expect("alpha frame"));
+            assert_eq!(Some(&buf[..]), parser.frame(i).unwrap().alpha_data.as_deref());
+        }
+        assert!(p.frame_into(5, &mut buf).is_err());
+
+        // Item data appends after what the buffer already holds
+        buf.clear();
+        buf.extend_from_slice(b"prefix");
+        p.primary_data_into(&mut buf).expect("primary");
+        assert_eq!(&buf[..6], b"prefix");
+        assert_eq!(buf[6..], parser.primary_data().unwrap()[..]);
+        buf.clear();
+        assert!(p.alpha_data_into(&mut buf).expect("alpha"));
+        assert_eq!(buf, &parser.alpha_data().unwrap().unwrap()[..]);
+    }
+
+    // Multi-extent items are concatenated into the buffer
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let mut buf = Vec::new();
+    parser.primary_data_into(&mut buf).expect("primary");
+    assert_eq!(buf, &parser.primary_data().unwrap()[..]);
+    assert!(!parser.alpha_data_into(&mut buf).expect("no alpha"));
+    assert!(parser.frame_into(0, &mut buf).is_err());
+}
+
+/// Append `child` to the color track's `stbl` in [`ANIM_8BPC_ALPHA`],
+/// growing the enclosing boxes and shifting the `iloc` and `stco` offsets
+/// into the `mdat` that follows.
+fn anim_with_color_stbl_child(child: &[u8]) -> Vec<u8> {
+    let mut bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let grow = child.len() as u32;
+    // moov, trak, mdia, minf and stbl of the color track; its stbl ends at 1474
+    for at in [566, 694, 1029, 1121, 1185] {
+        let size = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap());
+        bytes[at..at + 4].copy_from_slice(&(size + grow).to_be_bytes());
+    }
+    bytes.splice(1474..1474, child.iter().copied());
+    // The four iloc items have one 4-byte extent offset each; each stco has one chunk
+    for at in [132, 146, 160, 174, 1434 + 16, 2092 + 16 + child.len()] {
+        let offset = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap());
+        bytes[at..at + 4].copy_from_slice(&(offset + grow).to_be_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn ctts_offsets_shift_frame_timestamps() {
+    let bytes = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    // Timescale 30, five samples of 5 ticks each
+    let timestamps: Vec<u64> = parser.frames().map(|f| f.expect("frame").timestamp_ms).collect();
+    assert_eq!(timestamps, [0, 166, 333, 500, 666]);
+
+    // Signed (version 1) offsets 0, +5, -5, 0, 0: presentation order 0, 2, 1, 3, 4
+    let mut ctts = Vec::new();
+    ctts.extend_from_slice(&4u32.to_be_bytes());
+    for (count, offset) in [(1u32, 0i32), (1, 5), (1, -5), (2, 0)] {
+        ctts.extend_from_slice(&count.to_be_bytes());
+        ctts.extend_from_slice(&offset.to_be_bytes());
+    }
+    let bytes = anim_with_color_stbl_child(&full_box(b"ctts", 1, 0, &ctts));
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let timestamps: Vec<u64> = parser.frames().map(|f| f.expect("frame").timestamp_ms).collect();
+    assert_eq!(timestamps, [0, 333, 166, 500, 666]);
+    let summary = parser.summary();
+    assert_eq!(summary.frames[1].timestamp_ms, 333);
+    assert_eq!(summary.frames[1].duration_ms, 166);
+    // Frame data still lines up after the insertion
+    let original = std::fs::read(ANIM_8BPC_ALPHA).unwrap();
+    let original = zenavif_parse::AvifParser::from_bytes(&original).unwrap();
+    assert_eq!(parser.frame(2).unwrap().data, original.frame(2).unwrap().data);
+}
+
+#[test]
+fn tile_byte_ranges_cover_tiles() {
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"grid", &[0, 0, 0, 1, 0, 8, 0, 4]).prop(ispe(16, 4)),
+            TestItem::new(2, b"av01", b"left").prop(ispe(8, 4)),
+            TestItem::new(3, b"av01", b"right").prop(ispe(8, 4)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2, 3])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(parser.grid_tile_count(), 2);
+    for i in 0..2 {
+        let ranges = parser.tile_byte_ranges(i).expect("ranges");
+        assert_eq!(gather(&bytes, &ranges), parser.tile_data(i).expect("tile").as_ref());
+    }
+    assert!(parser.tile_byte_ranges(2).is_err());
+}
+
+#[test]
+fn tile_layout_crops_edge_tiles() {
+    let grid = |width: u8, height: u8| {
+        let tile = |id| TestItem::new(id, b"av01", b"tile").prop(ispe(8, 4));
+        build_avif(
+            &[
+                TestItem::new(1, b"grid", &[0, 0, 1, 1, 0, width, 0, height]).prop(ispe(width.into(), height.into())),
+                tile(2), tile(3), tile(4), tile(5),
+            ],
+            1,
+            &[(b"dimg", 1, &[2, 3, 4, 5])],
+        )
+    };
+    let bytes = grid(12, 6);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let layout: Vec<_> = parser.tile_layout().expect("layout").iter()
+        .map(|t| (t.row, t.column, t.x, t.y, t.width, t.height))
+        .collect();
+    assert_eq!(layout, [(0, 0, 0, 0, 8, 4), (0, 1, 8, 0, 4, 4), (1, 0, 0, 4, 8, 2), (1, 1, 8, 4, 4, 2)]);
+
+    // A 2×2 grid of 8×4 tiles cannot fill 17 columns, and leaves a tile row unused at 4 rows
+    for (width, height) in [(17, 8), (16, 4)] {
+        let bytes = grid(width, height);
+        let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+        assert!(parser.tile_layout().is_err(), "{width}x{height}");
+    }
+
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    assert!(parser.tile_layout().is_err(), "not a grid");
+}
+
+#[test]
+fn grid_with_256_columns() {
+    let tile_ids: Vec<u16> = (2..258).collect();
+    let mut items = vec![TestItem::new(1, b"grid", &[0, 0, 0, 255, 1, 0, 0, 1]).prop(ispe(256, 1))];
+    items.extend(tile_ids.iter().map(|&id| TestItem::new(id, b"av01", b"t").prop(ispe(1, 1))));
+    let bytes = build_avif(&items, 1, &[(b"dimg", 1, &tile_ids[..])]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    let grid = parser.grid_config().expect("grid");
+    assert_eq!((grid.rows, grid.columns, grid.output_width, grid.output_height), (1, 256, 256, 1));
+    let layout = parser.tile_layout().expect("layout");
+    assert_eq!(layout.len(), 256);
+    assert_eq!((layout[255].column, layout[255].x), (255, 255));
+
+    // The same layout derived from ispe alone
+    items[0] = TestItem::new(1, b"grid", &[1]).prop(ispe(256, 1));
+    let bytes = build_avif(&items, 1, &[(b"dimg", 1, &tile_ids[..])]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.grid_config().map(|g| (g.rows, g.columns)), Some((1, 256)));
+}
+
+#[test]
+fn grid_without_layout_is_rejected_unless_fallback_allowed() {
+    // No usable ImageGrid payload, and 12×6 is not a multiple of the 8×4 tiles
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"grid", &[1]).prop(ispe(12, 6)),
+            TestItem::new(2, b"av01", b"top").prop(ispe(8, 4)),
+            TestItem::new(3, b"av01", b"bottom").prop(ispe(8, 4)),
+        ],
+        1,
+        &[(b"dimg", 1, &[2, 3])],
+    );
+    let err = zenavif_parse::AvifParser::from_bytes(&bytes).err().expect("layout is unknown");
+    assert!(
+        matches!(err.error(), zenavif_parse::Error::InvalidData(msg) if msg.contains("not a multiple of the tile ispe")),
+        "{err:?}",
+    );
+
+    let config = zenavif_parse::DecodeConfig::default().allow_grid_fallback(true);
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &config, &zenavif_parse::Unstoppable)
+        .expect("fallback layout");
+    let grid = parser.grid_config().expect("grid");
+    assert_eq!((grid.rows, grid.columns, grid.output_width, grid.output_height), (2, 1, 0, 0));
+}
+
+// ============================================================================
+// Truncated input tests
+// ============================================================================
+
+fn truncated_config() -> zenavif_parse::DecodeConfig {
+    zenavif_parse::DecodeConfig::default().allow_truncated(true)
+}
+
+#[test]
+fn truncated_input_rejected_by_default() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes[..bytes.len() - 100]).is_err());
+}
+
+#[test]
+fn truncated_still_image_reports_missing_data() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let cut = &bytes[..bytes.len() - 100];
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(cut, &truncated_config(), &zenavif_parse::Unstoppable)
+        .expect("parse truncated");
+
+    assert!(!parser.is_complete());
+    assert!(parser.spatial_extents().is_some());
+    let err = parser.primary_data().expect_err("primary extends past the cut");
+    assert!(matches!(err.error(), zenavif_parse::Error::DataNotAvailable), "{err:?}");
+}
+
+#[test]
+fn complete_input_with_allow_truncated() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &truncated_config(), &zenavif_parse::Unstoppable)
+        .expect("parse");
+
+    assert!(parser.is_complete());
+    assert!(parser.primary_data().is_ok());
+}
+
+#[test]
+fn truncated_animation_keeps_early_frames() {
+    let bytes = std::fs::read(ANIM_8BPC).expect("read file");
+    let full = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let cut_len = bytes.len() - 60;
+
+    for parser in [
+        zenavif_parse::AvifParser::from_bytes_with_config(&bytes[..cut_len], &truncated_config(), &zenavif_parse::Unstoppable)
+            .expect("parse truncated"),
+        zenavif_parse::AvifParser::from_seekable_with_config(
+            std::io::Cursor::new(bytes[..cut_len].to_vec()),
+            &truncated_config(),
+            &zenavif_parse::Unstoppable,
+        )
+        .expect("parse truncated seekable"),
+    ] {
+        assert!(!parser.is_complete());
+        let frame_count = full.animation_info().expect("animated").frame_count;
+        let mut available = 0;
+        for i in 0..frame_count {
+            let end = full.frame_byte_ranges(i).expect("ranges").iter().map(|r| r.end).max().unwrap();
+            match parser.frame(i) {
+                Ok(frame) => {
+                    assert!(end <= cut_len as u64);
+                    assert_eq!(frame.data, full.frame(i).expect("frame").data);
+                    available += 1;
+                }
+                Err(e) => {
+                    assert!(end > cut_len as u64);
+                    assert!(matches!(e.error(), zenavif_parse::Error::DataNotAvailable), "{e:?}");
+                }
+            }
+        }
+        assert!(available > 0 && available < frame_count);
+    }
+}
+
+#[test]
+fn truncated_seekable_rejected_by_default() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let cut = bytes[..bytes.len() - 100].to_vec();
+    assert!(zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(cut)).is_err());
+}
+
+// ============================================================================
+// Lenient-mode warning tests
+// ============================================================================
+
+fn lenient_config() -> zenavif_parse::DecodeConfig {
+    zenavif_parse::DecodeConfig::default().with_strictness(zenavif_parse::Strictness::Permissive)
+}
+
+#[test]
+fn lenient_records_nonzero_flags() {
+    let mut size = 64u32.to_be_bytes().to_vec();
+    size.extend_from_slice(&48u32.to_be_bytes());
+    let flagged_ispe = full_box(b"ispe", 0, 1, &size);
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(flagged_ispe.clone())], 1, &[]);
+
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("lenient parse");
+    let ispe_at = bytes.windows(flagged_ispe.len()).position(|w| w == flagged_ispe).unwrap();
+    let warnings = parser.warnings();
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert_eq!(warnings[0].kind, zenavif_parse::ParseWarningKind::NonZeroFlags);
+    assert_eq!(&warnings[0].fourcc, b"ispe");
+    assert_eq!(warnings[0].offset, ispe_at as u64);
+}
+
+#[test]
+fn lenient_records_trailing_pixi_bytes() {
+    let pixi = full_box(b"pixi", 0, 0, &[3, 8, 8, 8, 0xAA, 0xBB]);
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(64, 48)).prop(pixi.clone())], 1, &[]);
+
+    assert!(zenavif_parse::AvifParser::from_bytes(&bytes).is_err());
+
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("lenient parse");
+    let pixi_at = bytes.windows(pixi.len()).position(|w| w == pixi).unwrap();
+    let warnings = parser.warnings();
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert_eq!(warnings[0].kind, zenavif_parse::ParseWarningKind::TrailingBytes);
+    assert_eq!(&warnings[0].fourcc, b"pixi");
+    assert_eq!(warnings[0].offset, pixi_at as u64);
+}
+
+#[test]
+#[allow(deprecated)]
+fn lenient_shim_maps_to_permissive() {
+    let config = zenavif_parse::DecodeConfig::default().lenient(true);
+    assert_eq!(config.strictness, zenavif_parse::Strictness::Permissive);
+
+    let flagged = zenavif_parse::DecodeConfig { lenient: true, ..Default::default() };
+    let pixi = full_box(b"pixi", 0, 0, &[3, 8, 8, 8, 0xAA]);
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(64, 48)).prop(pixi)], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &flagged, &zenavif_parse::Unstoppable)
+        .expect("the lenient field still selects permissive parsing");
+    assert_eq!(parser.warnings().len(), 1);
+}
+
+/// Position of the first extent's offset for `item_id` in a [`build_avif`] `iloc`.
+fn iloc_extent_at(bytes: &[u8], item_id: u16) -> usize {
+    let mut entry = item_id.to_be_bytes().to_vec();
+    entry.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+    bytes.windows(entry.len()).position(|w| w == entry).expect("iloc entry") + entry.len()
+}
+
+#[test]
+fn parser_reports_external_data_references() {
+    let dinf = |entry: Vec<u8>| {
+        let mut dref = 1u32.to_be_bytes().to_vec();
+        dref.extend_from_slice(&entry);
+        bmff_box(b"dinf", &full_box(b"dref", 0, 0, &dref))
+    };
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))];
+    let with_dref = |entry: Vec<u8>| {
+        let mut bytes = build_avif_with_meta_boxes(&items, 1, &[], &[dinf(entry)]);
+        // data_reference_index of item 1: the first dref entry
+        let extent_at = iloc_extent_at(&bytes, 1);
+        bytes[extent_at - 7] = 1;
+        bytes
+    };
+
+    let external = with_dref(full_box(b"url ", 0, 0, b"https://example.com/color.obu\0"));
+    let err = zenavif_parse::AvifParser::from_bytes(&external).err().expect("data is in another file");
+    match err.error().root() {
+        zenavif_parse::Error::ExternalDataReference { urls } => assert_eq!(urls, &["https://example.com/color.obu"]),
+        e => panic!("unexpected error: {e:?}"),
+    }
+
+    // A self-contained entry points back into this file
+    let local = with_dref(full_box(b"url ", 0, 1, &[]));
+    let parser = zenavif_parse::AvifParser::from_bytes(&local).expect("parse");
+    assert_eq!(&*parser.primary_data().expect("primary"), b"color");
+
+    // Without a dref, an index is taken to mean this file
+    let mut missing = build_avif(&items, 1, &[]);
+    let extent_at = iloc_extent_at(&missing, 1);
+    missing[extent_at - 7] = 1;
+    let parser = zenavif_parse::AvifParser::from_bytes(&missing).expect("parse without dref");
+    assert_eq!(&*parser.primary_data().expect("primary"), b"color");
+}
+
+#[test]
+fn parser_reports_protected_items() {
+    let mut schm = b"cenc".to_vec();
+    schm.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    let mut sinf = bmff_box(b"frma", b"av01");
+    sinf.extend_from_slice(&full_box(b"schm", 0, 0, &schm));
+    let mut ipro = 1u16.to_be_bytes().to_vec();
+    ipro.extend_from_slice(&bmff_box(b"sinf", &sinf));
+
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+    ];
+    let bytes = build_avif_with_meta_boxes(&items, 1, &[(b"cdsc", 2, &[1])], &[full_box(b"ipro", 0, 0, &ipro)]);
+    // item_protection_index of the Exif item's infe
+    let infe_at = bytes.windows(4).enumerate()
+        .filter(|(_, w)| w == b"infe")
+        .nth(1)
+        .expect("second infe").0;
+    let mut exif_protected = bytes.clone();
+    exif_protected[infe_at + 11] = 1;
+
+    // Protected metadata is left out
+    let parser = zenavif_parse::AvifParser::from_bytes(&exif_protected).expect("protected Exif");
+    assert_eq!(&*parser.primary_data().expect("primary"), b"color");
+    assert!(parser.exif().is_none());
+    let protected = parser.protected_items();
+    assert_eq!(protected.len(), 1);
+    assert_eq!(protected[0].item_id, 2);
+    assert_eq!(protected[0].scheme, Some(*b"cenc"));
+
+    // A protected primary cannot be shown
+    let first_infe = bytes.windows(4).position(|w| w == b"infe").expect("first infe");
+    let mut primary_protected = bytes.clone();
+    primary_protected[first_infe + 11] = 1;
+    let err = zenavif_parse::AvifParser::from_bytes(&primary_protected).err().expect("protected primary");
+    match err.error().root() {
+        zenavif_parse::Error::ProtectedItem { item_id, scheme } => {
+            assert_eq!(*item_id, 1);
+            assert_eq!(scheme, &Some(*b"cenc"));
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[test]
+fn validate_extents_checks_iloc_at_parse_time() {
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+    ];
+    let file = build_avif(&items, 1, &[(b"cdsc", 2, &[1])]);
+    let validating = zenavif_parse::DecodeConfig::default().validate_extents(true);
+    let parse = |bytes: &[u8]| zenavif_parse::AvifParser::from_bytes_with_config(bytes, &validating, &zenavif_parse::Unstoppable).map(drop);
+    parse(&file).expect("valid extents");
+
+    // The Exif item runs one byte past a shortened mdat
+    let mut outside = file.clone();
+    let mdat_at = outside.windows(4).rposition(|w| w == b"mdat").unwrap() - 4;
+    let mdat_size = u32::from_be_bytes(outside[mdat_at..mdat_at + 4].try_into().unwrap());
+    outside[mdat_at..mdat_at + 4].copy_from_slice(&(mdat_size - 1).to_be_bytes());
+    zenavif_parse::AvifParser::from_bytes(&outside).expect("unchecked until read");
+    let err = parse(&outside).expect_err("extent outside mdat");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData("item extent outside mdat")));
+
+    // Items may share bytes exactly, but not in part
+    let color_at = iloc_extent_at(&file, 1);
+    let exif_at = iloc_extent_at(&file, 2);
+    let mut shared = file.clone();
+    shared.copy_within(color_at..color_at + 8, exif_at);
+    parse(&shared).expect("identical extents");
+    let mut overlapping = shared.clone();
+    overlapping[exif_at + 7] += 1;
+    let err = parse(&overlapping).expect_err("partial overlap");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData("item extents overlap")));
+}
+
+#[test]
+fn detect_overlapping_extents_across_items_and_tracks() {
+    let detecting = zenavif_parse::DecodeConfig::default().detect_overlapping_extents(true);
+    let overlaps = |bytes: &[u8]| -> Vec<([u8; 4], u64)> {
+        let parser = zenavif_parse::AvifParser::from_bytes_with_config(bytes, &detecting, &zenavif_parse::Unstoppable)
+            .expect("parse");
+        parser.warnings().iter()
+            .inspect(|w| assert_eq!(w.kind, zenavif_parse::ParseWarningKind::OverlappingExtents))
+            .map(|w| (w.fourcc, w.offset))
+            .collect()
+    };
+
+    // libavif stores the still image as the first frame's bytes
+    let anim = std::fs::read(ANIM_8BPC).expect("read file");
+    assert_eq!(overlaps(&anim), []);
+    assert_eq!(overlaps(&std::fs::read(ANIM_8BPC_ALPHA).expect("read file")), []);
+
+    // One byte longer, the item covers the first frame and the start of the second
+    let mut longer = anim.clone();
+    let at = longer.windows(8).position(|w| w == [0, 0, 3, 0xff, 0, 0, 0, 0x27]).expect("iloc extent");
+    longer[at + 7] += 1;
+    assert!(zenavif_parse::AvifParser::from_bytes(&longer).expect("parse").warnings().is_empty());
+    assert_eq!(overlaps(&longer), [(*b"iloc", 0x3ff), (*b"trak", 0x3ff + 0x27)]);
+
+    // Two items aliasing the same bytes
+    let items = [
+        TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)),
+        TestItem::new(2, b"Exif", b"\0\0\0\0II*\0"),
+    ];
+    let mut shared = build_avif(&items, 1, &[(b"cdsc", 2, &[1])]);
+    assert_eq!(overlaps(&shared), []);
+    let color_at = iloc_extent_at(&shared, 1);
+    let exif_at = iloc_extent_at(&shared, 2);
+    shared.copy_within(color_at..color_at + 8, exif_at);
+    let color_offset = u32::from_be_bytes(shared[color_at..color_at + 4].try_into().unwrap());
+    assert_eq!(overlaps(&shared), [(*b"iloc", u64::from(color_offset))]);
+}
+
+#[test]
+fn permissive_recovers_missing_pitm() {
+    let items = [
+        TestItem::new(1, b"Exif", b"\0\0\0\0II*\0"),
+        TestItem::new(2, b"av01", b"thumb").prop(ispe(2, 2)),
+        TestItem::new(3, b"av01", b"color").prop(ispe(4, 4)),
+    ];
+    let bytes = build_avif(&items, 0, &[(b"cdsc", 1, &[3]), (b"thmb", 2, &[3])]);
+
+    let err = zenavif_parse::AvifParser::from_bytes(&bytes).err().expect("pitm is required");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData(_)));
+
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("permissive parse");
+    assert_eq!(parser.primary_data().unwrap().as_ref(), b"color");
+    let warnings = parser.warnings();
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert_eq!(warnings[0].kind, zenavif_parse::ParseWarningKind::MissingPrimaryItem);
+    assert_eq!(&warnings[0].fourcc, b"pitm");
+
+    // An iden item is preferred over the coded item it derives from
+    let bytes = build_avif(
+        &[
+            TestItem::new(1, b"iden", b"").prop(ispe(8, 4)).prop(irot(1)),
+            TestItem::new(2, b"av01", b"coded").prop(ispe(8, 4)),
+        ],
+        0,
+        &[(b"dimg", 1, &[2])],
+    );
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("permissive parse of iden without pitm");
+    assert_eq!(parser.primary_data().unwrap().as_ref(), b"coded");
+    assert_eq!(parser.rotation().map(|r| r.angle), Some(90));
+}
+
+#[test]
+fn permissive_skips_padding_before_ftyp_and_trailing_garbage() {
+    let file = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))], 1, &[]);
+    let mut padded = bmff_box(b"free", &[0; 8]);
+    padded.extend_from_slice(&file);
+    let mut malformed = file.clone();
+    malformed.extend_from_slice(&[0, 0, 0, 3, b'j', b'u', b'n', b'k', 0xff, 0xff]);
+    let mut oversized = file.clone();
+    oversized.extend_from_slice(&bmff_box(b"junk", &[0; 16])[..12]);
+
+    let cases: [(&[u8], _, &[u8; 4], u64); 3] = [
+        (&padded, zenavif_parse::ParseWarningKind::BoxBeforeFileType, b"free", 0),
+        (&malformed, zenavif_parse::ParseWarningKind::TrailingData, b"junk", file.len() as u64),
+        (&oversized, zenavif_parse::ParseWarningKind::TrailingData, b"junk", file.len() as u64),
+    ];
+    for (bytes, kind, fourcc, offset) in cases {
+        assert!(zenavif_parse::AvifParser::from_bytes(bytes).is_err(), "{kind:?} fails by default");
+        assert!(zenavif_parse::AvifParser::from_seekable(std::io::Cursor::new(bytes.to_vec())).is_err());
+
+        let parsers = [
+            zenavif_parse::AvifParser::from_bytes_with_config(bytes, &lenient_config(), &zenavif_parse::Unstoppable),
+            zenavif_parse::AvifParser::from_seekable_with_config(
+                std::io::Cursor::new(bytes.to_vec()), &lenient_config(), &zenavif_parse::Unstoppable,
+            ),
+        ];
+        for parser in parsers {
+            let parser = parser.expect("permissive parse");
+            let warnings = parser.warnings();
+            assert_eq!(warnings.len(), 1, "{warnings:?}");
+            assert_eq!((warnings[0].kind, &warnings[0].fourcc, warnings[0].offset), (kind, fourcc, offset));
+        }
+    }
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&malformed, &lenient_config(), &zenavif_parse::Unstoppable)
+        .unwrap();
+    assert_eq!(parser.primary_data().unwrap().as_ref(), b"color");
+
+    // Anything other than padding before ftyp is still rejected
+    let mut misplaced = bmff_box(b"junk", &[0; 8]);
+    misplaced.extend_from_slice(&file);
+    assert!(zenavif_parse::AvifParser::from_bytes_with_config(&misplaced, &lenient_config(), &zenavif_parse::Unstoppable).is_err());
+
+    // Limits hit after the structural boxes are errors, not trailing garbage
+    let mut trailing = file.clone();
+    trailing.extend_from_slice(&bmff_box(b"free", &[]));
+    let boxes = zenavif_parse::AvifParser::from_bytes(&trailing).expect("parse").stats().boxes;
+    let limited = lenient_config().with_max_total_boxes(boxes - 1);
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&trailing, &limited, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("box limit applies after the structure");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::ResourceLimitExceeded(_)));
+}
+
+#[test]
+fn item_properties_report_essential_and_supported() {
+    let items = [TestItem::new(1, b"av01", b"color")
+        .prop(ispe(4, 4))
+        .prop(irot(1))
+        .prop(bmff_box(b"xess", &[0; 4]))
+        .prop(bmff_box(b"xopt", &[0; 4]))];
+    let bytes = build_avif(&items, 1, &[]);
+
+    let err = zenavif_parse::AvifParser::from_bytes(&bytes).err().expect("unsupported essential property");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::Unsupported(_)));
+
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("permissive parse");
+    let props: Vec<_> = parser.item_properties(1).expect("item 1").iter()
+        .map(|p| (p.fourcc.as_str(), p.essential, p.supported))
+        .collect();
+    // Unknown non-essential properties are skipped
+    assert_eq!(props, [("ispe", false, true), ("irot", true, true), ("xess", true, false)]);
+    assert!(parser.item_properties(2).is_none());
+    let warnings = parser.warnings();
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert_eq!(warnings[0].kind, zenavif_parse::ParseWarningKind::UnsupportedEssentialProperty);
+    assert_eq!(&warnings[0].fourcc, b"xess");
+}
+
+#[test]
+fn parser_user_descriptions() {
+    let udes = |fields: &[&str]| full_box(b"udes", 0, 0, &fields.iter().flat_map(|f| [f.as_bytes(), b"\0"].concat()).collect::<Vec<_>>());
+    let items = [
+        TestItem::new(1, b"av01", b"color")
+            .prop(ispe(4, 4))
+            .prop(udes(&["en-US", "Harbor", "Boats at dawn", "boats,sea"]))
+            .prop(udes(&["fr", "Port", "", ""])),
+        TestItem::new(2, b"av01", b"other").prop(ispe(4, 4)).prop(udes(&["en", "Other", "", ""])),
+    ];
+    let bytes = build_avif(&items, 1, &[]);
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let descriptions: Vec<_> = parser.user_descriptions().iter()
+        .map(|d| (d.lang.as_str(), d.name.as_str(), d.description.as_str(), d.tags.as_str()))
+        .collect();
+    assert_eq!(descriptions, [("en-US", "Harbor", "Boats at dawn", "boats,sea"), ("fr", "Port", "", "")]);
+    let props: Vec<_> = parser.item_properties(2).expect("item 2").iter().map(|p| (p.fourcc.as_str(), p.supported)).collect();
+    assert_eq!(props, [("ispe", true), ("udes", true)]);
+
+    // Invalid UTF-8 is replaced rather than rejected
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(full_box(b"udes", 0, 0, b"en\0\xff\0\0\0"))], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    assert_eq!(parser.user_descriptions()[0].name, "\u{fffd}");
+
+    // Later versions are skipped as unsupported properties, which are only
+    // listed when essential
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(full_box(b"udes", 1, 0, b"en\0x\0\0\0"))], 1, &[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse udes v1");
+    assert!(parser.user_descriptions().is_empty());
+    let props: Vec<_> = parser.item_properties(1).expect("item 1").iter().map(|p| (p.fourcc.as_str(), p.supported)).collect();
+    assert_eq!(props, [("ispe", true)]);
+}
+
+#[test]
+fn parser_regions() {
+    use zenavif_parse::Region;
+    let be16 = |v: i16| v.to_be_bytes();
+    let rgan: &[&[u8]] = &[
+        &[0, 0], // version, 16-bit fields
+        &be16(640), &be16(480),
+        &[5],
+        &[1], &be16(10), &be16(-20), &be16(100), &be16(50), // rectangle
+        &[0], &be16(3), &be16(4), // point
+        &[5], &be16(0), &be16(0), &be16(3), &be16(3), &[0], &[0xff, 0x80], // inline mask, 9 bits
+        &[3], &be16(3), &be16(0), &be16(0), &be16(8), &be16(0), &be16(0), &be16(8), // polygon
+        &[2], &be16(320), &be16(240), &be16(30), &be16(40), // ellipse
+    ];
+    let items = [TestItem::new(1, b"av01", b"color").prop(ispe(640, 480)), TestItem::new(2, b"rgan", &rgan.concat())];
+    let bytes = build_avif(&items, 1, &[(b"cdsc", 2, &[1])]);
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let regions = parser.regions().expect("regions");
+    assert_eq!(regions.len(), 1);
+    let item = &regions[0];
+    assert_eq!((item.item_id, item.reference_width, item.reference_height), (2, 640, 480));
+    assert_eq!(item.described_items, [1]);
+    assert_eq!(
+        item.regions,
+        [
+            Region::Rectangle { x: 10, y: -20, width: 100, height: 50 },
+            Region::Point { x: 3, y: 4 },
+            Region::InlineMask { x: 0, y: 0, width: 3, height: 3 },
+            Region::Polygon { points: vec![(0, 0), (8, 0), (0, 8)] },
+            Region::Ellipse { x: 320, y: 240, radius_x: 30, radius_y: 40 },
+        ]
+    );
+
+    let no_regions = build_avif(&items[..1], 1, &[]);
+    assert!(zenavif_parse::AvifParser::from_bytes(&no_regions).unwrap().regions().unwrap().is_empty());
+}
+
+#[test]
+fn pedantic_rejects_common_encoder_shortcuts() {
+    let pedantic = zenavif_parse::DecodeConfig::default().with_strictness(zenavif_parse::Strictness::Pedantic);
+
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4)).prop(av1c(0, 0))], 1, &[]);
+    zenavif_parse::AvifParser::from_bytes(&bytes).expect("standard accepts a non-essential av1C");
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &pedantic, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("pedantic requires av1C to be essential");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::InvalidData("av1C must be marked essential")));
+
+    let mut bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(ispe(4, 4))], 1, &[]);
+    zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &pedantic, &zenavif_parse::Unstoppable)
+        .expect("pedantic accepts a conforming file");
+    let mif1_at = bytes.windows(4).position(|w| w == b"mif1").unwrap();
+    bytes[mif1_at..mif1_at + 4].copy_from_slice(b"MA1B");
+    zenavif_parse::AvifParser::from_bytes(&bytes).expect("standard ignores brands");
+    let err = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &pedantic, &zenavif_parse::Unstoppable)
+        .err()
+        .expect("pedantic requires mif1");
+    assert!(matches!(
+        err.error().root(),
+        zenavif_parse::Error::InvalidData("ftyp compatible_brands lacks the HEIF structural brand")
+    ));
+}
+
+#[test]
+fn well_formed_file_has_no_warnings() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(&bytes, &lenient_config(), &zenavif_parse::Unstoppable)
+        .expect("parse");
+    assert!(parser.warnings().is_empty());
+}
+
+// ============================================================================
+// Error context tests
+// ============================================================================
+
+#[test]
+fn error_root_without_context_is_itself() {
+    let err = zenavif_parse::Error::InvalidData("x");
+    assert!(matches!(err.root(), zenavif_parse::Error::InvalidData("x")));
+    assert!(matches!(err.into_root(), zenavif_parse::Error::InvalidData("x")));
+}
+
+#[cfg(feature = "rich-errors")]
+#[test]
+fn error_context_names_box_path_and_offset() {
+    let mut size = 64u32.to_be_bytes().to_vec();
+    size.extend_from_slice(&48u32.to_be_bytes());
+    let flagged_ispe = full_box(b"ispe", 0, 1, &size);
+    let bytes = build_avif(&[TestItem::new(1, b"av01", b"color").prop(flagged_ispe.clone())], 1, &[]);
+    let ispe_at = bytes.windows(flagged_ispe.len()).position(|w| w == flagged_ispe).unwrap();
+
+    let err = zenavif_parse::AvifParser::from_bytes(&bytes).err().expect("strict parse fails").decompose().0;
+    assert!(matches!(err.root(), zenavif_parse::Error::Unsupported("expected flags to be 0")));
+    let context = err.context().expect("box context");
+    assert_eq!(&context.box_path[..], &[*b"meta", *b"iprp", *b"ipco", *b"ispe"]);
+    assert_eq!(context.offset, ispe_at as u64);
+    assert_eq!(err.to_string(), format!("expected flags to be 0 (in meta/iprp/ipco/ispe at byte {ispe_at})"));
+}
+
+
+// ============================================================================
+// Writer tests
+// ============================================================================
+
+fn test_av1_config(monochrome: bool) -> zenavif_parse::AV1Config {
+    zenavif_parse::AV1Config {
+        profile: 0,
+        level: 8,
+        tier: 0,
+        bit_depth: 10,
+        monochrome,
+        chroma_subsampling_x: 1,
+        chroma_subsampling_y: 1,
+        chroma_sample_position: 0,
+        config_obus: Vec::new(),
+    }
+}
+
+fn summary_test_file() -> Vec<u8> {
+    let mut file = Vec::new();
+    zenavif_parse::AvifBuilder::new()
+        .primary_av1(b"color payload", test_av1_config(false))
+        .alpha(b"alpha payload", test_av1_config(true))
+        .ispe(3, 2)
+        .icc(b"fake icc")
+        .write_to(&mut file)
+        .expect("write");
+    file
+}
+
+#[test]
+fn summary_describes_primary_image() {
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    let summary = parser.summary();
+
+    assert_eq!(summary.major_brand, "avif");
+    assert_eq!(summary.compatible_brands, ["avif", "mif1", "miaf"]);
+    assert_eq!(summary.spatial_extents.map(|e| (e.width, e.height)), Some((3, 2)));
+    assert_eq!(summary.av1_config.as_ref().map(|c| c.bit_depth), Some(10));
+    assert!(summary.sequence_header.is_none(), "the payload is not AV1");
+    assert_eq!(summary.icc_profile_size, Some(8));
+    assert!(summary.nclx.is_none());
+    assert!(summary.has_alpha && !summary.premultiplied_alpha);
+    assert!(summary.grid.is_none() && summary.animation.is_none());
+    assert!(!summary.has_exif && !summary.has_depth_map);
+    assert!(summary.warnings.is_empty());
+}
+
+#[test]
+fn summary_lists_items_and_frames() {
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    let summary = parser.summary();
+
+    assert_eq!(summary.items.len(), 2);
+    let primary = &summary.items[0];
+    assert!(primary.primary && primary.item_type == "av01" && !primary.in_idat);
+    assert_eq!(primary.size, b"color payload".len() as u64);
+    assert_eq!(primary.byte_ranges, parser.primary_byte_ranges().expect("ranges"));
+    let props: Vec<_> = primary.properties.iter().map(|p| (p.fourcc.as_str(), p.essential)).collect();
+    assert_eq!(props, [("ispe", false), ("av1C", true), ("pixi", false), ("colr", false)]);
+    assert!(primary.references.is_empty());
+
+    let alpha = &summary.items[1];
+    assert!(!alpha.primary);
+    assert_eq!(alpha.size, b"alpha payload".len() as u64);
+    assert_eq!(alpha.properties.last().map(|p| p.fourcc.as_str()), Some("auxC"));
+    assert_eq!(alpha.references.len(), 1);
+    assert_eq!((&alpha.references[0].reference_type, alpha.references[0].to_item_id), (b"auxl", 1));
+    assert!(summary.frames.is_empty());
+
+    let bytes = std::fs::read(ANIMATED_AVIF).expect("read file");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let frames = parser.summary().frames;
+    assert_eq!(frames.len(), 5);
+    for (i, frame) in frames.iter().enumerate() {
+        let data = parser.frame(i).expect("frame");
+        assert_eq!(frame.size as usize, data.data.len());
+        assert_eq!(frame.alpha_size.map(|s| s as usize), data.alpha_data.as_ref().map(|a| a.len()));
+        assert_eq!(frame.duration_ms, 100);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn summary_serializes_to_json() {
+    let file = summary_test_file();
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    let json = serde_json::to_value(parser.summary()).expect("serialize");
+
+    assert_eq!(json["major_brand"], "avif");
+    assert_eq!(json["spatial_extents"], serde_json::json!({ "width": 3, "height": 2 }));
+    assert_eq!(json["av1_config"]["bit_depth"], 10);
+    assert_eq!(json["has_alpha"], true);
+    assert_eq!(json["grid"], serde_json::Value::Null);
+    assert_eq!(json["items"][0]["properties"][1], serde_json::json!({ "fourcc": "av1C", "essential": true, "supported": true }));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn avif_inspect_prints_boxes_and_frames() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_avif-inspect"))
+        .arg(ANIM_8BPC_ALPHA)
+        .output()
+        .expect("run avif-inspect");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let text = String::from_utf8(output.stdout).expect("utf-8");
+    assert!(text.contains("  ftyp @0 size "), "{text}");
+    assert!(text.contains("\n    mvhd @"), "moov children are listed: {text}");
+    assert!(text.contains("\n                av1C @1295 size 12\n"), "sample entry children are listed: {text}");
+    assert!(text.contains("auxl -> #1"), "{text}");
+    assert!(text.contains("Frames: 5"), "{text}");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_avif-inspect"))
+        .args(["--json", ANIM_8BPC_ALPHA])
+        .output()
+        .expect("run avif-inspect");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(json["frames"].as_array().map(|f| f.len()), Some(5));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn image_decoder_reports_header_and_delegates_pixels() {
+    use image::ImageDecoder;
+
+    let mut file = Vec::new();
+    zenavif_parse::AvifBuilder::new()
+        .primary_av1(b"color payload", test_av1_config(false))
+        .alpha(b"alpha payload", test_av1_config(true))
+        .ispe(3, 2)
+        .icc(b"fake icc")
+        .write_to(&mut file)
+        .expect("write");
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+
+    let mut decoder = zenavif_parse::image::AvifImageDecoder::new(parser, |parser, color_type, out| {
+        assert_eq!(&parser.primary_data().expect("primary")[..], b"color payload");
+        assert_eq!(color_type, image::ColorType::Rgba16);
+        out.fill(0xAB);
+        Ok(())
+    })
+    .expect("decoder");
+    assert_eq!(decoder.dimensions(), (3, 2));
+    assert_eq!(decoder.color_type(), image::ColorType::Rgba16);
+    assert_eq!(decoder.icc_profile().expect("icc").as_deref(), Some(&b"fake icc"[..]));
+    assert_eq!(decoder.exif_metadata().expect("exif"), None);
+    assert_eq!(decoder.orientation().expect("orientation"), image::metadata::Orientation::NoTransforms);
+
+    let mut pixels = vec![0; decoder.total_bytes() as usize];
+    assert_eq!(pixels.len(), 3 * 2 * 8);
+    decoder.read_image(&mut pixels).expect("read_image");
+    assert!(pixels.iter().all(|&b| b == 0xAB));
+}
+
+#[test]
+fn builder_roundtrips_color_alpha_and_icc() {
+    let mut file = Vec::new();
+    zenavif_parse::AvifBuilder::new()
+        .primary_av1(b"color payload", test_av1_config(false))
+        .alpha(b"alpha", test_av1_config(true))
+        .ispe(320, 200)
+        .icc(b"fake icc profile")
+        .write_to(&mut file)
+        .expect("write");
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&file).expect("parse");
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"color payload");
+    assert_eq!(&parser.alpha_data().expect("alpha present").expect("alpha")[..], b"alpha");
+    assert_eq!(parser.spatial_extents().map(|e| (e.width, e.height)), Some((320, 200)));
+    assert_eq!(parser.av1_config(), Some(&test_av1_config(false)));
+    assert_eq!(
+        parser.color_info(),
+        Some(&zenavif_parse::ColorInformation::IccProfile(b"fake icc profile".to_vec())),
+    );
+    assert!(parser.warnings().is_empty());
+}
+
+#[test]
+fn builder_remuxes_real_file() {
+    let bytes = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let source = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+    let primary = source.primary_data().expect("primary");
+    let extents = source.spatial_extents().expect("ispe");
+
+    let mut file = Vec::new();
+    zenavif_parse::AvifBuilder::new()
+        .primary_av1(&primary, source.av1_config().expect("av1C").clone())
+        .ispe(extents.width, extents.height)
+        .write_to(&mut file)
+        .expect("write");
+
+    let remuxed = zenavif_parse::AvifParser::from_bytes(&file).expect("parse remuxed");
+    assert_eq!(remuxed.primary_data().expect("primary"), primary);
+    assert_eq!(remuxed.av1_config(), source.av1_config());
+    assert!(remuxed.alpha_data().is_none());
+}
+
+#[test]
+fn builder_requires_primary_and_dimensions() {
+    let mut file = Vec::new();
+    assert!(zenavif_parse::AvifBuilder::new().ispe(1, 1).write_to(&mut file).is_err());
+    assert!(zenavif_parse::AvifBuilder::new().primary_av1(b"x", test_av1_config(false)).write_to(&mut file).is_err());
+    assert!(file.is_empty());
+}
+
+// ============================================================================
+// Metadata rewriting tests
+// ============================================================================
+
+/// A still image with an ICC profile, an Exif item and an XMP item.
+fn avif_with_metadata() -> Vec<u8> {
+    let color = TestItem::new(1, b"av01", b"color payload").prop(ispe(4, 4)).prop(colr_icc(b"prof", b"old icc"));
+    let mut exif = 0u32.to_be_bytes().to_vec();
+    exif.extend_from_slice(b"II*\0 gps here");
+    let exif = TestItem::new(2, b"Exif", &exif);
+    let xmp = TestItem { content_type: Some("application/rdf+xml"), ..TestItem::new(3, b"mime", b"<x:xmpmeta/>") };
+    build_avif(&[color, exif, xmp], 1, &[(b"cdsc", 2, &[1]), (b"cdsc", 3, &[1])])
+}
+
+fn rewrite(input: &[u8], rewriter: impl FnOnce(zenavif_parse::MetadataRewriter<'_>) -> zenavif_parse::MetadataRewriter<'_>) -> Vec<u8> {
+    let mut output = Vec::new();
+    rewriter(zenavif_parse::MetadataRewriter::new(input)).write_to(&mut output).expect("rewrite");
+    output
+}
+
+#[test]
+fn rewriter_strips_exif_and_xmp() {
+    let input = avif_with_metadata();
+    let output = rewrite(&input, |r| r.exif(zenavif_parse::MetadataEdit::Strip).xmp(zenavif_parse::MetadataEdit::Strip));
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert!(parser.exif_data().is_none());
+    assert!(parser.xmp_data().is_none());
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"color payload");
+    assert_eq!(parser.icc_profile().as_deref(), Some(&b"old icc"[..]));
+    // The stripped bytes are gone from the file, not just unreferenced
+    assert!(!output.windows(8).any(|w| w == b"gps here"));
+    assert!(parser.warnings().is_empty());
+}
+
+#[test]
+fn rewriter_replaces_metadata() {
+    let input = avif_with_metadata();
+    let output = rewrite(&input, |r| {
+        r.exif(zenavif_parse::MetadataEdit::Replace(b"MM\0*new"))
+            .xmp(zenavif_parse::MetadataEdit::Replace(b"<new/>"))
+            .icc(zenavif_parse::MetadataEdit::Replace(b"srgb icc"))
+    });
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert_eq!(&parser.exif_data().expect("exif").expect("exif resolves")[..], b"MM\0*new");
+    assert_eq!(&parser.xmp_data().expect("xmp").expect("xmp resolves")[..], b"<new/>");
+    assert_eq!(parser.icc_profile().as_deref(), Some(&b"srgb icc"[..]));
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"color payload");
+    assert!(!output.windows(7).any(|w| w == b"old icc"));
+}
+
+#[test]
+fn rewriter_strips_icc_and_renumbers_properties() {
+    let color = TestItem::new(1, b"av01", b"color payload").prop(colr_icc(b"prof", b"old icc")).prop(ispe(10, 8)).prop(av1c(0, 3));
+    let input = build_avif(&[color], 1, &[]);
+    let output = rewrite(&input, |r| r.icc(zenavif_parse::MetadataEdit::Strip));
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert!(parser.icc_profile().is_none());
+    assert!(!output.windows(7).any(|w| w == b"old icc"));
+    // The properties after the removed colr are still associated with the primary item
+    let extents = parser.spatial_extents().expect("ispe");
+    assert_eq!((extents.width, extents.height), (10, 8));
+    assert_eq!(parser.av1_config().map(|c| c.level), Some(3));
+}
+
+#[test]
+fn rewriter_adds_metadata_and_keeps_real_file_data() {
+    let input = std::fs::read(IMAGE_AVIF_EXTENTS).expect("read file");
+    let source = zenavif_parse::AvifParser::from_bytes(&input).expect("parse");
+    let output = rewrite(&input, |r| {
+        r.exif(zenavif_parse::MetadataEdit::Replace(b"II*\0added")).icc(zenavif_parse::MetadataEdit::Replace(b"srgb icc"))
+    });
+
+    let rewritten = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert_eq!(rewritten.primary_data().expect("primary"), source.primary_data().expect("primary"));
+    assert_eq!(rewritten.spatial_extents(), source.spatial_extents());
+    assert_eq!(&rewritten.exif_data().expect("exif").expect("exif resolves")[..], b"II*\0added");
+    assert_eq!(rewritten.icc_profile().as_deref(), Some(&b"srgb icc"[..]));
+}
+
+#[test]
+fn rewriter_rejects_image_sequences() {
+    let input = std::fs::read(ANIM_8BPC_ALPHA).expect("read file");
+    let mut output = Vec::new();
+    let err = zenavif_parse::MetadataRewriter::new(&input)
+        .exif(zenavif_parse::MetadataEdit::Strip)
+        .write_to(&mut output)
+        .expect_err("sequences are not rewritable");
+    assert!(matches!(err.error().root(), zenavif_parse::Error::Unsupported(_)));
+    assert!(output.is_empty());
+}
+
+#[test]
+fn rewriter_sets_and_removes_transforms() {
+    let color = TestItem::new(1, b"av01", b"color payload").prop(ispe(10, 8)).prop(av1c(0, 0));
+    let alpha = TestItem::new(2, b"av01", b"alpha payload")
+        .prop(ispe(10, 8))
+        .prop(av1c(0, 0))
+        .prop(full_box(b"auxC", 0, 0, b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha\0"));
+    let input = build_avif(&[color, alpha], 1, &[(b"auxl", 2, &[1])]);
+    let crop = zenavif_parse::CleanAperture {
+        width_n: 6, width_d: 1,
+        height_n: 4, height_d: 1,
+        horiz_off_n: -1, horiz_off_d: 2,
+        vert_off_n: 0, vert_off_d: 1,
+    };
+    let output = rewrite(&input, |r| {
+        r.rotation(Some(zenavif_parse::ImageRotation { angle: 270 }))
+            .mirror(Some(zenavif_parse::ImageMirror { axis: 1 }))
+            .clean_aperture(Some(crop))
+    });
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert_eq!(parser.rotation(), Some(&zenavif_parse::ImageRotation { angle: 270 }));
+    assert_eq!(parser.mirror(), Some(&zenavif_parse::ImageMirror { axis: 1 }));
+    assert_eq!(parser.clean_aperture(), Some(&crop));
+    assert_eq!(&parser.alpha_data().expect("alpha present").expect("alpha")[..], b"alpha payload");
+    // Transforms are essential, so strict parsing accepts them without warnings
+    assert!(parser.warnings().is_empty());
+
+    let output = rewrite(&output, |r| r.rotation(None));
+    let parser = zenavif_parse::AvifParser::from_bytes(&output).expect("parse rewritten");
+    assert!(parser.rotation().is_none());
+    assert_eq!(parser.mirror(), Some(&zenavif_parse::ImageMirror { axis: 1 }));
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"color payload");
+}
+
+#[test]
+fn rewriter_rejects_invalid_rotation() {
+    let input = build_avif(&[TestItem::new(1, b"av01", b"av1").prop(ispe(4, 4))], 1, &[]);
+    let mut output = Vec::new();
+    let result = zenavif_parse::MetadataRewriter::new(&input)
+        .rotation(Some(zenavif_parse::ImageRotation { angle: 45 }))
+        .write_to(&mut output);
+    assert!(result.is_err());
+    assert!(output.is_empty());
+}
+
+#[cfg(feature = "heif")]
+#[test]
+fn heic_hvc1_primary_item() {
+    let mut hvcc = vec![1, 0x01, 0x60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 90, 0xF0, 0, 0xFC, 0xFD, 0xF8, 0xF8, 0, 0, 0x0F];
+    // One array: a complete SPS
+    hvcc.extend_from_slice(&[1, 0x80 | 33, 0, 1, 0, 3, 0x42, 0x01, 0x01]);
+    let item = TestItem::new(1, b"hvc1", b"\0\0\0\x02nal").prop(ispe(64, 64)).prop(bmff_box(b"hvcC", &hvcc));
+    let mut bytes = build_avif(&[item], 1, &[]);
+    // Major brand
+    bytes[8..12].copy_from_slice(b"heic");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"\0\0\0\x02nal");
+    assert!(parser.av1_config().is_none());
+    let config = parser.hevc_config().expect("hvcC");
+    assert_eq!((config.profile_idc, config.level_idc, config.chroma_format), (1, 90, 1));
+    assert_eq!((config.bit_depth_luma, config.bit_depth_chroma, config.length_size), (8, 8, 4));
+    assert_eq!(config.nal_arrays.len(), 1);
+    assert!(config.nal_arrays[0].complete);
+    assert_eq!(config.nal_arrays[0].nal_unit_type, 33);
+    assert_eq!(config.nal_arrays[0].units, vec![vec![0x42, 0x01, 0x01]]);
+}
+
+#[cfg(feature = "heif")]
+#[test]
+fn heic_hev1_primary_item() {
+    // hev1 may carry its parameter sets in-band, so the array is incomplete
+    let mut hvcc = vec![1, 0x01, 0x60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 90, 0xF0, 0, 0xFC, 0xFD, 0xF8, 0xF8, 0, 0, 0x0F];
+    hvcc.extend_from_slice(&[1, 33, 0, 1, 0, 3, 0x42, 0x01, 0x01]);
+    let item = TestItem::new(1, b"hev1", b"\0\0\0\x02nal").prop(ispe(64, 64)).prop(bmff_box(b"hvcC", &hvcc));
+    let mut bytes = build_avif(&[item], 1, &[]);
+    bytes[8..12].copy_from_slice(b"heic");
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], b"\0\0\0\x02nal");
+    let config = parser.hevc_config().expect("hvcC");
+    assert!(!config.nal_arrays[0].complete);
+}
+
+// ============================================================================
+// Minimized (mini) files
+// ============================================================================
+
+/// Pack `(value, bit count)` fields most significant bit first, padded to a byte.
+fn pack_bits(fields: &[(u32, u32)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut used = 0;
+    for &(value, count) in fields {
+        for bit in (0..count).rev() {
+            if used % 8 == 0 {
+                bytes.push(0);
+            }
+            *bytes.last_mut().unwrap() |= (((value >> bit) & 1) as u8) << (7 - used % 8);
+            used += 1;
+        }
+    }
+    bytes
+}
+
+#[test]
+fn parser_reads_minimized_avif() {
+    let main_config = [0x81, 0x00, 0x0c, 0x00];
+    let main = b"main image";
+    let alpha = b"alpha";
+    let exif = b"MM\0*\0\0\0\x08";
+
+    // `hdr` holds the HDR fields; hdr_flag is set if there are any
+    let build = |hdr: &[(u32, u32)]| {
+        let mut fields = vec![
+            (0, 2), // version
+            (0, 1), // explicit_codec_types_flag
+            (0, 1), // float_flag
+            (1, 1), // full_range_flag
+            (1, 1), // alpha_flag
+            (0, 1), // explicit_cicp_flag
+            (u32::from(!hdr.is_empty()), 1), // hdr_flag
+            (0, 1), // icc_flag
+            (1, 1), // exif_flag
+            (0, 1), // xmp_flag
+            (1, 2), // chroma_subsampling: 4:2:0
+            (5, 3), // orientation_minus1: EXIF 6, 90° clockwise
+            (0, 1), // large_dimensions_flag
+            (63, 7), // width_minus1
+            (31, 7), // height_minus1
+            (0, 1), // chroma_is_horizontally_centered
+            (0, 1), // chroma_is_vertically_centered
+            (0, 1), // high_bit_depth_flag
+            (0, 1), // alpha_is_premultiplied
+        ];
+        fields.extend_from_slice(hdr);
+        fields.extend_from_slice(&[
+            (1, 1), // few_metadata_bytes_flag
+            (1, 1), // few_codec_config_bytes_flag
+            (1, 1), // few_item_data_bytes_flag
+            (main_config.len() as u32, 3),
+            (main.len() as u32 - 1, 15),
+            (alpha.len() as u32, 15),
+            (0, 3), // alpha_item_codec_config_size: shares the main one
+            (0, 1), // exif_xmp_compressed_flag
+            (exif.len() as u32 - 1, 10),
+        ]);
+        let mut mini = pack_bits(&fields);
+        mini.extend_from_slice(&main_config);
+        mini.extend_from_slice(alpha);
+        mini.extend_from_slice(main);
+        mini.extend_from_slice(exif);
+
+        let mut bytes = bmff_box(b"ftyp", b"mif3avif");
+        bytes.extend(bmff_box(b"mini", &mini));
+        bytes
+    };
+    let bytes = build(&[]);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse");
+
+    assert_eq!(&parser.primary_data().expect("primary")[..], main);
+    assert_eq!(&parser.alpha_data().expect("alpha").expect("alpha")[..], alpha);
+    assert_eq!(parser.spatial_extents().map(|e| (e.width, e.height)), Some((64, 32)));
+    assert_eq!(parser.rotation().map(|r| r.angle), Some(270));
+    assert_eq!(parser.bits_per_channel(), Some(&[8, 8, 8][..]));
+    assert_eq!(parser.av1_config().map(|c| (c.bit_depth, c.chroma_subsampling_x)), Some((8, 1)));
+    assert_eq!(
+        parser.color_info(),
+        Some(&zenavif_parse::ColorInformation::Nclx {
+            color_primaries: 1,
+            transfer_characteristics: 13,
+            matrix_coefficients: 6,
+            full_range: true,
+        })
+    );
+    assert_eq!(&parser.exif().expect("exif").expect("exif")[..], exif);
+    assert!(parser.content_light_level().is_none());
+
+    let mut hdr = vec![
+        (0, 1), // gainmap_flag
+        (1, 1), // clli_flag
+        (1, 1), // mdcv_flag
+        (1, 1), // cclv_flag
+        (1, 1), // amve_flag
+        (0, 1), // reve_flag
+        (0, 1), // ndwt_flag
+        (1000, 16), (400, 16), // clli
+    ];
+    // mdcv: primaries, white point, luminances
+    hdr.extend([(1, 16), (2, 16), (3, 16), (4, 16), (5, 16), (6, 16), (7, 16), (8, 16), (10_000_000, 32), (50, 32)]);
+    // cclv: only ccv_min_luminance_value
+    hdr.extend([(0b0001_0000, 8), (20, 32)]);
+    // amve, skipped
+    hdr.extend([(314, 32), (15635, 16), (16450, 16)]);
+    let bytes = build(&hdr);
+    let parser = zenavif_parse::AvifParser::from_bytes(&bytes).expect("parse HDR mini");
+    assert_eq!(&parser.primary_data().expect("primary")[..], main);
+    let clli = parser.content_light_level().expect("clli");
+    assert_eq!((clli.max_content_light_level, clli.max_pic_average_light_level), (1000, 400));
+    let mdcv = parser.mastering_display().expect("mdcv");
+    assert_eq!((mdcv.primaries, mdcv.white_point), ([(1, 2), (3, 4), (5, 6)], (7, 8)));
+    assert_eq!((mdcv.max_luminance, mdcv.min_luminance), (10_000_000, 50));
+    let cclv = parser.content_colour_volume().expect("cclv");
+    assert_eq!((cclv.primaries, cclv.min_luminance, cclv.max_luminance), (None, Some(20), None));
+    assert_eq!(&parser.exif().expect("exif").expect("exif")[..], exif);
+
+    // A minimized file cannot also have a meta box
+    let mut both = bytes.clone();
+    both.extend(bmff_box(b"meta", &[0; 4]));
+    assert!(zenavif_parse::AvifParser::from_bytes(&both).is_err());
+}